@@ -0,0 +1,265 @@
+//! Subcommand implementations
+//!
+//! Each command returns `Ok(true)` on success, `Ok(false)` when it ran but found
+//! problems (parse errors, unformatted files, no matches), and `Err` when it could
+//! not run at all.
+
+use crate::input::{CliError, Input};
+use gram_codec::query::{Binding, Graph, Query, Row};
+use gram_codec::{
+    parse_gram, parse_gram_with_header, parse_to_ast, patterns_from_ast, to_gram,
+    to_gram_with_header, tokenize, AstPattern, AstSubject, TokenKind,
+};
+use pattern_core::{Pattern, Subject, Symbol};
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// `gram check`: reports every file that fails to parse.
+pub fn check(files: &[PathBuf]) -> Result<bool, CliError> {
+    let mut ok = true;
+    for input in Input::read_all(files)? {
+        if let Err(e) = parse_gram(&input.text) {
+            eprintln!("{}", input.diagnostic(&e));
+            ok = false;
+        }
+    }
+    Ok(ok)
+}
+
+/// `gram fmt`: rewrites files in canonical form, or lists them with `--check`.
+///
+/// Files with comments are reported and left as they are.
+pub fn fmt(files: &[PathBuf], check: bool) -> Result<bool, CliError> {
+    let mut ok = true;
+    for input in Input::read_all(files)? {
+        let Some(formatted) = format(&input)? else {
+            ok = false;
+            continue;
+        };
+
+        if check {
+            if formatted != input.text {
+                println!("{}", input.name);
+                ok = false;
+            }
+        } else if let Some(path) = &input.path {
+            if formatted != input.text {
+                std::fs::write(path, &formatted).map_err(|source| CliError::Io {
+                    name: input.name.clone(),
+                    source,
+                })?;
+            }
+        } else {
+            print!("{}", formatted);
+        }
+    }
+    Ok(ok)
+}
+
+/// Canonical gram text for `input`, or `None` after reporting a parse error or
+/// comments, which formatting would remove.
+///
+/// A leading header record is kept as the first line, and non-empty output ends
+/// with a newline.
+fn format(input: &Input) -> Result<Option<String>, CliError> {
+    let (header, patterns) = match parse_gram_with_header(&input.text) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("{}", input.diagnostic(&e));
+            return Ok(None);
+        }
+    };
+    if tokenize(&input.text).any(|token| token.kind == TokenKind::Comment) {
+        eprintln!("{}: formatting would remove comments", input.name);
+        return Ok(None);
+    }
+
+    let mut text = match header {
+        Some(header) => to_gram_with_header(header, &patterns)?,
+        None => to_gram(&patterns)?,
+    };
+    if !text.is_empty() {
+        text.push('\n');
+    }
+    Ok(Some(text))
+}
+
+/// `gram to-json`: prints the canonical AST.
+pub fn to_json(file: Option<&Path>, compact: bool) -> Result<bool, CliError> {
+    let input = Input::read(file)?;
+    let ast = match parse_to_ast(&input.text) {
+        Ok(ast) => ast,
+        Err(e) => {
+            eprintln!("{}", input.diagnostic(&e));
+            return Ok(false);
+        }
+    };
+
+    let json = if compact {
+        serde_json::to_string(&ast)
+    } else {
+        serde_json::to_string_pretty(&ast)
+    };
+    println!("{}", json.expect("AST serializes to JSON"));
+    Ok(true)
+}
+
+/// `gram from-json`: prints gram notation for a canonical AST.
+pub fn from_json(file: Option<&Path>) -> Result<bool, CliError> {
+    let input = Input::read(file)?;
+    let ast: AstPattern = serde_json::from_str(&input.text).map_err(|source| CliError::Json {
+        name: input.name.clone(),
+        source,
+    })?;
+    let patterns = patterns_from_ast(&ast).map_err(|source| CliError::Ast {
+        name: input.name.clone(),
+        source,
+    })?;
+
+    let gram = to_gram(&patterns)?;
+    if !gram.is_empty() {
+        println!("{}", gram);
+    }
+    Ok(true)
+}
+
+/// `gram stats`: summarizes each document with [`Pattern::analyze_structure`].
+///
+/// The document is analyzed as one pattern whose elements are its top-level
+/// patterns, so depth 1 is the top level. A header record is not counted.
+pub fn stats(files: &[PathBuf], json: bool) -> Result<bool, CliError> {
+    let mut ok = true;
+    for input in Input::read_all(files)? {
+        let patterns = match parse_gram_with_header(&input.text) {
+            Ok((_, patterns)) => patterns,
+            Err(e) => {
+                eprintln!("{}", input.diagnostic(&e));
+                ok = false;
+                continue;
+            }
+        };
+
+        let graph = Graph::new(&patterns);
+        let root = Subject {
+            identity: Symbol(String::new()),
+            labels: HashSet::new(),
+            properties: HashMap::new(),
+        };
+        let document = Pattern::pattern(root, patterns.clone());
+        let analysis = document.analyze_structure();
+
+        if json {
+            let stats = serde_json::json!({
+                "file": input.name,
+                "patterns": patterns.len(),
+                "nodes": graph.node_count(),
+                "relationships": graph.relationship_count(),
+                "depthDistribution": analysis.depth_distribution,
+                "elementCounts": analysis.element_counts,
+                "nestingPatterns": analysis.nesting_patterns,
+                "summary": analysis.summary,
+            });
+            println!("{}", stats);
+        } else {
+            println!("{}", input.name);
+            println!("  patterns       {}", patterns.len());
+            println!("  nodes          {}", graph.node_count());
+            println!("  relationships  {}", graph.relationship_count());
+            println!(
+                "  depth          {}",
+                analysis.depth_distribution.len().saturating_sub(1)
+            );
+            println!("  structure      {}", analysis.summary);
+        }
+    }
+    Ok(ok)
+}
+
+/// `gram query`: prints one line per match, succeeding if anything matched.
+///
+/// With several inputs, text lines are prefixed with the file name. Header records
+/// are not part of the queried graph.
+pub fn query(query: &str, files: &[PathBuf], json: bool) -> Result<bool, CliError> {
+    let query = Query::parse(query)?;
+    let inputs = Input::read_all(files)?;
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let mut parsed = true;
+    let mut matched = false;
+    for input in &inputs {
+        let patterns = match parse_gram_with_header(&input.text) {
+            Ok((_, patterns)) => patterns,
+            Err(e) => {
+                eprintln!("{}", input.diagnostic(&e));
+                parsed = false;
+                continue;
+            }
+        };
+
+        let graph = Graph::new(&patterns);
+        for row in graph.query(&query) {
+            matched = true;
+            let line = if json {
+                serde_json::json!({ "file": input.name, "bindings": row_json(&row) }).to_string()
+            } else if inputs.len() > 1 {
+                format!("{}: {}", input.name, row_text(&row))
+            } else {
+                row_text(&row)
+            };
+            writeln!(out, "{}", line).map_err(|source| CliError::Io {
+                name: "<stdout>".to_string(),
+                source,
+            })?;
+        }
+    }
+    Ok(parsed && matched)
+}
+
+/// `x=(a:Person) r=[r:KNOWS] p=[k1:KNOWS],[k2:KNOWS]`
+fn row_text(row: &Row<'_>) -> String {
+    let bindings: Vec<String> = row
+        .variables()
+        .map(|var| {
+            let value = match row.get(var).expect("variable is bound") {
+                Binding::Node(subject) => format!("({})", subject),
+                Binding::Relationship(subject) => format!("[{}]", subject),
+                Binding::Path(subjects) => subjects
+                    .iter()
+                    .map(|subject| format!("[{}]", subject))
+                    .collect::<Vec<_>>()
+                    .join(","),
+            };
+            format!("{}={}", var, value)
+        })
+        .collect();
+
+    if bindings.is_empty() {
+        "match".to_string()
+    } else {
+        bindings.join(" ")
+    }
+}
+
+/// Bindings as a JSON object of AST subjects (arrays of subjects for paths).
+fn row_json(row: &Row<'_>) -> serde_json::Value {
+    let subject_json = |subject: &Subject| {
+        serde_json::to_value(ast_subject(subject)).expect("AST serializes to JSON")
+    };
+
+    row.variables()
+        .map(|var| {
+            let value = match row.get(var).expect("variable is bound") {
+                Binding::Node(subject) | Binding::Relationship(subject) => subject_json(subject),
+                Binding::Path(subjects) => subjects.iter().map(|s| subject_json(s)).collect(),
+            };
+            (var.to_string(), value)
+        })
+        .collect::<serde_json::Map<_, _>>()
+        .into()
+}
+
+fn ast_subject(subject: &Subject) -> AstSubject {
+    AstPattern::from_pattern(&Pattern::point(subject.clone())).subject
+}
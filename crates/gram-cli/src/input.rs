@@ -0,0 +1,99 @@
+//! Reading command inputs and reporting errors
+
+use gram_codec::query::QueryError;
+use gram_codec::{AstError, ParseError, SerializeError};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Errors that abort a command
+#[derive(Debug, Error)]
+pub enum CliError {
+    /// A file could not be read or written
+    #[error("{name}: {source}")]
+    Io { name: String, source: io::Error },
+
+    /// JSON input is not a canonical AST
+    #[error("{name}: {source}")]
+    Json {
+        name: String,
+        source: serde_json::Error,
+    },
+
+    /// The AST holds a value with no gram equivalent
+    #[error("{name}: {source}")]
+    Ast { name: String, source: AstError },
+
+    /// Patterns could not be written as gram notation
+    #[error(transparent)]
+    Serialize(#[from] SerializeError),
+
+    /// The query is invalid
+    #[error("invalid query: {0}")]
+    Query(#[from] QueryError),
+}
+
+/// A file (or standard input) and its contents
+#[derive(Debug)]
+pub struct Input {
+    /// Name used in diagnostics: the path, or `<stdin>`
+    pub name: String,
+    /// The file path, `None` for standard input
+    pub path: Option<PathBuf>,
+    pub text: String,
+}
+
+impl Input {
+    /// Reads `path`, or standard input when `path` is `None` or `-`.
+    pub fn read(path: Option<&Path>) -> Result<Self, CliError> {
+        match path.filter(|p| *p != Path::new("-")) {
+            Some(path) => {
+                let name = path.display().to_string();
+                match std::fs::read_to_string(path) {
+                    Ok(text) => Ok(Input {
+                        name,
+                        path: Some(path.to_path_buf()),
+                        text,
+                    }),
+                    Err(source) => Err(CliError::Io { name, source }),
+                }
+            }
+            None => {
+                let mut text = String::new();
+                match io::stdin().read_to_string(&mut text) {
+                    Ok(_) => Ok(Input {
+                        name: "<stdin>".to_string(),
+                        path: None,
+                        text,
+                    }),
+                    Err(source) => Err(CliError::Io {
+                        name: "<stdin>".to_string(),
+                        source,
+                    }),
+                }
+            }
+        }
+    }
+
+    /// Reads every path in order, or standard input when `paths` is empty.
+    pub fn read_all(paths: &[PathBuf]) -> Result<Vec<Self>, CliError> {
+        if paths.is_empty() {
+            return Ok(vec![Input::read(None)?]);
+        }
+        paths.iter().map(|p| Input::read(Some(p))).collect()
+    }
+
+    /// Formats a parse error as a `name:line:column: error: message` diagnostic.
+    pub fn diagnostic(&self, error: &ParseError) -> String {
+        match error.location() {
+            Some(location) => format!(
+                "{}:{}:{}: error: {}",
+                self.name,
+                location.line,
+                location.column,
+                error.message()
+            ),
+            None => format!("{}: error: {}", self.name, error.message()),
+        }
+    }
+}
@@ -0,0 +1,117 @@
+//! # gram
+//!
+//! Command-line tool for Gram notation, built on `gram-codec`.
+//!
+//! ```text
+//! gram check [FILES]...             validate, printing file:line:column diagnostics
+//! gram fmt [--check] [FILES]...     format files in place (stdin to stdout)
+//! gram to-json [FILE]               print the canonical AST as JSON
+//! gram from-json [FILE]             print gram notation for a canonical AST
+//! gram stats [--json] [FILES]...    summarize document structure
+//! gram query [--json] QUERY [FILES]...
+//!                                   print one line per match of a gram query
+//! ```
+//!
+//! Every command reads standard input when no file (or `-`) is given.
+//!
+//! ## Exit Status
+//!
+//! - `0`: success
+//! - `1`: a file failed to parse, `fmt --check` found unformatted files, or a
+//!   query had no matches
+//! - `2`: usage, I/O or conversion error
+
+mod commands;
+mod input;
+
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// Check, format, convert and query Gram notation
+#[derive(Debug, Parser)]
+#[command(name = "gram", version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Validate files, reporting each syntax error with its location
+    Check {
+        /// Files to check (standard input if omitted or `-`)
+        files: Vec<PathBuf>,
+    },
+
+    /// Rewrite files in canonical gram notation
+    Fmt {
+        /// Report unformatted files instead of rewriting them
+        #[arg(long)]
+        check: bool,
+
+        /// Files to format (standard input to standard output if omitted or `-`)
+        files: Vec<PathBuf>,
+    },
+
+    /// Print the canonical AST of a document as JSON
+    ToJson {
+        /// Print on a single line
+        #[arg(long)]
+        compact: bool,
+
+        /// File to convert (standard input if omitted or `-`)
+        file: Option<PathBuf>,
+    },
+
+    /// Print gram notation for a canonical AST in JSON
+    FromJson {
+        /// File to convert (standard input if omitted or `-`)
+        file: Option<PathBuf>,
+    },
+
+    /// Summarize the structure of documents
+    Stats {
+        /// Print one JSON object per file
+        #[arg(long)]
+        json: bool,
+
+        /// Files to summarize (standard input if omitted or `-`)
+        files: Vec<PathBuf>,
+    },
+
+    /// Match a gram query against documents
+    Query {
+        /// Print one JSON object per match
+        #[arg(long)]
+        json: bool,
+
+        /// Query in gram path notation, e.g. `(a:Person)-[:KNOWS]->(b)`
+        query: String,
+
+        /// Files to query (standard input if omitted or `-`)
+        files: Vec<PathBuf>,
+    },
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let result = match &cli.command {
+        Command::Check { files } => commands::check(files),
+        Command::Fmt { check, files } => commands::fmt(files, *check),
+        Command::ToJson { compact, file } => commands::to_json(file.as_deref(), *compact),
+        Command::FromJson { file } => commands::from_json(file.as_deref()),
+        Command::Stats { json, files } => commands::stats(files, *json),
+        Command::Query { json, query, files } => commands::query(query, files, *json),
+    };
+
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(e) => {
+            eprintln!("gram: {}", e);
+            ExitCode::from(2)
+        }
+    }
+}
@@ -0,0 +1,228 @@
+//! End-to-end tests for the `gram` binary
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+fn gram(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_gram"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("gram runs");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8(output.stderr.clone()).unwrap()
+}
+
+/// Writes `text` to a file unique to this test process.
+fn temp_file(name: &str, text: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("gram-cli-{}-{}", std::process::id(), name));
+    std::fs::write(&path, text).unwrap();
+    path
+}
+
+const UNFORMATTED: &str = "(a:Person {name: \"Alice\"})  -[:KNOWS]->   (b)\n\n(c)";
+const FORMATTED: &str = "(a:Person {name: \"Alice\"})-[:KNOWS]->(b)\n(c)\n";
+
+#[test]
+fn check_accepts_valid_input() {
+    let output = gram(&["check"], "(a)-->(b)");
+
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stderr(&output).is_empty());
+}
+
+#[test]
+fn check_reports_located_diagnostics_for_each_bad_file() {
+    let good = temp_file("check-good.gram", "(a)");
+    let bad = temp_file("check-bad.gram", "(a)\n(b {k: })");
+    let output = gram(
+        &["check", good.to_str().unwrap(), bad.to_str().unwrap()],
+        "",
+    );
+
+    assert_eq!(output.status.code(), Some(1));
+    let diagnostics = stderr(&output);
+    assert_eq!(diagnostics.lines().count(), 1);
+    assert!(diagnostics.starts_with(&format!("{}:2:", bad.display())));
+    assert!(diagnostics.contains(": error: "));
+}
+
+#[test]
+fn missing_file_is_a_usage_error() {
+    let output = gram(&["check", "/nonexistent/file.gram"], "");
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).contains("/nonexistent/file.gram"));
+}
+
+#[test]
+fn fmt_formats_stdin_to_stdout() {
+    let output = gram(&["fmt"], UNFORMATTED);
+
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), FORMATTED);
+}
+
+#[test]
+fn fmt_keeps_header_record() {
+    let output = gram(&["fmt"], "{version: 1}   (a)");
+
+    assert_eq!(stdout(&output), "{version: 1}\n(a)\n");
+}
+
+#[test]
+fn fmt_check_lists_unformatted_files_without_writing() {
+    let unformatted = temp_file("fmt-check-a.gram", UNFORMATTED);
+    let formatted = temp_file("fmt-check-b.gram", FORMATTED);
+    let output = gram(
+        &[
+            "fmt",
+            "--check",
+            unformatted.to_str().unwrap(),
+            formatted.to_str().unwrap(),
+        ],
+        "",
+    );
+
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output), format!("{}\n", unformatted.display()));
+    assert_eq!(std::fs::read_to_string(&unformatted).unwrap(), UNFORMATTED);
+}
+
+#[test]
+fn fmt_rewrites_files_in_place() {
+    let path = temp_file("fmt-write.gram", UNFORMATTED);
+    let output = gram(&["fmt", path.to_str().unwrap()], "");
+
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout(&output).is_empty());
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), FORMATTED);
+
+    let recheck = gram(&["fmt", "--check", path.to_str().unwrap()], "");
+    assert_eq!(recheck.status.code(), Some(0));
+}
+
+#[test]
+fn fmt_reports_parse_errors() {
+    let output = gram(&["fmt"], "(a");
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).starts_with("<stdin>:1:"));
+}
+
+#[test]
+fn fmt_refuses_files_with_comments() {
+    let text = "// people\n(a:Person)  // alice\n(b)";
+    let path = temp_file("fmt-comments.gram", text);
+    let output = gram(&["fmt", path.to_str().unwrap()], "");
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).is_empty());
+    assert_eq!(
+        stderr(&output),
+        format!("{}: formatting would remove comments\n", path.display())
+    );
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), text);
+
+    let output = gram(&["fmt"], text);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).is_empty());
+}
+
+#[test]
+fn json_round_trip() {
+    let input = "{version: 1}\n(a:Person {age: 30})-[:KNOWS]->(b)\n(c)\n";
+    let json = gram(&["to-json"], input);
+    assert_eq!(json.status.code(), Some(0));
+
+    let ast: serde_json::Value = serde_json::from_str(&stdout(&json)).unwrap();
+    assert_eq!(ast["subject"]["properties"]["version"], 1);
+    assert_eq!(ast["elements"][0]["subject"]["labels"][0], "KNOWS");
+
+    let gram_text = gram(&["from-json"], &stdout(&json));
+    assert_eq!(gram_text.status.code(), Some(0));
+    assert_eq!(stdout(&gram_text), input);
+}
+
+#[test]
+fn from_json_rejects_invalid_ast() {
+    let output = gram(&["from-json"], r#"{"subject": {}}"#);
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).starts_with("gram: <stdin>:"));
+}
+
+#[test]
+fn stats_counts_nodes_and_relationships() {
+    let output = gram(&["stats", "--json"], "{v: 1} (a)-->(b)-->(c) [g | a, b, c]");
+
+    assert_eq!(output.status.code(), Some(0));
+    let stats: serde_json::Value = serde_json::from_str(stdout(&output).trim()).unwrap();
+    assert_eq!(stats["file"], "<stdin>");
+    assert_eq!(stats["patterns"], 2);
+    assert_eq!(stats["nodes"], 4);
+    assert_eq!(stats["relationships"], 2);
+    assert_eq!(stats["depthDistribution"], serde_json::json!([1, 2, 5, 2]));
+}
+
+#[test]
+fn query_prints_one_line_per_match() {
+    let output = gram(
+        &["query", "(x:Person)-[:KNOWS]->(y)"],
+        "(a:Person)-[:KNOWS]->(b) (c:Person)-[:KNOWS]->(a) (b)-[:KNOWS]->(c)",
+    );
+
+    assert_eq!(output.status.code(), Some(0));
+    let mut lines: Vec<_> = stdout(&output).lines().map(str::to_string).collect();
+    lines.sort();
+    assert_eq!(
+        lines,
+        vec!["x=(a:Person) y=(b)", "x=(c:Person) y=(a:Person)"]
+    );
+}
+
+#[test]
+fn query_json_names_the_file() {
+    let path = temp_file("query.gram", "(a:Person)-[r:KNOWS]->(b)");
+    let output = gram(
+        &["query", "--json", "(x)-[k]->(y)", path.to_str().unwrap()],
+        "",
+    );
+
+    let row: serde_json::Value = serde_json::from_str(stdout(&output).trim()).unwrap();
+    assert_eq!(row["file"], path.display().to_string());
+    assert_eq!(row["bindings"]["k"]["identity"], "r");
+    assert_eq!(row["bindings"]["x"]["labels"][0], "Person");
+}
+
+#[test]
+fn query_without_matches_exits_one() {
+    let output = gram(&["query", "(x:Missing)"], "(a:Person)");
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).is_empty());
+}
+
+#[test]
+fn invalid_query_is_a_usage_error() {
+    let output = gram(&["query", "(x"], "(a)");
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).starts_with("gram: invalid query"));
+}
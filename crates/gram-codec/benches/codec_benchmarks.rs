@@ -1,6 +1,6 @@
 // Performance benchmarks for gram codec
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
-use gram_codec::{parse_gram_notation, to_gram, to_gram_pattern};
+use gram_codec::{parse_gram_borrowed, parse_gram_notation, to_gram, to_gram_pattern};
 use pattern_core::{Pattern, Subject, Symbol};
 use std::collections::{HashMap, HashSet};
 
@@ -37,6 +37,19 @@ fn generate_node_with_properties(prop_count: usize) -> String {
     format!("(node {{{}}})", properties.join(", "))
 }
 
+/// Generate a bulk-loading document: nodes with string properties, joined by relationships
+fn generate_bulk_document(count: usize) -> String {
+    (0..count)
+        .map(|i| {
+            format!(
+                "(person_{i}:Person {{name: \"Person {i}\", email: \"p{i}@example.com\", tags: [\"a\", \"b\"]}})-[:KNOWS {{since: 2020}}]->(person_{})",
+                i + 1
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 /// Create a pattern for serialization
 fn create_pattern(id: &str) -> Pattern<Subject> {
     Pattern::point(Subject {
@@ -115,6 +128,24 @@ fn bench_parse_with_properties(c: &mut Criterion) {
     group.finish();
 }
 
+/// Benchmark owned against borrowed parsing of a bulk document
+fn bench_parse_borrowed(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_borrowed");
+
+    for size in [100, 1000].iter() {
+        let input = generate_bulk_document(*size);
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_with_input(BenchmarkId::new("owned", size), &input, |b, input| {
+            b.iter(|| parse_gram_notation(black_box(input)))
+        });
+        group.bench_with_input(BenchmarkId::new("borrowed", size), &input, |b, input| {
+            b.iter(|| parse_gram_borrowed(black_box(input)))
+        });
+    }
+
+    group.finish();
+}
+
 /// Benchmark serializing single patterns
 fn bench_serialize_single(c: &mut Criterion) {
     let mut group = c.benchmark_group("serialize_single");
@@ -188,6 +219,7 @@ criterion_group!(
     bench_parse_relationships,
     bench_parse_subject_patterns,
     bench_parse_with_properties,
+    bench_parse_borrowed,
     bench_serialize_single,
     bench_serialize_multiple,
     bench_round_trip,
@@ -36,6 +36,7 @@
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use thiserror::Error;
 
 /// Abstract Syntax Tree representation of a Pattern
 ///
@@ -252,6 +253,130 @@ fn value_to_json(value: &Value) -> serde_json::Value {
     }
 }
 
+// Conversion from AST back to Pattern<Subject>
+
+/// Error converting an AST back into a Pattern
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum AstError {
+    /// A property value has no gram equivalent
+    #[error("Invalid value for property '{key}': {reason}")]
+    InvalidValue { key: String, reason: String },
+}
+
+impl AstPattern {
+    /// Convert from AST back to native Pattern<Subject>
+    ///
+    /// This is the inverse of [`AstPattern::from_pattern`]: native JSON numbers
+    /// become integers when they are whole and decimals otherwise, and tagged
+    /// objects (`symbol`, `range`, `measurement`, `tagged`) become the matching
+    /// [`Value`] variant. Any other object is read as a map.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AstError::InvalidValue`] for `null` values and for tagged
+    /// objects with missing or mistyped fields.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use gram_codec::{parse_to_ast, AstPattern};
+    ///
+    /// let ast = parse_to_ast("(alice:Person {age: 30})")?;
+    /// let pattern = ast.to_pattern()?;
+    /// assert_eq!(AstPattern::from_pattern(&pattern), ast);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn to_pattern(&self) -> Result<Pattern<Subject>, AstError> {
+        let properties = self
+            .subject
+            .properties
+            .iter()
+            .map(|(k, v)| json_to_value(k, v).map(|v| (k.clone(), v)))
+            .collect::<Result<_, _>>()?;
+
+        let subject = Subject {
+            identity: pattern_core::Symbol(self.subject.identity.clone()),
+            labels: self.subject.labels.iter().cloned().collect(),
+            properties,
+        };
+        let elements = self
+            .elements
+            .iter()
+            .map(AstPattern::to_pattern)
+            .collect::<Result<_, _>>()?;
+
+        Ok(Pattern::pattern(subject, elements))
+    }
+}
+
+/// Convert canonical JSON back to a Value (inverse of [`value_to_json`])
+fn json_to_value(key: &str, json: &serde_json::Value) -> Result<Value, AstError> {
+    let invalid = |reason: &str| AstError::InvalidValue {
+        key: key.to_string(),
+        reason: reason.to_string(),
+    };
+
+    match json {
+        serde_json::Value::Null => Err(invalid("null has no gram equivalent")),
+
+        serde_json::Value::Bool(b) => Ok(Value::VBoolean(*b)),
+
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => Ok(Value::VInteger(i)),
+            None => n
+                .as_f64()
+                .map(Value::VDecimal)
+                .ok_or_else(|| invalid("number out of range")),
+        },
+
+        serde_json::Value::String(s) => Ok(Value::VString(s.clone())),
+
+        serde_json::Value::Array(items) => items
+            .iter()
+            .map(|item| json_to_value(key, item))
+            .collect::<Result<_, _>>()
+            .map(Value::VArray),
+
+        serde_json::Value::Object(map) => {
+            let string = |field: &str| {
+                map.get(field)
+                    .and_then(serde_json::Value::as_str)
+                    .map(str::to_string)
+                    .ok_or_else(|| invalid(&format!("expected string field '{}'", field)))
+            };
+            let bound = |field: &str| match map.get(field) {
+                None | Some(serde_json::Value::Null) => Ok(None),
+                Some(v) => v
+                    .as_f64()
+                    .map(Some)
+                    .ok_or_else(|| invalid(&format!("expected number field '{}'", field))),
+            };
+
+            match map.get("type").and_then(serde_json::Value::as_str) {
+                Some("symbol") => string("value").map(Value::VSymbol),
+                Some("range") => Ok(Value::VRange(pattern_core::RangeValue {
+                    lower: bound("lower")?,
+                    upper: bound("upper")?,
+                })),
+                Some("measurement") => Ok(Value::VMeasurement {
+                    unit: string("unit")?,
+                    value: bound("value")?
+                        .ok_or_else(|| invalid("expected number field 'value'"))?,
+                }),
+                Some("tagged") => Ok(Value::VTaggedString {
+                    tag: string("tag")?,
+                    content: string("content")?,
+                }),
+                _ => map
+                    .iter()
+                    .map(|(k, v)| json_to_value(key, v).map(|v| (k.clone(), v)))
+                    .collect::<Result<_, _>>()
+                    .map(Value::VMap),
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -454,4 +579,55 @@ mod tests {
         // Integer is now native JSON, not tagged
         assert_eq!(v["key2"], 42);
     }
+
+    #[test]
+    fn test_to_pattern_round_trip() {
+        let mut props = HashMap::new();
+        props.insert("age".to_string(), Value::VInteger(30));
+        props.insert("score".to_string(), Value::VDecimal(1.5));
+        props.insert("id".to_string(), Value::VSymbol("u1".to_string()));
+        props.insert(
+            "when".to_string(),
+            Value::VTaggedString {
+                tag: "date".to_string(),
+                content: "2024-01-09".to_string(),
+            },
+        );
+        props.insert(
+            "span".to_string(),
+            Value::VRange(pattern_core::RangeValue {
+                lower: Some(1.0),
+                upper: None,
+            }),
+        );
+        let subject = Subject {
+            identity: Symbol("alice".to_string()),
+            labels: HashSet::from(["Person".to_string()]),
+            properties: props,
+        };
+        let pattern = Pattern::pattern(
+            subject,
+            vec![Pattern::point(Subject {
+                identity: Symbol("bob".to_string()),
+                labels: HashSet::new(),
+                properties: HashMap::new(),
+            })],
+        );
+
+        let ast = AstPattern::from_pattern(&pattern);
+        assert_eq!(ast.to_pattern().unwrap(), pattern);
+    }
+
+    #[test]
+    fn test_to_pattern_rejects_null() {
+        let mut ast = AstPattern::empty();
+        ast.subject
+            .properties
+            .insert("k".to_string(), serde_json::Value::Null);
+
+        assert!(matches!(
+            ast.to_pattern(),
+            Err(AstError::InvalidValue { key, .. }) if key == "k"
+        ));
+    }
 }
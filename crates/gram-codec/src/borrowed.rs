@@ -0,0 +1,468 @@
+//! Zero-copy parsing into subjects that borrow from the input
+//!
+//! [`parse_gram_borrowed`] returns patterns of [`BorrowedSubject`]s whose
+//! identities, labels, keys and strings are [`Cow`]s: borrowed from the input
+//! unless they contain escapes. This saves an allocation per name and string
+//! when loading large documents. [`BorrowedSubject::into_owned`] and
+//! [`into_owned_pattern`] convert the result to the types
+//! [`parse_gram`](crate::parse_gram) returns.
+//!
+//! ```rust
+//! use gram_codec::{into_owned_pattern, parse_gram, parse_gram_borrowed};
+//! use std::borrow::Cow;
+//!
+//! let input = r#"(alice:Person {name: "Alice"})"#;
+//! let patterns = parse_gram_borrowed(input)?;
+//! assert!(matches!(patterns[0].value.identity, Cow::Borrowed("alice")));
+//!
+//! let owned: Vec<_> = patterns.into_iter().map(into_owned_pattern).collect();
+//! assert_eq!(owned, parse_gram(input)?);
+//! # Ok::<(), gram_codec::ParseError>(())
+//! ```
+
+use crate::events::{parse_events_with_options, Event};
+use crate::options::{Dialect, ParseOptions};
+use crate::parser::{tokenize, ArrowType, Location, ParseError, TokenKind};
+use crate::tolerant::PatternKind;
+use pattern_core::{Pattern, RangeValue, Subject, Symbol, Value};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+/// A property value that borrows its strings from the input where it can
+///
+/// The borrowed counterpart of [`pattern_core::Value`], with the same variants.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BorrowedValue<'a> {
+    /// Integer value (i64)
+    VInteger(i64),
+    /// Decimal value (f64)
+    VDecimal(f64),
+    /// Boolean value
+    VBoolean(bool),
+    /// String value
+    VString(Cow<'a, str>),
+    /// Symbol value (string identifier)
+    VSymbol(Cow<'a, str>),
+    /// Tagged string with a type tag and content
+    VTaggedString {
+        /// The type tag
+        tag: Cow<'a, str>,
+        /// The string content
+        content: Cow<'a, str>,
+    },
+    /// Array of values
+    VArray(Vec<BorrowedValue<'a>>),
+    /// Map from string keys to values
+    VMap(HashMap<Cow<'a, str>, BorrowedValue<'a>>),
+    /// Numeric range value
+    VRange(RangeValue),
+    /// Measurement with unit and numeric value
+    VMeasurement {
+        /// The unit string
+        unit: Cow<'a, str>,
+        /// The numeric value
+        value: f64,
+    },
+}
+
+impl BorrowedValue<'_> {
+    /// Converts to an owned [`Value`], copying any borrowed strings
+    pub fn into_owned(self) -> Value {
+        match self {
+            BorrowedValue::VInteger(i) => Value::VInteger(i),
+            BorrowedValue::VDecimal(d) => Value::VDecimal(d),
+            BorrowedValue::VBoolean(b) => Value::VBoolean(b),
+            BorrowedValue::VString(s) => Value::VString(s.into_owned()),
+            BorrowedValue::VSymbol(s) => Value::VSymbol(s.into_owned()),
+            BorrowedValue::VTaggedString { tag, content } => Value::VTaggedString {
+                tag: tag.into_owned(),
+                content: content.into_owned(),
+            },
+            BorrowedValue::VArray(values) => {
+                Value::VArray(values.into_iter().map(BorrowedValue::into_owned).collect())
+            }
+            BorrowedValue::VMap(map) => Value::VMap(
+                map.into_iter()
+                    .map(|(key, value)| (key.into_owned(), value.into_owned()))
+                    .collect(),
+            ),
+            BorrowedValue::VRange(range) => Value::VRange(range),
+            BorrowedValue::VMeasurement { unit, value } => Value::VMeasurement {
+                unit: unit.into_owned(),
+                value,
+            },
+        }
+    }
+}
+
+/// A subject that borrows its names and strings from the input where it can
+///
+/// The borrowed counterpart of [`Subject`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BorrowedSubject<'a> {
+    /// Identity, empty for an anonymous subject
+    pub identity: Cow<'a, str>,
+    /// Labels
+    pub labels: HashSet<Cow<'a, str>>,
+    /// Properties
+    pub properties: HashMap<Cow<'a, str>, BorrowedValue<'a>>,
+}
+
+impl BorrowedSubject<'_> {
+    /// Converts to an owned [`Subject`], copying any borrowed strings
+    pub fn into_owned(self) -> Subject {
+        Subject {
+            identity: Symbol(self.identity.into_owned()),
+            labels: self.labels.into_iter().map(Cow::into_owned).collect(),
+            properties: self
+                .properties
+                .into_iter()
+                .map(|(key, value)| (key.into_owned(), value.into_owned()))
+                .collect(),
+        }
+    }
+}
+
+/// Converts a pattern of borrowed subjects to the pattern
+/// [`parse_gram`](crate::parse_gram) returns
+pub fn into_owned_pattern(pattern: Pattern<BorrowedSubject<'_>>) -> Pattern<Subject> {
+    // Converts without recursion, since a path nests as deep as it is long:
+    // each entry is a pattern's subject, its elements still to convert and
+    // those already converted
+    let mut stack = vec![(
+        pattern.value.into_owned(),
+        pattern.elements.into_iter(),
+        Vec::new(),
+    )];
+    loop {
+        let (_, elements, _) = stack.last_mut().expect("a pattern being converted");
+        if let Some(element) = elements.next() {
+            stack.push((
+                element.value.into_owned(),
+                element.elements.into_iter(),
+                Vec::new(),
+            ));
+            continue;
+        }
+        let (subject, _, elements) = stack.pop().expect("a pattern being converted");
+        let pattern = Pattern::pattern(subject, elements);
+        match stack.last_mut() {
+            Some((_, _, converted)) => converted.push(pattern),
+            None => return pattern,
+        }
+    }
+}
+
+/// Parse gram notation into patterns of subjects that borrow from `input`
+///
+/// Accepts and rejects exactly what [`parse_gram`](crate::parse_gram) does,
+/// with the same errors.
+pub fn parse_gram_borrowed(input: &str) -> Result<Vec<Pattern<BorrowedSubject<'_>>>, ParseError> {
+    build_patterns(input, &ParseOptions::default())
+}
+
+/// Builds patterns from the events of `input`, in the dialect and within the
+/// limits in `options`
+pub(crate) fn build_patterns<'a>(
+    input: &'a str,
+    options: &ParseOptions,
+) -> Result<Vec<Pattern<BorrowedSubject<'a>>>, ParseError> {
+    build_top_level(input, options).map(|(patterns, _)| patterns)
+}
+
+/// Builds patterns as [`build_patterns`] does, with the kind and span of each
+/// top-level pattern
+pub(crate) fn build_top_level<'a>(
+    input: &'a str,
+    options: &ParseOptions,
+) -> Result<TopLevel<'a>, ParseError> {
+    options.check_input(input)?;
+    let strict = options.dialect == Dialect::Strict;
+    let mut patterns = Vec::new();
+    let mut spans = Vec::new();
+    let mut stack: Vec<Frame> = Vec::new();
+    let mut started = 0;
+    let mut definitions = Definitions::default();
+    for event in parse_events_with_options(input, options) {
+        let event = event?;
+        // A subject is complete once the first event after its properties
+        // arrives, and can then be checked against earlier definitions
+        if !matches!(event, Event::Property { .. }) {
+            if let Some(offset) = definitions.pending.take() {
+                let subject = stack.last_mut().expect("subject in a pattern").subject();
+                definitions.define(input, offset, subject)?;
+            }
+        }
+        match event {
+            Event::StartPattern { kind, span } => {
+                started += 1;
+                options.check_patterns(input, span.start, started)?;
+                stack.push(Frame {
+                    kind,
+                    subject: BorrowedSubject::default(),
+                    elements: Vec::new(),
+                    arrows: Vec::new(),
+                })
+            }
+            Event::Subject {
+                identity,
+                labels,
+                span,
+            } => {
+                options.check_string(input, span.start, &identity)?;
+                for label in &labels {
+                    options.check_string(input, span.start, label)?;
+                }
+                let subject = stack.last_mut().expect("subject in a pattern").subject();
+                subject.identity = identity;
+                for label in labels {
+                    if let Some(label) = subject.labels.replace(label).filter(|_| strict) {
+                        return Err(ParseError::DuplicateLabel {
+                            location: Location::from_offset(input, span.start),
+                            label: label.into_owned(),
+                        });
+                    }
+                }
+                if strict {
+                    definitions.pending = Some(span.start);
+                }
+            }
+            Event::Property { key, value, span } => {
+                options.check_string(input, span.start, &key)?;
+                options.check_value(input, span.start, &value)?;
+                let subject = stack.last_mut().expect("property in a pattern").subject();
+                if strict {
+                    if subject.properties.contains_key(&key) {
+                        return Err(ParseError::DuplicateKey {
+                            location: Location::from_offset(input, span.start),
+                            key: key.into_owned(),
+                        });
+                    }
+                    check_map_keys(input, span.clone())?;
+                }
+                subject.properties.insert(key, value);
+                options.check_properties(input, span.start, subject.properties.len())?;
+            }
+            Event::Arrow { arrow, span } => {
+                let frame = stack.last_mut().expect("arrow in a relationship");
+                frame
+                    .arrows
+                    .push((arrow, BorrowedSubject::default(), span.start));
+            }
+            Event::Annotation { key, value, span } => {
+                options.check_string(input, span.start, &key)?;
+                if let Some(value) = value {
+                    options.check_string(input, span.start, &value)?;
+                }
+            }
+            Event::EndPattern { kind, span } => {
+                let frame = stack.pop().expect("balanced pattern events");
+                if strict {
+                    definitions.connect(input, &frame)?;
+                }
+                let pattern = frame.finish();
+                match stack.last_mut() {
+                    Some(parent) => parent.elements.push(pattern),
+                    None => {
+                        patterns.push(pattern);
+                        spans.push((kind, span));
+                    }
+                }
+            }
+        }
+    }
+    Ok((patterns, spans))
+}
+
+/// Top-level patterns, with the kind and span of each
+pub(crate) type TopLevel<'a> = (
+    Vec<Pattern<BorrowedSubject<'a>>>,
+    Vec<(PatternKind, Range<usize>)>,
+);
+
+/// Checks that no map in the value of the property at `span` has a key twice,
+/// for the strict dialect
+///
+/// The value has been parsed into a map, which keeps only the last of repeated
+/// keys, so its text is scanned for them instead.
+fn check_map_keys(input: &str, span: Range<usize>) -> Result<(), ParseError> {
+    let mut maps: Vec<HashSet<&str>> = Vec::new();
+    for token in tokenize(&input[span.clone()]) {
+        match token.kind {
+            TokenKind::LeftBrace => maps.push(HashSet::new()),
+            TokenKind::RightBrace => {
+                maps.pop();
+            }
+            TokenKind::Key => {
+                if let Some(keys) = maps.last_mut() {
+                    if !keys.insert(token.text) {
+                        return Err(ParseError::DuplicateKey {
+                            location: Location::from_offset(input, span.start + token.span.start),
+                            key: token.text.to_string(),
+                        });
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// The subjects and edges defined so far, for the strict dialect
+#[derive(Default)]
+struct Definitions<'a> {
+    subjects: HashMap<Cow<'a, str>, BorrowedSubject<'a>>,
+    /// The identities of the nodes each identified edge goes from and to
+    edges: HashMap<Cow<'a, str>, (Cow<'a, str>, Cow<'a, str>)>,
+    /// Offset of the subject whose properties are still being parsed
+    pending: Option<usize>,
+}
+
+impl<'a> Definitions<'a> {
+    /// Records the definition of `subject` at `offset`, failing if it differs
+    /// from an earlier one. A subject without labels or properties is a
+    /// reference, not a definition.
+    fn define(
+        &mut self,
+        input: &str,
+        offset: usize,
+        subject: &BorrowedSubject<'a>,
+    ) -> Result<(), ParseError> {
+        if subject.identity.is_empty()
+            || (subject.labels.is_empty() && subject.properties.is_empty())
+        {
+            return Ok(());
+        }
+        match self.subjects.get(&subject.identity) {
+            Some(defined) if defined != subject => Err(ParseError::ConflictingDefinition {
+                location: Location::from_offset(input, offset),
+                identity: subject.identity.to_string(),
+            }),
+            Some(_) => Ok(()),
+            None => {
+                self.subjects
+                    .insert(subject.identity.clone(), subject.clone());
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<'a> Definitions<'a> {
+    /// Records the endpoints of the identified edges of a relationship, failing
+    /// if an edge was written earlier between other nodes
+    fn connect(&mut self, input: &str, frame: &Frame<'a>) -> Result<(), ParseError> {
+        let nodes = frame.elements.windows(2);
+        for ((arrow, edge, offset), nodes) in frame.arrows.iter().zip(nodes) {
+            if edge.identity.is_empty() {
+                continue;
+            }
+            let (from, to) = (&nodes[0].value.identity, &nodes[1].value.identity);
+            let endpoints = if arrow.is_backward() {
+                (to.clone(), from.clone())
+            } else {
+                (from.clone(), to.clone())
+            };
+            match self.edges.get(&edge.identity) {
+                Some(defined) if *defined != endpoints => {
+                    return Err(ParseError::ConflictingDefinition {
+                        location: Location::from_offset(input, *offset),
+                        identity: edge.identity.to_string(),
+                    })
+                }
+                Some(_) => {}
+                None => {
+                    self.edges.insert(edge.identity.clone(), endpoints);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A pattern whose events have started but not ended
+struct Frame<'a> {
+    kind: PatternKind,
+    subject: BorrowedSubject<'a>,
+    elements: Vec<Pattern<BorrowedSubject<'a>>>,
+    /// Arrows between the elements of a relationship, with their edge subjects
+    /// and offsets
+    arrows: Vec<(ArrowType, BorrowedSubject<'a>, usize)>,
+}
+
+impl<'a> Frame<'a> {
+    /// The subject that subject and property events describe: the last edge of
+    /// a relationship, or else the pattern's own
+    fn subject(&mut self) -> &mut BorrowedSubject<'a> {
+        match self.arrows.last_mut() {
+            Some((_, edge, _)) => edge,
+            None => &mut self.subject,
+        }
+    }
+
+    /// Builds the pattern, nesting the segments of a path from the left as
+    /// [`parse_gram`](crate::parse_gram) does
+    fn finish(self) -> Pattern<BorrowedSubject<'a>> {
+        if self.kind != PatternKind::Relationship {
+            return Pattern::pattern(self.subject, self.elements);
+        }
+        let mut elements = self.elements.into_iter();
+        let mut current = elements.next().expect("relationship with a first node");
+        for ((arrow, edge, _), next) in self.arrows.into_iter().zip(elements) {
+            let pair = if arrow.is_backward() {
+                vec![next, current]
+            } else {
+                vec![current, next]
+            };
+            current = Pattern::pattern(edge, pair);
+        }
+        current
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escaped_strings_are_owned() {
+        let patterns = parse_gram_borrowed(r#"(a {plain: "x", escaped: "a\"b"})"#).unwrap();
+        let properties = &patterns[0].value.properties;
+        assert!(matches!(
+            properties["plain"],
+            BorrowedValue::VString(Cow::Borrowed("x"))
+        ));
+        assert!(matches!(
+            &properties["escaped"],
+            BorrowedValue::VString(Cow::Owned(s)) if s == "a\"b"
+        ));
+    }
+
+    #[test]
+    fn test_into_owned_matches_parse_gram() {
+        let input = r#"{v: 1}
+            (a:L:M {s: 'x\ty', sym: on, t: date`2024-01-01`, m: 5kg, r: 1..3,
+                    list: [1, 2.5, "z"], map: {k: true}, f: ```
+fenced```})
+            [g | a, (b)]"#;
+        let owned: Vec<_> = parse_gram_borrowed(input)
+            .unwrap()
+            .into_iter()
+            .map(into_owned_pattern)
+            .collect();
+        assert_eq!(owned, crate::parse_gram(input).unwrap());
+    }
+
+    #[test]
+    fn test_path_nests_from_the_left() {
+        let patterns = parse_gram_borrowed("(a)<--(b)-[r]->(c)").unwrap();
+        let outer = &patterns[0];
+        assert_eq!(outer.value.identity, "r");
+        assert_eq!(outer.elements[1].value.identity, "c");
+        let inner = &outer.elements[0];
+        assert_eq!(inner.elements[0].value.identity, "b");
+        assert_eq!(inner.elements[1].value.identity, "a");
+    }
+}
@@ -0,0 +1,293 @@
+//! A parsed gram document with its header, patterns, positions and indexes
+//!
+//! [`GramDocument`] is the typed form of a gram file. A leading record is its
+//! [`header`](GramDocument::header), recognized by the parser rather than
+//! guessed from the shape of the first pattern, and the remaining top-level
+//! patterns are its [`patterns`](GramDocument::patterns). A document parsed
+//! from text keeps that text, the span of each pattern and its comments, and
+//! prints back exactly as it was written.
+//!
+//! ```rust
+//! use gram_codec::GramDocument;
+//!
+//! let input = "{version: 1}\n// people\n(alice:Person)-[:KNOWS]->(bob:Person)\n";
+//! let document = GramDocument::parse(input)?;
+//!
+//! assert!(document.header().is_some());
+//! assert_eq!(document.iter_nodes().count(), 2);
+//! assert_eq!(document.iter_relationships().count(), 1);
+//! assert!(document.get("bob").unwrap().value.labels.contains("Person"));
+//! assert_eq!(document.comments()[0].text, "// people");
+//! assert_eq!(document.to_gram()?, input);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::ast::AstPattern;
+use crate::borrowed::{build_top_level, into_owned_pattern};
+use crate::options::ParseOptions;
+use crate::parser::{tokenize, ParseError, TokenKind};
+use crate::serializer::{to_gram, to_gram_with_header};
+use crate::tolerant::PatternKind;
+use crate::{wrap_as_document, Record, SerializeError};
+use pattern_core::{Pattern, Subject, Symbol};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+/// A `//` comment in the text of a document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comment {
+    /// The text of the comment, from `//` to the end of the line
+    pub text: String,
+    /// Byte range of the comment in the text
+    pub span: Range<usize>,
+}
+
+/// A gram document: an optional header record and the top-level patterns
+#[derive(Debug, Clone)]
+pub struct GramDocument {
+    header: Option<Record>,
+    patterns: Vec<Pattern<Subject>>,
+    /// The text the document was parsed from
+    source: Option<String>,
+    /// Byte range of each top-level pattern in the source
+    spans: Vec<Range<usize>>,
+    comments: Vec<Comment>,
+    /// Path to the pattern that defines each identity: the index of its
+    /// top-level pattern, then of each element on the way down
+    identities: HashMap<String, Vec<usize>>,
+}
+
+impl GramDocument {
+    /// Creates a document from a header and patterns
+    pub fn new(header: Option<Record>, patterns: Vec<Pattern<Subject>>) -> Self {
+        let identities = index(&patterns);
+        Self {
+            header,
+            patterns,
+            source: None,
+            spans: Vec::new(),
+            comments: Vec::new(),
+            identities,
+        }
+    }
+
+    /// Parses a document
+    ///
+    /// Accepts and rejects exactly what [`parse_gram`](crate::parse_gram) does.
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        Self::parse_with_options(input, &ParseOptions::default())
+    }
+
+    /// Parses a document in the dialect and within the limits in `options`,
+    /// as [`parse_gram_with_options`](crate::parse_gram_with_options) does
+    ///
+    /// The `#` comments of the lenient dialect are not included in
+    /// [`comments`](Self::comments).
+    pub fn parse_with_options(input: &str, options: &ParseOptions) -> Result<Self, ParseError> {
+        let (parsed, top_level) = build_top_level(input, options)?;
+        let mut header = None;
+        let mut patterns = Vec::with_capacity(parsed.len());
+        let mut spans = Vec::with_capacity(parsed.len());
+        for (pattern, (kind, span)) in parsed.into_iter().zip(top_level) {
+            let pattern = into_owned_pattern(pattern);
+            if kind == PatternKind::Record {
+                header = Some(pattern.value.properties);
+            } else {
+                patterns.push(pattern);
+                spans.push(span);
+            }
+        }
+        let comments = tokenize(input)
+            .filter(|token| token.kind == TokenKind::Comment)
+            .map(|token| Comment {
+                text: token.text.to_string(),
+                span: token.span,
+            })
+            .collect();
+        let identities = index(&patterns);
+        Ok(Self {
+            header,
+            patterns,
+            source: Some(input.to_string()),
+            spans,
+            comments,
+            identities,
+        })
+    }
+
+    /// The leading record, if the document has one
+    pub fn header(&self) -> Option<&Record> {
+        self.header.as_ref()
+    }
+
+    /// The top-level patterns, without the header
+    pub fn patterns(&self) -> &[Pattern<Subject>] {
+        &self.patterns
+    }
+
+    /// The text the document was parsed from
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    /// The byte range of each of [`patterns`](Self::patterns) in the
+    /// [`source`](Self::source), if the document was parsed
+    pub fn spans(&self) -> Option<&[Range<usize>]> {
+        self.source.as_ref().map(|_| self.spans.as_slice())
+    }
+
+    /// The comments of a parsed document, in order
+    pub fn comments(&self) -> &[Comment] {
+        &self.comments
+    }
+
+    /// Every pattern without elements, at any depth
+    ///
+    /// A node referred to several times is yielded for each reference.
+    pub fn iter_nodes(&self) -> impl Iterator<Item = &Pattern<Subject>> {
+        self.iter_patterns()
+            .filter(|pattern| pattern.elements.is_empty())
+    }
+
+    /// Every pattern with two elements, at any depth
+    ///
+    /// These are the relationships of the document read as a graph, as
+    /// [`pattern_core::diff`] reads it: each arrow of a path like
+    /// `(a)-->(b)-->(c)` is one, as is a subject pattern like `[r | a, b]`.
+    pub fn iter_relationships(&self) -> impl Iterator<Item = &Pattern<Subject>> {
+        self.iter_patterns()
+            .filter(|pattern| pattern.elements.len() == 2)
+    }
+
+    /// The pattern that defines `identity`: the first with labels, properties
+    /// or elements, or else the first that refers to it
+    pub fn get(&self, identity: &str) -> Option<&Pattern<Subject>> {
+        let (first, rest) = self.identities.get(identity)?.split_first()?;
+        let pattern = &self.patterns[*first];
+        Some(
+            rest.iter()
+                .fold(pattern, |pattern, &i| &pattern.elements[i]),
+        )
+    }
+
+    /// Gram notation for the document
+    ///
+    /// A parsed document is returned exactly as it was written, with its
+    /// comments and formatting. Otherwise it is [`serialize`](Self::serialize)d.
+    pub fn to_gram(&self) -> Result<String, SerializeError> {
+        match &self.source {
+            Some(source) => Ok(source.clone()),
+            None => self.serialize(),
+        }
+    }
+
+    /// Serializes the header and patterns, as
+    /// [`to_gram_with_header`](crate::to_gram_with_header) does, regardless of
+    /// how the document was written
+    pub fn serialize(&self) -> Result<String, SerializeError> {
+        match &self.header {
+            Some(header) => to_gram_with_header(header.clone(), &self.patterns),
+            None => to_gram(&self.patterns),
+        }
+    }
+
+    /// The document as a single AST pattern, as
+    /// [`parse_to_ast`](crate::parse_to_ast) returns it
+    ///
+    /// The AST is built from [`to_patterns`](Self::to_patterns), so it does not
+    /// depend on whether the document was parsed with a header.
+    pub fn to_ast(&self) -> AstPattern {
+        let patterns = self.to_patterns();
+        if patterns.is_empty() {
+            return AstPattern::empty();
+        }
+        AstPattern::from_pattern(&wrap_as_document(patterns))
+    }
+
+    /// The top-level patterns as [`parse_gram`](crate::parse_gram) returns
+    /// them: the header, as a bare record, followed by the patterns
+    pub fn to_patterns(&self) -> Vec<Pattern<Subject>> {
+        let header = self.header.iter().map(|header| {
+            Pattern::point(Subject {
+                identity: Symbol(String::new()),
+                labels: HashSet::new(),
+                properties: header.clone(),
+            })
+        });
+        header.chain(self.patterns.iter().cloned()).collect()
+    }
+
+    /// The header and patterns
+    pub fn into_parts(self) -> (Option<Record>, Vec<Pattern<Subject>>) {
+        (self.header, self.patterns)
+    }
+
+    /// Every pattern at any depth, each before its elements
+    fn iter_patterns(&self) -> impl Iterator<Item = &Pattern<Subject>> {
+        let mut stack: Vec<&Pattern<Subject>> = self.patterns.iter().rev().collect();
+        std::iter::from_fn(move || {
+            let pattern = stack.pop()?;
+            stack.extend(pattern.elements.iter().rev());
+            Some(pattern)
+        })
+    }
+}
+
+/// Finds the pattern that defines each identity in `patterns`
+fn index(patterns: &[Pattern<Subject>]) -> HashMap<String, Vec<usize>> {
+    let mut identities = HashMap::new();
+    let mut defined = HashSet::new();
+    let mut stack: Vec<(&Pattern<Subject>, Vec<usize>)> = patterns
+        .iter()
+        .enumerate()
+        .rev()
+        .map(|(i, pattern)| (pattern, vec![i]))
+        .collect();
+    while let Some((pattern, path)) = stack.pop() {
+        for (i, element) in pattern.elements.iter().enumerate().rev() {
+            let mut element_path = path.clone();
+            element_path.push(i);
+            stack.push((element, element_path));
+        }
+        let identity = &pattern.value.identity.0;
+        if identity.is_empty() {
+            continue;
+        }
+        let defines = !pattern.value.labels.is_empty()
+            || !pattern.value.properties.is_empty()
+            || !pattern.elements.is_empty();
+        if defines && defined.insert(identity.clone()) {
+            identities.insert(identity.clone(), path);
+        } else {
+            identities.entry(identity.clone()).or_insert(path);
+        }
+    }
+    identities
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_is_recognized_by_the_parser() {
+        // A node with only properties looks like a record, but is not a header
+        let document = GramDocument::parse("({k: 1}) (a)").unwrap();
+        assert!(document.header().is_none());
+        assert_eq!(document.patterns().len(), 2);
+
+        let document = GramDocument::parse("{} (a)").unwrap();
+        assert_eq!(document.header(), Some(&Record::new()));
+        let spans = document.spans().unwrap();
+        assert_eq!((spans.len(), spans[0].start, spans[0].end), (1, 3, 6));
+    }
+
+    #[test]
+    fn test_get_prefers_the_definition() {
+        let document = GramDocument::parse("[g | a, (b)] (a:Person {age: 3})").unwrap();
+        assert!(document.get("a").unwrap().value.labels.contains("Person"));
+        assert_eq!(document.get("g").unwrap().elements.len(), 2);
+        assert!(document.get("b").unwrap().elements.is_empty());
+        assert!(document.get("c").is_none());
+    }
+}
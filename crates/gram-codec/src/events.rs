@@ -0,0 +1,568 @@
+//! Event-based parsing without building patterns
+//!
+//! [`parse_events`] reports the structure of a document as a stream of
+//! [`Event`]s in document order, for callers that only need counts or a
+//! projection of a large document and would rather not allocate a
+//! `Pattern<Subject>` tree for it. Names and strings are borrowed from the
+//! input unless they contain escapes.
+//!
+//! Each pattern is bracketed by [`Event::StartPattern`] and
+//! [`Event::EndPattern`]. Its [`Event::Subject`] and [`Event::Property`] events
+//! come first, then its elements. A relationship or path is a single pattern
+//! whose node elements are separated by [`Event::Arrow`] events; an arrow
+//! written with an edge subject, like `-[r:KNOWS]->`, is followed by that
+//! subject's events. Annotations come before the pattern they annotate.
+//!
+//! ```rust
+//! use gram_codec::{parse_events, Event, PatternKind};
+//!
+//! let mut nodes = 0;
+//! let mut arrows = 0;
+//! for event in parse_events("(a:Person)-[:KNOWS]->(b:Person) (c)") {
+//!     match event? {
+//!         Event::StartPattern { kind: PatternKind::Node, .. } => nodes += 1,
+//!         Event::Arrow { .. } => arrows += 1,
+//!         _ => {}
+//!     }
+//! }
+//! assert_eq!((nodes, arrows), (3, 1));
+//! # Ok::<(), gram_codec::ParseError>(())
+//! ```
+//!
+//! The events are checked against [`parse_gram`](crate::parse_gram): building
+//! patterns from them gives the same result, and a document that does not parse
+//! ends its stream with the same error.
+
+use crate::borrowed::BorrowedValue;
+use crate::options::{Dialect, ParseOptions};
+use crate::parser::combinators::{comma_list, space};
+use crate::parser::relationship::{
+    arrow_left_part, arrow_right_part, arrow_type, determine_arrow_type,
+};
+use crate::parser::value::{
+    borrowed_identifier as name, borrowed_value, lenient_value, unquoted_name,
+};
+use crate::parser::{ArrowType, Location, ParseError, ParseResult};
+use crate::tolerant::PatternKind;
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::char,
+    combinator::{cut, map, opt},
+    multi::{many1, separated_list1},
+    sequence::{delimited, preceded},
+};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::ops::Range;
+
+/// A parse event, with the byte range of the text it was parsed from
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<'a> {
+    /// The start of a pattern, spanning the whole pattern
+    StartPattern {
+        kind: PatternKind,
+        span: Range<usize>,
+    },
+    /// The end of the pattern most recently started
+    EndPattern {
+        kind: PatternKind,
+        span: Range<usize>,
+    },
+    /// The identity and labels of a subject, spanning the whole subject
+    /// including its record
+    Subject {
+        identity: Cow<'a, str>,
+        labels: Vec<Cow<'a, str>>,
+        span: Range<usize>,
+    },
+    /// A property of the last subject or leading record
+    Property {
+        key: Cow<'a, str>,
+        value: BorrowedValue<'a>,
+        span: Range<usize>,
+    },
+    /// An arrow between two elements of a relationship, in the direction
+    /// written
+    Arrow {
+        arrow: ArrowType,
+        span: Range<usize>,
+    },
+    /// An annotation of the next pattern: `@key` or `@key(value)`
+    Annotation {
+        key: Cow<'a, str>,
+        value: Option<Cow<'a, str>>,
+        span: Range<usize>,
+    },
+}
+
+/// Parse gram notation into a stream of events
+///
+/// Patterns are parsed one top-level pattern at a time, as the iterator
+/// advances. If the input does not parse, the events of the patterns before the
+/// error are followed by the error, as [`parse_gram`](crate::parse_gram)
+/// reports it, and then the stream ends.
+pub fn parse_events(input: &str) -> Events<'_> {
+    parse_events_with_options(input, &ParseOptions::default())
+}
+
+/// Parse gram notation into a stream of events, in the dialect of `options`,
+/// with a leading record as the header only if it allows one
+///
+/// The limits in `options` are not enforced here.
+pub(crate) fn parse_events_with_options<'a>(input: &'a str, options: &ParseOptions) -> Events<'a> {
+    Events {
+        cx: Context {
+            source: input,
+            events: RefCell::new(Vec::new()),
+            lenient: options.dialect == Dialect::Lenient,
+            header: options.header,
+        },
+        pending: Vec::new().into_iter(),
+        pos: 0,
+        started: false,
+        done: false,
+    }
+}
+
+/// Iterator over the events of a document, returned by [`parse_events`]
+pub struct Events<'a> {
+    cx: Context<'a>,
+    /// Events of the last top-level pattern parsed
+    pending: std::vec::IntoIter<Event<'a>>,
+    pos: usize,
+    started: bool,
+    done: bool,
+}
+
+impl<'a> Iterator for Events<'a> {
+    type Item = Result<Event<'a>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.pending.next() {
+                return Some(Ok(event));
+            }
+            if self.done {
+                return None;
+            }
+            match self.step() {
+                Ok(true) => {
+                    self.pending = self.cx.events.take().into_iter();
+                }
+                Ok(false) => {
+                    self.done = true;
+                }
+                Err(error) => {
+                    self.done = true;
+                    return Some(Err(error));
+                }
+            }
+        }
+    }
+}
+
+impl<'a> Events<'a> {
+    /// Parses the next top-level pattern, returning false at the end of the
+    /// input. Mirrors [`parser::gram_patterns`](crate::parser::gram_patterns)
+    /// followed by the check for leftover input in `parse_gram`.
+    fn step(&mut self) -> Result<bool, ParseError> {
+        let source = self.cx.source;
+        let lenient = self.cx.lenient;
+        let ws = |input| space(input, lenient);
+        let (input, _) = ws(&source[self.pos..]).unwrap_or((&source[self.pos..], ()));
+        if input.trim().is_empty() {
+            return Ok(false);
+        }
+
+        let cx = &self.cx;
+        let pattern =
+            |input| top_level_pattern(input, cx).map(|(rest, _)| ws(rest).unwrap_or((rest, ())).0);
+        let result = if std::mem::replace(&mut self.started, true) {
+            pattern(input)
+        } else {
+            match opt(|input| header(input, cx))(input) {
+                Ok((rest, Some(()))) => Ok(rest),
+                Ok((_, None)) => pattern(input),
+                Err(e) => Err(e),
+            }
+        };
+
+        match result {
+            Ok(rest) => {
+                self.pos = self.cx.offset(rest);
+                Ok(true)
+            }
+            Err(nom::Err::Error(_)) => Err(ParseError::UnexpectedInput {
+                location: Location::from_offset(source, self.cx.offset(input)),
+                snippet: input.chars().take(20).collect(),
+            }),
+            Err(e) => Err(ParseError::from_nom_error(source, e)),
+        }
+    }
+}
+
+/// The input and the events of the top-level pattern being parsed
+struct Context<'a> {
+    source: &'a str,
+    events: RefCell<Vec<Event<'a>>>,
+    /// Whether to parse the lenient dialect
+    lenient: bool,
+    /// Whether a leading record is the header, rather than a node
+    header: bool,
+}
+
+impl<'a> Context<'a> {
+    fn offset(&self, input: &str) -> usize {
+        self.source.len() - input.len()
+    }
+
+    /// Adds an event, returning its index
+    fn emit(&self, event: Event<'a>) -> usize {
+        let mut events = self.events.borrow_mut();
+        events.push(event);
+        events.len() - 1
+    }
+
+    /// Sets the end of the span of the event at `index`
+    fn extend(&self, index: usize, end: usize) {
+        match &mut self.events.borrow_mut()[index] {
+            Event::StartPattern { span, .. }
+            | Event::EndPattern { span, .. }
+            | Event::Subject { span, .. }
+            | Event::Property { span, .. }
+            | Event::Arrow { span, .. }
+            | Event::Annotation { span, .. } => span.end = end,
+        }
+    }
+
+    /// Runs `parser` between the start and end events of a pattern of `kind`,
+    /// discarding its events if it fails
+    fn pattern<O>(
+        &self,
+        kind: PatternKind,
+        input: &'a str,
+        parser: impl FnOnce(&'a str) -> ParseResult<'a, O>,
+    ) -> ParseResult<'a, O> {
+        let start = self.offset(input);
+        self.attempt(input, |input| {
+            let index = self.emit(Event::StartPattern {
+                kind,
+                span: start..start,
+            });
+            let (rest, output) = parser(input)?;
+            let end = self.offset(rest);
+            self.extend(index, end);
+            self.emit(Event::EndPattern {
+                kind,
+                span: start..end,
+            });
+            Ok((rest, output))
+        })
+    }
+
+    /// Runs `parser`, discarding its events if it fails, as nom backtracks
+    fn attempt<O>(
+        &self,
+        input: &'a str,
+        parser: impl FnOnce(&'a str) -> ParseResult<'a, O>,
+    ) -> ParseResult<'a, O> {
+        let len = self.events.borrow().len();
+        let result = parser(input);
+        if result.is_err() {
+            self.events.borrow_mut().truncate(len);
+        }
+        result
+    }
+}
+
+// The parsers below mirror those of the `parser` module combinator for
+// combinator, so that they accept the same input and fail in the same way.
+
+/// Mirrors [`parser::top_level_pattern`](crate::parser::top_level_pattern)
+fn top_level_pattern<'a>(input: &'a str, cx: &Context<'a>) -> ParseResult<'a, ()> {
+    alt((
+        |input| annotated_pattern(input, cx),
+        |input| subject_pattern(input, cx),
+        |input| path_pattern(input, cx),
+        |input| node(input, cx),
+    ))(input)
+}
+
+/// Mirrors [`parser::gram_pattern`](crate::parser::gram_pattern)
+fn gram_pattern<'a>(input: &'a str, cx: &Context<'a>) -> ParseResult<'a, ()> {
+    let ws = |input: &'a str| space(input, cx.lenient);
+    delimited(ws, |input| top_level_pattern(input, cx), ws)(input)
+}
+
+/// A leading record, reported as a pattern of kind [`PatternKind::Record`], or
+/// as an anonymous node with its properties if it is not the header
+fn header<'a>(input: &'a str, cx: &Context<'a>) -> ParseResult<'a, ()> {
+    if cx.header {
+        return cx.pattern(PatternKind::Record, input, |input| record(input, cx));
+    }
+    cx.pattern(PatternKind::Node, input, |input| {
+        let start = cx.offset(input);
+        cx.emit(Event::Subject {
+            identity: Cow::Borrowed(""),
+            labels: Vec::new(),
+            span: start..start,
+        });
+        record(input, cx)
+    })
+}
+
+fn annotated_pattern<'a>(input: &'a str, cx: &Context<'a>) -> ParseResult<'a, ()> {
+    let ws = |input: &'a str| space(input, cx.lenient);
+    cx.attempt(input, |input| {
+        let (input, _) = delimited(ws, |input| annotation(input, cx), ws)(input)?;
+        gram_pattern(input, cx)
+    })
+}
+
+/// Mirrors [`parser::annotation::annotation`](crate::parser::annotation::annotation)
+fn annotation<'a>(input: &'a str, cx: &Context<'a>) -> ParseResult<'a, ()> {
+    let ws = |input: &'a str| space(input, cx.lenient);
+    let start = cx.offset(input);
+    let (rest, (key, value)) = preceded(
+        char('@'),
+        nom::sequence::pair(
+            name,
+            opt(delimited(
+                char('('),
+                delimited(ws, name, ws),
+                cut(char(')')),
+            )),
+        ),
+    )(input)?;
+    cx.emit(Event::Annotation {
+        key,
+        value,
+        span: start..cx.offset(rest),
+    });
+    Ok((rest, ()))
+}
+
+fn subject_pattern<'a>(input: &'a str, cx: &Context<'a>) -> ParseResult<'a, ()> {
+    let ws = |input: &'a str| space(input, cx.lenient);
+    let elements = |input| {
+        cx.attempt(input, |input| {
+            let (input, _) = subject(input, cx)?;
+            let (input, _) = delimited(ws, char('|'), ws)(input)?;
+            comma_list(cx.lenient, |input| subject_element(input, cx))(input)
+        })
+    };
+    let empty = |input: &'a str| {
+        let at = cx.offset(input);
+        cx.emit(Event::Subject {
+            identity: Cow::Borrowed(""),
+            labels: Vec::new(),
+            span: at..at,
+        });
+        Ok((input, Vec::new()))
+    };
+
+    cx.pattern(PatternKind::SubjectPattern, input, |input| {
+        delimited(
+            char('['),
+            delimited(
+                ws,
+                alt((
+                    elements,
+                    map(|input| subject(input, cx), |_| Vec::new()),
+                    empty,
+                )),
+                ws,
+            ),
+            cut(char(']')),
+        )(input)
+        .map(|(rest, _)| (rest, ()))
+    })
+}
+
+fn subject_element<'a>(input: &'a str, cx: &Context<'a>) -> ParseResult<'a, ()> {
+    alt((
+        |input| gram_pattern(input, cx),
+        |input| pattern_reference(input, cx),
+    ))(input)
+}
+
+fn pattern_reference<'a>(input: &'a str, cx: &Context<'a>) -> ParseResult<'a, ()> {
+    cx.pattern(PatternKind::Reference, input, |input| {
+        let start = cx.offset(input);
+        let (rest, identity) = unquoted_name(input)?;
+        cx.emit(Event::Subject {
+            identity: Cow::Borrowed(identity),
+            labels: Vec::new(),
+            span: start..cx.offset(rest),
+        });
+        Ok((rest, ()))
+    })
+}
+
+/// Mirrors [`parser::relationship::path_pattern`](crate::parser::relationship::path_pattern)
+fn path_pattern<'a>(input: &'a str, cx: &Context<'a>) -> ParseResult<'a, ()> {
+    cx.pattern(PatternKind::Relationship, input, |input| {
+        let (input, _) = node(input, cx)?;
+        let (input, _) = many1(|input| arrow_segment(input, cx))(input)?;
+        Ok((input, ()))
+    })
+}
+
+fn arrow_segment<'a>(input: &'a str, cx: &Context<'a>) -> ParseResult<'a, ()> {
+    alt((
+        |input| arrow_segment_with_edge(input, cx),
+        |input| arrow_segment_simple(input, cx),
+    ))(input)
+}
+
+fn arrow_segment_simple<'a>(input: &'a str, cx: &Context<'a>) -> ParseResult<'a, ()> {
+    let ws = |input: &'a str| space(input, cx.lenient);
+    cx.attempt(input, |input| {
+        let (input, _) = ws(input)?;
+        let start = cx.offset(input);
+        let (input, arrow) = arrow_type(input)?;
+        cx.emit(Event::Arrow {
+            arrow,
+            span: start..cx.offset(input),
+        });
+        let (input, _) = ws(input)?;
+        node(input, cx)
+    })
+}
+
+fn arrow_segment_with_edge<'a>(input: &'a str, cx: &Context<'a>) -> ParseResult<'a, ()> {
+    let ws = |input: &'a str| space(input, cx.lenient);
+    cx.attempt(input, |input| {
+        let (input, _) = ws(input)?;
+        let start = cx.offset(input);
+        let (input, left) = arrow_left_part(input)?;
+        // The arrow type is only known once its right part is parsed
+        let index = cx.emit(Event::Arrow {
+            arrow: ArrowType::Undirected,
+            span: start..start,
+        });
+        let (input, _) = delimited(
+            char('['),
+            delimited(ws, |input| subject(input, cx), ws),
+            char(']'),
+        )(input)?;
+        let (input, right) = arrow_right_part(input)?;
+        let end = cx.offset(input);
+        if let Event::Arrow { arrow, span } = &mut cx.events.borrow_mut()[index] {
+            *arrow = determine_arrow_type(left, right);
+            span.end = end;
+        }
+        let (input, _) = ws(input)?;
+        node(input, cx)
+    })
+}
+
+/// Mirrors [`parser::node::node`](crate::parser::node::node)
+fn node<'a>(input: &'a str, cx: &Context<'a>) -> ParseResult<'a, ()> {
+    let ws = |input: &'a str| space(input, cx.lenient);
+    cx.pattern(PatternKind::Node, input, |input| {
+        delimited(
+            char('('),
+            delimited(ws, |input| subject(input, cx), ws),
+            cut(char(')')),
+        )(input)
+    })
+}
+
+/// Mirrors [`parser::subject::subject`](crate::parser::subject::subject)
+fn subject<'a>(input: &'a str, cx: &Context<'a>) -> ParseResult<'a, ()> {
+    let ws = |input: &'a str| space(input, cx.lenient);
+    cx.attempt(input, |input| {
+        let start = cx.offset(input);
+        let (input, identity) = opt(name)(input)?;
+        let (input, labels) = opt(preceded(char(':'), separated_list1(char(':'), name)))(input)?;
+        let index = cx.emit(Event::Subject {
+            identity: identity.unwrap_or_default(),
+            labels: labels.unwrap_or_default(),
+            span: start..cx.offset(input),
+        });
+        let (input, _) = opt(preceded(ws, |input| record(input, cx)))(input)?;
+        cx.extend(index, cx.offset(input));
+        Ok((input, ()))
+    })
+}
+
+/// Mirrors [`parser::subject::record`](crate::parser::subject::record)
+fn record<'a>(input: &'a str, cx: &Context<'a>) -> ParseResult<'a, ()> {
+    let ws = |input: &'a str| space(input, cx.lenient);
+    cx.attempt(input, |input| {
+        let (input, _) = delimited(
+            char('{'),
+            delimited(
+                ws,
+                comma_list(cx.lenient, |input| property_pair(input, cx)),
+                ws,
+            ),
+            cut(char('}')),
+        )(input)?;
+        Ok((input, ()))
+    })
+}
+
+fn property_pair<'a>(input: &'a str, cx: &Context<'a>) -> ParseResult<'a, ()> {
+    let ws = |input: &'a str| space(input, cx.lenient);
+    let (input, _) = ws(input)?;
+    let start = cx.offset(input);
+    let (input, key) = name(input)?;
+    let (input, _) = ws(input)?;
+    let (input, _) = alt((tag("::"), tag(":")))(input)?;
+    let (input, _) = ws(input)?;
+    let (rest, value) = if cx.lenient {
+        lenient_value(input)?
+    } else {
+        borrowed_value(input)?
+    };
+    let end = cx.offset(rest);
+    let (rest, _) = ws(rest)?;
+    cx.emit(Event::Property {
+        key,
+        value,
+        span: start..end,
+    });
+    Ok((rest, ()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(input: &str) -> Vec<PatternKind> {
+        parse_events(input)
+            .filter_map(|event| match event.unwrap() {
+                Event::StartPattern { kind, .. } => Some(kind),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_failed_alternatives_leave_no_events() {
+        // `[a]` is first tried as `[a | ...]`, and `(a)` as the start of a path
+        assert_eq!(
+            kinds("[a] (a)"),
+            vec![PatternKind::SubjectPattern, PatternKind::Node]
+        );
+    }
+
+    #[test]
+    fn test_leading_record_only_at_start() {
+        assert_eq!(
+            kinds("{k: 1} (a)"),
+            vec![PatternKind::Record, PatternKind::Node]
+        );
+        assert!(parse_events("(a) {k: 1}").any(|event| event.is_err()));
+    }
+
+    #[test]
+    fn test_stream_ends_after_error() {
+        let mut events = parse_events("(a");
+        assert!(events.next().unwrap().is_err());
+        assert!(events.next().is_none());
+    }
+}
@@ -0,0 +1,333 @@
+//! Incremental reparsing for editors
+//!
+//! A [`ParseSession`] owns the text of a document together with its parsed
+//! top-level patterns and their byte spans. Each [`Edit`] reparses only the
+//! patterns whose text (or the whitespace after it) the edit touches, and reuses
+//! the rest once parsing reaches the start of an unchanged pattern again.
+//!
+//! The result always equals what [`parse_gram`](crate::parse_gram) returns for
+//! the current text:
+//!
+//! ```rust
+//! use gram_codec::{Edit, ParseSession};
+//!
+//! let mut session = ParseSession::new("(a) (b) (c)");
+//! let reparse = session.edit(Edit::new(5..6, "x")).unwrap();
+//!
+//! // Only the middle pattern was replaced
+//! assert_eq!(reparse.removed, 1..2);
+//! assert_eq!(reparse.inserted, 1..2);
+//! assert_eq!(session.patterns().unwrap()[1].value.identity.0, "x");
+//! assert_eq!(session.spans()[1], 4..7);
+//! ```
+
+use crate::parser::{self, combinators::ws, Location, ParseError};
+use pattern_core::{Pattern, Subject, Symbol};
+use std::collections::HashSet;
+use std::ops::Range;
+
+/// A replacement of a byte range of the text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit {
+    /// Bytes of the current text to replace
+    pub range: Range<usize>,
+    /// Text to put in their place
+    pub text: String,
+}
+
+impl Edit {
+    /// Replaces `range` with `text`.
+    pub fn new(range: Range<usize>, text: impl Into<String>) -> Self {
+        Edit {
+            range,
+            text: text.into(),
+        }
+    }
+
+    /// Inserts `text` at `offset`.
+    pub fn insert(offset: usize, text: impl Into<String>) -> Self {
+        Edit::new(offset..offset, text)
+    }
+
+    /// Deletes `range`.
+    pub fn delete(range: Range<usize>) -> Self {
+        Edit::new(range, String::new())
+    }
+}
+
+/// The top-level patterns an edit replaced.
+///
+/// Patterns before `removed.start` and after `removed.end` were reused; their
+/// values are unchanged, although the spans of those after the edit moved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reparse {
+    /// Indices, before the edit, of the patterns that were replaced
+    pub removed: Range<usize>,
+    /// Indices, after the edit, of the patterns that replaced them
+    pub inserted: Range<usize>,
+}
+
+impl Reparse {
+    /// Whether no pattern was replaced.
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty() && self.inserted.is_empty()
+    }
+}
+
+/// A document that is reparsed incrementally as it is edited.
+///
+/// While the text does not parse, [`patterns`](Self::patterns) returns the
+/// error, and the patterns of the last text that parsed are kept so that the
+/// next successful edit reports its changes relative to them.
+#[derive(Debug)]
+pub struct ParseSession {
+    text: String,
+    patterns: Vec<Pattern<Subject>>,
+    spans: Vec<Range<usize>>,
+    /// Whether the first pattern is a leading record
+    header: bool,
+    /// Bytes changed since the patterns were last brought up to date
+    dirty: Option<Range<usize>>,
+    /// Indices of the patterns whose text was partly deleted since then
+    stale: Range<usize>,
+    error: Option<ParseError>,
+}
+
+impl ParseSession {
+    /// Parses `text` in full.
+    pub fn new(text: impl Into<String>) -> Self {
+        let text = text.into();
+        let len = text.len();
+        let mut session = ParseSession {
+            text,
+            patterns: Vec::new(),
+            spans: Vec::new(),
+            header: false,
+            dirty: None,
+            stale: 0..0,
+            error: None,
+        };
+        let _ = session.reparse(0..len);
+        session
+    }
+
+    /// The current text.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The top-level patterns of the current text, as
+    /// [`parse_gram`](crate::parse_gram) returns them, or why it does not parse.
+    pub fn patterns(&self) -> Result<&[Pattern<Subject>], &ParseError> {
+        match &self.error {
+            Some(error) => Err(error),
+            None => Ok(&self.patterns),
+        }
+    }
+
+    /// The byte range of each top-level pattern, without surrounding whitespace.
+    ///
+    /// Only meaningful while the text parses.
+    pub fn spans(&self) -> &[Range<usize>] {
+        &self.spans
+    }
+
+    /// Applies `edit` and reparses the patterns it affects.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range of the edit is out of bounds or does not lie on
+    /// `char` boundaries, as [`String::replace_range`] does.
+    pub fn edit(&mut self, edit: Edit) -> Result<Reparse, ParseError> {
+        let Range { start, end } = edit.range;
+        assert!(
+            start <= end && end <= self.text.len(),
+            "edit range {}..{} out of bounds of text of length {}",
+            start,
+            end,
+            self.text.len()
+        );
+        self.text.replace_range(start..end, &edit.text);
+
+        let inserted_end = start + edit.text.len();
+        let shift = |offset: usize| offset - end + inserted_end;
+        // Patterns starting in deleted text are stale and never reused
+        let map_start = |offset: usize| match offset {
+            o if o < start => o,
+            o if o >= end => shift(o),
+            _ => start,
+        };
+        let map_end = |offset: usize| match offset {
+            o if o <= start => o,
+            o if o >= end => shift(o),
+            _ => start,
+        };
+        let deleted = self.spans.partition_point(|span| span.start < start)
+            ..self.spans.partition_point(|span| span.start < end);
+        if !deleted.is_empty() {
+            self.stale = if self.stale.is_empty() {
+                deleted
+            } else {
+                self.stale.start.min(deleted.start)..self.stale.end.max(deleted.end)
+            };
+        }
+        for span in &mut self.spans {
+            *span = map_start(span.start)..map_end(span.end);
+        }
+
+        let mut region = start..inserted_end;
+        if let Some(dirty) = self.dirty.take() {
+            region = map_end(dirty.start).min(region.start)..map_end(dirty.end).max(region.end);
+        }
+
+        self.reparse(region)
+    }
+
+    /// Reparses from the pattern before `region` until parsing reaches the
+    /// start of an unchanged pattern after it, or the end of the text.
+    fn reparse(&mut self, region: Range<usize>) -> Result<Reparse, ParseError> {
+        // A pattern may extend up to the start of the next one, so the last
+        // pattern starting before the region is the first that can change.
+        let first = self
+            .spans
+            .partition_point(|span| span.start < region.start)
+            .saturating_sub(1);
+        let restart = if first == 0 {
+            0
+        } else {
+            self.spans[first].start
+        };
+
+        let mut patterns = Vec::new();
+        let mut spans = Vec::new();
+        let mut header = false;
+        let mut candidate = first;
+        let mut pos = restart;
+        let resumed = loop {
+            pos = self.skip_ws(pos);
+            if pos == self.text.len() {
+                break None;
+            }
+            let at_start = restart == 0 && patterns.is_empty();
+
+            if pos >= region.end {
+                while candidate < self.spans.len()
+                    && (self.spans[candidate].start < pos || self.stale.contains(&candidate))
+                {
+                    candidate += 1;
+                }
+                // A leading record can only be reused at the start of the document
+                let reusable = candidate > 0 || !self.header || at_start;
+                if candidate < self.spans.len() && self.spans[candidate].start == pos && reusable {
+                    break Some(candidate);
+                }
+            }
+
+            match self.parse_one(pos, at_start) {
+                Ok((pattern, end, is_header)) => {
+                    header |= is_header;
+                    patterns.push(pattern);
+                    spans.push(pos..end);
+                    pos = end;
+                }
+                Err(error) => {
+                    self.dirty = Some(region);
+                    self.error = Some(error.clone());
+                    return Err(error);
+                }
+            }
+        };
+
+        let removed_end = resumed.unwrap_or(self.spans.len());
+        let inserted = first..first + patterns.len();
+        if first == 0 && !patterns.is_empty() {
+            self.header = header;
+        } else if first == 0 && removed_end > 0 {
+            self.header = false;
+        }
+        self.patterns.splice(first..removed_end, patterns);
+        self.spans.splice(first..removed_end, spans);
+        self.dirty = None;
+        self.stale = 0..0;
+        self.error = None;
+
+        Ok(Reparse {
+            removed: first..removed_end,
+            inserted,
+        })
+    }
+
+    /// Parses the pattern at `pos`, returning it, the end of its text and
+    /// whether it is a leading record.
+    fn parse_one(
+        &self,
+        pos: usize,
+        at_start: bool,
+    ) -> Result<(Pattern<Subject>, usize, bool), ParseError> {
+        let input = &self.text[pos..];
+        let result = if at_start && input.starts_with('{') {
+            parser::subject::record(input).map(|(rest, properties)| {
+                let subject = Subject {
+                    identity: Symbol(String::new()),
+                    labels: HashSet::new(),
+                    properties,
+                };
+                (rest, (Pattern::point(subject), true))
+            })
+        } else {
+            parser::top_level_pattern(input).map(|(rest, pattern)| (rest, (pattern, false)))
+        };
+
+        match result {
+            Ok((rest, (pattern, is_header))) => {
+                let parsed = &input[..input.len() - rest.len()];
+                Ok((pattern, pos + parsed.trim_end().len(), is_header))
+            }
+            Err(nom::Err::Error(_)) => Err(ParseError::UnexpectedInput {
+                location: Location::from_offset(&self.text, pos),
+                snippet: input.chars().take(20).collect(),
+            }),
+            Err(e) => Err(ParseError::from_nom_error(&self.text, e)),
+        }
+    }
+
+    fn skip_ws(&self, pos: usize) -> usize {
+        let input = &self.text[pos..];
+        match ws(input) {
+            Ok((rest, _)) => self.text.len() - rest.len(),
+            Err(_) => pos,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_inside_pattern_replaces_only_it() {
+        let mut session = ParseSession::new("(a)\n(b)\n(c)\n");
+        let reparse = session.edit(Edit::new(5..6, "bb")).unwrap();
+        assert_eq!(reparse.removed, 1..2);
+        assert_eq!(reparse.inserted, 1..2);
+        assert_eq!(session.spans(), &[0..3, 4..8, 9..12]);
+    }
+
+    #[test]
+    fn test_whitespace_edit_reuses_everything() {
+        let mut session = ParseSession::new("(a) (b)");
+        let reparse = session.edit(Edit::insert(3, "   ")).unwrap();
+        // The pattern before the edit could have grown into a path, so it is reparsed
+        assert_eq!(reparse.removed, 0..1);
+        assert_eq!(reparse.inserted, 0..1);
+        assert_eq!(session.spans(), &[0..3, 7..10]);
+    }
+
+    #[test]
+    fn test_header_is_not_reused_after_inserted_pattern() {
+        let mut session = ParseSession::new("{v: 1} (a)");
+        assert!(session.edit(Edit::insert(0, "(x) ")).is_err());
+        assert!(session.edit(Edit::delete(0..4)).is_ok());
+        assert_eq!(session.patterns().unwrap().len(), 2);
+    }
+}
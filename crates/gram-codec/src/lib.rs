@@ -4,7 +4,16 @@
 //!
 //! This crate provides:
 //! - **Parsing**: Transform Gram notation text into Pattern structures
+//! - **Documents**: Parse a file into a [`GramDocument`] with its header, patterns, comments and indexes
 //! - **Serialization**: Transform Pattern structures into valid Gram notation
+//! - **Serde**: Serialize and deserialize arbitrary Rust types via [`serde_gram`]
+//! - **Queries**: Match gram-notation patterns against documents via [`query`]
+//! - **Tokenizing**: Split text into tokens for highlighters and linters via [`tokenize`]
+//! - **Incremental parsing**: Reparse only what an edit touches via [`ParseSession`]
+//! - **Tolerant parsing**: Always get a tree, with error and missing nodes, via [`parse_gram_tolerant`]
+//! - **Event parsing**: Stream the structure of large documents without building patterns via [`parse_events`]
+//! - **Zero-copy parsing**: Borrow names and strings from the input via [`parse_gram_borrowed`]
+//! - **Parse options**: Choose a strict or lenient dialect, and cap depth, counts and sizes for untrusted input, via [`parse_gram_with_options`]
 //!
 //! ## Features
 //!
@@ -38,8 +47,16 @@
 
 // Module declarations
 pub mod ast;
+mod borrowed;
+mod document;
 mod error;
+mod events;
+mod incremental;
+mod options;
+pub mod query;
+pub mod serde_gram;
 mod serializer;
+mod tolerant;
 mod value;
 
 // TODO: Temporarily commented out during migration to nom parser
@@ -58,11 +75,18 @@ mod wasm;
 mod python;
 
 // Public API exports
-pub use ast::{AstPattern, AstSubject};
+pub use ast::{AstError, AstPattern, AstSubject};
+pub use borrowed::{into_owned_pattern, parse_gram_borrowed, BorrowedSubject, BorrowedValue};
+pub use document::{Comment, GramDocument};
 pub use error::{Location, SerializeError};
+pub use events::{parse_events, Event, Events};
+pub use incremental::{Edit, ParseSession, Reparse};
+pub use options::{parse_gram_with_options, Dialect, ParseOptions};
 // Use the new nom-based ParseError from the parser module
 pub use parser::ParseError;
+pub use parser::{tokenize, ArrowType, Token, TokenKind};
 pub use serializer::{to_gram, to_gram_pattern, to_gram_with_header};
+pub use tolerant::{parse_gram_tolerant, PartialPattern, PatternKind, SyntaxNode, SyntaxTree};
 pub use value::Value;
 
 // Re-export Pattern and Subject from pattern-core for convenience
@@ -113,6 +137,10 @@ pub fn parse_gram(input: &str) -> Result<Vec<Pattern<Subject>>, ParseError> {
 /// If the first element is a bare record (identity and labels are empty, and it has no elements),
 /// it is returned separately as the header.
 ///
+/// Unlike [`GramDocument::header`], this goes by the shape of the first pattern:
+/// an empty `{}` stays one of the patterns, and a leading `({k: 1})` is taken
+/// as the header.
+///
 /// # Arguments
 ///
 /// * `input` - Gram notation text to parse
@@ -164,21 +192,11 @@ pub fn parse_gram_with_header(
 /// * `Ok(AstPattern)` - The parsed pattern as AST
 /// * `Err(ParseError)` - If parsing fails
 pub fn parse_to_ast(input: &str) -> Result<AstPattern, ParseError> {
-    let patterns = parse_gram(input)?;
-
-    if patterns.is_empty() {
-        return Ok(AstPattern::empty());
-    }
-
-    // Maintain "single file-level pattern" contract for AST
-    // If there's exactly one pattern and it's not a bare record, return it.
-    // Otherwise, wrap everything in a file-level pattern.
-    let document_pattern = wrap_as_document(patterns);
-    Ok(AstPattern::from_pattern(&document_pattern))
+    GramDocument::parse(input).map(|document| document.to_ast())
 }
 
 /// Internal helper to wrap multiple patterns into a single document-level pattern.
-fn wrap_as_document(mut patterns: Vec<Pattern<Subject>>) -> Pattern<Subject> {
+pub(crate) fn wrap_as_document(mut patterns: Vec<Pattern<Subject>>) -> Pattern<Subject> {
     if patterns.len() == 1 {
         let first = &patterns[0];
         // If it's a "real" pattern (has identity or labels or elements), return it.
@@ -215,9 +233,44 @@ fn wrap_as_document(mut patterns: Vec<Pattern<Subject>>) -> Pattern<Subject> {
     Pattern::pattern(subject, patterns)
 }
 
+/// Convert an AST produced by [`parse_to_ast`] back into top-level patterns.
+///
+/// This undoes the file-level wrapping of [`parse_to_ast`]: an anonymous, unlabeled
+/// root is unwrapped into its elements, with its properties (if any) restored as a
+/// leading bare record. Any other root is returned as the single top-level pattern.
+///
+/// A document consisting of one anonymous, unlabeled subject pattern (such as
+/// `[ | (a), (b)]`) has the same AST as the document `(a) (b)`, and is returned in
+/// the latter form.
+///
+/// # Examples
+///
+/// ```rust
+/// use gram_codec::{parse_gram, parse_to_ast, patterns_from_ast};
+///
+/// let input = "{version: 1} (a)-->(b) (c)";
+/// let ast = parse_to_ast(input)?;
+/// assert_eq!(patterns_from_ast(&ast)?, parse_gram(input)?);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn patterns_from_ast(ast: &AstPattern) -> Result<Vec<Pattern<Subject>>, AstError> {
+    let root = ast.to_pattern()?;
+    if !root.value.identity.0.is_empty() || !root.value.labels.is_empty() {
+        return Ok(vec![root]);
+    }
+
+    let Pattern { value, elements } = root;
+    let mut patterns = Vec::with_capacity(elements.len() + 1);
+    if !value.properties.is_empty() {
+        patterns.push(Pattern::point(value));
+    }
+    patterns.extend(elements);
+    Ok(patterns)
+}
+
 /// Validate gram notation syntax without constructing patterns.
 pub fn validate_gram(input: &str) -> Result<(), ParseError> {
-    parse_gram(input).map(|_| ())
+    parse_events(input).try_for_each(|event| event.map(drop))
 }
 
 /// Parse a single Gram pattern from text.
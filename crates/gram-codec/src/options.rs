@@ -0,0 +1,409 @@
+//! Options for parsing: dialects, headers and resource limits
+//!
+//! [`ParseOptions`] selects a [`Dialect`]: the strict dialect rejects
+//! duplicate keys and labels and conflicting definitions of a subject or edge,
+//! and the lenient dialect accepts trailing commas, `#` comments and unquoted
+//! strings.
+//! It also says whether a leading record is the document's header.
+//!
+//! [`parse_gram`](crate::parse_gram) accepts any document it can hold in
+//! memory, and its recursive descent can overflow the stack on deeply nested
+//! input. [`ParseOptions`] also caps the input size, nesting depth, pattern
+//! count, property count and string length; [`parse_gram_with_options`] stops
+//! at the first limit exceeded with a dedicated [`ParseError`] variant.
+//!
+//! The input size and nesting depth are checked before anything is parsed.
+//! The other limits are checked as patterns are built, and each top-level
+//! pattern is parsed whole before it is built, so they bound the document
+//! but not the work done on its first oversized pattern; set
+//! [`max_input_size`](ParseOptions::max_input_size) to bound that too.
+//!
+//! ```rust
+//! use gram_codec::{parse_gram_with_options, ParseError, ParseOptions};
+//!
+//! let options = ParseOptions {
+//!     max_depth: Some(2),
+//!     ..ParseOptions::default()
+//! };
+//! assert!(parse_gram_with_options("[g | (a)]", &options).is_ok());
+//! assert!(matches!(
+//!     parse_gram_with_options("[g | [h | (a)]]", &options),
+//!     Err(ParseError::NestingTooDeep { limit: 2, .. })
+//! ));
+//! ```
+
+use crate::borrowed::{build_patterns, into_owned_pattern, BorrowedValue};
+use crate::parser::value::string_value;
+use crate::parser::{tokenize, Location, ParseError, TokenKind};
+use pattern_core::{Pattern, Subject};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+/// The variant of gram notation to parse
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Dialect {
+    /// Gram notation as [`parse_gram`](crate::parse_gram) parses it
+    #[default]
+    Standard,
+    /// Standard gram notation, rejecting a record or map with a key twice, a
+    /// subject with a label twice, a subject defined again with different
+    /// labels or properties, and an edge written again between other nodes
+    Strict,
+    /// Standard gram notation, also accepting trailing commas in records,
+    /// maps, arrays and subject patterns, `# text` comments, and unquoted
+    /// string values such as `{city: New York}`
+    Lenient,
+}
+
+/// Options for [`parse_gram_with_options`]
+///
+/// The default options parse exactly what [`parse_gram`](crate::parse_gram)
+/// does: the standard dialect, a leading record as the header, and no limits.
+/// Set the limits when parsing input from untrusted sources.
+///
+/// The options can be deserialized from a map of their field names, with
+/// missing fields taking their defaults, as the WASM bindings do.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ParseOptions {
+    /// The variant of gram notation to accept
+    pub dialect: Dialect,
+    /// Whether a record at the start of the document is its header. When
+    /// false, it is read as an anonymous node with those properties, one of
+    /// the document's patterns, so a [`GramDocument`](crate::GramDocument)
+    /// has no header. Either way it is returned as the first pattern by
+    /// [`parse_gram_with_options`], and a record anywhere else is an error.
+    pub header: bool,
+    /// Deepest nesting of brackets, braces and parentheses, counting each
+    /// annotation as a level around the pattern it annotates, and each arrow
+    /// of a path as a level around the segments before it
+    pub max_depth: Option<usize>,
+    /// Most patterns in the document, counting nested patterns, references
+    /// and a leading record
+    pub max_patterns: Option<usize>,
+    /// Most properties in a single record or map value
+    pub max_properties: Option<usize>,
+    /// Longest identity, label, key or string value, in bytes
+    pub max_string_length: Option<usize>,
+    /// Largest input, in bytes
+    pub max_input_size: Option<usize>,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            dialect: Dialect::Standard,
+            header: true,
+            max_depth: None,
+            max_patterns: None,
+            max_properties: None,
+            max_string_length: None,
+            max_input_size: None,
+        }
+    }
+}
+
+/// Parse gram notation into patterns, in the dialect and within the limits in
+/// `options`
+///
+/// With the default options this returns what
+/// [`parse_gram`](crate::parse_gram) returns. The first limit the document
+/// exceeds is reported as [`ParseError::InputTooLarge`],
+/// [`ParseError::NestingTooDeep`], [`ParseError::TooManyPatterns`],
+/// [`ParseError::TooManyProperties`] or [`ParseError::StringTooLong`]. The
+/// strict dialect reports [`ParseError::DuplicateKey`],
+/// [`ParseError::DuplicateLabel`] and [`ParseError::ConflictingDefinition`].
+///
+/// ```rust
+/// use gram_codec::{parse_gram_with_options, Dialect, ParseError, ParseOptions};
+///
+/// let lenient = ParseOptions {
+///     dialect: Dialect::Lenient,
+///     ..ParseOptions::default()
+/// };
+/// let patterns = parse_gram_with_options("(a {city: New York,}) # note", &lenient)?;
+/// assert_eq!(patterns.len(), 1);
+///
+/// let strict = ParseOptions {
+///     dialect: Dialect::Strict,
+///     ..ParseOptions::default()
+/// };
+/// assert!(matches!(
+///     parse_gram_with_options("(a:Person) (a:Robot)", &strict),
+///     Err(ParseError::ConflictingDefinition { .. })
+/// ));
+/// # Ok::<(), ParseError>(())
+/// ```
+pub fn parse_gram_with_options(
+    input: &str,
+    options: &ParseOptions,
+) -> Result<Vec<Pattern<Subject>>, ParseError> {
+    let patterns = build_patterns(input, options)?;
+    Ok(patterns.into_iter().map(into_owned_pattern).collect())
+}
+
+impl ParseOptions {
+    /// Checks the limits that apply to the input as a whole, before any of it
+    /// is parsed
+    pub(crate) fn check_input(&self, input: &str) -> Result<(), ParseError> {
+        if let Some(limit) = self.max_input_size {
+            if input.len() > limit {
+                return Err(ParseError::InputTooLarge {
+                    size: input.len(),
+                    limit,
+                });
+            }
+        }
+        if let Some(limit) = self.max_depth {
+            match self.dialect {
+                Dialect::Lenient => check_depth(&blank_hash_comments(input), limit)?,
+                Dialect::Standard | Dialect::Strict => check_depth(input, limit)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the number of patterns started so far, the last at `offset`
+    pub(crate) fn check_patterns(
+        &self,
+        input: &str,
+        offset: usize,
+        count: usize,
+    ) -> Result<(), ParseError> {
+        match self.max_patterns {
+            Some(limit) if count > limit => Err(ParseError::TooManyPatterns {
+                location: Location::from_offset(input, offset),
+                limit,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the number of properties in the record that has one at `offset`
+    pub(crate) fn check_properties(
+        &self,
+        input: &str,
+        offset: usize,
+        count: usize,
+    ) -> Result<(), ParseError> {
+        match self.max_properties {
+            Some(limit) if count > limit => Err(ParseError::TooManyProperties {
+                location: Location::from_offset(input, offset),
+                limit,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the length of a name or string at `offset`
+    pub(crate) fn check_string(
+        &self,
+        input: &str,
+        offset: usize,
+        string: &str,
+    ) -> Result<(), ParseError> {
+        match self.max_string_length {
+            Some(limit) if string.len() > limit => Err(ParseError::StringTooLong {
+                location: Location::from_offset(input, offset),
+                limit,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the strings and maps in a property value at `offset`
+    pub(crate) fn check_value(
+        &self,
+        input: &str,
+        offset: usize,
+        value: &BorrowedValue,
+    ) -> Result<(), ParseError> {
+        match value {
+            BorrowedValue::VString(s) | BorrowedValue::VSymbol(s) => {
+                self.check_string(input, offset, s)
+            }
+            BorrowedValue::VTaggedString { tag, content } => {
+                self.check_string(input, offset, tag)?;
+                self.check_string(input, offset, content)
+            }
+            BorrowedValue::VMeasurement { unit, .. } => self.check_string(input, offset, unit),
+            BorrowedValue::VArray(values) => values
+                .iter()
+                .try_for_each(|value| self.check_value(input, offset, value)),
+            BorrowedValue::VMap(map) => {
+                self.check_properties(input, offset, map.len())?;
+                map.iter().try_for_each(|(key, value)| {
+                    self.check_string(input, offset, key)?;
+                    self.check_value(input, offset, value)
+                })
+            }
+            BorrowedValue::VInteger(_)
+            | BorrowedValue::VDecimal(_)
+            | BorrowedValue::VBoolean(_)
+            | BorrowedValue::VRange(_) => Ok(()),
+        }
+    }
+}
+
+/// Checks nesting depth with the tokenizer, which does not recurse, so that
+/// the recursive parser never sees input nested deeper than `limit`
+///
+/// An annotation adds a level that lasts until the pattern it annotates is
+/// closed, since each one is another recursion of the parser. Each arrow of a
+/// path adds a level that lasts until the path ends, since each segment nests
+/// the path built so far one level deeper.
+fn check_depth(input: &str, limit: usize) -> Result<(), ParseError> {
+    // The levels each open bracket adds, including its annotations
+    let mut open: Vec<usize> = Vec::new();
+    // The arrows of the path being read inside each open bracket, and at the
+    // top level first
+    let mut arrows: Vec<usize> = vec![0];
+    let mut depth = 0;
+    // Annotations waiting for the pattern they annotate
+    let mut annotations = 0;
+    // Whether the last token was an annotation's key, directly before its value
+    let mut after_key = false;
+    // Whether the last token other than trivia was part of an arrow, so that
+    // the node after it continues the path
+    let mut after_arrow = false;
+    for token in tokenize(input) {
+        let levels = match token.kind {
+            TokenKind::At => {
+                if !after_arrow {
+                    depth -= end_path(&mut arrows);
+                }
+                annotations += 1;
+                0
+            }
+            TokenKind::Arrow(_) | TokenKind::ArrowLeft(_) => {
+                *arrows.last_mut().expect("top-level arrows") += 1;
+                depth += 1;
+                0
+            }
+            TokenKind::Comma | TokenKind::Pipe => {
+                depth -= end_path(&mut arrows);
+                0
+            }
+            TokenKind::LeftParen if after_key => 1,
+            TokenKind::LeftParen | TokenKind::LeftBracket | TokenKind::LeftBrace => {
+                if !after_arrow {
+                    depth -= end_path(&mut arrows);
+                }
+                1 + std::mem::take(&mut annotations)
+            }
+            TokenKind::RightParen | TokenKind::RightBracket | TokenKind::RightBrace => {
+                if arrows.len() > 1 {
+                    depth -= arrows.pop().unwrap_or(0);
+                }
+                depth -= open.pop().unwrap_or(0);
+                0
+            }
+            _ => 0,
+        };
+        if levels > 0 {
+            open.push(levels);
+            arrows.push(0);
+            depth += levels;
+        }
+        if depth + annotations > limit {
+            return Err(ParseError::NestingTooDeep {
+                location: Location::from_offset(input, token.span.start),
+                limit,
+            });
+        }
+        after_key = annotations > 0
+            && matches!(token.kind, TokenKind::Identifier | TokenKind::String)
+            && !after_key;
+        if !token.kind.is_trivia() {
+            after_arrow = matches!(
+                token.kind,
+                TokenKind::Arrow(_) | TokenKind::ArrowLeft(_) | TokenKind::ArrowRight(_)
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Ends the path being read at the innermost level, returning the levels its
+/// arrows added
+fn end_path(arrows: &mut [usize]) -> usize {
+    std::mem::take(arrows.last_mut().expect("top-level arrows"))
+}
+
+/// Replaces the `#` comments of the lenient dialect, which the tokenizer does
+/// not know, with spaces, so that the brackets in them are not counted
+///
+/// Strings and `//` comments are copied as they are, and every byte stays at
+/// its offset so that errors point into `input`.
+fn blank_hash_comments(input: &str) -> Cow<'_, str> {
+    if !input.contains('#') {
+        return Cow::Borrowed(input);
+    }
+    let mut blanked = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(c) = rest.chars().next() {
+        let line = rest.find('\n').unwrap_or(rest.len());
+        let len = match c {
+            '#' => {
+                blanked.extend(std::iter::repeat(' ').take(line));
+                rest = &rest[line..];
+                continue;
+            }
+            '/' if rest.starts_with("//") => line,
+            _ => match string_value(rest) {
+                Ok((after, _)) => rest.len() - after.len(),
+                Err(_) => c.len_utf8(),
+            },
+        };
+        blanked.push_str(&rest[..len]);
+        rest = &rest[len..];
+    }
+    Cow::Owned(blanked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn depth_error(input: &str, limit: usize) -> Option<usize> {
+        match check_depth(input, limit) {
+            Err(ParseError::NestingTooDeep { location, .. }) => Some(location.offset),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_depth_counts_brackets_and_annotations() {
+        assert_eq!(depth_error("[g | (a {k: [1]})]", 4), None);
+        assert_eq!(depth_error("[g | (a {k: [1]})]", 3), Some(12));
+        assert_eq!(depth_error("@a(1) @b (x)", 3), None);
+        assert_eq!(depth_error("@a(1) @b (x)", 2), Some(9));
+        assert_eq!(depth_error("(a) (b) (c)", 1), None);
+    }
+
+    #[test]
+    fn test_depth_counts_each_arrow_of_a_path() {
+        assert_eq!(depth_error("(a)-->(b)<-[r]-(c)", 3), None);
+        assert_eq!(depth_error("(a)-->(b)<-[r]-(c)", 2), Some(11));
+        // A new pattern, element or subject pattern starts a new path
+        assert_eq!(depth_error("(a)-->(b) (c)-->(d), (e)-->(f)", 2), None);
+        assert_eq!(depth_error("[g | (a)-->(b), (c)-->(d)]", 3), None);
+        assert_eq!(depth_error("[g | (a)-->(b)-->(c)] (d)-->(e)", 3), Some(17));
+        assert_eq!(depth_error("[g | (a)-->(b)] (c)-->(d)-->(e)", 3), None);
+    }
+
+    #[test]
+    fn test_depth_ignores_brackets_in_strings_and_comments() {
+        assert_eq!(depth_error("(a {s: \"[[[\"}) // ((((", 2), None);
+    }
+
+    #[test]
+    fn test_blank_hash_comments() {
+        assert_eq!(blank_hash_comments("(a) # ((\n(b)"), "(a)     \n(b)");
+        assert_eq!(
+            blank_hash_comments("(a {s: \"#(\"}) // '#\n# \"\n((b))"),
+            "(a {s: \"#(\"}) // '#\n   \n((b))"
+        );
+    }
+}
@@ -4,9 +4,9 @@ use super::types::{Location, ParseResult, Span};
 use nom::{
     branch::alt,
     bytes::complete::{tag, take_until},
-    character::complete::multispace1,
+    character::complete::{char, multispace1},
     combinator::{recognize, rest, value},
-    multi::many0,
+    multi::{many0, separated_list0},
     sequence::{delimited, pair},
     Parser,
 };
@@ -30,6 +30,62 @@ pub fn comment(input: &str) -> ParseResult<'_, ()> {
     )(input)
 }
 
+/// Consume whitespace and comments, including the `# text` comments of the
+/// lenient dialect
+pub fn lenient_ws(input: &str) -> ParseResult<'_, ()> {
+    value(
+        (),
+        many0(alt((value((), multispace1), comment, hash_comment))),
+    )(input)
+}
+
+/// Parse a lenient dialect comment: # text until newline
+pub fn hash_comment(input: &str) -> ParseResult<'_, ()> {
+    value(
+        (),
+        pair(
+            tag("#"),
+            alt((recognize(pair(take_until("\n"), tag("\n"))), rest)),
+        ),
+    )(input)
+}
+
+/// Consume whitespace and comments, with [`lenient_ws`] when `lenient`
+pub fn space(input: &str, lenient: bool) -> ParseResult<'_, ()> {
+    if lenient {
+        lenient_ws(input)
+    } else {
+        ws(input)
+    }
+}
+
+/// Parse a comma-separated list, allowing a trailing comma after the last
+/// item when `lenient`
+pub fn comma_list<'a, O, F>(
+    lenient: bool,
+    mut item: F,
+) -> impl FnMut(&'a str) -> ParseResult<'a, Vec<O>>
+where
+    F: Parser<&'a str, O, nom::error::VerboseError<&'a str>>,
+{
+    let comma = move |input: &'a str| {
+        delimited(
+            |input| space(input, lenient),
+            char(','),
+            |input| space(input, lenient),
+        )(input)
+    };
+    move |input: &'a str| {
+        let (rest, items) = separated_list0(comma, |input| item.parse(input))(input)?;
+        if lenient && !items.is_empty() {
+            if let Ok((rest, _)) = comma(rest) {
+                return Ok((rest, items));
+            }
+        }
+        Ok((rest, items))
+    }
+}
+
 /// Wrap a parser with optional leading/trailing whitespace
 #[allow(dead_code)]
 pub fn padded<'a, O, F>(parser: F) -> impl FnMut(&'a str) -> ParseResult<O>
@@ -4,7 +4,7 @@ use super::types::Location;
 use thiserror::Error;
 
 /// Errors that can occur during parsing
-#[derive(Debug, Error)]
+#[derive(Debug, Clone, PartialEq, Error)]
 pub enum ParseError {
     /// Syntax error with location and context
     #[error("Syntax error at {location}: expected {expected}, found '{found}'")]
@@ -31,6 +31,44 @@ pub enum ParseError {
     #[error("Unmatched {delimiter} at {location}")]
     UnmatchedDelimiter { location: Location, delimiter: char },
 
+    /// Input larger than [`ParseOptions::max_input_size`](crate::ParseOptions::max_input_size)
+    #[error("Input of {size} bytes exceeds the limit of {limit} bytes")]
+    InputTooLarge { size: usize, limit: usize },
+
+    /// Nesting deeper than [`ParseOptions::max_depth`](crate::ParseOptions::max_depth)
+    #[error("Nesting deeper than {limit} levels at {location}")]
+    NestingTooDeep { location: Location, limit: usize },
+
+    /// More patterns than [`ParseOptions::max_patterns`](crate::ParseOptions::max_patterns)
+    #[error("More than {limit} patterns at {location}")]
+    TooManyPatterns { location: Location, limit: usize },
+
+    /// A record or map with more properties than
+    /// [`ParseOptions::max_properties`](crate::ParseOptions::max_properties)
+    #[error("More than {limit} properties at {location}")]
+    TooManyProperties { location: Location, limit: usize },
+
+    /// A name or string longer than
+    /// [`ParseOptions::max_string_length`](crate::ParseOptions::max_string_length)
+    #[error("String longer than {limit} bytes at {location}")]
+    StringTooLong { location: Location, limit: usize },
+
+    /// A key given twice in one record, in the strict dialect
+    #[error("Duplicate key '{key}' at {location}")]
+    DuplicateKey { location: Location, key: String },
+
+    /// A label given twice on one subject, in the strict dialect
+    #[error("Duplicate label '{label}' at {location}")]
+    DuplicateLabel { location: Location, label: String },
+
+    /// A subject defined again with different labels or properties, in the
+    /// strict dialect
+    #[error("Conflicting definition of '{identity}' at {location}")]
+    ConflictingDefinition {
+        location: Location,
+        identity: String,
+    },
+
     /// Internal parser error (should not occur in production)
     #[error("Internal parser error: {message}")]
     Internal { message: String },
@@ -72,8 +110,58 @@ impl ParseError {
             ParseError::SyntaxError { location, .. }
             | ParseError::UnexpectedInput { location, .. }
             | ParseError::InvalidValue { location, .. }
-            | ParseError::UnmatchedDelimiter { location, .. } => Some(*location),
-            ParseError::Internal { .. } => None,
+            | ParseError::UnmatchedDelimiter { location, .. }
+            | ParseError::NestingTooDeep { location, .. }
+            | ParseError::TooManyPatterns { location, .. }
+            | ParseError::TooManyProperties { location, .. }
+            | ParseError::StringTooLong { location, .. }
+            | ParseError::DuplicateKey { location, .. }
+            | ParseError::DuplicateLabel { location, .. }
+            | ParseError::ConflictingDefinition { location, .. } => Some(*location),
+            ParseError::InputTooLarge { .. } | ParseError::Internal { .. } => None,
+        }
+    }
+
+    /// Get the error message without its location
+    ///
+    /// Useful for tools that report the location separately, such as
+    /// `file:line:column: message` diagnostics.
+    pub fn message(&self) -> String {
+        match self {
+            ParseError::SyntaxError {
+                expected, found, ..
+            } => format!("expected {}, found '{}'", expected, found),
+            ParseError::UnexpectedInput { snippet, .. } => {
+                format!("unexpected input: {}", snippet)
+            }
+            ParseError::InvalidValue { kind, reason, .. } => {
+                format!("invalid {}: {}", kind, reason)
+            }
+            ParseError::UnmatchedDelimiter { delimiter, .. } => {
+                format!("unmatched {}", delimiter)
+            }
+            ParseError::InputTooLarge { size, limit } => {
+                format!(
+                    "input of {} bytes exceeds the limit of {} bytes",
+                    size, limit
+                )
+            }
+            ParseError::NestingTooDeep { limit, .. } => {
+                format!("nesting deeper than {} levels", limit)
+            }
+            ParseError::TooManyPatterns { limit, .. } => format!("more than {} patterns", limit),
+            ParseError::TooManyProperties { limit, .. } => {
+                format!("more than {} properties", limit)
+            }
+            ParseError::StringTooLong { limit, .. } => {
+                format!("string longer than {} bytes", limit)
+            }
+            ParseError::DuplicateKey { key, .. } => format!("duplicate key '{}'", key),
+            ParseError::DuplicateLabel { label, .. } => format!("duplicate label '{}'", label),
+            ParseError::ConflictingDefinition { identity, .. } => {
+                format!("conflicting definition of '{}'", identity)
+            }
+            ParseError::Internal { message } => message.clone(),
         }
     }
 
@@ -107,6 +195,17 @@ mod tests {
         assert_eq!(err.location().unwrap().column, 5);
     }
 
+    #[test]
+    fn test_error_message_omits_location() {
+        let err = ParseError::UnmatchedDelimiter {
+            location: Location::new(3, 7, 20),
+            delimiter: '(',
+        };
+
+        assert_eq!(err.message(), "unmatched (");
+        assert_eq!(err.to_string(), "Unmatched ( at 3:7");
+    }
+
     #[test]
     fn test_error_with_context() {
         let err = ParseError::SyntaxError {
@@ -0,0 +1,390 @@
+//! Tokenizer for gram notation
+//!
+//! Splits text into [`Token`]s for syntax highlighters and linters, using the
+//! same combinators as the parser for names, literals and arrows. Tokens cover
+//! the input exactly: concatenating their texts reproduces it, and text that
+//! cannot start a token becomes a [`TokenKind::Error`] token.
+
+use super::combinators::ws;
+use super::relationship::{arrow_left_part, arrow_right_part, arrow_type, determine_arrow_type};
+use super::types::ArrowType;
+use super::value::{measurement, number, range, string_value, tagged_string, unquoted_identifier};
+use nom::combinator::recognize;
+use pattern_core::Value;
+use std::ops::Range;
+
+/// The kind of a [`Token`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    /// Spaces, tabs and line breaks
+    Whitespace,
+    /// `// text`, up to the end of the line
+    Comment,
+
+    // Punctuation
+    /// `(`
+    LeftParen,
+    /// `)`
+    RightParen,
+    /// `[`
+    LeftBracket,
+    /// `]`
+    RightBracket,
+    /// `{`
+    LeftBrace,
+    /// `}`
+    RightBrace,
+    /// `:` before a label or a property value
+    Colon,
+    /// `::` before a property value
+    DoubleColon,
+    /// `,`
+    Comma,
+    /// `|` between a subject and its elements
+    Pipe,
+    /// `@` before an annotation
+    At,
+
+    // Arrows
+    /// A whole arrow: `-->`
+    Arrow(ArrowType),
+    /// The part of an arrow before its edge subject: `-` in `-[r]->`
+    ArrowLeft(ArrowType),
+    /// The part of an arrow after its edge subject: `->` in `-[r]->`
+    ArrowRight(ArrowType),
+
+    // Names
+    /// The identity of a subject, or a reference to one
+    Identifier,
+    /// A label after `:` in a subject
+    Label,
+    /// A property key in a record or map
+    Key,
+
+    // Literals
+    /// A quoted or fenced string
+    String,
+    /// A tagged string: ``date`2024-01-01` ``
+    TaggedString,
+    /// A decimal or hexadecimal integer
+    Integer,
+    /// A decimal number: `3.14`
+    Decimal,
+    /// A number with a unit: `168cm`
+    Measurement,
+    /// A range: `1..10`
+    Range,
+    /// `true` or `false`
+    Boolean,
+    /// An unquoted symbol value
+    Symbol,
+
+    /// Text that cannot start a token, or an unterminated string
+    Error,
+}
+
+impl TokenKind {
+    /// Returns true for whitespace and comments
+    pub fn is_trivia(&self) -> bool {
+        matches!(self, TokenKind::Whitespace | TokenKind::Comment)
+    }
+}
+
+/// A token of gram notation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token<'a> {
+    /// What the token is
+    pub kind: TokenKind,
+    /// The text of the token
+    pub text: &'a str,
+    /// Byte range of the token in the input
+    pub span: Range<usize>,
+}
+
+/// Split gram notation into tokens
+///
+/// Never fails: invalid input produces [`TokenKind::Error`] tokens, and the
+/// token texts always concatenate to `input`. The parts of an arrow around an
+/// edge subject both carry the type of the whole arrow.
+///
+/// # Example
+///
+/// ```rust
+/// use gram_codec::{tokenize, ArrowType, TokenKind};
+///
+/// let kinds: Vec<TokenKind> = tokenize("(a)-[:KNOWS]->(b)")
+///     .map(|token| token.kind)
+///     .collect();
+/// assert_eq!(kinds[3], TokenKind::ArrowLeft(ArrowType::Right));
+/// assert_eq!(kinds[6], TokenKind::Label);
+/// ```
+pub fn tokenize(input: &str) -> impl Iterator<Item = Token<'_>> {
+    let mut lexer = Lexer {
+        input,
+        pos: 0,
+        tokens: Vec::new(),
+        groups: Vec::new(),
+        closed_edge: None,
+    };
+    while lexer.pos < input.len() {
+        lexer.next();
+    }
+    lexer.tokens.into_iter()
+}
+
+/// What a bracketed group contains, which decides how names and numbers lex
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Context {
+    /// Subjects and patterns
+    Pattern,
+    /// Properties of a record or map
+    Record,
+    /// Values of an array
+    Array,
+}
+
+/// An open bracket
+struct Group {
+    close: char,
+    context: Context,
+    /// Index of the `ArrowLeft` token before an edge subject
+    arrow: Option<usize>,
+}
+
+struct Lexer<'a> {
+    input: &'a str,
+    pos: usize,
+    tokens: Vec<Token<'a>>,
+    groups: Vec<Group>,
+    /// Index of the `ArrowLeft` token of the edge subject the last token closed
+    closed_edge: Option<usize>,
+}
+
+impl<'a> Lexer<'a> {
+    fn next(&mut self) {
+        let rest = &self.input[self.pos..];
+        let closed_edge = self.closed_edge.take();
+        let (kind, len) = self.lex(rest, closed_edge);
+        debug_assert!(len > 0, "tokens are never empty");
+
+        self.tokens.push(Token {
+            kind,
+            text: &rest[..len],
+            span: self.pos..self.pos + len,
+        });
+        self.pos += len;
+    }
+
+    fn lex(&mut self, rest: &'a str, closed_edge: Option<usize>) -> (TokenKind, usize) {
+        if let Some(left) = closed_edge {
+            if let Ok((_, right)) = arrow_right_part(rest) {
+                let arrow = determine_arrow_type(self.tokens[left].text, right);
+                self.tokens[left].kind = TokenKind::ArrowLeft(arrow);
+                return (TokenKind::ArrowRight(arrow), right.len());
+            }
+        }
+
+        let context = self.groups.last().map_or(Context::Pattern, |g| g.context);
+        match rest.chars().next().expect("input remains") {
+            ' ' | '\t' | '\r' | '\n' => (
+                TokenKind::Whitespace,
+                rest.find(|c| !matches!(c, ' ' | '\t' | '\r' | '\n'))
+                    .unwrap_or(rest.len()),
+            ),
+            '/' if rest.starts_with("//") => {
+                (TokenKind::Comment, rest.find('\n').unwrap_or(rest.len()))
+            }
+            '(' => self.open(')', Context::Pattern, TokenKind::LeftParen),
+            '{' => self.open('}', Context::Record, TokenKind::LeftBrace),
+            '[' => {
+                let arrow = match self.tokens.last() {
+                    Some(Token {
+                        kind: TokenKind::ArrowLeft(_),
+                        ..
+                    }) => Some(self.tokens.len() - 1),
+                    _ => None,
+                };
+                self.groups.push(Group {
+                    close: ']',
+                    context: match context {
+                        Context::Pattern => Context::Pattern,
+                        Context::Record | Context::Array => Context::Array,
+                    },
+                    arrow,
+                });
+                (TokenKind::LeftBracket, 1)
+            }
+            ')' => self.close(')', TokenKind::RightParen),
+            ']' => self.close(']', TokenKind::RightBracket),
+            '}' => self.close('}', TokenKind::RightBrace),
+            ':' if rest.starts_with("::") => (TokenKind::DoubleColon, 2),
+            ':' => (TokenKind::Colon, 1),
+            ',' => (TokenKind::Comma, 1),
+            '|' => (TokenKind::Pipe, 1),
+            '@' => (TokenKind::At, 1),
+            _ => {
+                let token = match context {
+                    Context::Pattern => arrow(rest).or_else(|| {
+                        let kind = match self.tokens.last() {
+                            Some(token) if token.kind == TokenKind::Colon => TokenKind::Label,
+                            _ => TokenKind::Identifier,
+                        };
+                        name(rest).map(|len| (kind, len))
+                    }),
+                    Context::Record => key(rest).or_else(|| value(rest)),
+                    Context::Array => value(rest),
+                };
+                token.unwrap_or_else(|| error(rest))
+            }
+        }
+    }
+
+    fn open(&mut self, close: char, context: Context, kind: TokenKind) -> (TokenKind, usize) {
+        self.groups.push(Group {
+            close,
+            context,
+            arrow: None,
+        });
+        (kind, 1)
+    }
+
+    /// Closes the innermost group opened with the matching bracket, and any
+    /// unclosed groups inside it.
+    fn close(&mut self, close: char, kind: TokenKind) -> (TokenKind, usize) {
+        if let Some(i) = self.groups.iter().rposition(|group| group.close == close) {
+            self.closed_edge = self.groups[i].arrow;
+            self.groups.truncate(i);
+        }
+        (kind, 1)
+    }
+}
+
+/// A whole arrow, or the part of one before an edge subject
+fn arrow(rest: &str) -> Option<(TokenKind, usize)> {
+    if let Ok((after, left)) = arrow_left_part(rest) {
+        if after.starts_with('[') {
+            // The part after the edge subject decides the type; until it is
+            // seen, assume the arrow is symmetric
+            let guess = determine_arrow_type(left, left.trim_start_matches('<'));
+            return Some((TokenKind::ArrowLeft(guess), left.len()));
+        }
+    }
+    let (after, arrow) = arrow_type(rest).ok()?;
+    Some((TokenKind::Arrow(arrow), rest.len() - after.len()))
+}
+
+/// The length of a quoted or unquoted name
+fn name(rest: &str) -> Option<usize> {
+    let (after, _) = recognize(string_value)(rest)
+        .or_else(|_| recognize(unquoted_identifier)(rest))
+        .ok()?;
+    Some(rest.len() - after.len())
+}
+
+/// A name followed by `:` or `::`
+fn key(rest: &str) -> Option<(TokenKind, usize)> {
+    let len = name(rest)?;
+    let (after, _) = ws(&rest[len..]).ok()?;
+    after.starts_with(':').then_some((TokenKind::Key, len))
+}
+
+/// A literal value, in the order the value parser tries them
+fn value(rest: &str) -> Option<(TokenKind, usize)> {
+    let len = |after: &str| rest.len() - after.len();
+
+    if let Ok((after, _)) = tagged_string(rest) {
+        return Some((TokenKind::TaggedString, len(after)));
+    }
+    if let Ok((after, _)) = string_value(rest) {
+        return Some((TokenKind::String, len(after)));
+    }
+    if let Ok((after, _)) = range(rest) {
+        return Some((TokenKind::Range, len(after)));
+    }
+    if let Ok((after, _)) = measurement(rest) {
+        return Some((TokenKind::Measurement, len(after)));
+    }
+    if let Ok((after, number)) = number(rest) {
+        let kind = match number {
+            Value::VDecimal(_) => TokenKind::Decimal,
+            _ => TokenKind::Integer,
+        };
+        return Some((kind, len(after)));
+    }
+    let (after, word) = unquoted_identifier(rest).ok()?;
+    let kind = match word.as_str() {
+        "true" | "false" => TokenKind::Boolean,
+        _ => TokenKind::Symbol,
+    };
+    Some((kind, len(after)))
+}
+
+/// An unterminated string up to the end of its line, or else one character
+fn error(rest: &str) -> (TokenKind, usize) {
+    let c = rest.chars().next().expect("input remains");
+    let len = match c {
+        '"' | '\'' | '`' => rest.find('\n').unwrap_or(rest.len()),
+        _ => c.len_utf8(),
+    };
+    (TokenKind::Error, len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(input: &str) -> Vec<(TokenKind, &str)> {
+        tokenize(input)
+            .filter(|token| !token.kind.is_trivia())
+            .map(|token| (token.kind, token.text))
+            .collect()
+    }
+
+    #[test]
+    fn test_node_with_labels_and_record() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds(r#"(a:Person {name: "Alice", age: 30})"#),
+            vec![
+                (LeftParen, "("),
+                (Identifier, "a"),
+                (Colon, ":"),
+                (Label, "Person"),
+                (LeftBrace, "{"),
+                (Key, "name"),
+                (Colon, ":"),
+                (String, "\"Alice\""),
+                (Comma, ","),
+                (Key, "age"),
+                (Colon, ":"),
+                (Integer, "30"),
+                (RightBrace, "}"),
+                (RightParen, ")"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_arrows() {
+        assert_eq!(
+            kinds("(a)<--(b)")[3],
+            (TokenKind::Arrow(ArrowType::Left), "<--")
+        );
+        let tokens = kinds("(a)<=[r]=(b)");
+        assert_eq!(
+            tokens[3],
+            (TokenKind::ArrowLeft(ArrowType::DoubleLeft), "<=")
+        );
+        assert_eq!(
+            tokens[7],
+            (TokenKind::ArrowRight(ArrowType::DoubleLeft), "=")
+        );
+    }
+
+    #[test]
+    fn test_unterminated_string() {
+        let tokens = kinds("(a {k: \"open\n})");
+        assert!(tokens.contains(&(TokenKind::Error, "\"open")));
+        assert_eq!(tokens.last(), Some(&(TokenKind::RightParen, ")")));
+    }
+}
@@ -7,6 +7,7 @@
 pub mod annotation;
 pub mod combinators;
 pub mod error;
+pub mod lexer;
 pub mod node;
 pub mod relationship;
 pub mod subject;
@@ -15,7 +16,8 @@ pub mod value;
 
 // Re-exports
 pub use error::ParseError;
-pub use types::{Location, ParseResult};
+pub use lexer::{tokenize, Token, TokenKind};
+pub use types::{ArrowType, Location, ParseResult};
 
 use combinators::ws;
 use nom::{
@@ -112,6 +114,19 @@ pub fn gram_pattern(input: &str) -> ParseResult<'_, Pattern<Subject>> {
     )(input)
 }
 
+/// Parse one top-level pattern, without surrounding whitespace
+///
+/// A leading record is not included; it is only valid at the start of a document
+/// and is handled by [`gram_patterns`].
+pub fn top_level_pattern(input: &str) -> ParseResult<'_, Pattern<Subject>> {
+    alt((
+        annotated_pattern,
+        subject_pattern,
+        relationship::path_pattern,
+        node::node,
+    ))(input)
+}
+
 /// Parse multiple gram patterns (top-level)
 ///
 /// Returns all top-level patterns found in the input.
@@ -127,16 +142,7 @@ pub fn gram_patterns(input: &str) -> ParseResult<'_, Vec<Pattern<Subject>>> {
                 // Optional leading record
                 opt(subject::record),
                 // All patterns
-                many0(delimited(
-                    ws,
-                    alt((
-                        annotated_pattern,
-                        subject_pattern,
-                        relationship::path_pattern,
-                        node::node,
-                    )),
-                    ws,
-                )),
+                many0(delimited(ws, top_level_pattern, ws)),
             ),
             ws,
         ),
@@ -13,34 +13,35 @@ use nom::{
 };
 use pattern_core::{Pattern, Subject};
 
-/// Parse an arrow: all gram notation arrow types
-/// Order matters: longer patterns must come first to avoid partial matches
+/// Parse an arrow, with surrounding whitespace
 pub fn arrow(input: &str) -> ParseResult<'_, ArrowType> {
-    delimited(
-        ws,
-        alt((
-            // Squiggle arrows (check 4-char patterns first)
-            nom_value(ArrowType::SquiggleBidirectional, tag("<~~>")),
-            // Double arrows (check 4-char patterns)
-            nom_value(ArrowType::DoubleBidirectional, tag("<==>")),
-            // Single arrows (check 4-char patterns)
-            nom_value(ArrowType::Bidirectional, tag("<-->")),
-            // Squiggle arrows (3-char patterns)
-            nom_value(ArrowType::SquiggleRight, tag("~~>")),
-            nom_value(ArrowType::SquiggleLeft, tag("<~~")),
-            // Double arrows (3-char patterns)
-            nom_value(ArrowType::DoubleRight, tag("==>")),
-            nom_value(ArrowType::DoubleLeft, tag("<==")),
-            // Single arrows (3-char patterns)
-            nom_value(ArrowType::Right, tag("-->")),
-            nom_value(ArrowType::Left, tag("<--")),
-            // Undirected (2-char patterns)
-            nom_value(ArrowType::Squiggle, tag("~~")),
-            nom_value(ArrowType::DoubleUndirected, tag("==")),
-            nom_value(ArrowType::Undirected, tag("--")),
-        )),
-        ws,
-    )(input)
+    delimited(ws, arrow_type, ws)(input)
+}
+
+/// Parse the text of an arrow: all gram notation arrow types
+/// Order matters: longer patterns must come first to avoid partial matches
+pub fn arrow_type(input: &str) -> ParseResult<'_, ArrowType> {
+    alt((
+        // Squiggle arrows (check 4-char patterns first)
+        nom_value(ArrowType::SquiggleBidirectional, tag("<~~>")),
+        // Double arrows (check 4-char patterns)
+        nom_value(ArrowType::DoubleBidirectional, tag("<==>")),
+        // Single arrows (check 4-char patterns)
+        nom_value(ArrowType::Bidirectional, tag("<-->")),
+        // Squiggle arrows (3-char patterns)
+        nom_value(ArrowType::SquiggleRight, tag("~~>")),
+        nom_value(ArrowType::SquiggleLeft, tag("<~~")),
+        // Double arrows (3-char patterns)
+        nom_value(ArrowType::DoubleRight, tag("==>")),
+        nom_value(ArrowType::DoubleLeft, tag("<==")),
+        // Single arrows (3-char patterns)
+        nom_value(ArrowType::Right, tag("-->")),
+        nom_value(ArrowType::Left, tag("<--")),
+        // Undirected (2-char patterns)
+        nom_value(ArrowType::Squiggle, tag("~~")),
+        nom_value(ArrowType::DoubleUndirected, tag("==")),
+        nom_value(ArrowType::Undirected, tag("--")),
+    ))(input)
 }
 
 /// Parse a relationship: (a)-->(b) or (a)-[subject]->(b)
@@ -111,7 +112,7 @@ fn relationship_with_edge_subject(input: &str) -> ParseResult<'_, Pattern<Subjec
 }
 
 /// Parse left part of arrow: -, <-, ~, <~, =, <=
-fn arrow_left_part(input: &str) -> ParseResult<'_, &str> {
+pub fn arrow_left_part(input: &str) -> ParseResult<'_, &str> {
     alt((
         tag("<~~"),
         tag("<=="),
@@ -129,7 +130,7 @@ fn arrow_left_part(input: &str) -> ParseResult<'_, &str> {
 }
 
 /// Parse right part of arrow: ->, -, ~>, ~, =>, =
-fn arrow_right_part(input: &str) -> ParseResult<'_, &str> {
+pub fn arrow_right_part(input: &str) -> ParseResult<'_, &str> {
     alt((
         tag("~~>"),
         tag("==>"),
@@ -185,7 +186,7 @@ fn arrow_segment_with_edge(
 }
 
 /// Determine arrow type from left and right parts
-fn determine_arrow_type(left: &str, right: &str) -> ArrowType {
+pub fn determine_arrow_type(left: &str, right: &str) -> ArrowType {
     // Combine to see what arrow it represents
     match (left, right) {
         // Bidirectional
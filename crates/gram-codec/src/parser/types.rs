@@ -84,7 +84,7 @@ impl Span {
 }
 
 /// Relationship arrow types from gram notation
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ArrowType {
     // Single-line arrows
     /// Right arrow: -->
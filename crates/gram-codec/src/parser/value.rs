@@ -1,49 +1,61 @@
 //! Value parsers for gram notation property values
 
-use super::combinators::ws;
+use super::combinators::{comma_list, lenient_ws, space, ws};
 use super::error::ParseError;
 use super::types::ParseResult;
+use crate::borrowed::BorrowedValue;
 use nom::{
     branch::alt,
-    bytes::complete::{tag, take_while, take_while1},
-    character::complete::{char, digit1},
-    combinator::{cut, map, map_res, opt, recognize, value as nom_value},
-    error::VerboseError,
-    multi::{many0, separated_list0},
+    bytes::complete::{is_not, tag, take_while, take_while1},
+    character::complete::{char, digit1, one_of},
+    combinator::{cut, eof, map, map_res, opt, peek, recognize, value as nom_value},
+    error::{ErrorKind, ParseError as _, VerboseError},
+    multi::many0,
     number::complete::double,
-    sequence::{delimited, pair, separated_pair, tuple},
+    sequence::{delimited, pair, preceded, separated_pair, terminated, tuple},
 };
 use pattern_core::{RangeValue, Value};
+use std::borrow::Cow;
 
 /// Parse an identifier (symbol or quoted string)
 pub fn identifier(input: &str) -> ParseResult<'_, String> {
-    alt((quoted_identifier, unquoted_identifier))(input)
+    map(borrowed_identifier, Cow::into_owned)(input)
+}
+
+/// Parse an identifier, borrowing it from the input unless it has escapes
+pub fn borrowed_identifier(input: &str) -> ParseResult<'_, Cow<'_, str>> {
+    alt((quoted_identifier, map(unquoted_name, Cow::Borrowed)))(input)
 }
 
 /// Parse an unquoted identifier (symbol)
 /// Supports: letters, digits, underscore, hyphen, @, . (not first char)
 /// Can start with: letter, underscore, or digit
 pub fn unquoted_identifier(input: &str) -> ParseResult<'_, String> {
-    map(
-        recognize(pair(
-            // First character: letter, underscore, or digit
-            take_while1(|c: char| c.is_alphanumeric() || c == '_'),
-            // Subsequent characters: letters, digits, underscore, hyphen, @, .
-            take_while(|c: char| {
-                c.is_alphanumeric() || c == '_' || c == '-' || c == '@' || c == '.'
-            }),
-        )),
-        |s: &str| s.to_string(),
-    )(input)
+    map(unquoted_name, |s: &str| s.to_string())(input)
+}
+
+/// Parse an unquoted identifier, borrowing it from the input
+pub fn unquoted_name(input: &str) -> ParseResult<'_, &str> {
+    recognize(pair(
+        // First character: letter, underscore, or digit
+        take_while1(|c: char| c.is_alphanumeric() || c == '_'),
+        // Subsequent characters: letters, digits, underscore, hyphen, @, .
+        take_while(|c: char| c.is_alphanumeric() || c == '_' || c == '-' || c == '@' || c == '.'),
+    ))(input)
 }
 
 /// Parse a quoted identifier (quoted string)
-fn quoted_identifier(input: &str) -> ParseResult<'_, String> {
-    string_value(input)
+fn quoted_identifier(input: &str) -> ParseResult<'_, Cow<'_, str>> {
+    borrowed_string(input)
 }
 
 /// Parse a string value - supports multiple quote styles
 pub fn string_value(input: &str) -> ParseResult<'_, String> {
+    map(borrowed_string, Cow::into_owned)(input)
+}
+
+/// Parse a string value, borrowing it from the input unless it has escapes
+pub fn borrowed_string(input: &str) -> ParseResult<'_, Cow<'_, str>> {
     alt((
         fenced_string,          // ``` ... ``` (triple backticks)
         double_quoted_string,   // "..."
@@ -53,7 +65,7 @@ pub fn string_value(input: &str) -> ParseResult<'_, String> {
 }
 
 /// Parse a double-quoted string: "text"
-fn double_quoted_string(input: &str) -> ParseResult<'_, String> {
+fn double_quoted_string(input: &str) -> ParseResult<'_, Cow<'_, str>> {
     delimited(
         char('"'),
         map(
@@ -70,14 +82,14 @@ fn double_quoted_string(input: &str) -> ParseResult<'_, String> {
                 nom_value((), tag("\\f")),  // \f
                 nom_value((), take_while1(|c| c != '\\' && c != '"')),
             )))),
-            |s: &str| unescape_string(s),
+            unescape,
         ),
         cut(char('"')),
     )(input)
 }
 
 /// Parse a single-quoted string: 'text'
-fn single_quoted_string(input: &str) -> ParseResult<'_, String> {
+fn single_quoted_string(input: &str) -> ParseResult<'_, Cow<'_, str>> {
     delimited(
         char('\''),
         map(
@@ -94,14 +106,14 @@ fn single_quoted_string(input: &str) -> ParseResult<'_, String> {
                 nom_value((), tag("\\f")),  // \f
                 nom_value((), take_while1(|c| c != '\\' && c != '\'')),
             )))),
-            |s: &str| unescape_string(s),
+            unescape,
         ),
         cut(char('\'')),
     )(input)
 }
 
 /// Parse a backtick-quoted string: `text`
-fn backtick_quoted_string(input: &str) -> ParseResult<'_, String> {
+fn backtick_quoted_string(input: &str) -> ParseResult<'_, Cow<'_, str>> {
     delimited(
         char('`'),
         map(
@@ -118,14 +130,14 @@ fn backtick_quoted_string(input: &str) -> ParseResult<'_, String> {
                 nom_value((), tag("\\f")),  // \f
                 nom_value((), take_while1(|c| c != '\\' && c != '`')),
             )))),
-            |s: &str| unescape_string(s),
+            unescape,
         ),
         cut(char('`')),
     )(input)
 }
 
 /// Parse a fenced string: ```text``` or ```tag\ntext```
-fn fenced_string(input: &str) -> ParseResult<'_, String> {
+fn fenced_string(input: &str) -> ParseResult<'_, Cow<'_, str>> {
     // Look for opening ```
     let (input, _) = tag("```")(input)?;
 
@@ -142,36 +154,26 @@ fn fenced_string(input: &str) -> ParseResult<'_, String> {
         input
     };
 
-    // Collect everything until closing ```
-    let mut content = String::new();
-    let mut remaining = input;
-
-    loop {
-        // Try to find closing ```
-        if remaining.starts_with("```") {
-            let (rest, _) = tag("```")(remaining)?;
-            return Ok((rest, content));
-        }
-
-        // Take one character
-        if let Some(c) = remaining.chars().next() {
-            content.push(c);
-            remaining = &remaining[c.len_utf8()..];
-        } else {
-            // EOF without closing ```
-            return Err(nom::Err::Error(VerboseError {
-                errors: vec![(
-                    input,
-                    nom::error::VerboseErrorKind::Context("Unclosed fenced string"),
-                )],
-            }));
-        }
+    // Everything until the closing ```
+    match input.find("```") {
+        Some(end) => Ok((&input[end + 3..], Cow::Borrowed(&input[..end]))),
+        // EOF without closing ```
+        None => Err(nom::Err::Error(VerboseError {
+            errors: vec![(
+                input,
+                nom::error::VerboseErrorKind::Context("Unclosed fenced string"),
+            )],
+        })),
     }
 }
 
-/// Unescape string escape sequences
-fn unescape_string(s: &str) -> String {
-    let mut result = String::new();
+/// Unescape string escape sequences, borrowing `s` if it has none
+fn unescape(s: &str) -> Cow<'_, str> {
+    if !s.contains('\\') {
+        return Cow::Borrowed(s);
+    }
+
+    let mut result = String::with_capacity(s.len());
     let mut chars = s.chars();
 
     while let Some(c) = chars.next() {
@@ -198,7 +200,7 @@ fn unescape_string(s: &str) -> String {
         }
     }
 
-    result
+    Cow::Owned(result)
 }
 
 /// Parse an integer value (decimal or hexadecimal)
@@ -234,7 +236,11 @@ pub fn decimal(input: &str) -> ParseResult<'_, f64> {
 }
 
 /// Parse a measurement: number followed by unit letters (e.g., "168cm", "3.5kg")
-fn measurement(input: &str) -> ParseResult<'_, Value> {
+pub fn measurement(input: &str) -> ParseResult<'_, Value> {
+    map(borrowed_measurement, BorrowedValue::into_owned)(input)
+}
+
+fn borrowed_measurement(input: &str) -> ParseResult<'_, BorrowedValue<'_>> {
     map(
         pair(
             alt((
@@ -249,23 +255,27 @@ fn measurement(input: &str) -> ParseResult<'_, Value> {
             // Unit: one or more letters
             take_while1(|c: char| c.is_alphabetic()),
         ),
-        |(value, unit): (f64, &str)| Value::VMeasurement {
-            unit: unit.to_string(),
+        |(value, unit): (f64, &str)| BorrowedValue::VMeasurement {
+            unit: Cow::Borrowed(unit),
             value,
         },
     )(input)
 }
 
 /// Parse a number (integer, hexadecimal, or decimal)
-fn number(input: &str) -> ParseResult<'_, Value> {
+pub fn number(input: &str) -> ParseResult<'_, Value> {
+    map(borrowed_number, BorrowedValue::into_owned)(input)
+}
+
+fn borrowed_number(input: &str) -> ParseResult<'_, BorrowedValue<'_>> {
     alt((
         // Decimal: 123.45
         map(
             recognize(tuple((opt(char('-')), digit1, char('.'), digit1))),
-            |s: &str| Value::VDecimal(s.parse().unwrap()),
+            |s: &str| BorrowedValue::VDecimal(s.parse().unwrap()),
         ),
         // Use the integer parser which handles both decimal and hex
-        map(integer, Value::VInteger),
+        map(integer, BorrowedValue::VInteger),
     ))(input)
 }
 
@@ -275,13 +285,21 @@ pub fn boolean(input: &str) -> ParseResult<'_, bool> {
 }
 
 /// Parse an array of values: [value1, value2, ...]
+#[allow(dead_code)]
 pub fn array(input: &str) -> ParseResult<'_, Vec<Value>> {
+    map(
+        |input| borrowed_array(input, false),
+        |values| values.into_iter().map(BorrowedValue::into_owned).collect(),
+    )(input)
+}
+
+fn borrowed_array(input: &str, lenient: bool) -> ParseResult<'_, Vec<BorrowedValue<'_>>> {
     delimited(
         char('['),
         delimited(
-            ws,
-            separated_list0(delimited(ws, char(','), ws), value_parser),
-            ws,
+            |input| space(input, lenient),
+            comma_list(lenient, |input| padded_value(input, lenient)),
+            |input| space(input, lenient),
         ),
         cut(char(']')),
     )(input)
@@ -325,13 +343,16 @@ pub fn range(input: &str) -> ParseResult<'_, RangeValue> {
 
 /// Parse a tagged string: ```tag content``` or """tag content"""
 pub fn tagged_string(input: &str) -> ParseResult<'_, (String, String)> {
+    map(borrowed_tagged_string, |(tag, content)| {
+        (tag.to_string(), content.into_owned())
+    })(input)
+}
+
+fn borrowed_tagged_string(input: &str) -> ParseResult<'_, (&str, Cow<'_, str>)> {
     // Format: tag`content` - identifier followed by backtick-quoted string
-    map(
-        pair(
-            unquoted_identifier,    // Tag (e.g., "date", "url", "md")
-            backtick_quoted_string, // Content in backticks
-        ),
-        |(tag, content)| (tag, content),
+    pair(
+        unquoted_name,          // Tag (e.g., "date", "url", "md")
+        backtick_quoted_string, // Content in backticks
     )(input)
 }
 
@@ -421,65 +442,134 @@ fn tagged_triple_quoted(input: &str) -> ParseResult<'_, (String, String)> {
 
 /// Parse a map: { key: value, key2: value2 }
 /// Same syntax as records, but used in value context
-fn map_value(input: &str) -> ParseResult<'_, Value> {
+fn map_value(input: &str, lenient: bool) -> ParseResult<'_, BorrowedValue<'_>> {
     map(
         delimited(
             char('{'),
             delimited(
-                ws,
-                separated_list0(
-                    delimited(ws, char(','), ws),
+                |input| space(input, lenient),
+                comma_list(
+                    lenient,
                     separated_pair(
-                        delimited(ws, unquoted_identifier, ws),
+                        delimited(
+                            |input| space(input, lenient),
+                            unquoted_name,
+                            |input| space(input, lenient),
+                        ),
                         char(':'),
-                        value_parser, // Recursive call for nested values
+                        |input| padded_value(input, lenient), // Recursive call for nested values
                     ),
                 ),
-                ws,
+                |input| space(input, lenient),
             ),
             char('}'),
         ),
         |pairs| {
             let mut map = std::collections::HashMap::new();
             for (key, value) in pairs {
-                map.insert(key, value);
+                map.insert(Cow::Borrowed(key), value);
             }
-            Value::VMap(map)
+            BorrowedValue::VMap(map)
         },
     )(input)
 }
 
 /// Parse any value type
 pub fn value_parser(input: &str) -> ParseResult<'_, Value> {
+    delimited(ws, bare_value, ws)(input)
+}
+
+/// Parse any value type, without surrounding whitespace
+pub fn bare_value(input: &str) -> ParseResult<'_, Value> {
+    map(borrowed_value, BorrowedValue::into_owned)(input)
+}
+
+/// Parse any value type with surrounding whitespace, in the lenient dialect
+/// when `lenient`
+fn padded_value(input: &str, lenient: bool) -> ParseResult<'_, BorrowedValue<'_>> {
+    let value = |input| {
+        if lenient {
+            lenient_value(input)
+        } else {
+            borrowed_value(input)
+        }
+    };
     delimited(
-        ws,
-        alt((
-            // Try tagged string first (starts with """)
-            map(tagged_string, |(tag, content)| Value::VTaggedString {
-                tag,
-                content,
-            }),
-            // String (quoted)
-            map(string_value, Value::VString),
-            // Map (before array, since both use braces/brackets)
-            map_value,
-            // Range (before number, since it contains ..)
-            map(range, Value::VRange),
-            // Measurement (before number, since it's number + letters)
-            measurement,
-            // Number (integer or decimal)
-            number,
-            // Boolean
-            map(boolean, Value::VBoolean),
-            // Array
-            map(array, Value::VArray),
-            // Unquoted symbol (last, most permissive)
-            map(unquoted_identifier, Value::VSymbol),
-        )),
-        ws,
+        |input| space(input, lenient),
+        value,
+        |input| space(input, lenient),
     )(input)
 }
 
+/// Parse any value type, without surrounding whitespace, borrowing strings from
+/// the input unless they have escapes
+pub fn borrowed_value(input: &str) -> ParseResult<'_, BorrowedValue<'_>> {
+    any_value(input, false)
+}
+
+/// Parse any value type in the lenient dialect, without surrounding whitespace
+///
+/// Arrays and maps may have trailing commas and `#` comments. Text that is not
+/// a value, or not followed by the end of one, such as `New York` or
+/// `2024-01-01`, is an unquoted string up to the next `,`, `}`, `]`, `#` or
+/// line end.
+pub fn lenient_value(input: &str) -> ParseResult<'_, BorrowedValue<'_>> {
+    alt((
+        terminated(
+            |input| any_value(input, true),
+            peek(preceded(
+                lenient_ws,
+                alt((nom_value((), one_of(",}]")), nom_value((), eof))),
+            )),
+        ),
+        map(unquoted_string, BorrowedValue::VString),
+    ))(input)
+}
+
+/// Parse the text of an unquoted string, without its trailing whitespace
+fn unquoted_string(input: &str) -> ParseResult<'_, Cow<'_, str>> {
+    let (_, text) = is_not(",}]#\r\n")(input)?;
+    let text = text.trim_end();
+    if text.is_empty() {
+        return Err(nom::Err::Error(VerboseError::from_error_kind(
+            input,
+            ErrorKind::IsNot,
+        )));
+    }
+    Ok((&input[text.len()..], Cow::Borrowed(text)))
+}
+
+fn any_value(input: &str, lenient: bool) -> ParseResult<'_, BorrowedValue<'_>> {
+    alt((
+        // Try tagged string first (starts with """)
+        map(borrowed_tagged_string, |(tag, content)| {
+            BorrowedValue::VTaggedString {
+                tag: Cow::Borrowed(tag),
+                content,
+            }
+        }),
+        // String (quoted)
+        map(borrowed_string, BorrowedValue::VString),
+        // Map (before array, since both use braces/brackets)
+        |input| map_value(input, lenient),
+        // Range (before number, since it contains ..)
+        map(range, BorrowedValue::VRange),
+        // Measurement (before number, since it's number + letters)
+        borrowed_measurement,
+        // Number (integer or decimal)
+        borrowed_number,
+        // Boolean
+        map(boolean, BorrowedValue::VBoolean),
+        // Array
+        map(
+            |input| borrowed_array(input, lenient),
+            BorrowedValue::VArray,
+        ),
+        // Unquoted symbol (last, most permissive)
+        map(unquoted_name, |s| BorrowedValue::VSymbol(Cow::Borrowed(s))),
+    ))(input)
+}
+
 /// Convert ParseError from nom error for value parsing
 #[allow(dead_code)]
 pub fn value_parse_error(
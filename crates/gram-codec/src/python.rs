@@ -13,6 +13,9 @@
 //! print(f"Parsed {result['pattern_count']} patterns")
 //! print(f"Identifiers: {result['identifiers']}")
 //!
+//! # Parse with options, here the lenient dialect
+//! result = gram_codec.parse_gram_with_options("(a {city: New York,})", dialect="lenient")
+//!
 //! # Validate gram notation
 //! is_valid = gram_codec.validate_gram("(hello)-->(world)")
 //! print(f"Valid: {is_valid}")
@@ -89,27 +92,95 @@ impl ParseResult {
 ///     []
 #[pyfunction]
 fn parse_gram(input: &str) -> PyResult<ParseResult> {
-    // Use the main parse function
-    let patterns = crate::parse_gram(input)
+    let document = crate::GramDocument::parse(input)
         .map_err(|e| PyValueError::new_err(format!("Parse error: {}", e)))?;
+    Ok(summarize(&document))
+}
 
-    // Extract identifiers from patterns
-    let identifiers: Vec<String> = patterns
-        .iter()
-        .filter_map(|p| {
-            let id = &p.value().identity.0;
-            if !id.is_empty() {
-                Some(id.clone())
-            } else {
-                None
-            }
-        })
-        .collect();
-
-    Ok(ParseResult {
+/// Summarize the top-level patterns of a document, including a header record,
+/// with the identities of those that have one
+fn summarize(document: &crate::GramDocument) -> ParseResult {
+    let patterns = document.to_patterns();
+    ParseResult {
         pattern_count: patterns.len(),
-        identifiers,
-    })
+        identifiers: patterns
+            .iter()
+            .map(|p| p.value().identity.0.clone())
+            .filter(|id| !id.is_empty())
+            .collect(),
+    }
+}
+
+/// Parse gram notation with options and return basic information
+///
+/// Args:
+///     input (str): Gram notation string to parse
+///     dialect (str): "standard", "strict" or "lenient"
+///     header (bool): Whether a leading record is the header, rather than an
+///         anonymous node
+///     max_depth (int | None): Deepest nesting allowed
+///     max_patterns (int | None): Most patterns allowed
+///     max_properties (int | None): Most properties allowed in a record or map
+///     max_string_length (int | None): Longest name or string allowed, in bytes
+///     max_input_size (int | None): Largest input allowed, in bytes
+///
+/// Returns:
+///     ParseResult: Object containing pattern_count and identifiers
+///
+/// Raises:
+///     ValueError: If the dialect is unknown or parsing fails
+///
+/// Example:
+///     >>> import gram_codec
+///     >>> result = gram_codec.parse_gram_with_options("(a {city: New York,})", dialect="lenient")
+///     >>> print(result.pattern_count)
+///     1
+#[pyfunction]
+#[pyo3(signature = (
+    input,
+    *,
+    dialect = "standard",
+    header = true,
+    max_depth = None,
+    max_patterns = None,
+    max_properties = None,
+    max_string_length = None,
+    max_input_size = None,
+))]
+#[allow(clippy::too_many_arguments)]
+fn parse_gram_with_options(
+    input: &str,
+    dialect: &str,
+    header: bool,
+    max_depth: Option<usize>,
+    max_patterns: Option<usize>,
+    max_properties: Option<usize>,
+    max_string_length: Option<usize>,
+    max_input_size: Option<usize>,
+) -> PyResult<ParseResult> {
+    let dialect = match dialect {
+        "standard" => crate::Dialect::Standard,
+        "strict" => crate::Dialect::Strict,
+        "lenient" => crate::Dialect::Lenient,
+        other => {
+            return Err(PyValueError::new_err(format!(
+                "Unknown dialect '{}': expected 'standard', 'strict' or 'lenient'",
+                other
+            )))
+        }
+    };
+    let options = crate::ParseOptions {
+        dialect,
+        header,
+        max_depth,
+        max_patterns,
+        max_properties,
+        max_string_length,
+        max_input_size,
+    };
+    let document = crate::GramDocument::parse_with_options(input, &options)
+        .map_err(|e| PyValueError::new_err(format!("Parse error: {}", e)))?;
+    Ok(summarize(&document))
 }
 
 /// Validate gram notation without parsing
@@ -128,7 +199,7 @@ fn parse_gram(input: &str) -> PyResult<ParseResult> {
 ///     False
 #[pyfunction]
 fn validate_gram(input: &str) -> bool {
-    crate::parse_gram(input).is_ok()
+    crate::validate_gram(input).is_ok()
 }
 
 /// Parse gram notation, serialize it back, and return the serialized form
@@ -150,10 +221,11 @@ fn validate_gram(input: &str) -> bool {
 ///     '(alice)-->(bob)'
 #[pyfunction]
 fn round_trip(input: &str) -> PyResult<String> {
-    let patterns = crate::parse_gram(input)
+    let document = crate::GramDocument::parse(input)
         .map_err(|e| PyValueError::new_err(format!("Parse error: {}", e)))?;
 
-    crate::to_gram(&patterns).map_err(|e| PyValueError::new_err(format!("Serialize error: {}", e)))
+    crate::to_gram(&document.to_patterns())
+        .map_err(|e| PyValueError::new_err(format!("Serialize error: {}", e)))
 }
 
 /// Parse gram notation to AST (Python dict)
@@ -187,8 +259,9 @@ fn round_trip(input: &str) -> PyResult<String> {
 ///     ['Person']
 #[pyfunction]
 fn parse_to_ast(py: Python, input: &str) -> PyResult<PyObject> {
-    let ast = crate::parse_to_ast(input)
-        .map_err(|e| PyValueError::new_err(format!("Parse error: {}", e)))?;
+    let ast = crate::GramDocument::parse(input)
+        .map_err(|e| PyValueError::new_err(format!("Parse error: {}", e)))?
+        .to_ast();
 
     // Convert AST to Python dict manually
     // Serialize to JSON first, then parse as Python
@@ -219,6 +292,7 @@ fn version() -> &'static str {
 #[pymodule]
 fn gram_codec(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(parse_gram, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_gram_with_options, m)?)?;
     m.add_function(wrap_pyfunction!(parse_to_ast, m)?)?;
     m.add_function(wrap_pyfunction!(validate_gram, m)?)?;
     m.add_function(wrap_pyfunction!(round_trip, m)?)?;
@@ -230,3 +304,17 @@ fn gram_codec(m: &Bound<'_, PyModule>) -> PyResult<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_counts_the_header() {
+        // `parse_gram` itself needs an interpreter to report errors
+        let document = crate::GramDocument::parse("{v: 1} (a) ()").unwrap();
+        let result = summarize(&document);
+        assert_eq!(result.pattern_count, 3);
+        assert_eq!(result.identifiers, vec!["a".to_string()]);
+    }
+}
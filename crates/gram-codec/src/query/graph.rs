@@ -0,0 +1,307 @@
+//! Node and relationship view of a parsed document
+
+use super::parse::Constraint;
+use super::{Query, QueryError, Row};
+use pattern_core::{Combinable, Pattern, PatternIndex, PatternPath, RangeValue, Subject, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::ops::Bound;
+
+/// A relationship between two nodes of a [`Graph`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Edge<'a> {
+    pub subject: &'a Subject,
+    pub source: usize,
+    pub target: usize,
+}
+
+/// A queryable graph view over a slice of top-level patterns.
+///
+/// The document is read as nodes and relationships:
+///
+/// - A pattern with exactly two elements is a **relationship** from its first element
+///   to its second. When an element is itself a relationship (as in the nested form
+///   produced for paths like `(a)-->(b)-->(c)`), its rightmost node is the endpoint.
+/// - Every other pattern is a **node**. Nodes with the same identity are merged into
+///   one node (labels unioned, later properties overwriting earlier ones, as in
+///   [`Combinable`] for `Subject`); anonymous nodes are distinct per occurrence.
+/// - Elements of all patterns are walked, so nodes and relationships nested inside
+///   subject patterns are part of the graph.
+///
+/// The parser reverses the elements of left-pointing arrows, so `(a)<--(b)` is stored
+/// as a relationship from `b` to `a`. In paths that chain several left-pointing arrows
+/// the shared node cannot be recovered from the nested form, and the rightmost node
+/// of the inner relationship is used.
+///
+/// A [`PatternIndex`] over the document is used to estimate how many nodes can match
+/// each part of a query, so matching starts from the most selective node.
+///
+/// # Examples
+///
+/// ```rust
+/// use gram_codec::parse_gram;
+/// use gram_codec::query::Graph;
+///
+/// let patterns = parse_gram("(a)-->(b)-->(c) (a:Start)").unwrap();
+/// let graph = Graph::new(&patterns);
+///
+/// assert_eq!(graph.node_count(), 3);
+/// assert_eq!(graph.relationship_count(), 2);
+/// assert!(graph.node("a").unwrap().labels.contains("Start"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Graph<'a> {
+    patterns: &'a [Pattern<Subject>],
+    index: PatternIndex,
+    pub(crate) nodes: Vec<Subject>,
+    node_ids: HashMap<String, usize>,
+    node_paths: HashMap<PatternPath, usize>,
+    pub(crate) edges: Vec<Edge<'a>>,
+    outgoing: Vec<Vec<usize>>,
+    incoming: Vec<Vec<usize>>,
+}
+
+impl<'a> Graph<'a> {
+    /// Builds the graph view of a document.
+    ///
+    /// # Complexity
+    ///
+    /// O(n) in the total number of subjects, plus the cost of merging repeated
+    /// identities and of [`PatternIndex::build`].
+    pub fn new(patterns: &'a [Pattern<Subject>]) -> Self {
+        let mut graph = Graph {
+            patterns,
+            index: PatternIndex::build(patterns),
+            nodes: Vec::new(),
+            node_ids: HashMap::new(),
+            node_paths: HashMap::new(),
+            edges: Vec::new(),
+            outgoing: Vec::new(),
+            incoming: Vec::new(),
+        };
+        for (slot, pattern) in patterns.iter().enumerate() {
+            graph.add(pattern, &mut vec![slot]);
+        }
+        graph
+    }
+
+    /// Returns the patterns this graph was built from.
+    pub fn patterns(&self) -> &'a [Pattern<Subject>] {
+        self.patterns
+    }
+
+    /// Returns the number of distinct nodes.
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns the number of relationships.
+    pub fn relationship_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// Iterates over all nodes, in order of first appearance.
+    pub fn nodes(&self) -> impl Iterator<Item = &Subject> {
+        self.nodes.iter()
+    }
+
+    /// Returns the merged node with the given identity.
+    pub fn node(&self, identity: &str) -> Option<&Subject> {
+        self.node_ids.get(identity).map(|&id| &self.nodes[id])
+    }
+
+    /// Runs a parsed query and returns one row per match.
+    ///
+    /// Rows are produced in a deterministic order for a given document and query.
+    /// Distinct matches that bind the same variables to the same values are all
+    /// returned.
+    pub fn query(&self, query: &Query) -> Vec<Row<'_>> {
+        super::matcher::run(self, query)
+    }
+
+    /// Parses `query` and runs it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use gram_codec::parse_gram;
+    /// use gram_codec::query::Graph;
+    ///
+    /// let patterns = parse_gram("(a:Person)-[:KNOWS]->(b:Person)").unwrap();
+    /// let graph = Graph::new(&patterns);
+    ///
+    /// let rows = graph.run("(x)-[:KNOWS]->(y)").unwrap();
+    /// assert_eq!(rows[0].node("y").unwrap().identity.0, "b");
+    /// ```
+    pub fn run(&self, query: &str) -> Result<Vec<Row<'_>>, QueryError> {
+        Ok(self.query(&Query::parse(query)?))
+    }
+
+    /// Adds a pattern and returns the node that serves as its endpoint.
+    fn add(&mut self, pattern: &'a Pattern<Subject>, path: &mut PatternPath) -> usize {
+        if pattern.elements.len() == 2 {
+            path.push(0);
+            let source = self.add(&pattern.elements[0], path);
+            path.pop();
+            path.push(1);
+            let target = self.add(&pattern.elements[1], path);
+            path.pop();
+
+            let edge = self.edges.len();
+            self.edges.push(Edge {
+                subject: &pattern.value,
+                source,
+                target,
+            });
+            self.outgoing[source].push(edge);
+            self.incoming[target].push(edge);
+            target
+        } else {
+            let node = self.add_node(&pattern.value, path);
+            for (i, element) in pattern.elements.iter().enumerate() {
+                path.push(i);
+                self.add(element, path);
+                path.pop();
+            }
+            node
+        }
+    }
+
+    fn add_node(&mut self, subject: &Subject, path: &PatternPath) -> usize {
+        let identity = &subject.identity.0;
+        let existing = if identity.is_empty() {
+            None
+        } else {
+            self.node_ids.get(identity).copied()
+        };
+        let node = match existing {
+            Some(node) => {
+                self.nodes[node] = self.nodes[node].clone().combine(subject.clone());
+                node
+            }
+            None => {
+                let node = self.nodes.len();
+                self.nodes.push(subject.clone());
+                self.outgoing.push(Vec::new());
+                self.incoming.push(Vec::new());
+                if !identity.is_empty() {
+                    self.node_ids.insert(identity.clone(), node);
+                }
+                node
+            }
+        };
+        self.node_paths.insert(path.clone(), node);
+        node
+    }
+
+    /// Relationships leaving `node`, with the node at their other end.
+    pub(crate) fn outgoing(&self, node: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.outgoing[node]
+            .iter()
+            .map(|&e| (e, self.edges[e].target))
+    }
+
+    /// Relationships arriving at `node`, with the node at their other end.
+    pub(crate) fn incoming(&self, node: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.incoming[node]
+            .iter()
+            .map(|&e| (e, self.edges[e].source))
+    }
+
+    /// Nodes that may satisfy `constraint`, according to the index.
+    ///
+    /// Returns `None` when the constraint has no labels or properties. The result
+    /// is a superset of the matching nodes: merged nodes are only checked in full
+    /// by [`matches`].
+    pub(crate) fn candidates(&self, constraint: &Constraint) -> Option<Vec<usize>> {
+        let mut result: Option<BTreeSet<usize>> = None;
+        let mut narrow = |paths: Vec<&PatternPath>| {
+            let nodes: BTreeSet<usize> = paths
+                .into_iter()
+                .filter_map(|path| self.node_paths.get(path).copied())
+                .collect();
+            result = Some(match result.take() {
+                Some(current) => current.intersection(&nodes).copied().collect(),
+                None => nodes,
+            });
+        };
+        for label in &constraint.labels {
+            narrow(self.index.with_label(label).collect());
+        }
+        for (key, value) in &constraint.properties {
+            narrow(self.property_paths(key, value));
+        }
+        result.map(|nodes| nodes.into_iter().collect())
+    }
+
+    /// Index lookup mirroring the leniency of [`value_matches`].
+    fn property_paths(&self, key: &str, value: &Value) -> Vec<&PatternPath> {
+        match value {
+            Value::VInteger(_) | Value::VDecimal(_) => {
+                let n = numeric(value).unwrap_or_default();
+                self.index.property_range(key, n..=n)
+            }
+            Value::VString(s) | Value::VSymbol(s) => {
+                let mut paths: Vec<&PatternPath> = self
+                    .index
+                    .with_property(key, &Value::VString(s.clone()))
+                    .chain(self.index.with_property(key, &Value::VSymbol(s.clone())))
+                    .collect();
+                paths.sort();
+                paths
+            }
+            Value::VRange(range) => {
+                let bound = |b: Option<f64>| b.map_or(Bound::Unbounded, Bound::Included);
+                let mut paths = self
+                    .index
+                    .property_range(key, (bound(range.lower), bound(range.upper)));
+                paths.extend(self.index.with_property(key, value));
+                paths
+            }
+            _ => self.index.with_property(key, value).collect(),
+        }
+    }
+}
+
+/// Returns `true` if `subject` carries every label and property of `constraint`.
+pub(crate) fn matches(constraint: &Constraint, subject: &Subject) -> bool {
+    constraint
+        .labels
+        .iter()
+        .all(|label| subject.labels.contains(label))
+        && constraint.properties.iter().all(|(key, expected)| {
+            subject
+                .properties
+                .get(key)
+                .is_some_and(|actual| value_matches(expected, actual))
+        })
+}
+
+/// Compares a query value with a document value.
+///
+/// Integers and decimals compare numerically, strings and symbols compare by text,
+/// and a range matches any numeric value it contains. Everything else must be equal.
+fn value_matches(expected: &Value, actual: &Value) -> bool {
+    match (expected, actual) {
+        (Value::VString(e) | Value::VSymbol(e), Value::VString(a) | Value::VSymbol(a)) => e == a,
+        (Value::VInteger(_) | Value::VDecimal(_), Value::VInteger(_) | Value::VDecimal(_)) => {
+            numeric(expected) == numeric(actual)
+        }
+        (Value::VRange(range), _) => {
+            numeric(actual).is_some_and(|n| contains(range, n)) || expected == actual
+        }
+        _ => expected == actual,
+    }
+}
+
+fn numeric(value: &Value) -> Option<f64> {
+    match value {
+        Value::VInteger(i) => Some(*i as f64),
+        Value::VDecimal(d) => Some(*d),
+        Value::VMeasurement { value, .. } => Some(*value),
+        _ => None,
+    }
+}
+
+fn contains(range: &RangeValue, n: f64) -> bool {
+    range.lower.map_or(true, |lower| lower <= n) && range.upper.map_or(true, |upper| n <= upper)
+}
@@ -0,0 +1,357 @@
+//! Backtracking matcher and result rows
+
+use super::graph::{matches, Graph};
+use super::parse::{Constraint, Direction, EdgeQuery};
+use super::Query;
+use pattern_core::Subject;
+use std::collections::{BTreeMap, HashSet};
+
+/// The value bound to a query variable.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Binding<'g> {
+    /// A node variable: the merged node subject
+    Node(&'g Subject),
+    /// A single-hop relationship variable
+    Relationship(&'g Subject),
+    /// A variable-length relationship variable: the relationships walked, in query order
+    Path(Vec<&'g Subject>),
+}
+
+/// One match of a query: the subjects bound to each named variable.
+///
+/// Anonymous nodes and relationships (those without an identity in the query)
+/// must match but are not bound.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Row<'g> {
+    bindings: BTreeMap<String, Binding<'g>>,
+}
+
+impl<'g> Row<'g> {
+    /// Returns the binding of `variable`.
+    pub fn get(&self, variable: &str) -> Option<&Binding<'g>> {
+        self.bindings.get(variable)
+    }
+
+    /// Returns the node bound to `variable`.
+    pub fn node(&self, variable: &str) -> Option<&'g Subject> {
+        match self.bindings.get(variable)? {
+            Binding::Node(subject) => Some(subject),
+            _ => None,
+        }
+    }
+
+    /// Returns the single-hop relationship bound to `variable`.
+    pub fn relationship(&self, variable: &str) -> Option<&'g Subject> {
+        match self.bindings.get(variable)? {
+            Binding::Relationship(subject) => Some(subject),
+            _ => None,
+        }
+    }
+
+    /// Returns the relationships bound to a variable-length `variable`.
+    pub fn path(&self, variable: &str) -> Option<&[&'g Subject]> {
+        match self.bindings.get(variable)? {
+            Binding::Path(subjects) => Some(subjects),
+            _ => None,
+        }
+    }
+
+    /// Iterates over the bound variables in alphabetical order.
+    pub fn variables(&self) -> impl Iterator<Item = &str> {
+        self.bindings.keys().map(String::as_str)
+    }
+}
+
+/// Order in which the nodes of one query path are bound.
+struct Plan {
+    anchor: usize,
+    candidates: Option<Vec<usize>>,
+    steps: Vec<Step>,
+}
+
+/// Bind `nodes[to]` from the already bound `nodes[from]` through `edges[edge]`.
+#[derive(Clone, Copy)]
+struct Step {
+    edge: usize,
+    from: usize,
+    to: usize,
+}
+
+#[derive(Clone)]
+enum Bound {
+    Node(usize),
+    Edge(usize),
+    Edges(Vec<usize>),
+}
+
+struct Search<'q, 'g, 'a> {
+    graph: &'g Graph<'a>,
+    query: &'q Query,
+    plans: Vec<Plan>,
+    bindings: BTreeMap<&'q str, Bound>,
+    used: HashSet<usize>,
+    rows: Vec<Row<'g>>,
+}
+
+pub(crate) fn run<'g>(graph: &'g Graph<'_>, query: &Query) -> Vec<Row<'g>> {
+    let mut bound: HashSet<&str> = HashSet::new();
+    let plans = query
+        .paths
+        .iter()
+        .map(|path| {
+            let plan = plan(graph, &path.nodes, &path.edges, &bound);
+            bound.extend(path.nodes.iter().filter_map(|n| n.variable.as_deref()));
+            plan
+        })
+        .collect();
+
+    let mut search = Search {
+        graph,
+        query,
+        plans,
+        bindings: BTreeMap::new(),
+        used: HashSet::new(),
+        rows: Vec::new(),
+    };
+    search.path(0);
+    search.rows
+}
+
+/// Starts each path at its most selective node: one already bound by an earlier
+/// path, otherwise the one with the fewest index candidates. Matching then extends
+/// to the right of the anchor and afterwards to its left.
+fn plan(
+    graph: &Graph<'_>,
+    nodes: &[Constraint],
+    edges: &[EdgeQuery],
+    bound: &HashSet<&str>,
+) -> Plan {
+    let mut best: Option<(usize, usize, Option<Vec<usize>>)> = None;
+    for (i, node) in nodes.iter().enumerate() {
+        let (estimate, candidates) = if node.variable.as_deref().is_some_and(|v| bound.contains(v))
+        {
+            (1, None)
+        } else {
+            let candidates = graph.candidates(node);
+            let estimate = candidates.as_ref().map_or(graph.node_count(), Vec::len);
+            (estimate, candidates)
+        };
+        if best.as_ref().map_or(true, |(_, e, _)| estimate < *e) {
+            best = Some((i, estimate, candidates));
+        }
+    }
+    let (anchor, _, candidates) = best.expect("query paths have at least one node");
+
+    let right = (anchor..edges.len()).map(|i| Step {
+        edge: i,
+        from: i,
+        to: i + 1,
+    });
+    let left = (0..anchor).rev().map(|i| Step {
+        edge: i,
+        from: i + 1,
+        to: i,
+    });
+    Plan {
+        anchor,
+        candidates,
+        steps: right.chain(left).collect(),
+    }
+}
+
+impl<'q, 'g, 'a> Search<'q, 'g, 'a> {
+    /// Matches query path `index`, then the following paths.
+    fn path(&mut self, index: usize) {
+        let query = self.query;
+        let Some(path) = query.paths.get(index) else {
+            self.emit();
+            return;
+        };
+        let plan = &self.plans[index];
+        let anchor = &path.nodes[plan.anchor];
+        let bound = anchor
+            .variable
+            .as_deref()
+            .and_then(|v| match self.bindings.get(v) {
+                Some(Bound::Node(node)) => Some(vec![*node]),
+                _ => None,
+            });
+        let candidates = bound
+            .or_else(|| plan.candidates.clone())
+            .unwrap_or_else(|| (0..self.graph.node_count()).collect());
+
+        let mut positions = vec![usize::MAX; path.nodes.len()];
+        for node in candidates {
+            if let Some(fresh) = self.bind_node(anchor, node) {
+                positions[self.plans[index].anchor] = node;
+                self.step(index, 0, &mut positions);
+                self.unbind(anchor, fresh);
+            }
+        }
+    }
+
+    /// Performs step `step` of path `index`, then the remaining steps.
+    fn step(&mut self, index: usize, step: usize, positions: &mut Vec<usize>) {
+        let Some(&Step { edge, from, to }) = self.plans[index].steps.get(step) else {
+            self.path(index + 1);
+            return;
+        };
+        let query = self.query;
+        let path = &query.paths[index];
+        let edge_query = &path.edges[edge];
+        // Walking right to left reverses the direction the query arrow points
+        let reverse = to < from;
+
+        match edge_query.length {
+            None => {
+                for (e, next) in self.neighbours(positions[from], edge_query.direction, reverse) {
+                    if self.used.contains(&e)
+                        || !matches(&edge_query.constraint, self.graph.edges[e].subject)
+                    {
+                        continue;
+                    }
+                    let Some(fresh) = self.bind_node(&path.nodes[to], next) else {
+                        continue;
+                    };
+                    self.used.insert(e);
+                    if let Some(variable) = edge_query.constraint.variable.as_deref() {
+                        self.bindings.insert(variable, Bound::Edge(e));
+                    }
+                    positions[to] = next;
+                    self.step(index, step + 1, positions);
+                    if let Some(variable) = edge_query.constraint.variable.as_deref() {
+                        self.bindings.remove(variable);
+                    }
+                    self.used.remove(&e);
+                    self.unbind(&path.nodes[to], fresh);
+                }
+            }
+            Some(_) => {
+                let mut hops = Vec::new();
+                self.walk(index, step, positions, positions[from], &mut hops, reverse);
+            }
+        }
+    }
+
+    /// Extends a variable-length step from `current`, trying every trail length
+    /// within the bounds. `hops` holds the relationships walked so far.
+    fn walk(
+        &mut self,
+        index: usize,
+        step: usize,
+        positions: &mut Vec<usize>,
+        current: usize,
+        hops: &mut Vec<usize>,
+        reverse: bool,
+    ) {
+        let query = self.query;
+        let Step { edge, to, .. } = self.plans[index].steps[step];
+        let path = &query.paths[index];
+        let edge_query = &path.edges[edge];
+        let length = edge_query.length.expect("variable-length step");
+
+        if hops.len() >= length.min {
+            if let Some(fresh) = self.bind_node(&path.nodes[to], current) {
+                if let Some(variable) = edge_query.constraint.variable.as_deref() {
+                    let mut edges = hops.clone();
+                    if reverse {
+                        edges.reverse();
+                    }
+                    self.bindings.insert(variable, Bound::Edges(edges));
+                }
+                positions[to] = current;
+                self.step(index, step + 1, positions);
+                if let Some(variable) = edge_query.constraint.variable.as_deref() {
+                    self.bindings.remove(variable);
+                }
+                self.unbind(&path.nodes[to], fresh);
+            }
+        }
+
+        if length.max.is_some_and(|max| hops.len() >= max) {
+            return;
+        }
+        for (e, next) in self.neighbours(current, edge_query.direction, reverse) {
+            if self.used.contains(&e)
+                || !matches(&edge_query.constraint, self.graph.edges[e].subject)
+            {
+                continue;
+            }
+            self.used.insert(e);
+            hops.push(e);
+            self.walk(index, step, positions, next, hops, reverse);
+            hops.pop();
+            self.used.remove(&e);
+        }
+    }
+
+    /// Relationships that can be followed from `node`, with the node they lead to.
+    fn neighbours(&self, node: usize, direction: Direction, reverse: bool) -> Vec<(usize, usize)> {
+        let graph = self.graph;
+        let direction = match (direction, reverse) {
+            (Direction::Forward, true) => Direction::Backward,
+            (Direction::Backward, true) => Direction::Forward,
+            (direction, _) => direction,
+        };
+        match direction {
+            Direction::Forward => graph.outgoing(node).collect(),
+            Direction::Backward => graph.incoming(node).collect(),
+            // Self-loops are reachable both ways; follow them once
+            Direction::Either => graph
+                .outgoing(node)
+                .chain(graph.incoming(node).filter(|&(e, _)| {
+                    let edge = &graph.edges[e];
+                    edge.source != edge.target
+                }))
+                .collect(),
+        }
+    }
+
+    /// Checks `node` against `constraint` and binds its variable.
+    ///
+    /// Returns `None` if the node does not match or the variable is bound to a
+    /// different node, otherwise whether a new binding was made.
+    fn bind_node(&mut self, constraint: &'q Constraint, node: usize) -> Option<bool> {
+        if !matches(constraint, &self.graph.nodes[node]) {
+            return None;
+        }
+        let Some(variable) = constraint.variable.as_deref() else {
+            return Some(false);
+        };
+        match self.bindings.get(variable) {
+            Some(Bound::Node(bound)) if *bound == node => Some(false),
+            Some(_) => None,
+            None => {
+                self.bindings.insert(variable, Bound::Node(node));
+                Some(true)
+            }
+        }
+    }
+
+    fn unbind(&mut self, constraint: &Constraint, fresh: bool) {
+        if fresh {
+            if let Some(variable) = constraint.variable.as_deref() {
+                self.bindings.remove(variable);
+            }
+        }
+    }
+
+    fn emit(&mut self) {
+        let graph = self.graph;
+        let bindings = self
+            .bindings
+            .iter()
+            .map(|(variable, bound)| {
+                let binding = match bound {
+                    Bound::Node(node) => Binding::Node(&graph.nodes[*node]),
+                    Bound::Edge(edge) => Binding::Relationship(graph.edges[*edge].subject),
+                    Bound::Edges(edges) => {
+                        Binding::Path(edges.iter().map(|e| graph.edges[*e].subject).collect())
+                    }
+                };
+                (variable.to_string(), binding)
+            })
+            .collect();
+        self.rows.push(Row { bindings });
+    }
+}
@@ -0,0 +1,151 @@
+//! Declarative pattern queries over gram documents.
+//!
+//! Queries are written in gram notation. Identities in the query are **variables**,
+//! while labels and properties are **filters**:
+//!
+//! ```text
+//! (p:Person)-[:WORKS_AT]->(c:Company {country: 'NZ'})
+//! ```
+//!
+//! finds every `Person` with a `WORKS_AT` relationship to a `Company` whose `country`
+//! is `'NZ'`, binding the person to `p` and the company to `c`.
+//!
+//! # Query Syntax
+//!
+//! - **Nodes** are `(subject)`: `(p)`, `(:Person)`, `(p:Person:Employee {age: 30})`, `()`.
+//!   All labels must be present.
+//! - **Relationships** use any gram arrow (`-->`, `<--`, `--`, `<-->`, and the `=` and `~`
+//!   styles), optionally with a subject: `-[r:KNOWS {since: 2020}]->`. Undirected and
+//!   bidirectional arrows match relationships pointing either way.
+//! - **Variable-length relationships** add `*` inside the brackets: `-[*]->` (one or
+//!   more hops), `-[*2]->` (exactly two), `-[:KNOWS *1..3]->`, `-[*0..]->` (zero or more).
+//!   Labels and properties apply to every hop.
+//! - **Paths** chain any number of segments: `(a)-->(b)<--(c)`.
+//! - **Several paths** are separated by whitespace or commas. A variable used in more
+//!   than one place must bind the same node, which joins the paths.
+//!
+//! Property filters compare integers and decimals numerically and strings and symbols
+//! by text. A range filter (`{age: 30..40}`) matches any numeric value it contains.
+//!
+//! # Results
+//!
+//! A query returns one [`Row`] per match. Within a row each relationship is used at
+//! most once. Node variables bind the merged node (see [`Graph`]); relationship
+//! variables bind the relationship subject, or the list of relationships walked for
+//! variable-length segments.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use gram_codec::parse_gram;
+//! use gram_codec::query::{Graph, Query};
+//!
+//! let patterns = parse_gram(
+//!     "(alice:Person)-[:WORKS_AT]->(acme:Company {country: 'NZ'})
+//!      (bob:Person)-[:WORKS_AT]->(globex:Company {country: 'US'})",
+//! )
+//! .unwrap();
+//! let graph = Graph::new(&patterns);
+//!
+//! let query = Query::parse("(p:Person)-[:WORKS_AT]->(c:Company {country: 'NZ'})").unwrap();
+//! let rows = graph.query(&query);
+//!
+//! assert_eq!(rows.len(), 1);
+//! assert_eq!(rows[0].node("p").unwrap().identity.0, "alice");
+//! assert_eq!(rows[0].node("c").unwrap().identity.0, "acme");
+//! ```
+
+mod graph;
+mod matcher;
+mod parse;
+
+pub use graph::Graph;
+pub use matcher::{Binding, Row};
+
+use crate::parser::ParseError;
+use parse::PathQuery;
+use std::collections::HashSet;
+use thiserror::Error;
+
+/// Errors that can occur when parsing a query
+#[derive(Debug, Error)]
+pub enum QueryError {
+    /// The query is not valid gram query syntax
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+
+    /// The query parses but cannot be run
+    #[error("Invalid query: {0}")]
+    Invalid(String),
+}
+
+/// A parsed query, reusable across graphs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    pub(crate) paths: Vec<PathQuery>,
+}
+
+impl Query {
+    /// Parses query text.
+    ///
+    /// Fails if the text is not valid query syntax, if a variable-length range is
+    /// empty or negative, or if a relationship variable is reused.
+    pub fn parse(input: &str) -> Result<Self, QueryError> {
+        let paths = parse::parse_paths(input)?;
+
+        let nodes: HashSet<&str> = paths
+            .iter()
+            .flat_map(|path| &path.nodes)
+            .filter_map(|node| node.variable.as_deref())
+            .collect();
+        let mut relationships = HashSet::new();
+        for edge in paths.iter().flat_map(|path| &path.edges) {
+            let Some(variable) = edge.constraint.variable.as_deref() else {
+                continue;
+            };
+            if nodes.contains(variable) {
+                return Err(QueryError::Invalid(format!(
+                    "'{}' is used for both a node and a relationship",
+                    variable
+                )));
+            }
+            if !relationships.insert(variable) {
+                return Err(QueryError::Invalid(format!(
+                    "relationship variable '{}' is used more than once",
+                    variable
+                )));
+            }
+        }
+
+        Ok(Query { paths })
+    }
+
+    /// Returns the query's variables in order of first appearance.
+    pub fn variables(&self) -> Vec<&str> {
+        let mut seen = HashSet::new();
+        let mut variables = Vec::new();
+        for path in &self.paths {
+            let mut nodes = path.nodes.iter();
+            let constraints = nodes.next().into_iter().chain(
+                path.edges
+                    .iter()
+                    .zip(nodes)
+                    .flat_map(|(edge, node)| [&edge.constraint, node]),
+            );
+            for variable in constraints.filter_map(|c| c.variable.as_deref()) {
+                if seen.insert(variable) {
+                    variables.push(variable);
+                }
+            }
+        }
+        variables
+    }
+}
+
+impl std::str::FromStr for Query {
+    type Err = QueryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Query::parse(s)
+    }
+}
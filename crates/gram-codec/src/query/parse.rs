@@ -0,0 +1,207 @@
+//! Query text parser, built from the gram notation parser combinators
+
+use super::QueryError;
+use crate::parser::combinators::ws;
+use crate::parser::node::node;
+use crate::parser::relationship::{arrow, arrow_left_part, arrow_right_part, determine_arrow_type};
+use crate::parser::subject::subject;
+use crate::parser::types::{ArrowType, ParseResult};
+use crate::parser::value::{integer, range};
+use crate::parser::{Location, ParseError};
+use nom::{
+    branch::alt,
+    character::complete::char,
+    combinator::{map, opt},
+    multi::{many0, many1},
+    sequence::{delimited, pair, preceded, terminated, tuple},
+};
+use pattern_core::{RangeValue, Subject, Value};
+
+/// Label and property filters for a node or relationship, plus its variable.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Constraint {
+    pub variable: Option<String>,
+    pub labels: Vec<String>,
+    pub properties: Vec<(String, Value)>,
+}
+
+/// Which way a relationship must point, read left to right in the query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Direction {
+    Forward,
+    Backward,
+    Either,
+}
+
+/// Number of hops a variable-length relationship may span.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Length {
+    pub min: usize,
+    pub max: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct EdgeQuery {
+    pub constraint: Constraint,
+    pub direction: Direction,
+    /// `None` for a single hop, `Some` for a `*` segment
+    pub length: Option<Length>,
+}
+
+/// One path of a query: `nodes[i]` and `nodes[i + 1]` are joined by `edges[i]`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PathQuery {
+    pub nodes: Vec<Constraint>,
+    pub edges: Vec<EdgeQuery>,
+}
+
+/// Parses query text into paths. Variable-length bounds are validated here;
+/// variable usage across paths is validated by the caller.
+pub(crate) fn parse_paths(input: &str) -> Result<Vec<PathQuery>, QueryError> {
+    if input.trim().is_empty() {
+        return Err(QueryError::Invalid("query is empty".to_string()));
+    }
+
+    let (remaining, raw) = many1(delimited(ws, path, pair(ws, opt(char(',')))))(input)
+        .map_err(|e| ParseError::from_nom_error(input, e))?;
+
+    if !remaining.trim().is_empty() {
+        let offset = input.len() - remaining.len();
+        return Err(ParseError::UnexpectedInput {
+            location: Location::from_offset(input, offset),
+            snippet: remaining.chars().take(20).collect(),
+        }
+        .into());
+    }
+
+    raw.into_iter()
+        .map(|(first, segments)| {
+            let mut nodes = vec![first];
+            let mut edges = Vec::with_capacity(segments.len());
+            for ((arrow_type, edge, length), next) in segments {
+                edges.push(EdgeQuery {
+                    constraint: Constraint::from(edge),
+                    direction: Direction::from(arrow_type),
+                    length: length.map(Length::try_from).transpose()?,
+                });
+                nodes.push(next);
+            }
+            Ok(PathQuery { nodes, edges })
+        })
+        .collect()
+}
+
+type Segment = ((ArrowType, Subject, Option<Option<RangeValue>>), Constraint);
+
+fn path(input: &str) -> ParseResult<'_, (Constraint, Vec<Segment>)> {
+    pair(node_constraint, many0(pair(edge, node_constraint)))(input)
+}
+
+fn node_constraint(input: &str) -> ParseResult<'_, Constraint> {
+    map(node, |pattern| Constraint::from(pattern.value))(input)
+}
+
+/// Parse an arrow, optionally with a bracketed subject and `*` length: `-[r:KNOWS *1..3]->`
+fn edge(input: &str) -> ParseResult<'_, (ArrowType, Subject, Option<Option<RangeValue>>)> {
+    alt((
+        map(
+            tuple((
+                ws,
+                arrow_left_part,
+                char('['),
+                delimited(ws, subject, ws),
+                opt(terminated(length, ws)),
+                char(']'),
+                arrow_right_part,
+                ws,
+            )),
+            |(_, left, _, subject, length, _, right, _)| {
+                (determine_arrow_type(left, right), subject, length)
+            },
+        ),
+        map(arrow, |arrow_type| (arrow_type, empty_subject(), None)),
+    ))(input)
+}
+
+/// Parse a length: `*`, `*2`, `*1..3`, `*2..` or `*..3`
+fn length(input: &str) -> ParseResult<'_, Option<RangeValue>> {
+    preceded(
+        char('*'),
+        preceded(
+            ws,
+            opt(alt((
+                range,
+                map(integer, |n| RangeValue {
+                    lower: Some(n as f64),
+                    upper: Some(n as f64),
+                }),
+            ))),
+        ),
+    )(input)
+}
+
+fn empty_subject() -> Subject {
+    Subject {
+        identity: pattern_core::Symbol(String::new()),
+        labels: std::collections::HashSet::new(),
+        properties: std::collections::HashMap::new(),
+    }
+}
+
+impl From<Subject> for Constraint {
+    fn from(subject: Subject) -> Self {
+        let mut labels: Vec<String> = subject.labels.into_iter().collect();
+        labels.sort();
+        let mut properties: Vec<(String, Value)> = subject.properties.into_iter().collect();
+        properties.sort_by(|a, b| a.0.cmp(&b.0));
+        Constraint {
+            variable: Some(subject.identity.0).filter(|id| !id.is_empty()),
+            labels,
+            properties,
+        }
+    }
+}
+
+impl From<ArrowType> for Direction {
+    fn from(arrow: ArrowType) -> Self {
+        if arrow.is_backward() {
+            Direction::Backward
+        } else if arrow.is_forward() {
+            Direction::Forward
+        } else {
+            Direction::Either
+        }
+    }
+}
+
+impl TryFrom<Option<RangeValue>> for Length {
+    type Error = QueryError;
+
+    /// A bare `*` is one or more hops; a missing lower bound also means one.
+    fn try_from(range: Option<RangeValue>) -> Result<Self, QueryError> {
+        let range = range.unwrap_or(RangeValue {
+            lower: None,
+            upper: None,
+        });
+        let bound = |b: f64| {
+            if b < 0.0 {
+                Err(QueryError::Invalid(format!(
+                    "variable-length bound {} is negative",
+                    b
+                )))
+            } else {
+                Ok(b as usize)
+            }
+        };
+        let min = range.lower.map(bound).transpose()?.unwrap_or(1);
+        let max = range.upper.map(bound).transpose()?;
+        if max.is_some_and(|max| max < min) {
+            return Err(QueryError::Invalid(format!(
+                "variable-length range *{}..{} is empty",
+                min,
+                max.unwrap_or_default()
+            )));
+        }
+        Ok(Length { min, max })
+    }
+}
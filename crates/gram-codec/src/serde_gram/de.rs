@@ -0,0 +1,554 @@
+//! Deserialization of `serde::Deserialize` types from gram notation
+//!
+//! The input is parsed with [`crate::parse_gram`] and the resulting
+//! `Pattern<Subject>`s are then walked by the deserializers below.
+
+use super::error::{Error, Result};
+use pattern_core::{Pattern, RangeValue, Subject, Value};
+use serde::de::value::{BorrowedStrDeserializer, MapDeserializer};
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess,
+    Unexpected, VariantAccess, Visitor,
+};
+use serde::forward_to_deserialize_any;
+use serde::Deserialize;
+
+// ============================================================================
+// Entry Points
+// ============================================================================
+
+/// Deserializes a value from gram notation.
+///
+/// See the [module documentation](super) for how gram notation is mapped to
+/// Rust types.
+///
+/// # Errors
+///
+/// Returns [`Error::Parse`] if `input` is not valid gram notation, and
+/// [`Error::Message`] if it does not match the shape of `T`.
+pub fn from_str<T: DeserializeOwned>(input: &str) -> Result<T> {
+    let patterns = crate::parse_gram(input)?;
+    from_patterns(&patterns)
+}
+
+/// Deserializes a value from already parsed patterns.
+///
+/// Strings may be borrowed from `patterns`.
+pub fn from_patterns<'de, T: Deserialize<'de>>(patterns: &'de [Pattern<Subject>]) -> Result<T> {
+    T::deserialize(DocumentDeserializer { patterns })
+}
+
+// ============================================================================
+// Document Deserializer
+// ============================================================================
+
+/// Deserializes the top-level patterns: as a sequence, or as a single pattern.
+struct DocumentDeserializer<'de> {
+    patterns: &'de [Pattern<Subject>],
+}
+
+impl<'de> DocumentDeserializer<'de> {
+    /// The only pattern, or an empty one if there are none.
+    fn single(&self) -> Result<PatternDeserializer<'de>> {
+        match self.patterns {
+            [] => Ok(PatternDeserializer {
+                subject: None,
+                elements: &[],
+            }),
+            [pattern] => Ok(PatternDeserializer::new(pattern)),
+            _ => Err(Error::Message(format!(
+                "expected a single pattern, found {}",
+                self.patterns.len()
+            ))),
+        }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for DocumentDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        if self.patterns.len() == 1 {
+            self.single()?.deserialize_any(visitor)
+        } else {
+            self.deserialize_seq(visitor)
+        }
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_seq(Seq(self.patterns.iter().map(PatternDeserializer::new)))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        if self.patterns.is_empty() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.single()?.deserialize_unit(visitor)
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.single()?.deserialize_map(visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.single()?.deserialize_struct(name, fields, visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.single()?.deserialize_enum(name, variants, visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf identifier ignored_any
+    }
+}
+
+// ============================================================================
+// Pattern Deserializer
+// ============================================================================
+
+/// Deserializes one pattern: properties as a struct or map, labels as an enum
+/// variant, elements as a sequence.
+struct PatternDeserializer<'de> {
+    /// `None` stands for the empty document
+    subject: Option<&'de Subject>,
+    elements: &'de [Pattern<Subject>],
+}
+
+impl<'de> PatternDeserializer<'de> {
+    fn new(pattern: &'de Pattern<Subject>) -> Self {
+        PatternDeserializer {
+            subject: Some(&pattern.value),
+            elements: &pattern.elements,
+        }
+    }
+
+    fn properties(&self) -> Map<'de, impl Iterator<Item = (&'de String, &'de Value)>> {
+        Map {
+            entries: self.subject.into_iter().flat_map(|s| s.properties.iter()),
+            value: None,
+        }
+    }
+
+    fn has_properties_or_labels(&self) -> bool {
+        self.subject
+            .is_some_and(|s| !s.properties.is_empty() || !s.labels.is_empty())
+    }
+}
+
+impl<'de> de::Deserializer<'de> for PatternDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        if !self.elements.is_empty() && !self.has_properties_or_labels() {
+            self.deserialize_seq(visitor)
+        } else {
+            self.deserialize_map(visitor)
+        }
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_seq(Seq(self.elements.iter().map(PatternDeserializer::new)))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_map(self.properties())
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        let mut labels = self.subject.into_iter().flat_map(|s| s.labels.iter());
+        match (labels.next(), labels.next()) {
+            (Some(label), None) => visitor.visit_enum(LabelledPattern {
+                label,
+                pattern: self,
+            }),
+            (None, _) => Err(Error::Message(format!(
+                "expected a label naming a variant of `{}`, found none",
+                name
+            ))),
+            (Some(_), Some(_)) => Err(Error::Message(format!(
+                "expected a single label naming a variant of `{}`, found several",
+                name
+            ))),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf identifier ignored_any
+    }
+}
+
+/// A pattern whose single label selects an enum variant.
+struct LabelledPattern<'de> {
+    label: &'de str,
+    pattern: PatternDeserializer<'de>,
+}
+
+impl<'de> EnumAccess<'de> for LabelledPattern<'de> {
+    type Error = Error;
+    type Variant = PatternDeserializer<'de>;
+
+    fn variant_seed<S: DeserializeSeed<'de>>(self, seed: S) -> Result<(S::Value, Self::Variant)> {
+        let variant = seed.deserialize(BorrowedStrDeserializer::<Error>::new(self.label))?;
+        Ok((variant, self.pattern))
+    }
+}
+
+impl<'de> VariantAccess<'de> for PatternDeserializer<'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<S: DeserializeSeed<'de>>(self, seed: S) -> Result<S::Value> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, _visitor: V) -> Result<V::Value> {
+        Err(Error::Message(
+            "tuple variants cannot be read from a labelled pattern".to_string(),
+        ))
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_map(self.properties())
+    }
+}
+
+// ============================================================================
+// Value Deserializer
+// ============================================================================
+
+/// Deserializes a property value.
+struct ValueDeserializer<'de>(&'de Value);
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.0 {
+            Value::VInteger(i) => visitor.visit_i64(*i),
+            Value::VDecimal(d) => visitor.visit_f64(*d),
+            Value::VBoolean(b) => visitor.visit_bool(*b),
+            Value::VString(s) | Value::VSymbol(s) => visitor.visit_borrowed_str(s),
+            Value::VArray(items) => visitor.visit_seq(Seq(items.iter().map(ValueDeserializer))),
+            Value::VMap(map) => visitor.visit_map(Map {
+                entries: map.iter(),
+                value: None,
+            }),
+            Value::VTaggedString { tag, content } => visitor.visit_map(fields([
+                ("tag", Some(Scalar::Str(tag))),
+                ("content", Some(Scalar::Str(content))),
+            ])),
+            Value::VRange(RangeValue { lower, upper }) => visitor.visit_map(fields([
+                ("lower", lower.map(Scalar::F64)),
+                ("upper", upper.map(Scalar::F64)),
+            ])),
+            Value::VMeasurement { unit, value } => visitor.visit_map(fields([
+                ("unit", Some(Scalar::Str(unit))),
+                ("value", Some(Scalar::F64(*value))),
+            ])),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        match self.0 {
+            Value::VString(s) | Value::VSymbol(s) => {
+                visitor.visit_enum(BorrowedStrDeserializer::<Error>::new(s))
+            }
+            Value::VMap(map) if map.len() == 1 => {
+                let (variant, content) = map.iter().next().unwrap();
+                visitor.visit_enum(TaggedValue { variant, content })
+            }
+            other => Err(de::Error::invalid_type(
+                unexpected(other),
+                &format!(
+                    "a symbol or single-entry map naming a variant of `{}`",
+                    name
+                )
+                .as_str(),
+            )),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+fn unexpected(value: &Value) -> Unexpected<'_> {
+    match value {
+        Value::VInteger(i) => Unexpected::Signed(*i),
+        Value::VDecimal(d) => Unexpected::Float(*d),
+        Value::VBoolean(b) => Unexpected::Bool(*b),
+        Value::VString(s) | Value::VSymbol(s) => Unexpected::Str(s),
+        Value::VArray(_) => Unexpected::Seq,
+        Value::VMap(_) => Unexpected::Map,
+        other => Unexpected::Other(other.type_name()),
+    }
+}
+
+/// `{Variant: content}`, an externally tagged enum inside a property value.
+struct TaggedValue<'de> {
+    variant: &'de str,
+    content: &'de Value,
+}
+
+impl<'de> EnumAccess<'de> for TaggedValue<'de> {
+    type Error = Error;
+    type Variant = ValueDeserializer<'de>;
+
+    fn variant_seed<S: DeserializeSeed<'de>>(self, seed: S) -> Result<(S::Value, Self::Variant)> {
+        let variant = seed.deserialize(BorrowedStrDeserializer::<Error>::new(self.variant))?;
+        Ok((variant, ValueDeserializer(self.content)))
+    }
+}
+
+impl<'de> VariantAccess<'de> for ValueDeserializer<'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        Err(de::Error::invalid_type(
+            unexpected(self.0),
+            &"a unit variant",
+        ))
+    }
+
+    fn newtype_variant_seed<S: DeserializeSeed<'de>>(self, seed: S) -> Result<S::Value> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        de::Deserializer::deserialize_seq(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        de::Deserializer::deserialize_map(self, visitor)
+    }
+}
+
+// ============================================================================
+// Access Helpers
+// ============================================================================
+
+/// Sequence access over an iterator of deserializers.
+struct Seq<I>(I);
+
+impl<'de, I, D> SeqAccess<'de> for Seq<I>
+where
+    I: Iterator<Item = D>,
+    D: de::Deserializer<'de, Error = Error>,
+{
+    type Error = Error;
+
+    fn next_element_seed<S: DeserializeSeed<'de>>(&mut self, seed: S) -> Result<Option<S::Value>> {
+        self.0.next().map(|d| seed.deserialize(d)).transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        match self.0.size_hint() {
+            (lower, Some(upper)) if lower == upper => Some(upper),
+            _ => None,
+        }
+    }
+}
+
+/// Map access over string-keyed values.
+struct Map<'de, I> {
+    entries: I,
+    value: Option<&'de Value>,
+}
+
+impl<'de, I> MapAccess<'de> for Map<'de, I>
+where
+    I: Iterator<Item = (&'de String, &'de Value)>,
+{
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        match self.entries.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(BorrowedStrDeserializer::<Error>::new(key))
+                    .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<S: DeserializeSeed<'de>>(&mut self, seed: S) -> Result<S::Value> {
+        let value = self
+            .value
+            .take()
+            .ok_or_else(|| Error::Message("map value requested before its key".to_string()))?;
+        seed.deserialize(ValueDeserializer(value))
+    }
+}
+
+/// A string or number field of a tagged string, range or measurement.
+#[derive(Clone, Copy)]
+enum Scalar<'de> {
+    Str(&'de str),
+    F64(f64),
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Scalar<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Scalar<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Scalar::Str(s) => visitor.visit_borrowed_str(s),
+            Scalar::F64(d) => visitor.visit_f64(d),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+/// The present fields of a tagged string, range or measurement, as a map.
+fn fields<'de>(
+    fields: [(&'static str, Option<Scalar<'de>>); 2],
+) -> impl MapAccess<'de, Error = Error> {
+    MapDeserializer::new(fields.into_iter().filter_map(|(k, v)| v.map(|v| (k, v))))
+}
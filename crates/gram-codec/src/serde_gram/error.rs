@@ -0,0 +1,37 @@
+//! Error type for serde_gram
+
+use crate::ParseError;
+use std::fmt;
+use thiserror::Error;
+
+/// Errors produced while serializing to or deserializing from gram notation
+#[derive(Debug, Error)]
+pub enum Error {
+    /// The input is not valid gram notation
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+
+    /// The Rust value has no representation in gram notation
+    /// (e.g. a `None` inside a sequence, or a non-finite float)
+    #[error("Cannot serialize to gram notation: {0}")]
+    Unsupported(String),
+
+    /// The gram data does not match the shape of the target type
+    #[error("{0}")]
+    Message(String),
+}
+
+impl serde::ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::Message(msg.to_string())
+    }
+}
+
+impl serde::de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::Message(msg.to_string())
+    }
+}
+
+/// Result alias for serde_gram operations
+pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,74 @@
+//! Serde data format for gram notation
+//!
+//! This module serializes any `serde::Serialize` type to gram notation and
+//! deserializes any `serde::Deserialize` type from it, in the style of
+//! `serde_json`:
+//!
+//! ```rust
+//! use gram_codec::serde_gram;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize, Debug, PartialEq)]
+//! struct Person {
+//!     name: String,
+//!     age: u32,
+//!     email: Option<String>,
+//! }
+//!
+//! let people = vec![
+//!     Person { name: "Alice".into(), age: 30, email: None },
+//!     Person { name: "Bob".into(), age: 25, email: Some("bob@example.com".into()) },
+//! ];
+//!
+//! let gram = serde_gram::to_string(&people).unwrap();
+//! assert_eq!(
+//!     gram,
+//!     "({age: 30, name: \"Alice\"})\n({age: 25, email: \"bob@example.com\", name: \"Bob\"})"
+//! );
+//!
+//! let back: Vec<Person> = serde_gram::from_str(&gram).unwrap();
+//! assert_eq!(back, people);
+//! ```
+//!
+//! # Mapping
+//!
+//! At the top level and inside sequences of patterns:
+//!
+//! | Rust | Gram |
+//! |------|------|
+//! | struct, map | node whose record holds the fields: `({name: "Alice"})` |
+//! | enum variant | node labelled with the variant name: `(:Circle {radius: 1.0})` |
+//! | sequence, tuple | one pattern per item at the top level, `[ \| items]` when nested |
+//! | `None`, `()` | empty document |
+//!
+//! Inside records (property values):
+//!
+//! | Rust | Gram |
+//! |------|------|
+//! | integers, floats, `bool` | `42`, `1.5`, `true` |
+//! | `String`, `char` | `"text"` |
+//! | sequence, tuple | array: `[1, 2, 3]` |
+//! | struct, map | map: `{city: "Oslo"}` |
+//! | unit variant | symbol: `active` |
+//! | other variants | single-entry map: `{Circle: {radius: 1.0}}` |
+//! | `None` | the property is omitted |
+//!
+//! Map keys must be strings. Identities are not used: patterns are written
+//! anonymously and identities are ignored when reading. When reading, a
+//! tagged string, range or measurement value deserializes as a map with the
+//! fields `tag`/`content`, `lower`/`upper` or `unit`/`value`.
+//!
+//! # Limitations
+//!
+//! Some Rust values have no gram representation and fail with
+//! [`Error::Unsupported`]: `None` inside arrays, integers above `i64::MAX`,
+//! non-finite floats, tuple variants at pattern level, and nested map keys that
+//! are not plain identifiers.
+
+mod de;
+mod error;
+mod ser;
+
+pub use de::{from_patterns, from_str};
+pub use error::{Error, Result};
+pub use ser::{to_patterns, to_string};
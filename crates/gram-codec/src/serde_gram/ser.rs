@@ -0,0 +1,949 @@
+//! Serialization of `serde::Serialize` types to gram notation
+//!
+//! Serialization happens in two steps: the Rust value is first converted to
+//! `Pattern<Subject>`s and property [`Value`]s, which are then written as text.
+
+use super::error::{Error, Result};
+use crate::serializer::{escape_string, quote_identifier};
+use pattern_core::{Pattern, RangeValue, Subject, Symbol, Value};
+use serde::ser::{self, Impossible, Serialize};
+use std::collections::{HashMap, HashSet};
+
+// ============================================================================
+// Entry Points
+// ============================================================================
+
+/// Serializes a value as gram notation.
+///
+/// See the [module documentation](super) for how Rust types are mapped.
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> Result<String> {
+    let patterns = to_patterns(value)?;
+    let lines = patterns
+        .iter()
+        .map(write_pattern)
+        .collect::<Result<Vec<_>>>()?;
+    Ok(lines.join("\n"))
+}
+
+/// Serializes a value to the patterns its gram notation would contain.
+///
+/// A top-level sequence yields one pattern per item; `None`, `()` and empty
+/// sequences yield no patterns; anything else yields exactly one pattern.
+pub fn to_patterns<T: Serialize + ?Sized>(value: &T) -> Result<Vec<Pattern<Subject>>> {
+    let pattern = value.serialize(PatternSerializer { top_level: true })?;
+    if is_anonymous(&pattern.value) {
+        Ok(pattern.elements)
+    } else {
+        Ok(vec![pattern])
+    }
+}
+
+fn is_anonymous(subject: &Subject) -> bool {
+    subject.identity.0.is_empty() && subject.labels.is_empty() && subject.properties.is_empty()
+}
+
+fn subject(label: Option<&str>, properties: HashMap<String, Value>) -> Subject {
+    Subject {
+        identity: Symbol(String::new()),
+        labels: label
+            .map(str::to_string)
+            .into_iter()
+            .collect::<HashSet<_>>(),
+        properties,
+    }
+}
+
+fn unsupported(what: &str, position: &str) -> Error {
+    Error::Unsupported(format!("{} cannot be represented {}", what, position))
+}
+
+// ============================================================================
+// Pattern Serializer
+// ============================================================================
+
+/// Serializes structs, maps and enums as patterns, and sequences as patterns
+/// with elements.
+struct PatternSerializer {
+    /// `None` is allowed only as the whole document
+    top_level: bool,
+}
+
+const AT_PATTERN_LEVEL: &str = "as a pattern; expected a struct, map, enum or sequence of those";
+
+macro_rules! reject_scalars {
+    ($($method:ident($ty:ty) => $what:expr),* $(,)?) => {
+        $(
+            fn $method(self, _v: $ty) -> Result<Self::Ok> {
+                Err(unsupported($what, AT_PATTERN_LEVEL))
+            }
+        )*
+    };
+}
+
+impl ser::Serializer for PatternSerializer {
+    type Ok = Pattern<Subject>;
+    type Error = Error;
+    type SerializeSeq = ElementsSerializer;
+    type SerializeTuple = ElementsSerializer;
+    type SerializeTupleStruct = ElementsSerializer;
+    type SerializeTupleVariant = Impossible<Self::Ok, Error>;
+    type SerializeMap = PropertiesSerializer;
+    type SerializeStruct = PropertiesSerializer;
+    type SerializeStructVariant = PropertiesSerializer;
+
+    reject_scalars! {
+        serialize_bool(bool) => "a boolean",
+        serialize_i8(i8) => "an integer",
+        serialize_i16(i16) => "an integer",
+        serialize_i32(i32) => "an integer",
+        serialize_i64(i64) => "an integer",
+        serialize_u8(u8) => "an integer",
+        serialize_u16(u16) => "an integer",
+        serialize_u32(u32) => "an integer",
+        serialize_u64(u64) => "an integer",
+        serialize_f32(f32) => "a decimal",
+        serialize_f64(f64) => "a decimal",
+        serialize_char(char) => "a character",
+        serialize_str(&str) => "a string",
+        serialize_bytes(&[u8]) => "a byte array",
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok> {
+        if self.top_level {
+            Ok(Pattern::point(subject(None, HashMap::new())))
+        } else {
+            Err(unsupported("None", "inside a sequence of patterns"))
+        }
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Self::Ok> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok> {
+        Ok(Pattern::point(subject(None, HashMap::new())))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok> {
+        Ok(Pattern::point(subject(Some(variant), HashMap::new())))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok> {
+        let mut pattern = value.serialize(PatternSerializer { top_level: false })?;
+        if !pattern.value.labels.is_empty() {
+            return Err(unsupported(
+                &format!("newtype variant `{}` wrapping an enum", variant),
+                AT_PATTERN_LEVEL,
+            ));
+        }
+        pattern.value.labels.insert(variant.to_string());
+        Ok(pattern)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Ok(ElementsSerializer {
+            elements: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(unsupported(
+            &format!("tuple variant `{}`", variant),
+            AT_PATTERN_LEVEL,
+        ))
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap> {
+        Ok(PropertiesSerializer::new(None, len.unwrap_or(0)))
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
+        Ok(PropertiesSerializer::new(None, len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Ok(PropertiesSerializer::new(Some(variant), len))
+    }
+}
+
+/// Collects sequence items as the elements of an anonymous pattern.
+struct ElementsSerializer {
+    elements: Vec<Pattern<Subject>>,
+}
+
+impl ElementsSerializer {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.elements
+            .push(value.serialize(PatternSerializer { top_level: false })?);
+        Ok(())
+    }
+
+    fn finish(self) -> Pattern<Subject> {
+        Pattern::pattern(subject(None, HashMap::new()), self.elements)
+    }
+}
+
+impl ser::SerializeSeq for ElementsSerializer {
+    type Ok = Pattern<Subject>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeTuple for ElementsSerializer {
+    type Ok = Pattern<Subject>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeTupleStruct for ElementsSerializer {
+    type Ok = Pattern<Subject>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(self.finish())
+    }
+}
+
+/// Collects struct fields or map entries as subject properties.
+///
+/// `None` fields are omitted.
+struct PropertiesSerializer {
+    label: Option<&'static str>,
+    properties: HashMap<String, Value>,
+    key: Option<String>,
+}
+
+impl PropertiesSerializer {
+    fn new(label: Option<&'static str>, len: usize) -> Self {
+        PropertiesSerializer {
+            label,
+            properties: HashMap::with_capacity(len),
+            key: None,
+        }
+    }
+
+    fn insert<T: Serialize + ?Sized>(&mut self, key: String, value: &T) -> Result<()> {
+        if let Some(value) = value.serialize(ValueSerializer)? {
+            self.properties.insert(key, value);
+        }
+        Ok(())
+    }
+
+    fn into_value(self) -> Value {
+        let map = Value::VMap(self.properties);
+        match self.label {
+            Some(variant) => Value::VMap(HashMap::from([(variant.to_string(), map)])),
+            None => map,
+        }
+    }
+}
+
+impl ser::SerializeMap for PropertiesSerializer {
+    type Ok = Pattern<Subject>;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        self.key = Some(key.serialize(KeySerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| Error::Message("map value without a key".to_string()))?;
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(Pattern::point(subject(self.label, self.properties)))
+    }
+}
+
+impl ser::SerializeStruct for PropertiesSerializer {
+    type Ok = Pattern<Subject>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.insert(key.to_string(), value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(Pattern::point(subject(self.label, self.properties)))
+    }
+}
+
+impl ser::SerializeStructVariant for PropertiesSerializer {
+    type Ok = Pattern<Subject>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.insert(key.to_string(), value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(Pattern::point(subject(self.label, self.properties)))
+    }
+}
+
+// ============================================================================
+// Value Serializer
+// ============================================================================
+
+/// Serializes property values. Returns `None` for `Option::None`, which the
+/// caller omits.
+struct ValueSerializer;
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Option<Value>;
+    type Error = Error;
+    type SerializeSeq = ArraySerializer;
+    type SerializeTuple = ArraySerializer;
+    type SerializeTupleStruct = ArraySerializer;
+    type SerializeTupleVariant = ArraySerializer;
+    type SerializeMap = MapSerializer;
+    type SerializeStruct = MapSerializer;
+    type SerializeStructVariant = MapSerializer;
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok> {
+        Ok(Some(Value::VBoolean(v)))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok> {
+        Ok(Some(Value::VInteger(v)))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok> {
+        i64::try_from(v)
+            .map(|v| Some(Value::VInteger(v)))
+            .map_err(|_| unsupported(&format!("integer {}", v), "as a 64-bit signed integer"))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok> {
+        self.serialize_f64(f64::from(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok> {
+        Ok(Some(Value::VDecimal(v)))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok> {
+        Ok(Some(Value::VString(v.to_string())))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok> {
+        Ok(Some(Value::VString(v.to_string())))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok> {
+        Ok(Some(Value::VArray(
+            v.iter().map(|b| Value::VInteger(i64::from(*b))).collect(),
+        )))
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok> {
+        Ok(None)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Self::Ok> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok> {
+        Err(unsupported("()", "as a property value"))
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<Self::Ok> {
+        Err(unsupported(
+            &format!("unit struct `{}`", name),
+            "as a property value",
+        ))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok> {
+        Ok(Some(Value::VSymbol(variant.to_string())))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok> {
+        let value = value.serialize(ValueSerializer)?.ok_or_else(|| {
+            unsupported(
+                &format!("None inside variant `{}`", variant),
+                "as a property value",
+            )
+        })?;
+        Ok(Some(Value::VMap(HashMap::from([(
+            variant.to_string(),
+            value,
+        )]))))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Ok(ArraySerializer {
+            variant: None,
+            items: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Ok(ArraySerializer {
+            variant: Some(variant),
+            items: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap> {
+        Ok(MapSerializer(PropertiesSerializer::new(
+            None,
+            len.unwrap_or(0),
+        )))
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
+        Ok(MapSerializer(PropertiesSerializer::new(None, len)))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Ok(MapSerializer(PropertiesSerializer::new(Some(variant), len)))
+    }
+}
+
+/// Collects sequence items as a `VArray`; tuple variants are wrapped as
+/// `{Variant: [...]}`.
+struct ArraySerializer {
+    variant: Option<&'static str>,
+    items: Vec<Value>,
+}
+
+impl ArraySerializer {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let value = value
+            .serialize(ValueSerializer)?
+            .ok_or_else(|| unsupported("None", "inside an array"))?;
+        self.items.push(value);
+        Ok(())
+    }
+
+    fn finish(self) -> Option<Value> {
+        let array = Value::VArray(self.items);
+        Some(match self.variant {
+            Some(variant) => Value::VMap(HashMap::from([(variant.to_string(), array)])),
+            None => array,
+        })
+    }
+}
+
+impl ser::SerializeSeq for ArraySerializer {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeTuple for ArraySerializer {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeTupleStruct for ArraySerializer {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeTupleVariant for ArraySerializer {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(self.finish())
+    }
+}
+
+/// Collects struct fields or map entries as a `VMap`; struct variants are
+/// wrapped as `{Variant: {...}}`.
+struct MapSerializer(PropertiesSerializer);
+
+impl ser::SerializeMap for MapSerializer {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        ser::SerializeMap::serialize_key(&mut self.0, key)
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeMap::serialize_value(&mut self.0, value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(Some(self.0.into_value()))
+    }
+}
+
+impl ser::SerializeStruct for MapSerializer {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.0.insert(key.to_string(), value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(Some(self.0.into_value()))
+    }
+}
+
+impl ser::SerializeStructVariant for MapSerializer {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.0.insert(key.to_string(), value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(Some(self.0.into_value()))
+    }
+}
+
+// ============================================================================
+// Key Serializer
+// ============================================================================
+
+/// Serializes map keys, which must be strings (or chars, or unit variants).
+struct KeySerializer;
+
+const AS_KEY: &str = "as a map key; keys must be strings";
+
+macro_rules! reject_keys {
+    ($($method:ident($ty:ty) => $what:expr),* $(,)?) => {
+        $(
+            fn $method(self, _v: $ty) -> Result<String> {
+                Err(unsupported($what, AS_KEY))
+            }
+        )*
+    };
+}
+
+impl ser::Serializer for KeySerializer {
+    type Ok = String;
+    type Error = Error;
+    type SerializeSeq = Impossible<String, Error>;
+    type SerializeTuple = Impossible<String, Error>;
+    type SerializeTupleStruct = Impossible<String, Error>;
+    type SerializeTupleVariant = Impossible<String, Error>;
+    type SerializeMap = Impossible<String, Error>;
+    type SerializeStruct = Impossible<String, Error>;
+    type SerializeStructVariant = Impossible<String, Error>;
+
+    reject_keys! {
+        serialize_bool(bool) => "a boolean",
+        serialize_i8(i8) => "an integer",
+        serialize_i16(i16) => "an integer",
+        serialize_i32(i32) => "an integer",
+        serialize_i64(i64) => "an integer",
+        serialize_u8(u8) => "an integer",
+        serialize_u16(u16) => "an integer",
+        serialize_u32(u32) => "an integer",
+        serialize_u64(u64) => "an integer",
+        serialize_f32(f32) => "a decimal",
+        serialize_f64(f64) => "a decimal",
+        serialize_bytes(&[u8]) => "a byte array",
+    }
+
+    fn serialize_char(self, v: char) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_none(self) -> Result<String> {
+        Err(unsupported("None", AS_KEY))
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<String> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<String> {
+        Err(unsupported("()", AS_KEY))
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<String> {
+        Err(unsupported(&format!("unit struct `{}`", name), AS_KEY))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<String> {
+        Ok(variant.to_string())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<String> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _value: &T,
+    ) -> Result<String> {
+        Err(unsupported(&format!("variant `{}`", variant), AS_KEY))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(unsupported("a sequence", AS_KEY))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(unsupported("a tuple", AS_KEY))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Err(unsupported(&format!("tuple struct `{}`", name), AS_KEY))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(unsupported(&format!("variant `{}`", variant), AS_KEY))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(unsupported("a map", AS_KEY))
+    }
+
+    fn serialize_struct(self, name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Err(unsupported(&format!("struct `{}`", name), AS_KEY))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(unsupported(&format!("variant `{}`", variant), AS_KEY))
+    }
+}
+
+// ============================================================================
+// Writer
+// ============================================================================
+
+/// Writes `(subject)` for atomic patterns and `[subject | elements]` otherwise.
+///
+/// Unlike [`crate::to_gram_pattern`], this never uses relationship or bare
+/// record notation, so the shape of the output depends only on the Rust type.
+fn write_pattern(pattern: &Pattern<Subject>) -> Result<String> {
+    let subject = write_subject(&pattern.value)?;
+    if pattern.elements.is_empty() {
+        return Ok(format!("({})", subject));
+    }
+    let elements = pattern
+        .elements
+        .iter()
+        .map(write_pattern)
+        .collect::<Result<Vec<_>>>()?;
+    Ok(format!("[{} | {}]", subject, elements.join(", ")))
+}
+
+fn write_subject(subject: &Subject) -> Result<String> {
+    let mut out = String::new();
+    if !subject.identity.0.is_empty() {
+        out.push_str(&quote_identifier(&subject.identity.0));
+    }
+    let mut labels: Vec<&String> = subject.labels.iter().collect();
+    labels.sort();
+    for label in labels {
+        out.push(':');
+        out.push_str(&quote_identifier(label));
+    }
+    if !subject.properties.is_empty() {
+        if !out.is_empty() {
+            out.push(' ');
+        }
+        let mut properties: Vec<(&String, &Value)> = subject.properties.iter().collect();
+        properties.sort_by_key(|(k, _)| *k);
+        let entries = properties
+            .into_iter()
+            .map(|(k, v)| Ok(format!("{}: {}", quote_identifier(k), write_value(v)?)))
+            .collect::<Result<Vec<_>>>()?;
+        out.push_str(&format!("{{{}}}", entries.join(", ")));
+    }
+    Ok(out)
+}
+
+fn write_value(value: &Value) -> Result<String> {
+    Ok(match value {
+        Value::VInteger(i) => i.to_string(),
+        Value::VDecimal(d) => write_decimal(*d)?,
+        Value::VBoolean(b) => b.to_string(),
+        Value::VString(s) => format!("\"{}\"", escape_string(s)),
+        Value::VSymbol(s) if is_bare_symbol(s) => s.clone(),
+        Value::VSymbol(s) => format!("\"{}\"", escape_string(s)),
+        Value::VTaggedString { tag, content } => {
+            format!(
+                "{}`{}`",
+                tag,
+                content.replace('\\', "\\\\").replace('`', "\\`")
+            )
+        }
+        Value::VArray(items) => {
+            let items = items.iter().map(write_value).collect::<Result<Vec<_>>>()?;
+            format!("[{}]", items.join(", "))
+        }
+        Value::VMap(map) => {
+            let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+            entries.sort_by_key(|(k, _)| *k);
+            let entries = entries
+                .into_iter()
+                .map(|(k, v)| {
+                    // Map values only accept unquoted keys
+                    if !is_bare_key(k) {
+                        return Err(unsupported(
+                            &format!("nested map key {:?}", k),
+                            "without quoting",
+                        ));
+                    }
+                    Ok(format!("{}: {}", k, write_value(v)?))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            format!("{{{}}}", entries.join(", "))
+        }
+        Value::VRange(RangeValue { lower, upper }) => {
+            let bound = |b: &Option<f64>| match b {
+                Some(x) if x.fract() == 0.0 => Ok(format!("{}", *x as i64)),
+                Some(x) => Err(unsupported(
+                    &format!("range bound {}", x),
+                    "unless it is an integer",
+                )),
+                None => Ok(String::new()),
+            };
+            format!("{}..{}", bound(lower)?, bound(upper)?)
+        }
+        Value::VMeasurement { unit, value } => format!("{}{}", write_decimal(*value)?, unit),
+    })
+}
+
+/// Writes a float so that it parses back as a decimal (always with a fraction,
+/// never in exponent notation).
+fn write_decimal(d: f64) -> Result<String> {
+    if !d.is_finite() {
+        return Err(unsupported(&format!("decimal {}", d), "in gram notation"));
+    }
+    let s = d.to_string();
+    Ok(if s.contains('.') {
+        s
+    } else {
+        format!("{}.0", s)
+    })
+}
+
+/// Whether `s` can be written as an unquoted map key.
+fn is_bare_key(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_alphanumeric() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '@' | '.'))
+}
+
+/// Whether `s` can be written unquoted without being read back as another kind
+/// of value.
+fn is_bare_symbol(s: &str) -> bool {
+    is_bare_key(s) && !s.starts_with(|c: char| c.is_ascii_digit()) && s != "true" && s != "false"
+}
@@ -345,7 +345,7 @@ fn value_from_pattern_value(value: &pattern_core::Value) -> Result<Value, Serial
 }
 
 /// Quote identifier if needed (contains spaces, special chars, or starts with digit)
-fn quote_identifier(s: &str) -> String {
+pub(crate) fn quote_identifier(s: &str) -> String {
     if needs_quoting(s) {
         format!("\"{}\"", escape_string(s))
     } else {
@@ -376,7 +376,7 @@ fn needs_quoting(s: &str) -> bool {
 }
 
 /// Escape special characters in strings
-fn escape_string(s: &str) -> String {
+pub(crate) fn escape_string(s: &str) -> String {
     s.replace('\\', "\\\\")
         .replace('"', "\\\"")
         .replace('\n', "\\n")
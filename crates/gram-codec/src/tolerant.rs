@@ -0,0 +1,715 @@
+//! Fault-tolerant parsing for editors
+//!
+//! [`parse_gram_tolerant`] always returns a [`SyntaxTree`], even for broken
+//! input. Text that cannot be parsed becomes an error node, and anything
+//! required that is absent, such as a closing `)`, becomes a missing node, while
+//! the patterns and subjects around them are kept. For valid input the tree has
+//! no error or missing nodes, and [`SyntaxTree::patterns`] equals what
+//! [`parse_gram`](crate::parse_gram) returns.
+//!
+//! ```rust
+//! use gram_codec::{parse_gram_tolerant, SyntaxNode};
+//!
+//! let tree = parse_gram_tolerant("(alice:Person {age: })\n(bob");
+//! let patterns = tree.patterns();
+//! assert_eq!(patterns.len(), 2);
+//! assert!(patterns[0].value.labels.contains("Person"));
+//!
+//! let errors = tree.errors();
+//! assert!(matches!(errors[0], SyntaxNode::Missing { expected, .. } if expected == "property value"));
+//! assert!(matches!(errors[1], SyntaxNode::Missing { expected, .. } if expected == "`)`"));
+//! ```
+
+use crate::parser::value::{identifier, unquoted_identifier, value_parser};
+use crate::parser::{tokenize, Token, TokenKind};
+use pattern_core::{Pattern, Subject, Symbol, Value};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+/// How a [`PartialPattern`] was written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatternKind {
+    /// A record at the start of a document: `{version: 1}`
+    Record,
+    /// A node: `(a:Person)`
+    Node,
+    /// Two patterns joined by an arrow: `(a)-[r]->(b)`
+    Relationship,
+    /// A subject pattern: `[team | a, b]`
+    SubjectPattern,
+    /// A bare identifier among the elements of a subject pattern
+    Reference,
+}
+
+/// A pattern of a [`SyntaxTree`], with whatever of it parsed
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartialPattern {
+    /// How the pattern was written
+    pub kind: PatternKind,
+    /// The parts of the subject that parsed
+    pub subject: Subject,
+    /// The elements, in pattern order, with error and missing nodes where they
+    /// occurred
+    pub children: Vec<SyntaxNode>,
+    /// Byte range of the pattern, including any annotation
+    pub span: Range<usize>,
+}
+
+impl PartialPattern {
+    /// The elements that parsed
+    pub fn elements(&self) -> impl Iterator<Item = &PartialPattern> {
+        self.children.iter().filter_map(|child| match child {
+            SyntaxNode::Pattern(pattern) => Some(pattern),
+            _ => None,
+        })
+    }
+
+    /// Converts to a pattern, leaving out error and missing nodes
+    pub fn to_pattern(&self) -> Pattern<Subject> {
+        Pattern::pattern(
+            self.subject.clone(),
+            self.elements().map(PartialPattern::to_pattern).collect(),
+        )
+    }
+}
+
+/// A node of a [`SyntaxTree`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyntaxNode {
+    /// A pattern, possibly containing error and missing nodes
+    Pattern(PartialPattern),
+    /// Text that could not be parsed, and was skipped
+    Error {
+        /// Byte range of the skipped text
+        span: Range<usize>,
+        /// What is wrong with it
+        message: String,
+    },
+    /// Something required that is absent
+    Missing {
+        /// Empty byte range where it should be
+        span: Range<usize>,
+        /// What is missing, such as "`)`" or "property value"
+        expected: String,
+    },
+}
+
+impl SyntaxNode {
+    /// Byte range of the node
+    pub fn span(&self) -> Range<usize> {
+        match self {
+            SyntaxNode::Pattern(pattern) => pattern.span.clone(),
+            SyntaxNode::Error { span, .. } | SyntaxNode::Missing { span, .. } => span.clone(),
+        }
+    }
+
+    /// Returns true for error and missing nodes
+    pub fn is_error(&self) -> bool {
+        !matches!(self, SyntaxNode::Pattern(_))
+    }
+
+    /// A message describing an error or missing node
+    pub fn message(&self) -> Option<String> {
+        match self {
+            SyntaxNode::Pattern(_) => None,
+            SyntaxNode::Error { message, .. } => Some(message.clone()),
+            SyntaxNode::Missing { expected, .. } => Some(format!("missing {}", expected)),
+        }
+    }
+}
+
+/// The result of a fault-tolerant parse
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SyntaxTree {
+    /// Top-level patterns, and the error nodes between them
+    pub nodes: Vec<SyntaxNode>,
+}
+
+impl SyntaxTree {
+    /// The top-level patterns, leaving out error and missing nodes
+    pub fn patterns(&self) -> Vec<Pattern<Subject>> {
+        self.nodes
+            .iter()
+            .filter_map(|node| match node {
+                SyntaxNode::Pattern(pattern) => Some(pattern.to_pattern()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// All error and missing nodes, in the order they occur
+    pub fn errors(&self) -> Vec<&SyntaxNode> {
+        fn collect<'a>(nodes: &'a [SyntaxNode], errors: &mut Vec<&'a SyntaxNode>) {
+            for node in nodes {
+                match node {
+                    SyntaxNode::Pattern(pattern) => collect(&pattern.children, errors),
+                    _ => errors.push(node),
+                }
+            }
+        }
+
+        let mut errors = Vec::new();
+        collect(&self.nodes, &mut errors);
+        errors.sort_by_key(|node| node.span().start);
+        errors
+    }
+
+    /// Returns true if the input did not parse cleanly
+    pub fn has_errors(&self) -> bool {
+        !self.errors().is_empty()
+    }
+}
+
+/// Parse gram notation, recovering from errors
+///
+/// Never fails. See the [module documentation](self) for how errors appear in
+/// the tree.
+pub fn parse_gram_tolerant(input: &str) -> SyntaxTree {
+    let mut parser = Parser {
+        input,
+        tokens: tokenize(input)
+            .filter(|token| !token.kind.is_trivia())
+            .collect(),
+        pos: 0,
+    };
+    let mut nodes = Vec::new();
+
+    if parser.at(TokenKind::LeftBrace) {
+        let start = parser.start();
+        let mut children = Vec::new();
+        let properties = parser.record(&mut children);
+        nodes.push(SyntaxNode::Pattern(PartialPattern {
+            kind: PatternKind::Record,
+            subject: subject(properties),
+            children,
+            span: start..parser.end(),
+        }));
+    }
+
+    while let Some(kind) = parser.kind() {
+        if starts_pattern(kind) {
+            nodes.push(parser.pattern());
+        } else if kind == TokenKind::LeftBrace {
+            let start = parser.start();
+            parser.record(&mut Vec::new());
+            nodes.push(SyntaxNode::Error {
+                span: start..parser.end(),
+                message: "a record is only allowed at the start of a document".to_string(),
+            });
+        } else {
+            nodes.push(parser.skip(|kind| starts_pattern(kind) || kind == TokenKind::LeftBrace));
+        }
+    }
+
+    SyntaxTree { nodes }
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    /// The tokens, without whitespace and comments
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn kind(&self) -> Option<TokenKind> {
+        self.tokens.get(self.pos).map(|token| token.kind)
+    }
+
+    fn at(&self, kind: TokenKind) -> bool {
+        self.kind() == Some(kind)
+    }
+
+    fn bump(&mut self) -> Token<'a> {
+        let token = self.tokens[self.pos].clone();
+        self.pos += 1;
+        token
+    }
+
+    /// Start of the next token
+    fn start(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .map_or(self.input.len(), |token| token.span.start)
+    }
+
+    /// End of the last consumed token
+    fn end(&self) -> usize {
+        self.pos
+            .checked_sub(1)
+            .map_or(0, |last| self.tokens[last].span.end)
+    }
+
+    /// Whether the next token directly follows the last consumed one
+    fn adjacent(&self) -> bool {
+        self.pos > 0 && self.start() == self.end()
+    }
+
+    fn missing(&self, expected: &str) -> SyntaxNode {
+        let at = self.end();
+        SyntaxNode::Missing {
+            span: at..at,
+            expected: expected.to_string(),
+        }
+    }
+
+    /// Skips the next token and what follows it, up to a token where `stop`
+    /// holds or a closing bracket that was not opened while skipping.
+    fn skip(&mut self, stop: fn(TokenKind) -> bool) -> SyntaxNode {
+        let first = self.bump();
+        self.skip_after(first, stop, None)
+    }
+
+    fn skip_after(
+        &mut self,
+        first: Token<'a>,
+        stop: fn(TokenKind) -> bool,
+        message: Option<&str>,
+    ) -> SyntaxNode {
+        let mut open: Vec<TokenKind> = closer(first.kind).into_iter().collect();
+        while let Some(kind) = self.kind() {
+            if open.is_empty() && (stop(kind) || is_closer(kind)) {
+                break;
+            }
+            if let Some(close) = closer(kind) {
+                open.push(close);
+            } else if is_closer(kind) {
+                if open.last() != Some(&kind) {
+                    break;
+                }
+                open.pop();
+            }
+            self.bump();
+        }
+
+        let message = message.map_or_else(
+            || {
+                format!(
+                    "unexpected `{}`",
+                    first.text.chars().take(20).collect::<String>()
+                )
+            },
+            str::to_string,
+        );
+        SyntaxNode::Error {
+            span: first.span.start..self.end(),
+            message,
+        }
+    }
+
+    /// Consumes `close`, skipping anything before it that cannot start a
+    /// pattern. Reports it missing if a pattern or another group starts or
+    /// ends first.
+    fn close(&mut self, close: TokenKind, expected: &str, children: &mut Vec<SyntaxNode>) {
+        loop {
+            match self.kind() {
+                Some(kind) if kind == close => {
+                    self.bump();
+                    return;
+                }
+                Some(kind) if !starts_pattern(kind) && !is_closer(kind) => {
+                    children.push(self.skip(starts_pattern));
+                }
+                _ => {
+                    children.push(self.missing(expected));
+                    return;
+                }
+            }
+        }
+    }
+
+    /// An annotated pattern, subject pattern, path or node
+    fn pattern(&mut self) -> SyntaxNode {
+        match self.kind() {
+            Some(TokenKind::At) => self.annotated(),
+            Some(TokenKind::LeftBracket) => SyntaxNode::Pattern(self.subject_pattern()),
+            _ => SyntaxNode::Pattern(self.path()),
+        }
+    }
+
+    /// `@key(value) pattern`; the annotation itself is not kept, as in
+    /// [`parse_gram`](crate::parse_gram)
+    fn annotated(&mut self) -> SyntaxNode {
+        let at = self.bump();
+        let mut errors = Vec::new();
+        if self.adjacent() && self.at(TokenKind::Identifier) {
+            self.bump();
+            let has_value = self.adjacent()
+                && self.at(TokenKind::LeftParen)
+                && self.tokens.get(self.pos + 1).map(|token| token.kind)
+                    == Some(TokenKind::Identifier);
+            if has_value {
+                self.bump();
+                self.bump();
+                self.close(TokenKind::RightParen, "`)`", &mut errors);
+            }
+        } else {
+            errors.push(self.missing("annotation name"));
+        }
+
+        match self.kind() {
+            Some(kind) if starts_pattern(kind) => {
+                let mut node = self.pattern();
+                match &mut node {
+                    SyntaxNode::Pattern(pattern) => {
+                        pattern.span.start = at.span.start;
+                        pattern.children.splice(0..0, errors);
+                    }
+                    SyntaxNode::Error { span, .. } | SyntaxNode::Missing { span, .. } => {
+                        span.start = at.span.start;
+                    }
+                }
+                node
+            }
+            _ => SyntaxNode::Error {
+                span: at.span.start..self.end(),
+                message: "expected a pattern after the annotation".to_string(),
+            },
+        }
+    }
+
+    /// A node followed by any number of arrows and nodes
+    fn path(&mut self) -> PartialPattern {
+        let mut current = self.node();
+        loop {
+            let (arrow, edge, mut children) = match self.kind() {
+                Some(TokenKind::Arrow(arrow)) => {
+                    self.bump();
+                    (arrow, subject(HashMap::new()), Vec::new())
+                }
+                Some(TokenKind::ArrowLeft(arrow)) => {
+                    self.bump();
+                    let mut children = Vec::new();
+                    if self.at(TokenKind::LeftBracket) {
+                        self.bump();
+                    }
+                    let edge = self.subject(&mut children);
+                    self.close(TokenKind::RightBracket, "`]`", &mut children);
+                    let arrow = match self.kind() {
+                        Some(TokenKind::ArrowRight(arrow)) if self.adjacent() => {
+                            self.bump();
+                            arrow
+                        }
+                        _ => {
+                            children.push(self.missing("arrow head"));
+                            arrow
+                        }
+                    };
+                    (arrow, edge, children)
+                }
+                _ => return current,
+            };
+
+            let start = current.span.start;
+            let left = SyntaxNode::Pattern(current);
+            let right = if self.at(TokenKind::LeftParen) {
+                SyntaxNode::Pattern(self.node())
+            } else {
+                self.missing("node")
+            };
+            let complete = !right.is_error();
+            if arrow.is_backward() {
+                children.extend([right, left]);
+            } else {
+                children.extend([left, right]);
+            }
+            current = PartialPattern {
+                kind: PatternKind::Relationship,
+                subject: edge,
+                children,
+                span: start..self.end(),
+            };
+            if !complete {
+                return current;
+            }
+        }
+    }
+
+    /// `(subject)`
+    fn node(&mut self) -> PartialPattern {
+        let open = self.bump();
+        let mut children = Vec::new();
+        let subject = self.subject(&mut children);
+        self.close(TokenKind::RightParen, "`)`", &mut children);
+        PartialPattern {
+            kind: PatternKind::Node,
+            subject,
+            children,
+            span: open.span.start..self.end(),
+        }
+    }
+
+    /// `[subject | elements]`
+    fn subject_pattern(&mut self) -> PartialPattern {
+        let open = self.bump();
+        let mut children = Vec::new();
+        let subject = self.subject(&mut children);
+        if self.at(TokenKind::Pipe) {
+            self.bump();
+            self.elements(&mut children);
+        }
+        self.close(TokenKind::RightBracket, "`]`", &mut children);
+        PartialPattern {
+            kind: PatternKind::SubjectPattern,
+            subject,
+            children,
+            span: open.span.start..self.end(),
+        }
+    }
+
+    /// Elements of a subject pattern, separated by commas
+    fn elements(&mut self, children: &mut Vec<SyntaxNode>) {
+        loop {
+            match self.kind() {
+                Some(kind) if starts_pattern(kind) => children.push(self.pattern()),
+                Some(TokenKind::Identifier) if is_reference(self.tokens[self.pos].text) => {
+                    let token = self.bump();
+                    let mut reference = subject(HashMap::new());
+                    reference.identity = Symbol(token.text.to_string());
+                    children.push(SyntaxNode::Pattern(PartialPattern {
+                        kind: PatternKind::Reference,
+                        subject: reference,
+                        children: Vec::new(),
+                        span: token.span,
+                    }));
+                }
+                Some(TokenKind::Comma) => children.push(self.missing("element")),
+                Some(kind) if !is_closer(kind) => children
+                    .push(self.skip(|kind| kind == TokenKind::Comma || starts_pattern(kind))),
+                _ => return,
+            }
+
+            match self.kind() {
+                Some(TokenKind::Comma) => {
+                    let comma = self.bump();
+                    if self.at(TokenKind::RightBracket) {
+                        children.push(trailing_comma(comma));
+                    }
+                }
+                Some(TokenKind::Identifier) => children.push(self.missing("`,`")),
+                // Anything else ends the elements, and is left to `close`
+                _ => return,
+            }
+        }
+    }
+
+    /// `identity:Label {properties}`, each part optional
+    fn subject(&mut self, children: &mut Vec<SyntaxNode>) -> Subject {
+        let mut subject = subject(HashMap::new());
+        let mut named = false;
+        if self.at(TokenKind::Identifier) {
+            subject.identity = Symbol(name(self.bump().text));
+            named = true;
+        }
+        // Labels directly follow the identity and each other
+        while self.at(TokenKind::Colon) && (!named || self.adjacent()) {
+            self.bump();
+            if !self.at(TokenKind::Label) {
+                children.push(self.missing("label"));
+                break;
+            }
+            subject.labels.insert(name(self.bump().text));
+            named = true;
+        }
+        if self.at(TokenKind::LeftBrace) {
+            subject.properties = self.record(children);
+        }
+        subject
+    }
+
+    /// `{key: value, ...}`
+    fn record(&mut self, children: &mut Vec<SyntaxNode>) -> HashMap<String, Value> {
+        self.bump();
+        let mut properties = HashMap::new();
+        loop {
+            match self.kind() {
+                Some(TokenKind::RightBrace) => {
+                    self.bump();
+                    return properties;
+                }
+                Some(TokenKind::Key) => self.property(&mut properties, children),
+                Some(TokenKind::Comma) => children.push(self.missing("property")),
+                Some(kind) if !ends_record(kind) => children.push(self.skip(ends_property)),
+                _ => {
+                    children.push(self.missing("`}`"));
+                    return properties;
+                }
+            }
+
+            loop {
+                match self.kind() {
+                    Some(TokenKind::Comma) => {
+                        let comma = self.bump();
+                        if self.at(TokenKind::RightBrace) {
+                            children.push(trailing_comma(comma));
+                        }
+                        break;
+                    }
+                    Some(TokenKind::RightBrace) => {
+                        self.bump();
+                        return properties;
+                    }
+                    Some(TokenKind::Key) => {
+                        children.push(self.missing("`,`"));
+                        break;
+                    }
+                    Some(kind) if !ends_record(kind) => children.push(self.skip(ends_property)),
+                    _ => {
+                        children.push(self.missing("`}`"));
+                        return properties;
+                    }
+                }
+            }
+        }
+    }
+
+    /// `key: value`, parsing the value as the strict parser does
+    fn property(
+        &mut self,
+        properties: &mut HashMap<String, Value>,
+        children: &mut Vec<SyntaxNode>,
+    ) {
+        let key = name(self.bump().text);
+        match self.kind() {
+            Some(TokenKind::Colon | TokenKind::DoubleColon) => {
+                self.bump();
+            }
+            _ => children.push(self.missing("`:`")),
+        }
+
+        match self.kind() {
+            // In value position `[` starts an array rather than a pattern
+            Some(TokenKind::LeftBracket) => {}
+            Some(kind) if !ends_property(kind) && kind != TokenKind::RightBrace => {}
+            _ => {
+                children.push(self.missing("property value"));
+                return;
+            }
+        }
+        let start = self.start();
+        match value_parser(&self.input[start..]) {
+            Ok((rest, value)) => {
+                let end = self.input.len() - rest.len();
+                while self.start() < end {
+                    self.bump();
+                }
+                // The value ended inside a token, as `true` does in `true1`
+                if self.end() > end {
+                    children.push(SyntaxNode::Error {
+                        span: end..self.end(),
+                        message: "invalid property value".to_string(),
+                    });
+                }
+                properties.insert(key, value);
+            }
+            Err(_) => {
+                let first = self.bump();
+                children.push(self.skip_after(
+                    first,
+                    ends_property,
+                    Some("invalid property value"),
+                ));
+            }
+        }
+    }
+}
+
+fn subject(properties: HashMap<String, Value>) -> Subject {
+    Subject {
+        identity: Symbol(String::new()),
+        labels: HashSet::new(),
+        properties,
+    }
+}
+
+/// The value of a quoted or unquoted name
+fn name(text: &str) -> String {
+    identifier(text).map_or_else(|_| text.to_string(), |(_, name)| name)
+}
+
+/// Whether `text` is an unquoted identifier, as element references must be
+fn is_reference(text: &str) -> bool {
+    unquoted_identifier(text).is_ok_and(|(rest, _)| rest.is_empty())
+}
+
+fn trailing_comma(comma: Token) -> SyntaxNode {
+    SyntaxNode::Error {
+        span: comma.span,
+        message: "trailing comma".to_string(),
+    }
+}
+
+fn starts_pattern(kind: TokenKind) -> bool {
+    matches!(
+        kind,
+        TokenKind::LeftParen | TokenKind::LeftBracket | TokenKind::At
+    )
+}
+
+fn is_closer(kind: TokenKind) -> bool {
+    matches!(
+        kind,
+        TokenKind::RightParen | TokenKind::RightBracket | TokenKind::RightBrace
+    )
+}
+
+fn closer(kind: TokenKind) -> Option<TokenKind> {
+    match kind {
+        TokenKind::LeftParen => Some(TokenKind::RightParen),
+        TokenKind::LeftBracket => Some(TokenKind::RightBracket),
+        TokenKind::LeftBrace => Some(TokenKind::RightBrace),
+        _ => None,
+    }
+}
+
+/// Tokens that end a record left unclosed: the end of an enclosing group, or
+/// the start of a pattern
+fn ends_record(kind: TokenKind) -> bool {
+    starts_pattern(kind) || matches!(kind, TokenKind::RightParen | TokenKind::RightBracket)
+}
+
+/// Tokens where skipping inside a record stops
+fn ends_property(kind: TokenKind) -> bool {
+    matches!(kind, TokenKind::Comma | TokenKind::Key) || ends_record(kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expected(tree: &SyntaxTree) -> Vec<String> {
+        tree.errors()
+            .into_iter()
+            .filter_map(|node| node.message())
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_input_has_no_errors() {
+        let tree = parse_gram_tolerant("{v: 1} (a:Person)-[r]->(b) [g | a, (c)]");
+        assert!(!tree.has_errors());
+        assert_eq!(tree.nodes.len(), 3);
+    }
+
+    #[test]
+    fn test_unclosed_node_keeps_following_patterns() {
+        let tree = parse_gram_tolerant("(a:Person\n(b)");
+        assert_eq!(expected(&tree), ["missing `)`"]);
+        assert_eq!(tree.patterns().len(), 2);
+    }
+
+    #[test]
+    fn test_missing_bracket() {
+        let tree = parse_gram_tolerant("[team | a, b");
+        assert_eq!(expected(&tree), ["missing `]`"]);
+        assert_eq!(tree.patterns()[0].elements.len(), 2);
+    }
+
+    #[test]
+    fn test_bad_property_value_keeps_other_properties() {
+        let tree = parse_gram_tolerant("(a {x: 1, y: ;;, z: 2})");
+        assert_eq!(expected(&tree), ["invalid property value"]);
+        let properties = &tree.patterns()[0].value.properties;
+        assert_eq!(properties.len(), 2);
+    }
+}
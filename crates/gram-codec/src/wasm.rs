@@ -6,7 +6,7 @@
 //! # Usage
 //!
 //! ```javascript
-//! import init, { parse_gram, validate_gram } from './gram_codec.js';
+//! import init, { parse_gram, parse_gram_with_options, validate_gram } from './gram_codec.js';
 //!
 //! await init();
 //!
@@ -20,6 +20,9 @@
 //!     console.error("Parse error:", e);
 //! }
 //!
+//! // Parse with options, here the lenient dialect
+//! const lenient = parse_gram_with_options("(a {city: New York,})", { dialect: "lenient" });
+//!
 //! // Quick validation
 //! const isValid = validate_gram("(hello)-->(world)");
 //! console.log("Is valid:", isValid);
@@ -30,9 +33,9 @@ use wasm_bindgen::prelude::*;
 /// Result of parsing gram notation
 #[wasm_bindgen]
 pub struct ParseResult {
-    /// Number of top-level patterns parsed
+    /// Number of top-level patterns parsed, not counting a header record
     pattern_count: usize,
-    /// Identifiers of root patterns (for debugging)
+    /// Identifiers of root patterns, without the header (for debugging)
     identifiers: Vec<String>,
 }
 
@@ -67,20 +70,56 @@ impl ParseResult {
 /// Throws JavaScript error if parsing fails
 #[wasm_bindgen]
 pub fn parse_gram(input: &str) -> Result<ParseResult, JsValue> {
-    // Parse using the native parser (now uses nom, not tree-sitter)
-    let patterns =
-        crate::parse_gram(input).map_err(|e| JsValue::from_str(&format!("Parse error: {}", e)))?;
-
-    // Extract identifiers from root patterns
-    let identifiers: Vec<String> = patterns
-        .iter()
-        .map(|p| p.value().identity.0.clone())
-        .collect();
+    let document = crate::GramDocument::parse(input)
+        .map_err(|e| JsValue::from_str(&format!("Parse error: {}", e)))?;
+    Ok(summarize(&document))
+}
 
-    Ok(ParseResult {
+/// Summarize the top-level patterns of a document, including a header record
+fn summarize(document: &crate::GramDocument) -> ParseResult {
+    let patterns = document.to_patterns();
+    ParseResult {
         pattern_count: patterns.len(),
-        identifiers,
-    })
+        identifiers: patterns
+            .iter()
+            .map(|p| p.value().identity.0.clone())
+            .collect(),
+    }
+}
+
+/// Parse Gram notation text with options and return summary information
+///
+/// `options` is an object with any of the fields of the native `ParseOptions`,
+/// in snake case; missing fields take their defaults.
+///
+/// ```javascript
+/// const result = parse_gram_with_options("(a {city: New York,})", {
+///     dialect: "lenient",    // "standard", "strict" or "lenient"
+///     header: true,          // a leading record is the header
+///     max_depth: 64,
+/// });
+/// ```
+///
+/// # Errors
+///
+/// Throws JavaScript error if the options are invalid or parsing fails
+#[wasm_bindgen]
+pub fn parse_gram_with_options(input: &str, options: JsValue) -> Result<ParseResult, JsValue> {
+    let options = parse_options(&options)?;
+    let document = crate::GramDocument::parse_with_options(input, &options)
+        .map_err(|e| JsValue::from_str(&format!("Parse error: {}", e)))?;
+    Ok(summarize(&document))
+}
+
+/// Read `ParseOptions` from a JavaScript object via JSON
+fn parse_options(options: &JsValue) -> Result<crate::ParseOptions, JsValue> {
+    if options.is_undefined() || options.is_null() {
+        return Ok(crate::ParseOptions::default());
+    }
+    let json = js_sys::JSON::stringify(options)
+        .map_err(|e| JsValue::from_str(&format!("Invalid options: {:?}", e)))?;
+    serde_json::from_str(&String::from(json))
+        .map_err(|e| JsValue::from_str(&format!("Invalid options: {}", e)))
 }
 
 /// Validate gram notation syntax
@@ -112,12 +151,11 @@ pub fn validate_gram(input: &str) -> bool {
 /// Serialized gram notation (may differ in formatting but should be semantically equivalent)
 #[wasm_bindgen]
 pub fn round_trip(input: &str) -> Result<String, JsValue> {
-    // Parse
-    let patterns =
-        crate::parse_gram(input).map_err(|e| JsValue::from_str(&format!("Parse error: {}", e)))?;
+    let document = crate::GramDocument::parse(input)
+        .map_err(|e| JsValue::from_str(&format!("Parse error: {}", e)))?;
 
-    // Serialize all patterns
-    crate::to_gram(&patterns).map_err(|e| JsValue::from_str(&format!("Serialize error: {}", e)))
+    crate::to_gram(&document.to_patterns())
+        .map_err(|e| JsValue::from_str(&format!("Serialize error: {}", e)))
 }
 
 /// Parse gram notation to AST (JavaScript-friendly)
@@ -150,8 +188,9 @@ pub fn round_trip(input: &str) -> Result<String, JsValue> {
 /// ```
 #[wasm_bindgen]
 pub fn parse_to_ast(input: &str) -> Result<JsValue, JsValue> {
-    let ast = crate::parse_to_ast(input)
-        .map_err(|e| JsValue::from_str(&format!("Parse error: {}", e)))?;
+    let ast = crate::GramDocument::parse(input)
+        .map_err(|e| JsValue::from_str(&format!("Parse error: {}", e)))?
+        .to_ast();
 
     // Serialize to JSON string first (workaround for serde-wasm-bindgen HashMap issue)
     let json_str = serde_json::to_string(&ast)
@@ -164,6 +203,90 @@ pub fn parse_to_ast(input: &str) -> Result<JsValue, JsValue> {
     Ok(js_json)
 }
 
+/// Parse gram notation and return the subpatterns matching a predicate expression
+///
+/// Every top-level pattern and its elements are searched in document (pre-order)
+/// order, starting with a leading header record. Matches are returned as an
+/// array of AST objects (see [`parse_to_ast`]).
+///
+/// # Example
+///
+/// ```javascript
+/// import init, { filter_expr } from './gram_codec.js';
+/// await init();
+///
+/// const people = filter_expr(
+///   "(alice:Person {age: 34}) (bob:Person {age: 27})",
+///   ":Person AND age >= 30",
+/// );
+/// console.log(people.map(p => p.subject.identity));  // ["alice"]
+/// ```
+///
+/// # Errors
+///
+/// Throws a JavaScript error if the gram notation or the expression is invalid, or if
+/// the expression compares values of mismatched types.
+#[wasm_bindgen]
+pub fn filter_expr(input: &str, expr: &str) -> Result<JsValue, JsValue> {
+    let matches = matching(input, expr).map_err(|e| JsValue::from_str(&e))?;
+    to_js(&matches)
+}
+
+/// The subpatterns of the document in `input` matching `expr`
+fn matching(input: &str, expr: &str) -> Result<Vec<crate::AstPattern>, String> {
+    let document = crate::GramDocument::parse(input).map_err(|e| format!("Parse error: {}", e))?;
+    let expr = pattern_core::Expr::parse(expr).map_err(|e| format!("Expression error: {}", e))?;
+
+    let mut matches = Vec::new();
+    for pattern in document.to_patterns() {
+        let found = pattern
+            .filter_by_expr(&expr)
+            .map_err(|e| format!("Expression error: {}", e))?;
+        matches.extend(found.into_iter().map(crate::AstPattern::from_pattern));
+    }
+    Ok(matches)
+}
+
+/// Parse gram notation and return the first subpattern matching a predicate expression
+///
+/// Returns an AST object (see [`parse_to_ast`]), or `null` if nothing matches.
+///
+/// # Errors
+///
+/// Throws a JavaScript error if the gram notation or the expression is invalid, or if
+/// the expression compares values of mismatched types before a match is found.
+#[wasm_bindgen]
+pub fn find_first_expr(input: &str, expr: &str) -> Result<JsValue, JsValue> {
+    match first_match(input, expr).map_err(|e| JsValue::from_str(&e))? {
+        Some(found) => to_js(&found),
+        None => Ok(JsValue::NULL),
+    }
+}
+
+/// The first subpattern of the document in `input` matching `expr`
+fn first_match(input: &str, expr: &str) -> Result<Option<crate::AstPattern>, String> {
+    let document = crate::GramDocument::parse(input).map_err(|e| format!("Parse error: {}", e))?;
+    let expr = pattern_core::Expr::parse(expr).map_err(|e| format!("Expression error: {}", e))?;
+
+    for pattern in document.to_patterns() {
+        let found = pattern
+            .find_first_by_expr(&expr)
+            .map_err(|e| format!("Expression error: {}", e))?;
+        if let Some(found) = found {
+            return Ok(Some(crate::AstPattern::from_pattern(found)));
+        }
+    }
+    Ok(None)
+}
+
+/// Convert a serializable value to a JavaScript object via JSON
+fn to_js<T: serde::Serialize>(value: &T) -> Result<JsValue, JsValue> {
+    let json_str = serde_json::to_string(value)
+        .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))?;
+    js_sys::JSON::parse(&json_str)
+        .map_err(|e| JsValue::from_str(&format!("JSON parse error: {:?}", e)))
+}
+
 /// Get version information for the gram codec
 #[wasm_bindgen]
 pub fn version() -> String {
@@ -188,6 +311,36 @@ mod tests {
         assert_eq!(result.identifiers.len(), 2);
     }
 
+    #[test]
+    fn test_parse_gram_counts_the_header() {
+        let result = parse_gram("{v: 1} (a)").unwrap();
+        assert_eq!(result.pattern_count, 2);
+        assert_eq!(result.identifiers, vec!["".to_string(), "a".to_string()]);
+        assert_eq!(parse_gram("{} (a)").unwrap().pattern_count, 2);
+    }
+
+    #[test]
+    fn test_filters_search_the_header() {
+        let input = "{v: 1} (a {v: 1}) (b {v: 2})";
+        let matches = matching(input, "v = 1").unwrap();
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[1].subject.identity, "a");
+        let first = first_match(input, "v = 1").unwrap().unwrap();
+        assert_eq!(first.subject.identity, "");
+        assert_eq!(first.subject.properties.len(), 1);
+    }
+
+    #[test]
+    fn test_filters_reject_an_invalid_expression_without_patterns() {
+        assert!(matching("", "age >").is_err());
+        assert!(first_match("", "age >").is_err());
+    }
+
+    #[test]
+    fn test_round_trip_keeps_the_header() {
+        assert_eq!(round_trip("{v: 1} (a)").unwrap(), "{v: 1}\n(a)");
+    }
+
     #[test]
     fn test_round_trip() {
         let input = "(hello)";
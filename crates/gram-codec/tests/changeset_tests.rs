@@ -0,0 +1,82 @@
+//! Tests for writing document diffs as gram changesets
+//!
+//! Diffs two parsed documents, writes the changeset as gram text, parses it back
+//! and applies it to the old version.
+
+use gram_codec::{parse_gram, to_gram};
+use pattern_core::{apply_changeset, diff};
+
+const OLD: &str = r#"
+(alice:Person:Intern {name: "Alice", age: 34})
+(bob:Person {name: "Bob"})
+(carol:Person {name: "Carol"})
+(alice)-[k1:KNOWS]->(bob)
+(bob)-[:KNOWS]->(carol)
+[t1:Team | alice, bob, carol]
+"#;
+
+const NEW: &str = r#"
+(bob:Person {name: "Bob"})
+(alice:Employee:Person {age: 35, name: "Alice"})
+(dave:Person {name: "Dave"})
+(alice)-[k1:KNOWS]->(bob)
+(bob)-[:KNOWS]->(dave)
+[t1:Team | bob, dave, alice]
+"#;
+
+#[test]
+fn test_reordered_document_has_no_diff() {
+    let old = parse_gram(OLD).unwrap();
+    let reordered = parse_gram(
+        r#"
+        [t1:Team | alice, bob, carol]
+        (bob)-[:KNOWS]->(carol)
+        (alice)-[k1:KNOWS]->(bob)
+        (carol:Person {name: "Carol"})
+        (alice:Intern:Person {age: 34, name: "Alice"})
+        (bob:Person {name: "Bob"})
+        "#,
+    )
+    .unwrap();
+
+    assert!(diff(&old, &reordered).is_empty());
+}
+
+#[test]
+fn test_report_of_parsed_documents() {
+    let old = parse_gram(OLD).unwrap();
+    let new = parse_gram(NEW).unwrap();
+
+    let report = diff(&old, &new).to_string();
+    assert_eq!(
+        report,
+        "\
++ (dave:Person {name: \"Dave\"})
+- (carol:Person {name: \"Carol\"})
++ (bob)-[:KNOWS]->(dave)
+- (bob)-[:KNOWS]->(carol)
+~ alice
+    + :Employee
+    - :Intern
+    age: 34 -> 35
+> dave: [] -> [t1]
+"
+    );
+}
+
+#[test]
+fn test_changeset_round_trips_through_gram() {
+    let old = parse_gram(OLD).unwrap();
+    let new = parse_gram(NEW).unwrap();
+
+    let text = to_gram(&diff(&old, &new).changeset()).unwrap();
+    assert!(text.contains("Changed"));
+
+    let changeset = parse_gram(&text).unwrap();
+    let patched = apply_changeset(&old, &changeset).unwrap();
+    assert!(diff(&patched, &new).is_empty());
+
+    // The patched document can be written and read again
+    let reparsed = parse_gram(&to_gram(&patched).unwrap()).unwrap();
+    assert!(diff(&reparsed, &new).is_empty());
+}
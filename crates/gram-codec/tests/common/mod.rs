@@ -0,0 +1,42 @@
+//! Fixtures shared by the parser tests
+
+use proptest::prelude::*;
+
+/// Fragments, valid and broken, that documents are built from
+pub const FRAGMENTS: &[&str] = &[
+    " ",
+    "\n",
+    "(",
+    ")",
+    "[",
+    "]",
+    "{",
+    "}",
+    "a",
+    "b:Label",
+    ":",
+    "-->",
+    "<--",
+    "-[r]->",
+    "<-[:T {w: 1}]-",
+    "|",
+    ",",
+    "(x)",
+    "(y:Person {k: 1})",
+    "[p | a, b]",
+    "{n: 2}",
+    "{m: 'v', o:: 3}",
+    "{t: [1, \"u\"], r: {s: 0}}",
+    "@m(1) ",
+    "@m ",
+    "// note\n",
+    "\"s\"",
+    "1..",
+    ";",
+];
+
+/// Documents of up to 15 fragments
+pub fn documents() -> impl Strategy<Value = String> {
+    prop::collection::vec(0..FRAGMENTS.len(), 0..16)
+        .prop_map(|fragments| fragments.iter().map(|&i| FRAGMENTS[i]).collect())
+}
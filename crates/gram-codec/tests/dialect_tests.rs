@@ -0,0 +1,211 @@
+//! Tests for the strict and lenient dialects and the header switch
+//!
+//! Checks what each dialect adds to or removes from standard gram notation,
+//! and that a document either dialect accepts in standard form parses as
+//! `parse_gram` parses it.
+
+use gram_codec::{
+    parse_gram, parse_gram_with_options, Dialect, GramDocument, ParseError, ParseOptions,
+};
+use pattern_core::Value;
+use proptest::prelude::*;
+
+fn options(dialect: Dialect) -> ParseOptions {
+    ParseOptions {
+        dialect,
+        ..ParseOptions::default()
+    }
+}
+
+fn strict(input: &str) -> Result<(), ParseError> {
+    parse_gram_with_options(input, &options(Dialect::Strict)).map(drop)
+}
+
+fn lenient_property(input: &str, key: &str) -> Value {
+    let patterns = parse_gram_with_options(input, &options(Dialect::Lenient)).unwrap();
+    patterns[0].value.properties[key].clone()
+}
+
+#[test]
+fn test_strict_rejects_duplicate_keys() {
+    assert!(parse_gram("(a {k: 1, k: 2})").is_ok());
+    let error = strict("(a {k: 1, k: 2})").unwrap_err();
+    assert!(matches!(&error, ParseError::DuplicateKey { key, .. } if key == "k"));
+    assert_eq!(error.location().unwrap().offset, 10);
+    assert!(strict("{v: 1, v: 2}").is_err());
+    assert!(strict("(a)-[{w: 1, w: 1}]->(b)").is_err());
+
+    // Keys repeat across maps, but not within one
+    assert!(strict("(a {m: {x: 1, n: {x: 2}}, x: 3})").is_ok());
+    let error = strict("(a {m: {x: 1, x: 2}})").unwrap_err();
+    assert!(matches!(&error, ParseError::DuplicateKey { key, .. } if key == "x"));
+    assert_eq!(error.location().unwrap().offset, 14);
+    assert!(strict("(a {m: [{y: 1, n: {y: 2, y: 3}}]})").is_err());
+}
+
+#[test]
+fn test_strict_rejects_duplicate_labels() {
+    assert!(parse_gram("(a:Person:Person)").is_ok());
+    let error = strict("(a) (b:Person:User:Person)").unwrap_err();
+    assert!(matches!(&error, ParseError::DuplicateLabel { label, .. } if label == "Person"));
+    assert_eq!(error.location().unwrap().offset, 5);
+}
+
+#[test]
+fn test_strict_rejects_conflicting_definitions() {
+    let error = strict("(a:Person {age: 30})-->(b) (a:Person {age: 31})").unwrap_err();
+    assert!(
+        matches!(&error, ParseError::ConflictingDefinition { identity, .. } if identity == "a")
+    );
+    assert_eq!(error.location().unwrap().offset, 28);
+    assert!(strict("(a:Person) (a:Robot)").is_err());
+    assert!(strict("(a)-[r:KNOWS]->(b) (c)-[r:LIKES]->(d)").is_err());
+    assert!(strict("[g:Group | (a)] (g:Team)").is_err());
+
+    // An edge is defined by its endpoints as well as its labels
+    let error = strict("(a)-[r:K]->(b) (c)-[r:K]->(d)").unwrap_err();
+    assert!(
+        matches!(&error, ParseError::ConflictingDefinition { identity, .. } if identity == "r")
+    );
+    assert_eq!(error.location().unwrap().offset, 18);
+    assert!(strict("(a)-[r]->(b)-[r]->(c)").is_err());
+    assert!(strict("(a)-[r:K]->(b) (a)<-[r:K]-(b)").is_err());
+}
+
+#[test]
+fn test_strict_accepts_references_and_repeated_definitions() {
+    assert!(strict("(a:Person {age: 30}) (a) [g | a, (a)] (a:Person {age: 30})").is_ok());
+    assert!(strict("(a:Person)-[:KNOWS]->(b) (b)-[:KNOWS]->(a)").is_ok());
+    assert!(strict("(a)-[r:K]->(b) (b)<-[r]-(a) (a)-[r:K]->(b)").is_ok());
+}
+
+#[test]
+fn test_lenient_accepts_trailing_commas() {
+    let input = "[g | (a {k: [1, 2,], m: {x: 1,},}), (b),]";
+    assert!(parse_gram(input).is_err());
+    let patterns = parse_gram_with_options(input, &options(Dialect::Lenient)).unwrap();
+    assert_eq!(
+        patterns,
+        parse_gram("[g | (a {k: [1, 2], m: {x: 1}}), (b)]").unwrap()
+    );
+    // A comma alone is not a trailing comma
+    assert!(parse_gram_with_options("(a {k: [,]})", &options(Dialect::Lenient)).is_err());
+    assert!(parse_gram_with_options("(a {,})", &options(Dialect::Lenient)).is_err());
+}
+
+#[test]
+fn test_lenient_accepts_hash_comments() {
+    let input = "# people\n(a {k: 1, # the key\n m: [2, # two\n 3]}) # end";
+    assert!(parse_gram(input).is_err());
+    assert_eq!(
+        parse_gram_with_options(input, &options(Dialect::Lenient)).unwrap(),
+        parse_gram("(a {k: 1, m: [2, 3]})").unwrap()
+    );
+}
+
+#[test]
+fn test_depth_limit_skips_hash_comments() {
+    let options = ParseOptions {
+        dialect: Dialect::Lenient,
+        max_depth: Some(2),
+        ..ParseOptions::default()
+    };
+    assert!(parse_gram_with_options("(a) # ((((", &options).is_ok());
+    // A quote in a comment does not hide the brackets after it
+    for input in ["# ((((\n[g | [h | (a)]]", "# \"\n[g | [h | (a {k: \"\"})]]"] {
+        assert!(matches!(
+            parse_gram_with_options(input, &options),
+            Err(ParseError::NestingTooDeep { limit: 2, .. })
+        ));
+    }
+}
+
+#[test]
+fn test_lenient_accepts_unquoted_strings() {
+    let string = |s: &str| Value::VString(s.to_string());
+    assert_eq!(
+        lenient_property("(a {city: New York})", "city"),
+        string("New York")
+    );
+    assert_eq!(
+        lenient_property("(a {on: 2024-01-01 })", "on"),
+        string("2024-01-01")
+    );
+    assert_eq!(
+        lenient_property("(a {urls: [http://x.org, y], n: 1})", "urls"),
+        Value::VArray(vec![
+            string("http://x.org"),
+            Value::VSymbol("y".to_string())
+        ])
+    );
+    assert_eq!(
+        lenient_property("(a {note: hello there # comment\n})", "note"),
+        string("hello there")
+    );
+    // Values that parse on their own are unchanged
+    assert_eq!(
+        lenient_property("(a {n: 5kg})", "n"),
+        Value::VMeasurement {
+            unit: "kg".to_string(),
+            value: 5.0
+        }
+    );
+    assert_eq!(
+        lenient_property("(a {s: Alice})", "s"),
+        Value::VSymbol("Alice".to_string())
+    );
+    assert!(parse_gram_with_options("(a {city: })", &options(Dialect::Lenient)).is_err());
+}
+
+#[test]
+fn test_header_switch() {
+    let no_header = ParseOptions {
+        header: false,
+        ..ParseOptions::default()
+    };
+    // The leading record is the same pattern either way
+    for options in [&no_header, &ParseOptions::default()] {
+        assert_eq!(
+            parse_gram_with_options("{version: 1} (a)", options),
+            parse_gram("{version: 1} (a)")
+        );
+    }
+    // Only a leading record is accepted
+    assert!(parse_gram_with_options("(a) {version: 1}", &no_header).is_err());
+
+    let document = GramDocument::parse_with_options("{version: 1} (a)", &no_header).unwrap();
+    assert!(document.header().is_none());
+    assert_eq!(document.patterns().len(), 2);
+    assert_eq!(document.spans(), Some(&[0..12, 13..16][..]));
+}
+
+#[test]
+fn test_options_deserialize_with_defaults() {
+    let options: ParseOptions =
+        serde_json::from_str(r#"{"dialect": "lenient", "max_depth": 8}"#).unwrap();
+    assert_eq!(
+        options,
+        ParseOptions {
+            dialect: Dialect::Lenient,
+            max_depth: Some(8),
+            ..ParseOptions::default()
+        }
+    );
+}
+
+proptest! {
+    #[test]
+    fn prop_dialects_agree_on_standard_documents(
+        input in "[()\\[\\]{}:,|@<>\\-a-z0-9 \n\"]{0,40}"
+    ) {
+        if let Ok(patterns) = parse_gram(&input) {
+            prop_assert_eq!(
+                parse_gram_with_options(&input, &options(Dialect::Lenient)),
+                Ok(patterns.clone())
+            );
+            if let Ok(strict) = parse_gram_with_options(&input, &options(Dialect::Strict)) {
+                prop_assert_eq!(strict, patterns);
+            }
+        }
+    }
+}
@@ -0,0 +1,215 @@
+//! Tests for gram documents
+//!
+//! Checks the header, patterns, spans, comments and indexes of a parsed
+//! document, that it prints back as it was written, and that it agrees with
+//! `parse_gram` and `parse_to_ast`.
+
+use gram_codec::{
+    parse_gram, parse_gram_with_header, parse_to_ast, to_gram, Dialect, GramDocument, ParseOptions,
+};
+use pattern_core::Value;
+use proptest::prelude::*;
+
+#[test]
+fn test_header_and_patterns() {
+    let document = GramDocument::parse("{version: 1} (a)-->(b) (c)").unwrap();
+    let header = document.header().unwrap();
+    assert_eq!(header.get("version"), Some(&Value::VInteger(1)));
+    assert_eq!(document.patterns().len(), 2);
+
+    let document = GramDocument::parse("(a) {version: 1}");
+    assert!(document.is_err());
+
+    let document = GramDocument::parse("").unwrap();
+    assert!(document.header().is_none());
+    assert!(document.patterns().is_empty());
+}
+
+#[test]
+fn test_spans_locate_each_pattern() {
+    let input = "{v: 1}\n(a)-->(b)\n  [g | a, b]\n";
+    let document = GramDocument::parse(input).unwrap();
+    let texts: Vec<&str> = document
+        .spans()
+        .unwrap()
+        .iter()
+        .map(|span| &input[span.clone()])
+        .collect();
+    assert_eq!(texts, ["(a)-->(b)", "[g | a, b]"]);
+}
+
+#[test]
+fn test_comments() {
+    let input = "// people\n(a) // alice\n(b {s: \"// not a comment\"})";
+    let document = GramDocument::parse(input).unwrap();
+    let comments: Vec<&str> = document
+        .comments()
+        .iter()
+        .map(|comment| comment.text.as_str())
+        .collect();
+    assert_eq!(comments, ["// people", "// alice"]);
+    for comment in document.comments() {
+        assert_eq!(&input[comment.span.clone()], comment.text);
+    }
+}
+
+#[test]
+fn test_nodes_and_relationships_of_a_path() {
+    let document = GramDocument::parse("(a)-->(b)-->(c) (d)").unwrap();
+    let nodes: Vec<&str> = document
+        .iter_nodes()
+        .map(|node| node.value.identity.0.as_str())
+        .collect();
+    assert_eq!(nodes, ["a", "b", "c", "d"]);
+    assert_eq!(document.iter_relationships().count(), 2);
+
+    let document = GramDocument::parse("[r:KNOWS | a, b] [g | a]").unwrap();
+    assert_eq!(document.iter_relationships().count(), 1);
+    assert_eq!(document.iter_nodes().count(), 3);
+}
+
+#[test]
+fn test_get_by_identity() {
+    let document = GramDocument::parse("(a)-[e:KNOWS]->(b:Person) [g | e, (c)]").unwrap();
+    assert!(document.get("b").unwrap().value.labels.contains("Person"));
+    assert_eq!(document.get("e").unwrap().elements.len(), 2);
+    assert_eq!(document.get("c").unwrap().value.identity.0, "c");
+    assert!(document.get("a").unwrap().elements.is_empty());
+    assert!(document.get("z").is_none());
+}
+
+#[test]
+fn test_to_gram_is_lossless() {
+    let input = "{v: 1}\n\n// a comment\n(a {m: {k: 1}, d: 5km})  -->  (b)\n";
+    let document = GramDocument::parse(input).unwrap();
+    assert_eq!(document.source(), Some(input));
+    assert_eq!(document.to_gram().unwrap(), input);
+}
+
+#[test]
+fn test_new_document_is_serialized() {
+    let parsed = GramDocument::parse("{v: 1} (a:Person)-->(b)").unwrap();
+    let (header, patterns) = parsed.clone().into_parts();
+    let document = GramDocument::new(header, patterns);
+    assert!(document.source().is_none());
+    assert!(document.spans().is_none());
+    assert_eq!(document.to_gram().unwrap(), parsed.serialize().unwrap());
+    assert_eq!(
+        GramDocument::parse(&document.to_gram().unwrap())
+            .unwrap()
+            .patterns(),
+        parsed.patterns()
+    );
+    assert!(document.get("a").is_some());
+}
+
+#[test]
+fn test_parse_with_options() {
+    let options = ParseOptions {
+        dialect: Dialect::Lenient,
+        ..ParseOptions::default()
+    };
+    let document =
+        GramDocument::parse_with_options("{v: 1,} (a {city: Paris}) # note", &options).unwrap();
+    assert!(document.header().is_some());
+    assert_eq!(document.patterns().len(), 1);
+    assert!(document.comments().is_empty());
+
+    let options = ParseOptions {
+        header: false,
+        ..ParseOptions::default()
+    };
+    let document = GramDocument::parse_with_options("{v: 1} (a)", &options).unwrap();
+    assert!(document.header().is_none());
+    assert_eq!(document.patterns().len(), 2);
+}
+
+#[test]
+fn test_to_ast_matches_parse_to_ast() {
+    for input in ["", "(a)", "()", "(a) (b)", "{v: 1}", "{v: 1} (a)-->(b)"] {
+        let document = GramDocument::parse(input).unwrap();
+        assert_eq!(document.to_ast(), parse_to_ast(input).unwrap(), "{}", input);
+    }
+}
+
+#[test]
+fn test_to_patterns_puts_the_header_first() {
+    for input in ["{v: 1} (a)", "{} (a)", "{}", "(a) (b)"] {
+        let document = GramDocument::parse(input).unwrap();
+        assert_eq!(
+            document.to_patterns(),
+            parse_gram(input).unwrap(),
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn test_existing_functions_keep_their_header_handling() {
+    // An empty record is not a header for `parse_gram_with_header` or the AST
+    let (header, patterns) = parse_gram_with_header("{} (a)").unwrap();
+    assert!(header.is_none());
+    assert_eq!(patterns.len(), 2);
+    assert_eq!(parse_to_ast("{} (a)").unwrap().elements.len(), 2);
+    assert_eq!(parse_to_ast("{}").unwrap().elements.len(), 1);
+
+    let (header, patterns) = parse_gram_with_header("{v: 1} (a)").unwrap();
+    assert_eq!(header.unwrap().len(), 1);
+    assert_eq!(patterns.len(), 1);
+    let ast = parse_to_ast("{v: 1} (a)").unwrap();
+    assert_eq!(ast.subject.properties.len(), 1);
+    assert_eq!(ast.elements.len(), 1);
+}
+
+/// Whether `input` starts with a record, after whitespace and comments
+fn starts_with_record(input: &str) -> bool {
+    let mut rest = input.trim_start();
+    while rest.starts_with("//") {
+        rest = rest[rest.find('\n').unwrap_or(rest.len())..].trim_start();
+    }
+    rest.starts_with('{')
+}
+
+proptest! {
+    #[test]
+    fn prop_document_agrees_with_parse_gram(
+        lead in "( |\n|// [a-z{]*\n){0,3}",
+        record in prop::option::of("\\{([a-z]: [0-9])?\\}"),
+        rest in prop::collection::vec(
+            prop_oneof![
+                Just("(a)"),
+                Just("(b:L {k: 1})"),
+                Just("(a)-[r]->(b)<--(c)"),
+                Just("[g | a, (d)]"),
+                Just("{k: 2}"),
+                Just("// note\n"),
+                Just("(e"),
+            ],
+            0..5,
+        )
+    ) {
+        let input = format!("{}{} {}", lead, record.unwrap_or_default(), rest.join("\n"));
+        let document = GramDocument::parse(&input);
+        prop_assert_eq!(document.is_ok(), parse_gram(&input).is_ok());
+        if let Ok(document) = document {
+            prop_assert_eq!(document.to_gram().unwrap(), input.clone());
+            prop_assert_eq!(document.to_patterns(), parse_gram(&input).unwrap());
+            // The header is the first pattern exactly when the input starts
+            // with a record
+            let patterns = parse_gram(&input).unwrap();
+            let (header, patterns) = match patterns.split_first() {
+                Some((first, rest)) if starts_with_record(&input) => {
+                    (Some(&first.value.properties), rest)
+                }
+                _ => (None, patterns.as_slice()),
+            };
+            prop_assert_eq!(document.header(), header);
+            prop_assert_eq!(document.patterns(), patterns);
+            prop_assert_eq!(document.spans().unwrap().len(), patterns.len());
+            if header.is_none() {
+                prop_assert_eq!(to_gram(document.patterns()), document.serialize());
+            }
+        }
+    }
+}
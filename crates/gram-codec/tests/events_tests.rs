@@ -0,0 +1,188 @@
+//! Tests for event-based parsing
+//!
+//! Rebuilds patterns from the events with `parse_gram_borrowed` and checks them, and any error, against
+//! `parse_gram`, and checks event order and spans for each syntax form.
+
+mod common;
+
+use gram_codec::{
+    into_owned_pattern, parse_events, parse_gram, parse_gram_borrowed, ArrowType, BorrowedValue,
+    Event, ParseError, PatternKind,
+};
+use pattern_core::{Pattern, Subject};
+use proptest::prelude::*;
+use std::borrow::Cow;
+
+/// Builds the patterns `parse_gram` returns from the events.
+fn build(input: &str) -> Result<Vec<Pattern<Subject>>, ParseError> {
+    parse_gram_borrowed(input)
+        .map(|patterns| patterns.into_iter().map(into_owned_pattern).collect())
+}
+
+/// Each event as its kind and the text of its span.
+fn outline(input: &str) -> Vec<(&'static str, &str)> {
+    parse_events(input)
+        .map(|event| {
+            let (name, span) = match event.unwrap() {
+                Event::StartPattern { span, .. } => ("start", span),
+                Event::EndPattern { span, .. } => ("end", span),
+                Event::Subject { span, .. } => ("subject", span),
+                Event::Property { span, .. } => ("property", span),
+                Event::Arrow { span, .. } => ("arrow", span),
+                Event::Annotation { span, .. } => ("annotation", span),
+            };
+            (name, &input[span])
+        })
+        .collect()
+}
+
+#[test]
+fn test_empty_input() {
+    assert_eq!(parse_events("").count(), 0);
+    assert_eq!(parse_events("  // comment\n").count(), 0);
+}
+
+#[test]
+fn test_node_events() {
+    let events: Vec<Event> = parse_events("(alice:Person:User {age: 30})")
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        events,
+        vec![
+            Event::StartPattern {
+                kind: PatternKind::Node,
+                span: 0..29
+            },
+            Event::Subject {
+                identity: Cow::Borrowed("alice"),
+                labels: vec![Cow::Borrowed("Person"), Cow::Borrowed("User")],
+                span: 1..28
+            },
+            Event::Property {
+                key: Cow::Borrowed("age"),
+                value: BorrowedValue::VInteger(30),
+                span: 20..27
+            },
+            Event::EndPattern {
+                kind: PatternKind::Node,
+                span: 0..29
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_names_are_borrowed_unless_escaped() {
+    let input = String::from(r#"(a:L {k: "v"}) ("q":`M`) ("e\"s")"#);
+    for event in parse_events(&input) {
+        match event.unwrap() {
+            Event::Subject { identity, .. } if identity.contains('"') => {
+                assert!(matches!(identity, Cow::Owned(_)))
+            }
+            Event::Subject {
+                identity, labels, ..
+            } => {
+                assert!(matches!(identity, Cow::Borrowed(_)));
+                assert!(matches!(labels[0], Cow::Borrowed(_)));
+            }
+            Event::Property { key, value, .. } => {
+                assert!(matches!(key, Cow::Borrowed(_)));
+                assert!(matches!(value, BorrowedValue::VString(Cow::Borrowed("v"))));
+            }
+            _ => {}
+        }
+    }
+}
+
+#[test]
+fn test_path_events() {
+    assert_eq!(
+        outline("(a)<-[r:T]-(b) --> (c)"),
+        vec![
+            ("start", "(a)<-[r:T]-(b) --> (c)"),
+            ("start", "(a)"),
+            ("subject", "a"),
+            ("end", "(a)"),
+            ("arrow", "<-[r:T]-"),
+            ("subject", "r:T"),
+            ("start", "(b)"),
+            ("subject", "b"),
+            ("end", "(b)"),
+            ("arrow", "-->"),
+            ("start", "(c)"),
+            ("subject", "c"),
+            ("end", "(c)"),
+            ("end", "(a)<-[r:T]-(b) --> (c)"),
+        ]
+    );
+    let arrows: Vec<ArrowType> = parse_events("(a)<-[r:T]-(b)-->(c)")
+        .filter_map(|event| match event.unwrap() {
+            Event::Arrow { arrow, .. } => Some(arrow),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(arrows, vec![ArrowType::Left, ArrowType::Right]);
+}
+
+#[test]
+fn test_subject_pattern_and_annotation_events() {
+    assert_eq!(
+        outline("{v: 1}\n@note(x) [g | a, (b)] []"),
+        vec![
+            ("start", "{v: 1}"),
+            ("property", "v: 1"),
+            ("end", "{v: 1}"),
+            ("annotation", "@note(x)"),
+            ("start", "[g | a, (b)]"),
+            ("subject", "g"),
+            ("start", "a"),
+            ("subject", "a"),
+            ("end", "a"),
+            ("start", "(b)"),
+            ("subject", "b"),
+            ("end", "(b)"),
+            ("end", "[g | a, (b)]"),
+            ("start", "[]"),
+            ("subject", ""),
+            ("end", "[]"),
+        ]
+    );
+}
+
+#[test]
+fn test_events_before_error() {
+    let events: Vec<Result<Event, ParseError>> = parse_events("(a) (b").collect();
+    assert_eq!(events.len(), 4);
+    assert!(events[..3].iter().all(Result::is_ok));
+    assert_eq!(events[3], Err(parse_gram("(a) (b").unwrap_err()));
+}
+
+#[test]
+fn test_matches_parse_gram() {
+    let documents = [
+        "{version: 1}\n(alice:Person {name: \"Alice\", age: 30})-[:KNOWS {since: 2020}]->(bob:Person)",
+        "(a)<-[r]-(b)==>(c)<~~>(d)\n[p:Path | a, r, (c)]",
+        "@layout(grid) @z [g | (x), [h | y]]",
+        "(n {tags: [\"a\", \"b\"], size: 2.5kg, id: 0x1F, window: ..10, typed:: 7, m: {x: 1}})",
+        "[empty |] [] (\"quoted id\":`Label`) (a {k: 1, k: 2})",
+        "(a) ) (b)",
+        "(a {k: })",
+        "\u{c}(a)\u{c}",
+    ];
+    for document in documents {
+        assert_eq!(build(document), parse_gram(document), "{}", document);
+    }
+}
+
+proptest! {
+    #[test]
+    fn prop_matches_parse_gram(input in common::documents()) {
+        prop_assert_eq!(build(&input), parse_gram(&input), "{:?}", input);
+    }
+
+    #[test]
+    fn prop_arbitrary_text_matches_parse_gram(input in "[()\\[\\]{}:,|@<>=~\\-a-z0-9 \n\"'`./]{0,40}") {
+        prop_assert_eq!(build(&input), parse_gram(&input), "{:?}", input);
+    }
+}
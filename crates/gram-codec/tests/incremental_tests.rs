@@ -0,0 +1,213 @@
+//! Tests for incremental reparsing
+//!
+//! Applies edits to a parse session and checks that its patterns, spans and
+//! errors always agree with a full reparse of the edited text.
+
+use gram_codec::{parse_gram, Edit, ParseSession};
+use proptest::prelude::*;
+
+const DOCUMENT: &str = r#"{version: 1}
+(alice:Person {name: "Alice"})
+(bob:Person {name: "Bob"})
+// friends
+(alice)-[:KNOWS]->(bob)
+[team:Team | alice, bob]
+@note("x") (carol)
+"#;
+
+/// Checks the session against a full parse of its text.
+fn assert_consistent(session: &ParseSession) {
+    let expected = parse_gram(session.text());
+    match (session.patterns(), &expected) {
+        (Ok(patterns), Ok(expected)) => {
+            assert_eq!(patterns, expected.as_slice(), "text: {:?}", session.text());
+            assert_eq!(session.spans().len(), patterns.len());
+            for (i, (span, pattern)) in session.spans().iter().zip(patterns).enumerate() {
+                let text = &session.text()[span.clone()];
+                assert_eq!(text, text.trim(), "span {} has surrounding whitespace", i);
+                // Patterns reparse from their own text, apart from a leading record
+                if i > 0 || !text.starts_with('{') {
+                    assert_eq!(&parse_gram(text).unwrap(), std::slice::from_ref(pattern));
+                }
+            }
+        }
+        (Err(error), Err(expected)) => assert_eq!(error, expected, "text: {:?}", session.text()),
+        (actual, expected) => panic!(
+            "session {:?} but full parse {:?} for {:?}",
+            actual.is_ok(),
+            expected.is_ok(),
+            session.text()
+        ),
+    }
+}
+
+fn offset_of(session: &ParseSession, needle: &str) -> usize {
+    session.text().find(needle).expect("needle in text")
+}
+
+#[test]
+fn test_new_session_matches_full_parse() {
+    let session = ParseSession::new(DOCUMENT);
+    assert_consistent(&session);
+    assert_eq!(session.patterns().unwrap().len(), 6);
+}
+
+#[test]
+fn test_edit_within_pattern() {
+    let mut session = ParseSession::new(DOCUMENT);
+    let at = offset_of(&session, "Bob\"");
+    let reparse = session.edit(Edit::new(at..at + 3, "Robert")).unwrap();
+    assert_eq!(reparse.removed, 2..3);
+    assert_eq!(reparse.inserted, 2..3);
+    assert_consistent(&session);
+}
+
+#[test]
+fn test_edit_shifts_later_spans() {
+    let mut session = ParseSession::new(DOCUMENT);
+    let before = session.spans().to_vec();
+    let at = offset_of(&session, "alice:Person") + "alice".len();
+    session.edit(Edit::insert(at, "_smith")).unwrap();
+    assert_eq!(session.spans()[0], before[0]);
+    for (old, new) in before[2..].iter().zip(&session.spans()[2..]) {
+        assert_eq!(new.start, old.start + 6);
+        assert_eq!(new.end, old.end + 6);
+    }
+    assert_consistent(&session);
+}
+
+#[test]
+fn test_inserting_patterns() {
+    let mut session = ParseSession::new(DOCUMENT);
+    let at = offset_of(&session, "[team");
+    let reparse = session.edit(Edit::insert(at, "(dave)\n(erin)\n")).unwrap();
+    assert_eq!(reparse.removed, 3..4);
+    assert_eq!(reparse.inserted, 3..6);
+    assert_consistent(&session);
+}
+
+#[test]
+fn test_deleting_patterns() {
+    let mut session = ParseSession::new(DOCUMENT);
+    let start = offset_of(&session, "(bob:Person");
+    let end = offset_of(&session, "[team");
+    let reparse = session.edit(Edit::delete(start..end)).unwrap();
+    assert_eq!(reparse.removed, 1..4);
+    assert_eq!(reparse.inserted, 1..2);
+    assert_consistent(&session);
+}
+
+#[test]
+fn test_edit_joining_patterns_into_path() {
+    let mut session = ParseSession::new("(a)\n(b)\n(c)");
+    let reparse = session.edit(Edit::new(3..4, "-->")).unwrap();
+    assert_eq!(reparse.removed, 0..2);
+    assert_eq!(reparse.inserted, 0..1);
+    assert_eq!(session.patterns().unwrap().len(), 2);
+    assert_consistent(&session);
+}
+
+#[test]
+fn test_edit_in_comment_keeps_patterns() {
+    let mut session = ParseSession::new(DOCUMENT);
+    let before = session.patterns().unwrap().to_vec();
+    let at = offset_of(&session, "friends");
+    session.edit(Edit::insert(at, "good ")).unwrap();
+    assert_eq!(session.patterns().unwrap(), before.as_slice());
+    assert_consistent(&session);
+}
+
+#[test]
+fn test_recovers_after_errors() {
+    let mut session = ParseSession::new(DOCUMENT);
+    let before = session.patterns().unwrap().to_vec();
+
+    // Type a new relationship one character at a time
+    let mut at = offset_of(&session, "[team");
+    for c in "(bob)-[:KNOWS]->(alice)\n".chars() {
+        let _ = session.edit(Edit::insert(at, c.to_string()));
+        at += c.len_utf8();
+        assert_consistent(&session);
+    }
+    let patterns = session.patterns().unwrap();
+    assert_eq!(patterns.len(), before.len() + 1);
+    assert_eq!(&patterns[..3], &before[..3]);
+    assert_eq!(&patterns[5..], &before[4..]);
+}
+
+#[test]
+fn test_reports_changes_since_last_successful_parse() {
+    let mut session = ParseSession::new("(a) (b) (c)");
+    assert!(session.edit(Edit::insert(5, "(")).is_err());
+    assert!(session.patterns().is_err());
+    let reparse = session.edit(Edit::new(5..7, "x")).unwrap();
+    assert_eq!(reparse.removed, 1..2);
+    assert_eq!(reparse.inserted, 1..2);
+    assert_consistent(&session);
+}
+
+#[test]
+fn test_leading_record() {
+    let mut session = ParseSession::new("{v: 1}\n(a)");
+    assert!(session.edit(Edit::insert(0, "(x)\n")).is_err());
+    assert_consistent(&session);
+    session.edit(Edit::delete(0..4)).unwrap();
+    assert_consistent(&session);
+    session.edit(Edit::delete(0..7)).unwrap();
+    assert_consistent(&session);
+    session.edit(Edit::insert(0, "{w: 2} ")).unwrap();
+    assert_consistent(&session);
+    assert_eq!(session.patterns().unwrap().len(), 2);
+}
+
+#[test]
+#[should_panic(expected = "out of bounds")]
+fn test_edit_out_of_bounds_panics() {
+    let mut session = ParseSession::new("(a)");
+    let _ = session.edit(Edit::delete(2..10));
+}
+
+// Fragments, including incomplete ones, that edits insert
+const FRAGMENTS: &[&str] = &[
+    "",
+    " ",
+    "\n",
+    "(",
+    ")",
+    "[",
+    "]",
+    "{",
+    "}",
+    "a",
+    "b:Label",
+    "-->",
+    "<--",
+    "-[r]->",
+    "|",
+    ",",
+    "(x)",
+    "(y:Person {k: 1})",
+    "[p | a, b]",
+    "{n: 2}",
+    "@m(1) ",
+    "// note\n",
+    "\"s\"",
+];
+
+fn edits() -> impl Strategy<Value = Vec<(usize, usize, usize)>> {
+    prop::collection::vec((any::<usize>(), 0usize..8, 0..FRAGMENTS.len()), 1..20)
+}
+
+proptest! {
+    #[test]
+    fn prop_edits_match_full_reparse(edits in edits()) {
+        let mut session = ParseSession::new(DOCUMENT);
+        for (start, len, fragment) in edits {
+            // Documents stay ASCII, so every offset is a char boundary
+            let start = start % (session.text().len() + 1);
+            let end = (start + len).min(session.text().len());
+            let _ = session.edit(Edit::new(start..end, FRAGMENTS[fragment]));
+            assert_consistent(&session);
+        }
+    }
+}
@@ -0,0 +1,173 @@
+//! Tests for parse options and resource limits
+//!
+//! Checks that each limit is reported with its own error at the right place,
+//! that deeply nested input is rejected before it can overflow the stack, and
+//! that documents within the limits parse as `parse_gram` parses them.
+
+use gram_codec::{parse_gram, parse_gram_with_options, ParseError, ParseOptions};
+use proptest::prelude::*;
+
+fn error_offset(input: &str, options: &ParseOptions) -> Option<usize> {
+    parse_gram_with_options(input, options)
+        .unwrap_err()
+        .location()
+        .map(|location| location.offset)
+}
+
+#[test]
+fn test_default_options_match_parse_gram() {
+    let documents = [
+        "",
+        "{v: 1} (a:Person {name: \"Alice\"})-[:KNOWS]->(b) [g | a, (c)]",
+        "@layout(grid) (a {m: {k: [1, 2]}})",
+        "(a) (b",
+    ];
+    for document in documents {
+        assert_eq!(
+            parse_gram_with_options(document, &ParseOptions::default()),
+            parse_gram(document)
+        );
+    }
+}
+
+#[test]
+fn test_max_input_size() {
+    let options = ParseOptions {
+        max_input_size: Some(8),
+        ..ParseOptions::default()
+    };
+    assert_eq!(
+        parse_gram_with_options("(a)-->(b)", &options),
+        Err(ParseError::InputTooLarge { size: 9, limit: 8 })
+    );
+    assert!(parse_gram_with_options("(a)--(b)", &options).is_ok());
+}
+
+#[test]
+fn test_max_depth() {
+    let options = ParseOptions {
+        max_depth: Some(3),
+        ..ParseOptions::default()
+    };
+    assert!(parse_gram_with_options("[a | [b | (c)]]", &options).is_ok());
+    assert!(matches!(
+        parse_gram_with_options("[a | [b | (c {k: 1})]]", &options),
+        Err(ParseError::NestingTooDeep { limit: 3, .. })
+    ));
+    assert_eq!(error_offset("[a | [b | (c {k: 1})]]", &options), Some(13));
+    assert_eq!(error_offset("(a {k: [[[1]]]})", &options), Some(8));
+    assert_eq!(error_offset("@x @y [g | (a)]", &options), Some(11));
+    // Each arrow nests the path before it one level deeper
+    assert!(parse_gram_with_options("(a)-->(b)-->(c)", &options).is_ok());
+    assert_eq!(error_offset("(a)-->(b)-->(c)-->(d)", &options), Some(18));
+}
+
+#[test]
+fn test_deep_nesting_is_rejected_without_overflow() {
+    let options = ParseOptions {
+        max_depth: Some(64),
+        ..ParseOptions::default()
+    };
+    let depth = 100_000;
+    let patterns = format!("{}{}", "[g | ".repeat(depth), "]".repeat(depth));
+    let arrays = format!("(a {{k: {}{}}})", "[".repeat(depth), "]".repeat(depth));
+    let annotations = format!("{}(a)", "@x ".repeat(depth));
+    let path = format!("(a){}", "-->(a)".repeat(depth));
+    for input in [patterns, arrays, annotations, path] {
+        assert!(matches!(
+            parse_gram_with_options(&input, &options),
+            Err(ParseError::NestingTooDeep { limit: 64, .. })
+        ));
+    }
+}
+
+#[test]
+fn test_max_patterns() {
+    let options = ParseOptions {
+        max_patterns: Some(3),
+        ..ParseOptions::default()
+    };
+    assert!(parse_gram_with_options("(a)-->(b)", &options).is_ok());
+    assert!(matches!(
+        parse_gram_with_options("(a) (b) (c) (d)", &options),
+        Err(ParseError::TooManyPatterns { limit: 3, .. })
+    ));
+    assert_eq!(error_offset("(a) (b) (c) (d)", &options), Some(12));
+    // Nested patterns, references and a leading record all count
+    assert_eq!(error_offset("[g | a, b, c]", &options), Some(11));
+    assert_eq!(error_offset("{v: 1} (a)-->(b)", &options), Some(13));
+}
+
+#[test]
+fn test_max_properties() {
+    let options = ParseOptions {
+        max_properties: Some(2),
+        ..ParseOptions::default()
+    };
+    assert!(parse_gram_with_options("(a {x: 1, y: 2})-[{z: 3, w: 4}]->(b)", &options).is_ok());
+    assert!(matches!(
+        parse_gram_with_options("(a {x: 1, y: 2, z: 3})", &options),
+        Err(ParseError::TooManyProperties { limit: 2, .. })
+    ));
+    assert_eq!(error_offset("(a {x: 1, y: 2, z: 3})", &options), Some(16));
+    assert_eq!(
+        error_offset("(a {m: {x: 1, y: 2, z: 3}})", &options),
+        Some(4)
+    );
+}
+
+#[test]
+fn test_max_string_length() {
+    let options = ParseOptions {
+        max_string_length: Some(5),
+        ..ParseOptions::default()
+    };
+    assert!(parse_gram_with_options("(alice:Human {name: \"Alice\"})", &options).is_ok());
+    for (input, offset) in [
+        ("(alexander)", 1),
+        ("(a:Persons)", 1),
+        ("(a {longkey: 1})", 4),
+        ("(a {k: \"too long\"})", 4),
+        ("(a {k: [\"ok\", \"too long\"]})", 4),
+        ("(a {k: date`2024-01-01`})", 4),
+        ("@layout(a) (x)", 0),
+    ] {
+        assert!(
+            matches!(
+                parse_gram_with_options(input, &options),
+                Err(ParseError::StringTooLong { limit: 5, .. })
+            ),
+            "{}",
+            input
+        );
+        assert_eq!(error_offset(input, &options), Some(offset), "{}", input);
+    }
+}
+
+#[test]
+fn test_limit_errors_have_messages() {
+    let options = ParseOptions {
+        max_patterns: Some(1),
+        ..ParseOptions::default()
+    };
+    let error = parse_gram_with_options("(a) (b)", &options).unwrap_err();
+    assert_eq!(error.message(), "more than 1 patterns");
+    assert_eq!(error.to_string(), "More than 1 patterns at 1:5");
+}
+
+proptest! {
+    #[test]
+    fn prop_generous_limits_match_parse_gram(
+        input in "[()\\[\\]{}:,|@<>\\-a-z0-9 \"]{0,40}"
+    ) {
+        let options = ParseOptions {
+            max_depth: Some(40),
+            max_patterns: Some(40),
+            max_properties: Some(40),
+            max_string_length: Some(40),
+            max_input_size: Some(40),
+            ..ParseOptions::default()
+        };
+        prop_assert_eq!(parse_gram_with_options(&input, &options), parse_gram(&input));
+    }
+}
@@ -0,0 +1,241 @@
+//! Tests for the gram pattern query engine
+//!
+//! Runs queries written in gram notation against parsed documents and checks
+//! the bound rows.
+
+use gram_codec::parse_gram;
+use gram_codec::query::{Binding, Graph, Query, QueryError};
+use gram_codec::{Pattern, Subject};
+
+const COMPANIES: &str = r#"
+(alice:Person {name: "Alice", age: 34})
+(bob:Person {name: "Bob", age: 27})
+(carol:Person:Manager {name: "Carol", age: 45})
+(acme:Company {country: 'NZ'})
+(globex:Company {country: 'US'})
+(alice)-[:WORKS_AT {since: 2019}]->(acme)
+(bob)-[:WORKS_AT {since: 2021}]->(globex)
+(carol)-[:WORKS_AT {since: 2010}]->(acme)
+(alice)-[:KNOWS]->(bob)
+(bob)-[:KNOWS]->(carol)
+"#;
+
+fn document(text: &str) -> Vec<Pattern<Subject>> {
+    parse_gram(text).expect("document parses")
+}
+
+fn ids<'g>(rows: &[gram_codec::query::Row<'g>], variable: &str) -> Vec<&'g str> {
+    let mut ids: Vec<&str> = rows
+        .iter()
+        .map(|row| row.node(variable).unwrap().identity.0.as_str())
+        .collect();
+    ids.sort();
+    ids
+}
+
+#[test]
+fn test_labels_and_properties_filter_matches() {
+    let patterns = document(COMPANIES);
+    let graph = Graph::new(&patterns);
+
+    let rows = graph
+        .run("(p:Person)-[:WORKS_AT]->(c:Company {country: 'NZ'})")
+        .unwrap();
+
+    assert_eq!(ids(&rows, "p"), vec!["alice", "carol"]);
+    assert!(rows
+        .iter()
+        .all(|row| row.node("c").unwrap().identity.0 == "acme"));
+}
+
+#[test]
+fn test_nodes_are_merged_by_identity() {
+    let patterns = document(COMPANIES);
+    let graph = Graph::new(&patterns);
+
+    assert_eq!(graph.node_count(), 5);
+    assert_eq!(graph.relationship_count(), 5);
+    // The relationship occurrence `(alice)` carries no labels; the declaration does
+    let rows = graph
+        .run("(p:Person {name: 'Alice'})-->(:Company)")
+        .unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].node("p").unwrap().properties.len(), 2);
+}
+
+#[test]
+fn test_relationship_variables_bind_subjects() {
+    let patterns = document(COMPANIES);
+    let graph = Graph::new(&patterns);
+
+    let rows = graph.run("(:Manager)-[w:WORKS_AT]->(c)").unwrap();
+
+    assert_eq!(rows.len(), 1);
+    let works_at = rows[0].relationship("w").unwrap();
+    assert!(works_at.labels.contains("WORKS_AT"));
+    assert_eq!(
+        works_at.properties.get("since"),
+        Some(&pattern_core::Value::VInteger(2010))
+    );
+    assert_eq!(rows[0].variables().collect::<Vec<_>>(), vec!["c", "w"]);
+}
+
+#[test]
+fn test_arrow_direction() {
+    let patterns = document(COMPANIES);
+    let graph = Graph::new(&patterns);
+
+    let incoming = graph.run("(c:Company)<--(p)").unwrap();
+    assert_eq!(ids(&incoming, "p"), vec!["alice", "bob", "carol"]);
+
+    let wrong_way = graph.run("(c:Company)-->(p)").unwrap();
+    assert!(wrong_way.is_empty());
+
+    let either = graph.run("(b {name: 'Bob'})-[:KNOWS]-(x)").unwrap();
+    assert_eq!(ids(&either, "x"), vec!["alice", "carol"]);
+}
+
+#[test]
+fn test_numeric_and_range_filters() {
+    let patterns = document(COMPANIES);
+    let graph = Graph::new(&patterns);
+
+    let exact = graph.run("(p:Person {age: 34.0})").unwrap();
+    assert_eq!(ids(&exact, "p"), vec!["alice"]);
+
+    let range = graph.run("(p:Person {age: 30..50})").unwrap();
+    assert_eq!(ids(&range, "p"), vec!["alice", "carol"]);
+}
+
+#[test]
+fn test_paths_of_several_segments() {
+    let patterns = document(COMPANIES);
+    let graph = Graph::new(&patterns);
+
+    let rows = graph
+        .run("(a)-[:KNOWS]->(b)-[:KNOWS]->(c)-[:WORKS_AT]->(co)")
+        .unwrap();
+
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].node("a").unwrap().identity.0, "alice");
+    assert_eq!(rows[0].node("co").unwrap().identity.0, "acme");
+}
+
+#[test]
+fn test_joins_across_paths() {
+    let patterns = document(COMPANIES);
+    let graph = Graph::new(&patterns);
+
+    // Colleagues: two different people working at the same company
+    let rows = graph
+        .run("(a:Person)-[:WORKS_AT]->(c), (b:Person)-[:WORKS_AT]->(c)")
+        .unwrap();
+    let pairs: Vec<(&str, &str)> = rows
+        .iter()
+        .map(|row| {
+            (
+                row.node("a").unwrap().identity.0.as_str(),
+                row.node("b").unwrap().identity.0.as_str(),
+            )
+        })
+        .filter(|(a, b)| a != b)
+        .collect();
+    assert_eq!(pairs.len(), 2);
+    assert!(pairs.contains(&("alice", "carol")));
+    assert!(pairs.contains(&("carol", "alice")));
+}
+
+#[test]
+fn test_repeated_variable_in_path_must_bind_same_node() {
+    let patterns = document("(a)-->(b) (b)-->(a) (b)-->(c)");
+    let graph = Graph::new(&patterns);
+
+    let cycles = graph.run("(x)-->(y)-->(x)").unwrap();
+    assert_eq!(ids(&cycles, "x"), vec!["a", "b"]);
+}
+
+#[test]
+fn test_variable_length_paths() {
+    let patterns = document("(a)-[:NEXT]->(b)-[:NEXT]->(c)-[:NEXT]->(d)");
+    let graph = Graph::new(&patterns);
+
+    let reachable = graph.run("(s)-[:NEXT *]->(t)").unwrap();
+    assert_eq!(reachable.len(), 6);
+
+    let from_a = graph.run("(a)-[r *1..2]->(t)").unwrap();
+    let from_a: Vec<_> = from_a
+        .iter()
+        .filter(|row| row.node("a").unwrap().identity.0 == "a")
+        .map(|row| {
+            (
+                row.node("t").unwrap().identity.0.as_str(),
+                row.path("r").unwrap().len(),
+            )
+        })
+        .collect();
+    assert_eq!(from_a, vec![("b", 1), ("c", 2)]);
+
+    let exactly_three = graph.run("(s)-[*3]->(t)").unwrap();
+    assert_eq!(exactly_three.len(), 1);
+    assert_eq!(exactly_three[0].node("t").unwrap().identity.0, "d");
+
+    let with_zero = graph.run("(s)-[*0..1]->(t)").unwrap();
+    assert_eq!(with_zero.len(), 4 + 3);
+}
+
+#[test]
+fn test_variable_length_path_binding_is_in_query_order() {
+    let patterns = document("(a)-[r1]->(b)-[r2]->(c) (c:End)");
+    let graph = Graph::new(&patterns);
+
+    // Anchored at the labelled end, so the path is walked right to left
+    let rows = graph.run("(s)-[p *2]->(:End)").unwrap();
+    assert_eq!(rows.len(), 1);
+    let hops: Vec<&str> = rows[0]
+        .path("p")
+        .unwrap()
+        .iter()
+        .map(|r| r.identity.0.as_str())
+        .collect();
+    assert_eq!(hops, vec!["r1", "r2"]);
+    assert!(matches!(rows[0].get("p"), Some(Binding::Path(_))));
+}
+
+#[test]
+fn test_relationships_are_not_reused_within_a_row() {
+    let patterns = document("(a)-->(b)");
+    let graph = Graph::new(&patterns);
+
+    assert_eq!(graph.run("(x)--(y)").unwrap().len(), 2);
+    assert!(graph.run("(x)--(y)--(z)").unwrap().is_empty());
+}
+
+#[test]
+fn test_query_variables_and_reuse() {
+    let query: Query = "(p:Person)-[w:WORKS_AT]->(c), (p)-[:KNOWS *]->(f)"
+        .parse()
+        .unwrap();
+    assert_eq!(query.variables(), vec!["p", "w", "c", "f"]);
+
+    let patterns = document(COMPANIES);
+    let graph = Graph::new(&patterns);
+    assert_eq!(graph.query(&query).len(), 3);
+}
+
+#[test]
+fn test_invalid_queries() {
+    assert!(matches!(Query::parse(""), Err(QueryError::Invalid(_))));
+    assert!(matches!(Query::parse("(a)-->"), Err(QueryError::Parse(_))));
+    assert!(matches!(
+        Query::parse("(a)-[*3..1]->(b)"),
+        Err(QueryError::Invalid(_))
+    ));
+    assert!(matches!(
+        Query::parse("(a)-[r]->(b)-[r]->(c)"),
+        Err(QueryError::Invalid(_))
+    ));
+    assert!(matches!(
+        Query::parse("(a)-[a]->(b)"),
+        Err(QueryError::Invalid(_))
+    ));
+}
@@ -0,0 +1,302 @@
+//! Tests for the serde_gram data format
+//!
+//! Round-trips Rust types through gram notation and checks the text produced
+//! for each kind of serde data.
+
+use gram_codec::serde_gram::{self, Error};
+use gram_codec::{parse_gram, Pattern, Subject};
+use pattern_core::Value;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Address {
+    city: String,
+    zip: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+enum Status {
+    Active,
+    Suspended { reason: String },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Person {
+    name: String,
+    age: u8,
+    score: f64,
+    admin: bool,
+    tags: Vec<String>,
+    address: Address,
+    status: Status,
+    nickname: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+enum Shape {
+    Empty,
+    Circle { radius: f64 },
+    Square(Side),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Side {
+    length: i64,
+}
+
+fn alice() -> Person {
+    Person {
+        name: "Alice \"Al\" Smith".to_string(),
+        age: 30,
+        score: 1.0,
+        admin: true,
+        tags: vec!["ops".to_string(), "dev".to_string()],
+        address: Address {
+            city: "Oslo".to_string(),
+            zip: None,
+        },
+        status: Status::Active,
+        nickname: None,
+    }
+}
+
+fn round_trip<T>(value: &T) -> T
+where
+    T: Serialize + serde::de::DeserializeOwned,
+{
+    let gram = serde_gram::to_string(value).unwrap();
+    serde_gram::from_str(&gram).unwrap_or_else(|e| panic!("{}\nfrom: {}", e, gram))
+}
+
+// ============================================================================
+// Serialization Shape
+// ============================================================================
+
+#[test]
+fn test_struct_serializes_to_node_with_record() {
+    let gram = serde_gram::to_string(&alice()).unwrap();
+    assert_eq!(
+        gram,
+        "({address: {city: \"Oslo\"}, admin: true, age: 30, name: \"Alice \\\"Al\\\" Smith\", \
+         score: 1.0, status: Active, tags: [\"ops\", \"dev\"]})"
+    );
+    // The output is ordinary gram notation
+    let patterns = parse_gram(&gram).unwrap();
+    assert_eq!(patterns.len(), 1);
+    assert_eq!(patterns[0].value.properties["age"], Value::VInteger(30));
+    assert_eq!(
+        patterns[0].value.properties["status"],
+        Value::VSymbol("Active".to_string())
+    );
+}
+
+#[test]
+fn test_enums_serialize_to_labels() {
+    let shapes = vec![
+        Shape::Empty,
+        Shape::Circle { radius: 2.5 },
+        Shape::Square(Side { length: 3 }),
+    ];
+    assert_eq!(
+        serde_gram::to_string(&shapes).unwrap(),
+        "(:Empty)\n(:Circle {radius: 2.5})\n(:Square {length: 3})"
+    );
+}
+
+#[test]
+fn test_nested_sequences_become_elements() {
+    let groups = vec![vec![Side { length: 1 }, Side { length: 2 }], vec![]];
+    let gram = serde_gram::to_string(&groups).unwrap();
+    assert_eq!(gram, "[ | ({length: 1}), ({length: 2})]\n()");
+    assert_eq!(round_trip(&groups), groups);
+}
+
+#[test]
+fn test_maps_serialize_to_records() {
+    let map = BTreeMap::from([("b".to_string(), 2), ("a".to_string(), 1)]);
+    assert_eq!(serde_gram::to_string(&map).unwrap(), "({a: 1, b: 2})");
+    assert_eq!(round_trip(&map), map);
+}
+
+#[test]
+fn test_value_level_variants() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Holder {
+        status: Status,
+        pair: (i64, String),
+    }
+    let holder = Holder {
+        status: Status::Suspended {
+            reason: "spam".to_string(),
+        },
+        pair: (1, "x".to_string()),
+    };
+    assert_eq!(
+        serde_gram::to_string(&holder).unwrap(),
+        "({pair: [1, \"x\"], status: {Suspended: {reason: \"spam\"}}})"
+    );
+    assert_eq!(round_trip(&holder), holder);
+}
+
+#[test]
+fn test_empty_documents() {
+    assert_eq!(serde_gram::to_string(&None::<Side>).unwrap(), "");
+    assert_eq!(serde_gram::to_string(&Vec::<Side>::new()).unwrap(), "");
+    assert_eq!(serde_gram::from_str::<Option<Side>>("").unwrap(), None);
+    assert_eq!(serde_gram::from_str::<Vec<Side>>("").unwrap(), vec![]);
+    assert_eq!(
+        serde_gram::from_str::<Option<Side>>("({length: 4})").unwrap(),
+        Some(Side { length: 4 })
+    );
+}
+
+// ============================================================================
+// Round Trips
+// ============================================================================
+
+#[test]
+fn test_round_trip_struct() {
+    let mut person = alice();
+    person.nickname = Some("Al".to_string());
+    person.address.zip = Some("0150".to_string());
+    person.status = Status::Suspended {
+        reason: "line\nbreak".to_string(),
+    };
+    person.score = -0.125;
+    assert_eq!(round_trip(&person), person);
+}
+
+#[test]
+fn test_round_trip_enums() {
+    let shapes = vec![
+        Shape::Empty,
+        Shape::Circle { radius: 1e20 },
+        Shape::Square(Side { length: -7 }),
+    ];
+    assert_eq!(round_trip(&shapes), shapes);
+}
+
+#[test]
+fn test_round_trip_hash_map_of_structs() {
+    let map = HashMap::from([
+        ("home".to_string(), alice().address),
+        ("work".to_string(), alice().address),
+    ]);
+    assert_eq!(round_trip(&map), map);
+}
+
+// ============================================================================
+// Reading Hand-Written Gram
+// ============================================================================
+
+#[test]
+fn test_from_str_reads_hand_written_notation() {
+    // Identities and extra properties are ignored, integers widen to floats
+    let side: Side = serde_gram::from_str("(s1 {length: 0x10, color: 'red'})").unwrap();
+    assert_eq!(side, Side { length: 16 });
+
+    let shape: Shape = serde_gram::from_str("(c1:Circle {radius: 2})").unwrap();
+    assert_eq!(shape, Shape::Circle { radius: 2.0 });
+
+    // A leading record is a pattern too
+    let address: Address = serde_gram::from_str("{city: 'Oslo'}").unwrap();
+    assert_eq!(address.city, "Oslo");
+}
+
+#[test]
+fn test_from_patterns_borrows() {
+    #[derive(Deserialize)]
+    struct Borrowed<'a> {
+        name: &'a str,
+    }
+    let patterns: Vec<Pattern<Subject>> = parse_gram("({name: 'Bob'})").unwrap();
+    let borrowed: Borrowed = serde_gram::from_patterns(&patterns).unwrap();
+    assert_eq!(borrowed.name, "Bob");
+}
+
+#[test]
+fn test_special_values_read_as_maps() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Range {
+        lower: Option<f64>,
+        upper: Option<f64>,
+    }
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Measured {
+        unit: String,
+        value: f64,
+    }
+    #[derive(Deserialize)]
+    struct Holder {
+        range: Range,
+        weight: Measured,
+    }
+    let holder: Holder = serde_gram::from_str("({range: 1.., weight: 5kg})").unwrap();
+    assert_eq!(
+        holder.range,
+        Range {
+            lower: Some(1.0),
+            upper: None
+        }
+    );
+    assert_eq!(
+        holder.weight,
+        Measured {
+            unit: "kg".to_string(),
+            value: 5.0
+        }
+    );
+}
+
+// ============================================================================
+// Errors
+// ============================================================================
+
+#[test]
+fn test_deserialize_errors() {
+    assert!(matches!(
+        serde_gram::from_str::<Side>("(unclosed"),
+        Err(Error::Parse(_))
+    ));
+
+    let err = serde_gram::from_str::<Side>("({length: 'long'})").unwrap_err();
+    assert!(err.to_string().contains("invalid type"), "{}", err);
+
+    let err = serde_gram::from_str::<Side>("({length: 1}) ({length: 2})").unwrap_err();
+    assert_eq!(err.to_string(), "expected a single pattern, found 2");
+
+    let err = serde_gram::from_str::<Shape>("(:Circle:Square)").unwrap_err();
+    assert!(err.to_string().contains("found several"), "{}", err);
+
+    let err = serde_gram::from_str::<Side>("({})").unwrap_err();
+    assert_eq!(err.to_string(), "missing field `length`");
+}
+
+#[test]
+fn test_serialize_errors() {
+    assert!(matches!(
+        serde_gram::to_string(&42),
+        Err(Error::Unsupported(_))
+    ));
+    #[derive(Serialize)]
+    struct Holder {
+        items: Vec<Option<i64>>,
+        big: u64,
+        ratio: f64,
+    }
+    let holder = |items, big, ratio| Holder { items, big, ratio };
+    let err = serde_gram::to_string(&holder(vec![None], 0, 0.0)).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Cannot serialize to gram notation: None cannot be represented inside an array"
+    );
+    assert!(serde_gram::to_string(&holder(vec![], u64::MAX, 0.0)).is_err());
+    assert!(serde_gram::to_string(&holder(vec![], 0, f64::NAN)).is_err());
+
+    let odd_keys = HashMap::from([(
+        "outer".to_string(),
+        HashMap::from([("has space".to_string(), 1)]),
+    )]);
+    assert!(serde_gram::to_string(&odd_keys).is_err());
+}
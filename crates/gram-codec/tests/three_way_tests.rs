@@ -0,0 +1,192 @@
+//! Tests for three-way merges of gram documents
+//!
+//! Merges parsed branches of a common base and checks the merged document,
+//! the reported conflicts and the annotated gram output.
+
+use gram_codec::{parse_gram, to_gram};
+use pattern_core::{diff, three_way_merge, MergeConflict, Pattern, Side, Subject, Symbol, Value};
+
+const BASE: &str = r#"
+(alice:Person {name: "Alice", age: 34})
+(bob:Person {name: "Bob"})
+(carol:Person {name: "Carol"})
+(alice)-[k1:KNOWS]->(bob)
+(bob)-[:KNOWS]->(carol)
+[team:Team | alice, bob, carol]
+"#;
+
+fn document(text: &str) -> Vec<Pattern<Subject>> {
+    parse_gram(text).expect("document parses")
+}
+
+fn merge(ours: &str, theirs: &str) -> (pattern_core::ThreeWayMerge, Vec<Pattern<Subject>>) {
+    let base = document(BASE);
+    let merged = three_way_merge(&base, &document(ours), &document(theirs));
+    (merged, base)
+}
+
+fn symbol(id: &str) -> Symbol {
+    Symbol(id.to_string())
+}
+
+#[test]
+fn test_unchanged_branches_merge_to_base() {
+    let (merged, base) = merge(BASE, BASE);
+    assert!(merged.is_clean());
+    assert_eq!(merged.patterns, base);
+}
+
+#[test]
+fn test_one_sided_changes_are_taken() {
+    let ours = BASE.replace("age: 34", "age: 35");
+    let (merged, base) = merge(&ours, BASE);
+    assert!(merged.is_clean());
+    assert!(diff(&merged.patterns, &document(&ours)).is_empty());
+    assert!(!diff(&merged.patterns, &base).is_empty());
+}
+
+#[test]
+fn test_non_overlapping_changes_are_combined() {
+    let ours = r#"
+        (alice:Person:Employee {name: "Alice", age: 35})
+        (bob:Person {name: "Bob"})
+        (carol:Person {name: "Carol"})
+        (alice)-[k1:KNOWS]->(bob)
+        (bob)-[:KNOWS]->(carol)
+        (alice)-[:MANAGES]->(carol)
+        [team:Team | alice, bob, carol]
+    "#;
+    let theirs = r#"
+        (alice:Person {name: "Alice", age: 34, email: "alice@example.com"})
+        (bob:Person {name: "Robert"})
+        (dave:Person {name: "Dave"})
+        (alice)-[k1:KNOWS]->(bob)
+        (bob)-[:KNOWS]->(dave)
+        [team:Team | alice, bob, dave]
+    "#;
+    let expected = r#"
+        (alice:Person:Employee {name: "Alice", age: 35, email: "alice@example.com"})
+        (bob:Person {name: "Robert"})
+        (dave:Person {name: "Dave"})
+        (alice)-[k1:KNOWS]->(bob)
+        (bob)-[:KNOWS]->(dave)
+        (alice)-[:MANAGES]->(carol)
+        [team:Team | alice, bob, dave]
+    "#;
+
+    let (merged, _) = merge(ours, theirs);
+
+    // carol was removed by theirs but ours still manages her
+    assert_eq!(
+        merged.conflicts,
+        vec![MergeConflict::RemovedAndModified {
+            identity: symbol("carol"),
+            removed_by: Side::Theirs,
+        }]
+    );
+    let mut expected = document(expected);
+    expected.push(document(r#"(carol:Person {name: "Carol"})"#).remove(0));
+    assert!(
+        diff(&merged.patterns, &expected).is_empty(),
+        "{}",
+        diff(&merged.patterns, &expected)
+    );
+}
+
+#[test]
+fn test_identical_changes_are_applied_once() {
+    let both = BASE.replace(
+        "(bob:Person {name: \"Bob\"})",
+        "(bob:Person:Admin {name: \"Bob\"})",
+    );
+    let (merged, _) = merge(&both, &both);
+    assert!(merged.is_clean());
+    assert!(diff(&merged.patterns, &document(&both)).is_empty());
+}
+
+#[test]
+fn test_property_conflicts_keep_base_value() {
+    let ours = BASE.replace("age: 34", "age: 35");
+    let theirs = BASE.replace("age: 34", "age: 36");
+
+    let (merged, _) = merge(&ours, &theirs);
+
+    assert_eq!(
+        merged.conflicts,
+        vec![MergeConflict::Property {
+            identity: symbol("alice"),
+            key: "age".to_string(),
+            base: Some(Value::from(34)),
+            ours: Some(Value::from(35)),
+            theirs: Some(Value::from(36)),
+        }]
+    );
+    assert_eq!(
+        merged.conflicts[0].to_string(),
+        "alice.age: base 34, ours 35, theirs 36"
+    );
+    assert!(diff(&merged.patterns, &document(BASE)).is_empty());
+}
+
+#[test]
+fn test_endpoint_conflicts() {
+    let ours = BASE.replace("(alice)-[k1:KNOWS]->(bob)", "(alice)-[k1:KNOWS]->(carol)");
+    let theirs = BASE.replace("(alice)-[k1:KNOWS]->(bob)", "(bob)-[k1:KNOWS]->(alice)");
+
+    let (merged, _) = merge(&ours, &theirs);
+
+    assert_eq!(merged.conflicts.len(), 1);
+    assert_eq!(
+        merged.conflicts[0].to_string(),
+        "k1: endpoints base (alice)->(bob), ours (alice)->(carol), theirs (bob)->(alice)"
+    );
+    assert!(diff(&merged.patterns, &document(BASE)).is_empty());
+}
+
+#[test]
+fn test_removed_and_modified() {
+    let ours = BASE.replace("(bob)-[:KNOWS]->(carol)\n", "");
+    let theirs = BASE.replace(
+        "(carol:Person {name: \"Carol\"})",
+        "(carol:Person {name: \"Caroline\"})",
+    );
+    // Removing a relationship and editing an endpoint do not overlap
+    let (merged, _) = merge(&ours, &theirs);
+    assert!(merged.is_clean());
+
+    let ours = BASE
+        .replace("(carol:Person {name: \"Carol\"})\n", "")
+        .replace("(bob)-[:KNOWS]->(carol)\n", "")
+        .replace("alice, bob, carol", "alice, bob");
+    let (merged, _) = merge(&ours, &theirs);
+    assert_eq!(
+        merged.conflicts,
+        vec![MergeConflict::RemovedAndModified {
+            identity: symbol("carol"),
+            removed_by: Side::Ours,
+        }]
+    );
+    assert_eq!(
+        merged.conflicts[0].to_string(),
+        "carol: removed by ours, modified by theirs"
+    );
+}
+
+#[test]
+fn test_annotated_output_is_gram() {
+    let ours = BASE.replace("age: 34", "age: 35");
+    let theirs = BASE.replace("age: 34", "age: 36");
+    let (merged, _) = merge(&ours, &theirs);
+
+    let annotated = merged.annotated();
+    assert_eq!(annotated.len(), merged.patterns.len() + 1);
+    let text = to_gram(&annotated).unwrap();
+    assert!(text.contains("Conflict"));
+
+    let reparsed = document(&text);
+    let conflict = reparsed.last().unwrap();
+    assert!(conflict.value.labels.contains("Conflict"));
+    assert_eq!(conflict.value.get::<String>("key"), Some("age".to_string()));
+    assert_eq!(conflict.value.get::<i64>("theirs"), Some(36));
+    assert_eq!(conflict.elements[0].value.identity, symbol("alice"));
+}
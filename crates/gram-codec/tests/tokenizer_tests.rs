@@ -0,0 +1,246 @@
+//! Tests for the public tokenizer
+//!
+//! Checks token kinds for each syntax form, and that tokens always cover the
+//! input exactly, whether or not it parses.
+
+use gram_codec::{parse_gram, tokenize, ArrowType, Token, TokenKind};
+use proptest::prelude::*;
+
+fn significant(input: &str) -> Vec<(TokenKind, &str)> {
+    tokenize(input)
+        .filter(|token| !token.kind.is_trivia())
+        .map(|token| (token.kind, token.text))
+        .collect()
+}
+
+fn kinds(input: &str) -> Vec<TokenKind> {
+    significant(input)
+        .into_iter()
+        .map(|(kind, _)| kind)
+        .collect()
+}
+
+/// Checks that the tokens are non-empty, contiguous and reproduce the input.
+fn assert_lossless(input: &str) {
+    let tokens: Vec<Token> = tokenize(input).collect();
+    let mut offset = 0;
+    for token in &tokens {
+        assert!(!token.text.is_empty(), "empty token in {:?}", input);
+        assert_eq!(token.span.start, offset);
+        assert_eq!(&input[token.span.clone()], token.text);
+        offset = token.span.end;
+    }
+    assert_eq!(offset, input.len());
+    assert_eq!(tokens.iter().map(|t| t.text).collect::<String>(), input);
+}
+
+#[test]
+fn test_empty_input() {
+    assert_eq!(tokenize("").count(), 0);
+}
+
+#[test]
+fn test_whole_arrows() {
+    let arrows = [
+        ("-->", ArrowType::Right),
+        ("<--", ArrowType::Left),
+        ("<-->", ArrowType::Bidirectional),
+        ("--", ArrowType::Undirected),
+        ("==>", ArrowType::DoubleRight),
+        ("<==", ArrowType::DoubleLeft),
+        ("<==>", ArrowType::DoubleBidirectional),
+        ("==", ArrowType::DoubleUndirected),
+        ("~~>", ArrowType::SquiggleRight),
+        ("<~~", ArrowType::SquiggleLeft),
+        ("<~~>", ArrowType::SquiggleBidirectional),
+        ("~~", ArrowType::Squiggle),
+    ];
+    for (arrow, arrow_type) in arrows {
+        let input = format!("(a){}(b)", arrow);
+        assert_eq!(
+            significant(&input)[3],
+            (TokenKind::Arrow(arrow_type), arrow),
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn test_arrows_around_edge_subjects() {
+    let arrows = [
+        ("-", "->", ArrowType::Right),
+        ("<-", "-", ArrowType::Left),
+        ("<-", "->", ArrowType::Bidirectional),
+        ("-", "-", ArrowType::Undirected),
+        ("=", "=>", ArrowType::DoubleRight),
+        ("<~", "~", ArrowType::SquiggleLeft),
+        ("--", "-->", ArrowType::Right),
+    ];
+    for (left, right, arrow_type) in arrows {
+        let input = format!("(a){}[r:KNOWS {{since: 2020}}]{}(b)", left, right);
+        let tokens = significant(&input);
+        assert_eq!(
+            tokens[3],
+            (TokenKind::ArrowLeft(arrow_type), left),
+            "{}",
+            input
+        );
+        let right_token = tokens
+            .iter()
+            .find(|(kind, _)| matches!(kind, TokenKind::ArrowRight(_)))
+            .unwrap();
+        assert_eq!(*right_token, (TokenKind::ArrowRight(arrow_type), right));
+    }
+}
+
+#[test]
+fn test_unclosed_edge_subject_keeps_a_guess() {
+    let tokens = significant("(a)<-[r");
+    assert_eq!(tokens[3], (TokenKind::ArrowLeft(ArrowType::Left), "<-"));
+}
+
+#[test]
+fn test_subject_pattern_and_annotation() {
+    use TokenKind::*;
+    assert_eq!(
+        kinds("@note(draft) [team:Team:Group | alice, bob]"),
+        vec![
+            At,
+            Identifier,
+            LeftParen,
+            Identifier,
+            RightParen,
+            LeftBracket,
+            Identifier,
+            Colon,
+            Label,
+            Colon,
+            Label,
+            Pipe,
+            Identifier,
+            Comma,
+            Identifier,
+            RightBracket,
+        ]
+    );
+}
+
+#[test]
+fn test_literal_kinds() {
+    use TokenKind::*;
+    let input = r#"(v {
+        s: "text", q: 'single', b: `tick`,
+        i: -42, h: 0xFF, d: 3.14,
+        m: 168cm, r: 1..10, open: 5..., all: ...,
+        yes: true, no: false, sym: active,
+        t: date`2024-01-01`,
+        list: [1, "two", 3.0],
+        nested: {inner: 1},
+        typed:: 7
+    })"#;
+    let values: Vec<(TokenKind, &str)> = significant(input)
+        .into_iter()
+        .filter(|(kind, _)| {
+            !matches!(
+                kind,
+                Key | Colon | DoubleColon | Comma | LeftBrace | RightBrace | LeftParen | RightParen
+            )
+        })
+        .collect();
+    assert_eq!(
+        values,
+        vec![
+            (Identifier, "v"),
+            (String, "\"text\""),
+            (String, "'single'"),
+            (String, "`tick`"),
+            (Integer, "-42"),
+            (Integer, "0xFF"),
+            (Decimal, "3.14"),
+            (Measurement, "168cm"),
+            (Range, "1..10"),
+            (Range, "5..."),
+            (Range, "..."),
+            (Boolean, "true"),
+            (Boolean, "false"),
+            (Symbol, "active"),
+            (TaggedString, "date`2024-01-01`"),
+            (LeftBracket, "["),
+            (Integer, "1"),
+            (String, "\"two\""),
+            (Decimal, "3.0"),
+            (RightBracket, "]"),
+            (Integer, "1"),
+            (Integer, "7"),
+        ]
+    );
+}
+
+#[test]
+fn test_keys_include_quoted_names() {
+    let tokens = significant(r#"{"first name": "Ada", version: 1} (a)"#);
+    assert_eq!(tokens[1], (TokenKind::Key, "\"first name\""));
+    assert_eq!(tokens[5], (TokenKind::Key, "version"));
+    assert_eq!(tokens[10], (TokenKind::Identifier, "a"));
+}
+
+#[test]
+fn test_comments_and_whitespace() {
+    let tokens: Vec<(TokenKind, &str)> = tokenize("(a) // note\n\t(b)")
+        .map(|token| (token.kind, token.text))
+        .collect();
+    assert_eq!(tokens[3], (TokenKind::Whitespace, " "));
+    assert_eq!(tokens[4], (TokenKind::Comment, "// note"));
+    assert_eq!(tokens[5], (TokenKind::Whitespace, "\n\t"));
+}
+
+#[test]
+fn test_error_tokens() {
+    let tokens = significant("(a) # (b {k: \"open\n}) -> ;");
+    let errors: Vec<&str> = tokens
+        .iter()
+        .filter(|(kind, _)| *kind == TokenKind::Error)
+        .map(|(_, text)| *text)
+        .collect();
+    assert_eq!(errors, vec!["#", "\"open", "-", ">", ";"]);
+}
+
+#[test]
+fn test_valid_documents_have_no_error_tokens() {
+    let documents = [
+        "(alice:Person {name: \"Alice\", age: 30})-[:KNOWS {since: 2020}]->(bob:Person)",
+        "{version: 1}\n(a)<-[r]-(b)==>(c)\n[p:Path | a, r, c]",
+        "@layout(grid) [g | (x), (y)]",
+        "(n {tags: [\"a\", \"b\"], size: 2.5kg, id: 0x1F, window: ..10})",
+        "// comment only\n(a)<~~>(b) // trailing",
+    ];
+    for document in documents {
+        assert!(parse_gram(document).is_ok(), "{}", document);
+        assert!(
+            !kinds(document).contains(&TokenKind::Error),
+            "{:?}",
+            significant(document)
+        );
+        assert_lossless(document);
+    }
+}
+
+#[test]
+fn test_multibyte_text() {
+    let input = "(café:Ü {name: \"日本\"}) ✓";
+    assert_lossless(input);
+    assert_eq!(significant(input).last(), Some(&(TokenKind::Error, "✓")));
+}
+
+proptest! {
+    #[test]
+    fn prop_arbitrary_text_is_lossless(input in any::<String>()) {
+        assert_lossless(&input);
+    }
+
+    #[test]
+    fn prop_gram_like_text_is_lossless(input in "[()\\[\\]{}:,|@<>=~\\-a-z0-9 \n\"'`./]{0,60}") {
+        assert_lossless(&input);
+    }
+}
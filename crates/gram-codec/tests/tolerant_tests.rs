@@ -0,0 +1,245 @@
+//! Tests for fault-tolerant parsing
+//!
+//! Checks that broken input yields error and missing nodes at the right places
+//! while the valid patterns around them are kept, and that valid input parses
+//! exactly as `parse_gram` parses it.
+
+mod common;
+
+use gram_codec::{parse_gram, parse_gram_tolerant, PatternKind, SyntaxNode, SyntaxTree};
+use proptest::prelude::*;
+
+/// Each error and missing node as `(message, text it covers)`.
+fn errors<'a>(tree: &SyntaxTree, input: &'a str) -> Vec<(String, &'a str)> {
+    tree.errors()
+        .into_iter()
+        .map(|node| (node.message().unwrap(), &input[node.span()]))
+        .collect()
+}
+
+fn identities(tree: &SyntaxTree) -> Vec<String> {
+    tree.patterns()
+        .iter()
+        .map(|pattern| pattern.value.identity.0.clone())
+        .collect()
+}
+
+#[test]
+fn test_empty_input() {
+    let tree = parse_gram_tolerant("  // nothing\n");
+    assert!(tree.nodes.is_empty());
+    assert!(!tree.has_errors());
+}
+
+#[test]
+fn test_unclosed_paren_before_valid_patterns() {
+    let input = "(alice:Person {name: \"Alice\"}\n(bob)\n(alice)-->(bob)";
+    let tree = parse_gram_tolerant(input);
+    assert_eq!(errors(&tree, input), vec![("missing `)`".to_string(), "")]);
+    assert_eq!(tree.errors()[0].span(), 29..29);
+    assert_eq!(identities(&tree), vec!["alice", "bob", ""]);
+
+    let alice = &tree.patterns()[0];
+    assert!(alice.value.labels.contains("Person"));
+    assert_eq!(alice.value.properties.len(), 1);
+}
+
+#[test]
+fn test_missing_bracket() {
+    let input = "[team:Team | alice, (bob)\n(carol)";
+    let tree = parse_gram_tolerant(input);
+    assert_eq!(errors(&tree, input), vec![("missing `]`".to_string(), "")]);
+    let patterns = tree.patterns();
+    assert_eq!(patterns.len(), 2);
+    assert_eq!(patterns[0].elements.len(), 2);
+    assert_eq!(patterns[1].value.identity.0, "carol");
+}
+
+#[test]
+fn test_bad_property_values_keep_other_properties() {
+    let input = "(a {x: 1, y: ;;, z: 2, w: true1, v: })";
+    let tree = parse_gram_tolerant(input);
+    assert_eq!(
+        errors(&tree, input),
+        vec![
+            ("invalid property value".to_string(), ";;"),
+            ("invalid property value".to_string(), "1"),
+            ("missing property value".to_string(), ""),
+        ]
+    );
+    let properties = &tree.patterns()[0].value.properties;
+    let mut keys: Vec<&String> = properties.keys().collect();
+    keys.sort();
+    assert_eq!(keys, vec!["w", "x", "z"]);
+}
+
+#[test]
+fn test_record_punctuation() {
+    let input = "(a {x: 1 y: 2, , z 3,})";
+    let tree = parse_gram_tolerant(input);
+    assert_eq!(
+        errors(&tree, input),
+        vec![
+            ("missing `,`".to_string(), ""),
+            ("missing property".to_string(), ""),
+            ("unexpected `z`".to_string(), "z 3"),
+            ("trailing comma".to_string(), ","),
+        ]
+    );
+    assert_eq!(tree.patterns()[0].value.properties.len(), 2);
+}
+
+#[test]
+fn test_unclosed_record() {
+    let input = "(a {k: 1\n(b)";
+    let tree = parse_gram_tolerant(input);
+    assert_eq!(
+        errors(&tree, input),
+        vec![
+            ("missing `}`".to_string(), ""),
+            ("missing `)`".to_string(), "")
+        ]
+    );
+    assert_eq!(identities(&tree), vec!["a", "b"]);
+}
+
+#[test]
+fn test_stray_tokens_between_patterns() {
+    let input = "(a) ;; ) (b) x:Y (c)";
+    let tree = parse_gram_tolerant(input);
+    assert_eq!(
+        errors(&tree, input),
+        vec![
+            ("unexpected `;`".to_string(), ";;"),
+            ("unexpected `)`".to_string(), ")"),
+            ("unexpected `x`".to_string(), "x:Y"),
+        ]
+    );
+    assert_eq!(identities(&tree), vec!["a", "b", "c"]);
+}
+
+#[test]
+fn test_junk_inside_node_is_skipped() {
+    let input = "(a:Person junk {k: 1}) (b)";
+    let tree = parse_gram_tolerant(input);
+    assert_eq!(
+        errors(&tree, input),
+        vec![("unexpected `junk`".to_string(), "junk {k: 1}")]
+    );
+    assert_eq!(identities(&tree), vec!["a", "b"]);
+}
+
+#[test]
+fn test_missing_label_and_node() {
+    let input = "(a:)-[r:KNOWS]->";
+    let tree = parse_gram_tolerant(input);
+    assert_eq!(
+        errors(&tree, input),
+        vec![
+            ("missing label".to_string(), ""),
+            ("missing node".to_string(), "")
+        ]
+    );
+    let SyntaxNode::Pattern(relationship) = &tree.nodes[0] else {
+        panic!("expected a pattern");
+    };
+    assert_eq!(relationship.kind, PatternKind::Relationship);
+    assert_eq!(relationship.span, 0..16);
+    assert_eq!(relationship.elements().count(), 1);
+}
+
+#[test]
+fn test_relationships_keep_direction() {
+    let input = "(a)<-[r]-(b)-->(c";
+    let tree = parse_gram_tolerant(input);
+    assert_eq!(errors(&tree, input), vec![("missing `)`".to_string(), "")]);
+    let patterns = tree.patterns();
+    let outer = &patterns[0];
+    assert_eq!(outer.elements[1].value.identity.0, "c");
+    let inner = &outer.elements[0];
+    assert_eq!(inner.value.identity.0, "r");
+    assert_eq!(inner.elements[0].value.identity.0, "b");
+    assert_eq!(inner.elements[1].value.identity.0, "a");
+}
+
+#[test]
+fn test_record_after_first_pattern() {
+    let input = "(a) {k: 1} (b)";
+    let tree = parse_gram_tolerant(input);
+    assert_eq!(
+        errors(&tree, input),
+        vec![(
+            "a record is only allowed at the start of a document".to_string(),
+            "{k: 1}"
+        )]
+    );
+    assert_eq!(identities(&tree), vec!["a", "b"]);
+}
+
+#[test]
+fn test_annotation_spans_and_errors() {
+    let input = "@note(x) (a) @ (b) @dangling";
+    let tree = parse_gram_tolerant(input);
+    assert_eq!(tree.nodes[0].span(), 0..12);
+    assert_eq!(tree.nodes[1].span(), 13..18);
+    assert_eq!(
+        errors(&tree, input),
+        vec![
+            ("missing annotation name".to_string(), ""),
+            (
+                "expected a pattern after the annotation".to_string(),
+                "@dangling"
+            ),
+        ]
+    );
+    assert_eq!(identities(&tree), vec!["a", "b"]);
+}
+
+#[test]
+fn test_valid_documents_match_parse_gram() {
+    let documents = [
+        "{version: 1}\n(alice:Person {name: \"Alice\", age: 30})-[:KNOWS {since: 2020}]->(bob:Person)",
+        "(a)<-[r]-(b)==>(c)<~~>(d)\n[p:Path | a, r, (c)]",
+        "@layout(grid) @z [g | (x), [h | y]]",
+        "(n {tags: [\"a\", \"b\"], size: 2.5kg, id: 0x1F, window: ..10, typed:: 7})",
+        "[empty |] [] (\"quoted id\":`Label`)",
+    ];
+    for document in documents {
+        let tree = parse_gram_tolerant(document);
+        assert!(!tree.has_errors(), "{}: {:?}", document, tree.errors());
+        assert_eq!(
+            tree.patterns(),
+            parse_gram(document).unwrap(),
+            "{}",
+            document
+        );
+    }
+}
+
+proptest! {
+    #[test]
+    fn prop_agrees_with_parse_gram(input in common::documents()) {
+        let tree = parse_gram_tolerant(&input);
+        match parse_gram(&input) {
+            Ok(patterns) => {
+                prop_assert!(!tree.has_errors(), "{:?}: {:?}", input, tree.errors());
+                prop_assert_eq!(tree.patterns(), patterns);
+            }
+            Err(error) => prop_assert!(tree.has_errors(), "{:?}: {:?}", input, error),
+        }
+    }
+
+    #[test]
+    fn prop_nodes_are_ordered_and_in_bounds(input in "[()\\[\\]{}:,|@<>=~\\-a-z0-9 \n\"'`./]{0,60}") {
+        let tree = parse_gram_tolerant(&input);
+        let mut end = 0;
+        for node in &tree.nodes {
+            let span = node.span();
+            prop_assert!(end <= span.start && span.start <= span.end && span.end <= input.len());
+            end = span.end;
+        }
+        for error in tree.errors() {
+            prop_assert!(error.span().end <= input.len());
+        }
+    }
+}
@@ -0,0 +1,87 @@
+//! Open documents and what is known about them
+
+use crate::line_index::LineIndex;
+use crate::scan::Scan;
+use gram_codec::{Edit, ParseError, ParseSession};
+use lsp_types::Range;
+use pattern_core::{Combinable, Pattern, Subject};
+use std::collections::HashMap;
+
+/// An open text document.
+#[derive(Debug)]
+pub(crate) struct Document {
+    /// The text, reparsed incrementally as it changes
+    pub session: ParseSession,
+    pub index: LineIndex,
+    pub scan: Scan,
+    /// Why the current text does not parse
+    pub error: Option<ParseError>,
+    /// Subjects by identity, merged over all their occurrences, from the most
+    /// recent text that parsed
+    pub subjects: HashMap<String, Subject>,
+}
+
+impl Document {
+    pub(crate) fn new(text: String) -> Self {
+        let mut document = Document {
+            session: ParseSession::new(text),
+            index: LineIndex::new(""),
+            scan: Scan::default(),
+            error: None,
+            subjects: HashMap::new(),
+        };
+        document.refresh();
+        document
+    }
+
+    pub(crate) fn text(&self) -> &str {
+        self.session.text()
+    }
+
+    /// Replaces `range` of the text, or all of it, with `text`. If the result
+    /// does not parse, the subjects of the previous text are kept, so hover
+    /// keeps working while the user types.
+    pub(crate) fn change(&mut self, range: Option<Range>, text: String) {
+        let current = self.session.text();
+        let range = match range {
+            Some(range) => {
+                let start = self.index.offset(current, range.start);
+                let end = self.index.offset(current, range.end);
+                start.min(end)..start.max(end)
+            }
+            None => 0..current.len(),
+        };
+        let _ = self.session.edit(Edit::new(range, text));
+        self.refresh();
+    }
+
+    fn refresh(&mut self) {
+        let text = self.session.text();
+        self.index = LineIndex::new(text);
+        self.scan = Scan::new(text);
+        match self.session.patterns() {
+            Ok(patterns) => {
+                self.error = None;
+                self.subjects.clear();
+                for pattern in patterns {
+                    collect_subjects(pattern, &mut self.subjects);
+                }
+            }
+            Err(e) => self.error = Some(e.clone()),
+        }
+    }
+}
+
+fn collect_subjects(pattern: &Pattern<Subject>, subjects: &mut HashMap<String, Subject>) {
+    let subject = &pattern.value;
+    if !subject.identity.0.is_empty() {
+        let merged = match subjects.remove(&subject.identity.0) {
+            Some(existing) => existing.combine(subject.clone()),
+            None => subject.clone(),
+        };
+        subjects.insert(subject.identity.0.clone(), merged);
+    }
+    for element in &pattern.elements {
+        collect_subjects(element, subjects);
+    }
+}
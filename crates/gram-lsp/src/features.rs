@@ -0,0 +1,300 @@
+//! Language features computed from a [`Document`]
+
+use crate::document::Document;
+use crate::scan::{Name, ScannedSubject, SubjectKind};
+use gram_codec::{parse_gram_with_header, to_gram, to_gram_pattern, to_gram_with_header};
+use lsp_types::{
+    CompletionItem, CompletionItemKind, Diagnostic, DiagnosticSeverity, DocumentSymbol, Hover,
+    HoverContents, Location, MarkupContent, MarkupKind, Position, SymbolKind, TextEdit, Url,
+};
+use pattern_core::{Pattern, Subject, Symbol};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// The parse error of the document, if any, spanning the offending token.
+pub(crate) fn diagnostics(document: &Document) -> Vec<Diagnostic> {
+    let Some(error) = &document.error else {
+        return Vec::new();
+    };
+
+    let text = document.text();
+    let start = error.location().map_or(0, |location| location.offset);
+    let token = text[start..]
+        .find(|c: char| c.is_whitespace() || "()[]{},|".contains(c))
+        .map_or(text.len() - start, |len| len.max(1));
+    let end = text[start..]
+        .find('\n')
+        .map_or(text.len(), |line_end| start + line_end)
+        .min(start + token);
+
+    vec![Diagnostic {
+        range: document.index.range(text, start..end),
+        severity: Some(DiagnosticSeverity::ERROR),
+        source: Some("gram".to_string()),
+        message: error.message(),
+        ..Diagnostic::default()
+    }]
+}
+
+/// Rewrites the document in canonical form, as `gram fmt` does.
+///
+/// Returns no edits if the document does not parse, and an error if it has
+/// comments, which formatting would remove.
+pub(crate) fn formatting(document: &Document) -> Result<Option<Vec<TextEdit>>, String> {
+    if document.scan.has_comments {
+        return Err("formatting would remove comments".to_string());
+    }
+    let Ok((header, patterns)) = parse_gram_with_header(document.text()) else {
+        return Ok(None);
+    };
+
+    let formatted = match header {
+        Some(header) => to_gram_with_header(header, &patterns),
+        None => to_gram(&patterns),
+    };
+    let mut formatted = formatted.map_err(|e| e.to_string())?;
+    if !formatted.is_empty() {
+        formatted.push('\n');
+    }
+
+    if formatted == document.text() {
+        return Ok(Some(Vec::new()));
+    }
+    let range = document
+        .index
+        .range(document.text(), 0..document.text().len());
+    Ok(Some(vec![TextEdit::new(range, formatted)]))
+}
+
+/// Where the identity at `position` is defined.
+pub(crate) fn definition(document: &Document, uri: &Url, position: Position) -> Option<Location> {
+    let offset = document.index.offset(document.text(), position);
+    let occurrence = document.scan.occurrence_at(offset)?;
+    let definition = document.scan.definition(&occurrence.name.text)?;
+    Some(location(document, uri, definition.name))
+}
+
+/// Every occurrence of the identity at `position`.
+pub(crate) fn references(
+    document: &Document,
+    uri: &Url,
+    position: Position,
+    include_declaration: bool,
+) -> Vec<Location> {
+    let offset = document.index.offset(document.text(), position);
+    let Some(occurrence) = document.scan.occurrence_at(offset) else {
+        return Vec::new();
+    };
+    let identity = &occurrence.name.text;
+    let definition = document
+        .scan
+        .definition(identity)
+        .map(|definition| definition.name.span.clone());
+
+    document
+        .scan
+        .occurrences()
+        .into_iter()
+        .filter(|occurrence| &occurrence.name.text == identity)
+        .filter(|occurrence| {
+            include_declaration || Some(&occurrence.name.span) != definition.as_ref()
+        })
+        .map(|occurrence| location(document, uri, occurrence.name))
+        .collect()
+}
+
+/// The labels and properties of the identity at `position`, as gram notation.
+///
+/// Uses the most recent text that parsed, falling back to the labels written in
+/// the current text.
+pub(crate) fn hover(document: &Document, position: Position) -> Option<Hover> {
+    let offset = document.index.offset(document.text(), position);
+    let occurrence = document.scan.occurrence_at(offset)?;
+    let identity = &occurrence.name.text;
+
+    let subject = document
+        .subjects
+        .get(identity)
+        .cloned()
+        .unwrap_or_else(|| Subject {
+            identity: Symbol(identity.clone()),
+            labels: document
+                .scan
+                .subjects
+                .iter()
+                .filter(|subject| {
+                    subject
+                        .identity
+                        .as_ref()
+                        .is_some_and(|name| &name.text == identity)
+                })
+                .flat_map(|subject| subject.labels.iter().map(|label| label.text.clone()))
+                .collect(),
+            properties: HashMap::new(),
+        });
+    let kind = document
+        .scan
+        .definition(identity)
+        .and_then(|definition| definition.subject)
+        .map_or(SubjectKind::Node, |subject| subject.kind);
+
+    let node = to_gram_pattern(&Pattern::point(subject)).ok()?;
+    let inner = &node[1..node.len() - 1];
+    let gram = match kind {
+        SubjectKind::Node => node.clone(),
+        SubjectKind::Relationship => format!("-[{}]->", inner),
+        SubjectKind::Pattern => format!("[{}]", inner),
+    };
+
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: format!("```gram\n{}\n```", gram),
+        }),
+        range: Some(
+            document
+                .index
+                .range(document.text(), occurrence.name.span.clone()),
+        ),
+    })
+}
+
+/// One symbol per defined identity, nested under the subject patterns that contain it.
+pub(crate) fn document_symbols(document: &Document) -> Vec<DocumentSymbol> {
+    let scan = &document.scan;
+    let definitions: HashSet<_> = scan
+        .definitions()
+        .values()
+        .filter(|definition| definition.subject.is_some())
+        .map(|definition| definition.name.span.start)
+        .collect();
+    let is_symbol = |subject: &ScannedSubject| {
+        subject
+            .identity
+            .as_ref()
+            .is_some_and(|name| definitions.contains(&name.span.start))
+    };
+
+    let mut children: HashMap<Option<usize>, Vec<usize>> = HashMap::new();
+    for (i, subject) in scan.subjects.iter().enumerate() {
+        if !is_symbol(subject) {
+            continue;
+        }
+        let mut parent = subject.parent;
+        while let Some(p) = parent.filter(|&p| !is_symbol(&scan.subjects[p])) {
+            parent = scan.subjects[p].parent;
+        }
+        children.entry(parent).or_default().push(i);
+    }
+
+    build_symbols(document, &children, None)
+}
+
+fn build_symbols(
+    document: &Document,
+    children: &HashMap<Option<usize>, Vec<usize>>,
+    parent: Option<usize>,
+) -> Vec<DocumentSymbol> {
+    let Some(indices) = children.get(&parent) else {
+        return Vec::new();
+    };
+
+    indices
+        .iter()
+        .map(|&i| {
+            let subject = &document.scan.subjects[i];
+            let name = subject.identity.as_ref().expect("symbols have identities");
+            let detail = subject
+                .labels
+                .iter()
+                .map(|label| format!(":{}", label.text))
+                .collect::<String>();
+            let nested = build_symbols(document, children, Some(i));
+
+            #[allow(deprecated)]
+            DocumentSymbol {
+                name: name.text.clone(),
+                detail: (!detail.is_empty()).then_some(detail),
+                kind: match subject.kind {
+                    SubjectKind::Node => SymbolKind::OBJECT,
+                    SubjectKind::Relationship => SymbolKind::EVENT,
+                    SubjectKind::Pattern => SymbolKind::NAMESPACE,
+                },
+                tags: None,
+                deprecated: None,
+                range: document.index.range(document.text(), subject.span.clone()),
+                selection_range: document.index.range(document.text(), name.span.clone()),
+                children: (!nested.is_empty()).then_some(nested),
+            }
+        })
+        .collect()
+}
+
+/// Known labels after a `:` in a subject, and known property keys at the key
+/// position of a record.
+pub(crate) fn completion(document: &Document, position: Position) -> Vec<CompletionItem> {
+    let text = document.text();
+    let offset = document.index.offset(text, position);
+    let start = text[..offset]
+        .char_indices()
+        .rev()
+        .take_while(|&(_, c)| c.is_alphanumeric() || matches!(c, '_' | '-' | '@' | '.'))
+        .last()
+        .map_or(offset, |(i, _)| i);
+    let before = text[..start].trim_end();
+
+    let keys = if document.scan.record_at(start).is_some() {
+        if !(before.ends_with('{') || before.ends_with(',')) {
+            return Vec::new();
+        }
+        true
+    } else if text[..start].ends_with(':') {
+        false
+    } else {
+        return Vec::new();
+    };
+
+    let names: Vec<&Name> = if keys {
+        document.scan.keys.iter().collect()
+    } else {
+        document
+            .scan
+            .subjects
+            .iter()
+            .flat_map(|s| &s.labels)
+            .collect()
+    };
+
+    let mut candidates: BTreeSet<&str> = names
+        .into_iter()
+        .filter(|name| name.span.start != start)
+        .map(|name| name.text.as_str())
+        .collect();
+    for subject in document.subjects.values() {
+        if keys {
+            candidates.extend(subject.properties.keys().map(String::as_str));
+        } else {
+            candidates.extend(subject.labels.iter().map(String::as_str));
+        }
+    }
+    let kind = if keys {
+        CompletionItemKind::PROPERTY
+    } else {
+        CompletionItemKind::CLASS
+    };
+
+    candidates
+        .into_iter()
+        .map(|candidate| CompletionItem {
+            label: candidate.to_string(),
+            kind: Some(kind),
+            ..CompletionItem::default()
+        })
+        .collect()
+}
+
+fn location(document: &Document, uri: &Url, name: &Name) -> Location {
+    Location::new(
+        uri.clone(),
+        document.index.range(document.text(), name.span.clone()),
+    )
+}
@@ -0,0 +1,57 @@
+//! # Gram Language Server
+//!
+//! A [Language Server Protocol](https://microsoft.github.io/language-server-protocol/)
+//! server for Gram notation, built on `gram-codec`. It provides:
+//!
+//! - **Diagnostics**: the parse error of each open document, with its range
+//! - **Formatting**: the canonical form written by `gram fmt`
+//! - **Go to definition / find references**: for identities, from bare references
+//!   such as `a` in `[g | a]` to the subject that defines them
+//! - **Hover**: a subject's labels and properties, merged over all its occurrences
+//! - **Document symbols**: defined identities, nested by subject pattern
+//! - **Completion**: known labels after `:`, and known property keys in records
+//!
+//! The `gram-lsp` binary serves over stdio. [`run`] serves any
+//! [`Connection`](lsp_server::Connection), so tests can drive the server from a
+//! client in the same process with [`Connection::memory`](lsp_server::Connection::memory):
+//!
+//! ```rust
+//! use lsp_server::{Connection, Message, Notification, Request, RequestId};
+//!
+//! let (server, client) = Connection::memory();
+//! let thread = std::thread::spawn(move || gram_lsp::run(&server).unwrap());
+//!
+//! let initialize = serde_json::json!({ "capabilities": {} });
+//! client
+//!     .sender
+//!     .send(Request::new(RequestId::from(1), "initialize".to_string(), initialize).into())
+//!     .unwrap();
+//! assert!(matches!(client.receiver.recv().unwrap(), Message::Response(_)));
+//! client
+//!     .sender
+//!     .send(Notification::new("initialized".to_string(), serde_json::json!({})).into())
+//!     .unwrap();
+//!
+//! client
+//!     .sender
+//!     .send(Request::new(RequestId::from(2), "shutdown".to_string(), ()).into())
+//!     .unwrap();
+//! client.receiver.recv().unwrap();
+//! client
+//!     .sender
+//!     .send(Notification::new("exit".to_string(), ()).into())
+//!     .unwrap();
+//! thread.join().unwrap();
+//! ```
+//!
+//! Documents are synced incrementally and reparsed with
+//! [`gram_codec::ParseSession`]. Positions use UTF-16 code units, as the
+//! protocol requires by default.
+
+mod document;
+mod features;
+mod line_index;
+mod scan;
+mod server;
+
+pub use server::{capabilities, run};
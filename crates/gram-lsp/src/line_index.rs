@@ -0,0 +1,96 @@
+//! Conversion between byte offsets and LSP positions
+
+use lsp_types::{Position, Range};
+
+/// Line start offsets of a text, for converting byte offsets to LSP positions.
+///
+/// LSP positions count characters in UTF-16 code units, so conversions walk the
+/// characters of a single line.
+#[derive(Debug, Clone)]
+pub(crate) struct LineIndex {
+    starts: Vec<usize>,
+}
+
+impl LineIndex {
+    pub(crate) fn new(text: &str) -> Self {
+        let starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        LineIndex { starts }
+    }
+
+    /// Position of byte `offset`, clamped to the text.
+    pub(crate) fn position(&self, text: &str, offset: usize) -> Position {
+        let offset = floor_char_boundary(text, offset.min(text.len()));
+        let line = self.starts.partition_point(|&start| start <= offset) - 1;
+        let character = text[self.starts[line]..offset]
+            .chars()
+            .map(char::len_utf16)
+            .sum::<usize>();
+        Position::new(line as u32, character as u32)
+    }
+
+    pub(crate) fn range(&self, text: &str, span: std::ops::Range<usize>) -> Range {
+        Range::new(
+            self.position(text, span.start),
+            self.position(text, span.end),
+        )
+    }
+
+    /// Byte offset of `position`, clamped to the end of its line (or the text).
+    pub(crate) fn offset(&self, text: &str, position: Position) -> usize {
+        let Some(&start) = self.starts.get(position.line as usize) else {
+            return text.len();
+        };
+        let end = self
+            .starts
+            .get(position.line as usize + 1)
+            .map_or(text.len(), |&next| next - 1);
+
+        let mut units = 0;
+        for (i, c) in text[start..end].char_indices() {
+            if units >= position.character as usize {
+                return start + i;
+            }
+            units += c.len_utf16();
+        }
+        end
+    }
+}
+
+fn floor_char_boundary(text: &str, mut offset: usize) -> usize {
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_with_multibyte_characters() {
+        let text = "(a {n: \"é😀\"})\n(b)";
+        let index = LineIndex::new(text);
+
+        let b = text.find("(b)").unwrap();
+        assert_eq!(index.position(text, b), Position::new(1, 0));
+        assert_eq!(index.offset(text, Position::new(1, 0)), b);
+
+        let close = text.find("\"}").unwrap();
+        let position = index.position(text, close);
+        assert_eq!(position, Position::new(0, 11));
+        assert_eq!(index.offset(text, position), close);
+    }
+
+    #[test]
+    fn test_offsets_are_clamped() {
+        let text = "(a)\n(b)";
+        let index = LineIndex::new(text);
+
+        assert_eq!(index.offset(text, Position::new(0, 99)), 3);
+        assert_eq!(index.offset(text, Position::new(9, 0)), text.len());
+        assert_eq!(index.position(text, 99), Position::new(1, 3));
+    }
+}
@@ -0,0 +1,538 @@
+//! Structural scan of gram text
+//!
+//! The parser reports no source positions and stops at the first error, so the
+//! editor features read identities, labels and record keys, with their byte spans,
+//! from this scan instead. It follows the bracket structure of gram notation
+//! without validating it, and so also works on documents that do not parse.
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// The bracket form a subject was written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SubjectKind {
+    /// `(subject)`
+    Node,
+    /// `-[subject]->` and the other arrow forms
+    Relationship,
+    /// `[subject | elements]`
+    Pattern,
+}
+
+/// An identifier, label or key and where it was written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Name {
+    pub text: String,
+    pub span: Range<usize>,
+}
+
+/// A subject as written in the text.
+#[derive(Debug, Clone)]
+pub(crate) struct ScannedSubject {
+    pub kind: SubjectKind,
+    pub identity: Option<Name>,
+    pub labels: Vec<Name>,
+    pub has_record: bool,
+    /// From the opening bracket through the closing one (or the end of the text)
+    pub span: Range<usize>,
+    /// The innermost subject pattern this subject is an element of
+    pub parent: Option<usize>,
+}
+
+impl ScannedSubject {
+    /// Whether the subject says anything about its identity beyond naming it.
+    pub(crate) fn is_defining(&self) -> bool {
+        !self.labels.is_empty() || self.has_record
+    }
+}
+
+/// One written occurrence of an identity.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Occurrence<'s> {
+    pub name: &'s Name,
+    /// The subject the identity names, `None` for a bare reference
+    pub subject: Option<&'s ScannedSubject>,
+}
+
+/// Everything the editor features need from one scan of a document.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Scan {
+    pub subjects: Vec<ScannedSubject>,
+    /// Bare identifiers among the elements of a subject pattern
+    pub references: Vec<Name>,
+    /// Property keys of subject records
+    pub keys: Vec<Name>,
+    /// Interiors of subject records, up to the closing brace (or where scanning stopped)
+    pub records: Vec<Range<usize>>,
+    pub has_comments: bool,
+}
+
+impl Scan {
+    pub(crate) fn new(text: &str) -> Self {
+        let mut scanner = Scanner {
+            text,
+            pos: 0,
+            scan: Scan::default(),
+            open: Vec::new(),
+        };
+        scanner.run();
+        scanner.scan
+    }
+
+    /// All occurrences of identities, in text order.
+    pub(crate) fn occurrences(&self) -> Vec<Occurrence<'_>> {
+        let mut occurrences: Vec<_> = self
+            .subjects
+            .iter()
+            .filter_map(|subject| {
+                subject.identity.as_ref().map(|name| Occurrence {
+                    name,
+                    subject: Some(subject),
+                })
+            })
+            .chain(self.references.iter().map(|name| Occurrence {
+                name,
+                subject: None,
+            }))
+            .collect();
+        occurrences.sort_by_key(|occurrence| occurrence.name.span.start);
+        occurrences
+    }
+
+    /// The occurrence of an identity at `offset`, including just past its end.
+    pub(crate) fn occurrence_at(&self, offset: usize) -> Option<Occurrence<'_>> {
+        self.occurrences().into_iter().find(|occurrence| {
+            occurrence.name.span.contains(&offset) || occurrence.name.span.end == offset
+        })
+    }
+
+    /// Where `identity` is defined; see [`Scan::definitions`].
+    pub(crate) fn definition(&self, identity: &str) -> Option<Occurrence<'_>> {
+        self.definitions().remove(identity)
+    }
+
+    /// Where each identity is defined.
+    ///
+    /// That is the first subject that gives it labels or properties, or else the
+    /// first subject that names it, or else its first bare reference.
+    pub(crate) fn definitions(&self) -> HashMap<&str, Occurrence<'_>> {
+        let rank = |occurrence: &Occurrence<'_>| match occurrence.subject {
+            Some(subject) if subject.is_defining() => 0,
+            Some(_) => 1,
+            None => 2,
+        };
+
+        let mut definitions: HashMap<&str, Occurrence<'_>> = HashMap::new();
+        for occurrence in self.occurrences() {
+            let better = definitions
+                .get(occurrence.name.text.as_str())
+                .map_or(true, |current| rank(&occurrence) < rank(current));
+            if better {
+                definitions.insert(&occurrence.name.text, occurrence);
+            }
+        }
+        definitions
+    }
+
+    /// The record interior containing `offset`, if any.
+    pub(crate) fn record_at(&self, offset: usize) -> Option<Range<usize>> {
+        self.records
+            .iter()
+            .find(|interior| interior.start <= offset && offset <= interior.end)
+            .cloned()
+    }
+}
+
+/// A subject whose closing bracket has not been reached yet.
+struct Open {
+    subject: usize,
+    /// After the `|` of a subject pattern
+    elements: bool,
+}
+
+struct Scanner<'a> {
+    text: &'a str,
+    pos: usize,
+    scan: Scan,
+    open: Vec<Open>,
+}
+
+impl<'a> Scanner<'a> {
+    fn run(&mut self) {
+        while let Some(c) = self.peek() {
+            match c {
+                '/' if self.rest().starts_with("//") => self.comment(),
+                '"' | '\'' | '`' => {
+                    self.string();
+                }
+                '(' => self.subject(SubjectKind::Node),
+                '[' if self.after_arrow() => self.subject(SubjectKind::Relationship),
+                '[' => self.subject(SubjectKind::Pattern),
+                ')' => self.close(&[SubjectKind::Node]),
+                ']' => self.close(&[SubjectKind::Relationship, SubjectKind::Pattern]),
+                '{' => {
+                    self.pos += 1;
+                    self.map(false);
+                }
+                '|' => {
+                    if let Some(open) = self.open.last_mut() {
+                        if self.scan.subjects[open.subject].kind == SubjectKind::Pattern {
+                            open.elements = true;
+                        }
+                    }
+                    self.pos += 1;
+                }
+                '@' => self.annotation(),
+                c if is_identifier_start(c) => {
+                    let name = self.identifier();
+                    if self.open.last().is_some_and(|open| open.elements) {
+                        self.scan.references.push(name);
+                    }
+                }
+                c => self.pos += c.len_utf8(),
+            }
+        }
+
+        let end = self.text.len();
+        for open in self.open.drain(..) {
+            self.scan.subjects[open.subject].span.end = end;
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn after_arrow(&self) -> bool {
+        matches!(
+            self.text[..self.pos].chars().next_back(),
+            Some('-' | '=' | '~' | '<')
+        )
+    }
+
+    /// Skips whitespace and comments.
+    fn ws(&mut self) {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+            if trimmed.starts_with("//") {
+                self.comment();
+            } else {
+                return;
+            }
+        }
+    }
+
+    fn comment(&mut self) {
+        self.scan.has_comments = true;
+        self.pos = self
+            .rest()
+            .find('\n')
+            .map_or(self.text.len(), |i| self.pos + i);
+    }
+
+    /// Skips a quoted or fenced string and returns its contents.
+    fn string(&mut self) -> Name {
+        let start = self.pos;
+        let rest = self.rest();
+        let fence = ["```", "\"\"\""]
+            .into_iter()
+            .find(|fence| rest.starts_with(fence));
+
+        let (open, close) = match fence {
+            Some(fence) => (
+                fence.len(),
+                rest[fence.len()..]
+                    .find(fence)
+                    .map(|i| (fence.len() + i, fence.len())),
+            ),
+            None => {
+                let quote = rest.chars().next().unwrap_or('"');
+                let mut escaped = false;
+                let end = rest[1..].char_indices().find_map(|(i, c)| {
+                    if escaped {
+                        escaped = false;
+                    } else if c == '\\' {
+                        escaped = true;
+                    } else if c == quote {
+                        return Some((1 + i, 1));
+                    }
+                    None
+                });
+                (1, end)
+            }
+        };
+
+        let text = match close {
+            Some((end, close_len)) => {
+                self.pos = start + end + close_len;
+                &rest[open..end]
+            }
+            None => {
+                self.pos = self.text.len();
+                &rest[open.min(rest.len())..]
+            }
+        };
+        Name {
+            text: text.to_string(),
+            span: start..self.pos,
+        }
+    }
+
+    fn identifier(&mut self) -> Name {
+        let start = self.pos;
+        let mut chars = self.rest().char_indices();
+        chars.next();
+        let len = chars
+            .find(|&(_, c)| !is_identifier_continue(c))
+            .map_or(self.rest().len(), |(i, _)| i);
+        self.pos += len;
+        Name {
+            text: self.text[start..self.pos].to_string(),
+            span: start..self.pos,
+        }
+    }
+
+    /// An identifier, quoted identifier, or nothing.
+    fn name(&mut self) -> Option<Name> {
+        match self.peek()? {
+            '"' | '\'' | '`' => Some(self.string()),
+            c if is_identifier_start(c) => Some(self.identifier()),
+            _ => None,
+        }
+    }
+
+    /// Reads the subject after an opening bracket and leaves it open.
+    fn subject(&mut self, kind: SubjectKind) {
+        let start = self.pos;
+        let parent = self
+            .open
+            .iter()
+            .rev()
+            .map(|open| open.subject)
+            .find(|&subject| self.scan.subjects[subject].kind == SubjectKind::Pattern);
+
+        self.pos += 1;
+        self.ws();
+        let identity = self.name();
+        let mut labels = Vec::new();
+        while self.peek() == Some(':') {
+            while self.peek() == Some(':') {
+                self.pos += 1;
+            }
+            match self.name() {
+                Some(label) => labels.push(label),
+                None => break,
+            }
+        }
+
+        self.ws();
+        let has_record = self.peek() == Some('{');
+        if has_record {
+            self.pos += 1;
+            self.map(true);
+        }
+
+        self.scan.subjects.push(ScannedSubject {
+            kind,
+            identity,
+            labels,
+            has_record,
+            span: start..self.text.len(),
+            parent,
+        });
+        self.open.push(Open {
+            subject: self.scan.subjects.len() - 1,
+            elements: false,
+        });
+    }
+
+    /// Closes the innermost open subject of one of `kinds`, and any left open inside it.
+    fn close(&mut self, kinds: &[SubjectKind]) {
+        self.pos += 1;
+        let Some(depth) = self
+            .open
+            .iter()
+            .rposition(|open| kinds.contains(&self.scan.subjects[open.subject].kind))
+        else {
+            return;
+        };
+
+        for open in self.open.drain(depth..) {
+            self.scan.subjects[open.subject].span.end = self.pos;
+        }
+    }
+
+    /// Reads a map after its opening brace, recording keys and the interior of
+    /// subject records. Stops after the closing brace, or before a bracket that
+    /// closes an enclosing subject.
+    fn map(&mut self, record: bool) {
+        let start = self.pos;
+        let mut expect_key = true;
+        let end = loop {
+            self.ws();
+            let Some(c) = self.peek() else {
+                break self.pos;
+            };
+            match c {
+                '}' => {
+                    self.pos += 1;
+                    break self.pos - 1;
+                }
+                ')' | ']' => break self.pos,
+                ',' => {
+                    expect_key = true;
+                    self.pos += 1;
+                }
+                ':' => {
+                    expect_key = false;
+                    self.pos += 1;
+                }
+                '{' => {
+                    self.pos += 1;
+                    self.map(false);
+                    expect_key = false;
+                }
+                '[' => {
+                    self.pos += 1;
+                    self.array();
+                    expect_key = false;
+                }
+                _ => match self.name() {
+                    Some(name) => {
+                        if expect_key && record {
+                            self.scan.keys.push(name);
+                        }
+                        expect_key = false;
+                    }
+                    None => self.pos += c.len_utf8(),
+                },
+            }
+        };
+
+        if record {
+            self.scan.records.push(start..end);
+        }
+    }
+
+    fn array(&mut self) {
+        loop {
+            self.ws();
+            let Some(c) = self.peek() else {
+                return;
+            };
+            match c {
+                ']' => {
+                    self.pos += 1;
+                    return;
+                }
+                ')' | '}' => return,
+                '[' => {
+                    self.pos += 1;
+                    self.array();
+                }
+                '{' => {
+                    self.pos += 1;
+                    self.map(false);
+                }
+                _ => {
+                    if self.name().is_none() {
+                        self.pos += c.len_utf8();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Skips `@key` or `@key(value)`.
+    fn annotation(&mut self) {
+        self.pos += 1;
+        self.name();
+        if self.peek() == Some('(') {
+            self.pos = self
+                .rest()
+                .find(')')
+                .map_or(self.text.len(), |i| self.pos + i + 1);
+        }
+    }
+}
+
+fn is_identifier_start(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn is_identifier_continue(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '@' | '.')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identities(scan: &Scan) -> Vec<&str> {
+        scan.occurrences()
+            .iter()
+            .map(|occurrence| occurrence.name.text.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_subjects_and_references() {
+        let text = "(a:Person {name: \"x\"})-[r:KNOWS]->(b) [g:Group | a, (c), b]";
+        let scan = Scan::new(text);
+
+        assert_eq!(identities(&scan), vec!["a", "r", "b", "g", "a", "c", "b"]);
+        assert_eq!(scan.references.len(), 2);
+
+        let kinds: Vec<_> = scan.subjects.iter().map(|s| s.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                SubjectKind::Node,
+                SubjectKind::Relationship,
+                SubjectKind::Node,
+                SubjectKind::Pattern,
+                SubjectKind::Node,
+            ]
+        );
+        assert_eq!(scan.subjects[4].parent, Some(3));
+        assert_eq!(
+            &text[scan.subjects[3].span.clone()],
+            "[g:Group | a, (c), b]"
+        );
+        assert_eq!(scan.keys[0].text, "name");
+    }
+
+    #[test]
+    fn test_definition_prefers_defining_subject() {
+        let scan = Scan::new("[g | a] (a) (a:Person) (a {k: 1})");
+        let definition = scan.definition("a").unwrap();
+
+        assert_eq!(definition.name.span, 13..14);
+        assert!(scan.definition("missing").is_none());
+    }
+
+    #[test]
+    fn test_strings_comments_and_annotations_are_skipped() {
+        let text = "// (x)\n@note(y) (a {s: \"(b) [c | d]\", t: date`(e)`}) ('quoted id')";
+        let scan = Scan::new(text);
+
+        assert_eq!(identities(&scan), vec!["a", "quoted id"]);
+        assert!(scan.has_comments);
+    }
+
+    #[test]
+    fn test_unclosed_brackets_extend_to_end() {
+        let text = "(a:Person {name: ";
+        let scan = Scan::new(text);
+
+        assert_eq!(scan.subjects[0].span, 0..text.len());
+        assert_eq!(scan.records[0], 11..text.len());
+        assert_eq!(scan.subjects[0].labels[0].text, "Person");
+    }
+}
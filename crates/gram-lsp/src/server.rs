@@ -0,0 +1,222 @@
+//! Message loop and request dispatch
+
+use crate::document::Document;
+use crate::features;
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _,
+    PublishDiagnostics,
+};
+use lsp_types::request::{
+    Completion, DocumentSymbolRequest, Formatting, GotoDefinition, HoverRequest, References,
+    Request as _,
+};
+use lsp_types::{
+    CompletionOptions, CompletionParams, CompletionResponse, DidChangeTextDocumentParams,
+    DidCloseTextDocumentParams, DidOpenTextDocumentParams, DocumentFormattingParams,
+    DocumentSymbolParams, DocumentSymbolResponse, GotoDefinitionParams, GotoDefinitionResponse,
+    HoverParams, HoverProviderCapability, OneOf, PublishDiagnosticsParams, ReferenceParams,
+    ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind, Url,
+};
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::error::Error;
+
+/// The features this server provides.
+pub fn capabilities() -> ServerCapabilities {
+    ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(
+            TextDocumentSyncKind::INCREMENTAL,
+        )),
+        document_formatting_provider: Some(OneOf::Left(true)),
+        definition_provider: Some(OneOf::Left(true)),
+        references_provider: Some(OneOf::Left(true)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec![":".to_string(), "{".to_string(), ",".to_string()]),
+            ..CompletionOptions::default()
+        }),
+        ..ServerCapabilities::default()
+    }
+}
+
+/// Runs the server on `connection` until the client shuts it down.
+///
+/// Performs the `initialize` handshake first. Use [`Connection::stdio`] for an
+/// editor, or [`Connection::memory`] to drive the server from a client in the
+/// same process.
+pub fn run(connection: &Connection) -> Result<(), Box<dyn Error + Send + Sync>> {
+    connection.initialize(serde_json::to_value(capabilities())?)?;
+
+    let mut server = Server::default();
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    return Ok(());
+                }
+                connection.sender.send(server.request(request).into())?;
+            }
+            Message::Notification(notification) => {
+                if let Some(diagnostics) = server.notification(notification) {
+                    connection.sender.send(diagnostics.into())?;
+                }
+            }
+            Message::Response(_) => {}
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Default)]
+struct Server {
+    documents: HashMap<Url, Document>,
+}
+
+impl Server {
+    fn request(&self, request: Request) -> Response {
+        let id = request.id.clone();
+        let result = match request.method.as_str() {
+            Formatting::METHOD => {
+                self.with::<DocumentFormattingParams, _>(request, |server, params| {
+                    let document = server.document(&params.text_document.uri)?;
+                    features::formatting(document)
+                        .map_err(|message| (ErrorCode::RequestFailed, message))
+                        .map(to_json)
+                })
+            }
+            GotoDefinition::METHOD => {
+                self.with::<GotoDefinitionParams, _>(request, |server, params| {
+                    let position = params.text_document_position_params;
+                    let uri = position.text_document.uri;
+                    let document = server.document(&uri)?;
+                    Ok(to_json(
+                        features::definition(document, &uri, position.position)
+                            .map(GotoDefinitionResponse::Scalar),
+                    ))
+                })
+            }
+            References::METHOD => self.with::<ReferenceParams, _>(request, |server, params| {
+                let position = params.text_document_position;
+                let uri = position.text_document.uri;
+                let document = server.document(&uri)?;
+                Ok(to_json(features::references(
+                    document,
+                    &uri,
+                    position.position,
+                    params.context.include_declaration,
+                )))
+            }),
+            HoverRequest::METHOD => self.with::<HoverParams, _>(request, |server, params| {
+                let position = params.text_document_position_params;
+                let document = server.document(&position.text_document.uri)?;
+                Ok(to_json(features::hover(document, position.position)))
+            }),
+            DocumentSymbolRequest::METHOD => {
+                self.with::<DocumentSymbolParams, _>(request, |server, params| {
+                    let document = server.document(&params.text_document.uri)?;
+                    Ok(to_json(DocumentSymbolResponse::Nested(
+                        features::document_symbols(document),
+                    )))
+                })
+            }
+            Completion::METHOD => self.with::<CompletionParams, _>(request, |server, params| {
+                let position = params.text_document_position;
+                let document = server.document(&position.text_document.uri)?;
+                Ok(to_json(CompletionResponse::Array(features::completion(
+                    document,
+                    position.position,
+                ))))
+            }),
+            method => Err((
+                ErrorCode::MethodNotFound,
+                format!("unhandled method {}", method),
+            )),
+        };
+
+        match result {
+            Ok(value) => Response::new_ok(id, value),
+            Err((code, message)) => Response::new_err(id, code as i32, message),
+        }
+    }
+
+    /// Applies a document notification, returning the diagnostics to publish.
+    fn notification(&mut self, notification: Notification) -> Option<Notification> {
+        let (uri, version) = match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: DidOpenTextDocumentParams = params(notification.params).ok()?;
+                let document = params.text_document;
+                self.documents
+                    .insert(document.uri.clone(), Document::new(document.text));
+                (document.uri, Some(document.version))
+            }
+            DidChangeTextDocument::METHOD => {
+                let params: DidChangeTextDocumentParams = params(notification.params).ok()?;
+                let uri = params.text_document.uri;
+                let document = self
+                    .documents
+                    .entry(uri.clone())
+                    .or_insert_with(|| Document::new(String::new()));
+                for change in params.content_changes {
+                    document.change(change.range, change.text);
+                }
+                (uri, Some(params.text_document.version))
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: DidCloseTextDocumentParams = params(notification.params).ok()?;
+                let uri = params.text_document.uri;
+                self.documents.remove(&uri);
+                // Clear the diagnostics of the closed document
+                return Some(publish(uri, Vec::new(), None));
+            }
+            _ => return None,
+        };
+
+        let diagnostics = features::diagnostics(&self.documents[&uri]);
+        Some(publish(uri, diagnostics, version))
+    }
+
+    fn document(&self, uri: &Url) -> Result<&Document, (ErrorCode, String)> {
+        self.documents.get(uri).ok_or_else(|| {
+            (
+                ErrorCode::InvalidParams,
+                format!("unknown document {}", uri),
+            )
+        })
+    }
+
+    /// Decodes the request parameters and runs `handler` on them.
+    fn with<P, F>(
+        &self,
+        request: Request,
+        handler: F,
+    ) -> Result<serde_json::Value, (ErrorCode, String)>
+    where
+        P: DeserializeOwned,
+        F: FnOnce(&Self, P) -> Result<serde_json::Value, (ErrorCode, String)>,
+    {
+        let params =
+            params(request.params).map_err(|e| (ErrorCode::InvalidParams, e.to_string()))?;
+        handler(self, params)
+    }
+}
+
+fn params<P: DeserializeOwned>(value: serde_json::Value) -> Result<P, serde_json::Error> {
+    serde_json::from_value(value)
+}
+
+fn to_json(value: impl serde::Serialize) -> serde_json::Value {
+    serde_json::to_value(value).expect("LSP types serialize to JSON")
+}
+
+fn publish(
+    uri: Url,
+    diagnostics: Vec<lsp_types::Diagnostic>,
+    version: Option<i32>,
+) -> Notification {
+    Notification::new(
+        PublishDiagnostics::METHOD.to_string(),
+        PublishDiagnosticsParams::new(uri, diagnostics, version),
+    )
+}
@@ -0,0 +1,480 @@
+//! Tests driving the language server through an in-process LSP client
+
+use lsp_server::{Connection, Message, Notification, Request, RequestId, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Exit, Initialized,
+    Notification as _, PublishDiagnostics,
+};
+use lsp_types::request::{
+    Completion, DocumentSymbolRequest, Formatting, GotoDefinition, HoverRequest, Initialize,
+    References, Request as _, Shutdown,
+};
+use lsp_types::{
+    CompletionParams, CompletionResponse, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, DocumentFormattingParams, DocumentSymbolParams,
+    DocumentSymbolResponse, FormattingOptions, GotoDefinitionParams, GotoDefinitionResponse, Hover,
+    HoverContents, HoverParams, InitializeParams, InitializeResult, Location, Position,
+    PublishDiagnosticsParams, Range, ReferenceContext, ReferenceParams,
+    TextDocumentContentChangeEvent, TextDocumentIdentifier, TextDocumentItem,
+    TextDocumentPositionParams, TextEdit, Url, VersionedTextDocumentIdentifier,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::io::{BufReader, Write};
+use std::process::{Command, Stdio};
+use std::thread::JoinHandle;
+
+struct Client {
+    connection: Connection,
+    server: Option<JoinHandle<()>>,
+    next_id: i32,
+}
+
+impl Client {
+    fn start() -> Self {
+        let (server, connection) = Connection::memory();
+        let handle = std::thread::spawn(move || gram_lsp::run(&server).unwrap());
+        let mut client = Client {
+            connection,
+            server: Some(handle),
+            next_id: 0,
+        };
+
+        let result: InitializeResult = client.request::<Initialize>(InitializeParams::default());
+        assert!(result.capabilities.hover_provider.is_some());
+        client.notify::<Initialized>(lsp_types::InitializedParams {});
+        client
+    }
+
+    fn request<R: lsp_types::request::Request>(&mut self, params: R::Params) -> R::Result
+    where
+        R::Params: Serialize,
+        R::Result: DeserializeOwned,
+    {
+        let response = self.request_raw::<R>(params);
+        assert!(response.error.is_none(), "{:?}", response.error);
+        serde_json::from_value(response.result.unwrap_or(serde_json::Value::Null)).unwrap()
+    }
+
+    fn request_raw<R: lsp_types::request::Request>(&mut self, params: R::Params) -> Response
+    where
+        R::Params: Serialize,
+    {
+        self.next_id += 1;
+        let id = RequestId::from(self.next_id);
+        self.connection
+            .sender
+            .send(Request::new(id.clone(), R::METHOD.to_string(), params).into())
+            .unwrap();
+
+        loop {
+            match self.connection.receiver.recv().unwrap() {
+                Message::Response(response) if response.id == id => return response,
+                _ => continue,
+            }
+        }
+    }
+
+    fn notify<N: lsp_types::notification::Notification>(&self, params: N::Params)
+    where
+        N::Params: Serialize,
+    {
+        self.connection
+            .sender
+            .send(Notification::new(N::METHOD.to_string(), params).into())
+            .unwrap();
+    }
+
+    /// Opens a document and returns the diagnostics published for it.
+    fn open(&self, uri: &Url, text: &str) -> PublishDiagnosticsParams {
+        self.notify::<DidOpenTextDocument>(DidOpenTextDocumentParams {
+            text_document: TextDocumentItem::new(
+                uri.clone(),
+                "gram".to_string(),
+                1,
+                text.to_string(),
+            ),
+        });
+        self.diagnostics()
+    }
+
+    fn change(&self, uri: &Url, version: i32, text: &str) -> PublishDiagnosticsParams {
+        self.notify::<DidChangeTextDocument>(DidChangeTextDocumentParams {
+            text_document: VersionedTextDocumentIdentifier::new(uri.clone(), version),
+            content_changes: vec![TextDocumentContentChangeEvent {
+                range: None,
+                range_length: None,
+                text: text.to_string(),
+            }],
+        });
+        self.diagnostics()
+    }
+
+    /// Replaces `range` of the document with `text`.
+    fn edit(&self, uri: &Url, version: i32, range: Range, text: &str) -> PublishDiagnosticsParams {
+        self.notify::<DidChangeTextDocument>(DidChangeTextDocumentParams {
+            text_document: VersionedTextDocumentIdentifier::new(uri.clone(), version),
+            content_changes: vec![TextDocumentContentChangeEvent {
+                range: Some(range),
+                range_length: None,
+                text: text.to_string(),
+            }],
+        });
+        self.diagnostics()
+    }
+
+    fn diagnostics(&self) -> PublishDiagnosticsParams {
+        loop {
+            if let Message::Notification(notification) = self.connection.receiver.recv().unwrap() {
+                if notification.method == PublishDiagnostics::METHOD {
+                    return serde_json::from_value(notification.params).unwrap();
+                }
+            }
+        }
+    }
+
+    fn shutdown(mut self) {
+        self.request::<Shutdown>(());
+        self.notify::<Exit>(());
+        self.server.take().unwrap().join().unwrap();
+    }
+}
+
+fn uri() -> Url {
+    Url::parse("file:///tmp/test.gram").unwrap()
+}
+
+fn position_params(text: &str, needle: &str, nth: usize) -> TextDocumentPositionParams {
+    TextDocumentPositionParams::new(
+        TextDocumentIdentifier::new(uri()),
+        position(text, needle, nth),
+    )
+}
+
+/// Position of the `nth` occurrence of `needle` (single-line, ASCII text).
+fn position(text: &str, needle: &str, nth: usize) -> Position {
+    let offset = text.match_indices(needle).nth(nth).unwrap().0;
+    let line = text[..offset].matches('\n').count();
+    let column = offset - text[..offset].rfind('\n').map_or(0, |i| i + 1);
+    Position::new(line as u32, column as u32)
+}
+
+const DOCUMENT: &str = "(alice:Person {name: \"Alice\", age: 30})-[k:KNOWS]->(bob:Person)\n\
+                        (bob {team: \"red\"})\n\
+                        [friends:Group | alice, bob]\n";
+
+#[test]
+fn publishes_diagnostics_with_ranges() {
+    let client = Client::start();
+    assert!(client.open(&uri(), "(a)").diagnostics.is_empty());
+
+    let published = client.change(&uri(), 2, "(a)\n(b {k: })");
+    assert_eq!(published.version, Some(2));
+    assert_eq!(published.diagnostics.len(), 1);
+    let diagnostic = &published.diagnostics[0];
+    assert_eq!(diagnostic.range.start.line, 1);
+    assert!(diagnostic.range.end > diagnostic.range.start);
+    assert_eq!(diagnostic.source.as_deref(), Some("gram"));
+
+    assert!(client
+        .change(&uri(), 3, "(a)\n(b {k: 1})")
+        .diagnostics
+        .is_empty());
+
+    client.notify::<DidCloseTextDocument>(DidCloseTextDocumentParams {
+        text_document: TextDocumentIdentifier::new(uri()),
+    });
+    assert!(client.diagnostics().diagnostics.is_empty());
+    client.shutdown();
+}
+
+#[test]
+fn applies_incremental_changes() {
+    let mut client = Client::start();
+    client.open(&uri(), "(a:Person)\n(b)\n");
+
+    // Type a relationship between the nodes, one character at a time
+    let mut published = None;
+    for (column, c) in "(a)-[:KNOWS]->(b)\n".chars().enumerate() {
+        let at = Position::new(2, column as u32);
+        let range = Range::new(at, at);
+        published = Some(client.edit(&uri(), column as i32 + 2, range, &c.to_string()));
+    }
+    assert!(published.unwrap().diagnostics.is_empty());
+
+    // Rename the first node in place
+    let published = client.edit(
+        &uri(),
+        100,
+        Range::new(Position::new(0, 1), Position::new(0, 2)),
+        "alice",
+    );
+    assert!(published.diagnostics.is_empty());
+
+    let symbols = client.request::<DocumentSymbolRequest>(DocumentSymbolParams {
+        text_document: TextDocumentIdentifier::new(uri()),
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+    });
+    let Some(DocumentSymbolResponse::Nested(symbols)) = symbols else {
+        panic!("expected nested symbols");
+    };
+    let names: Vec<_> = symbols.iter().map(|symbol| symbol.name.as_str()).collect();
+    assert_eq!(names, ["alice", "b", "a"]);
+    client.shutdown();
+}
+
+#[test]
+fn formats_documents() {
+    let mut client = Client::start();
+    let text = "(a:Person)  -->   (b)\n\n\n(c)";
+    client.open(&uri(), text);
+
+    let edits = client
+        .request::<Formatting>(DocumentFormattingParams {
+            text_document: TextDocumentIdentifier::new(uri()),
+            options: FormattingOptions::default(),
+            work_done_progress_params: Default::default(),
+        })
+        .unwrap();
+    assert_eq!(
+        edits,
+        vec![TextEdit::new(
+            Range::new(Position::new(0, 0), Position::new(3, 3)),
+            "(a:Person)-->(b)\n(c)\n".to_string()
+        )]
+    );
+    client.shutdown();
+}
+
+#[test]
+fn refuses_to_format_away_comments() {
+    let mut client = Client::start();
+    client.open(&uri(), "// people\n(a)");
+
+    let response = client.request_raw::<Formatting>(DocumentFormattingParams {
+        text_document: TextDocumentIdentifier::new(uri()),
+        options: FormattingOptions::default(),
+        work_done_progress_params: Default::default(),
+    });
+    assert!(response.error.unwrap().message.contains("comments"));
+    client.shutdown();
+}
+
+#[test]
+fn goes_from_references_to_definitions() {
+    let mut client = Client::start();
+    client.open(&uri(), DOCUMENT);
+
+    // `bob` in `[friends | alice, bob]` resolves to the first subject giving bob labels
+    let definition = client
+        .request::<GotoDefinition>(GotoDefinitionParams {
+            text_document_position_params: position_params(DOCUMENT, "bob", 2),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        })
+        .unwrap();
+    let GotoDefinitionResponse::Scalar(location) = definition else {
+        panic!("expected a single location");
+    };
+    let start = position(DOCUMENT, "bob", 0);
+    assert_eq!(
+        location.range,
+        Range::new(start, Position::new(0, start.character + 3))
+    );
+    client.shutdown();
+}
+
+#[test]
+fn finds_references() {
+    let mut client = Client::start();
+    client.open(&uri(), DOCUMENT);
+
+    let references = |client: &mut Client, include_declaration| {
+        client
+            .request::<References>(ReferenceParams {
+                text_document_position: position_params(DOCUMENT, "alice", 1),
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+                context: ReferenceContext {
+                    include_declaration,
+                },
+            })
+            .unwrap()
+            .into_iter()
+            .map(|location: Location| location.range.start)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        references(&mut client, true),
+        vec![
+            position(DOCUMENT, "alice", 0),
+            position(DOCUMENT, "alice", 1)
+        ]
+    );
+    assert_eq!(
+        references(&mut client, false),
+        vec![position(DOCUMENT, "alice", 1)]
+    );
+    client.shutdown();
+}
+
+#[test]
+fn hover_shows_merged_labels_and_properties() {
+    let mut client = Client::start();
+    client.open(&uri(), DOCUMENT);
+
+    let hover = |client: &mut Client, needle, nth| -> Option<Hover> {
+        client.request::<HoverRequest>(HoverParams {
+            text_document_position_params: position_params(DOCUMENT, needle, nth),
+            work_done_progress_params: Default::default(),
+        })
+    };
+    let contents = |hover: Hover| match hover.contents {
+        HoverContents::Markup(markup) => markup.value,
+        other => panic!("unexpected hover contents {:?}", other),
+    };
+
+    let bob = contents(hover(&mut client, "bob", 2).unwrap());
+    assert_eq!(bob, "```gram\n(bob:Person {team: \"red\"})\n```");
+
+    let knows = contents(hover(&mut client, "k:", 0).unwrap());
+    assert_eq!(knows, "```gram\n-[k:KNOWS]->\n```");
+
+    assert!(hover(&mut client, "Person", 0).is_none());
+
+    // While the text does not parse, the last parsed subjects are shown
+    client.change(&uri(), 2, &format!("{}(broken", DOCUMENT));
+    let alice = contents(hover(&mut client, "alice", 0).unwrap());
+    assert!(alice.contains("alice:Person"));
+    assert!(alice.contains("age: 30"));
+    client.shutdown();
+}
+
+#[test]
+fn lists_document_symbols() {
+    let mut client = Client::start();
+    client.open(&uri(), DOCUMENT);
+
+    let Some(DocumentSymbolResponse::Nested(symbols)) =
+        client.request::<DocumentSymbolRequest>(DocumentSymbolParams {
+            text_document: TextDocumentIdentifier::new(uri()),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        })
+    else {
+        panic!("expected nested symbols");
+    };
+
+    let names: Vec<_> = symbols.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, vec!["alice", "k", "bob", "friends"]);
+    assert_eq!(symbols[0].detail.as_deref(), Some(":Person"));
+    assert_eq!(symbols[3].range.start, Position::new(2, 0));
+    client.shutdown();
+}
+
+#[test]
+fn nests_symbols_in_subject_patterns() {
+    let mut client = Client::start();
+    let text = "[team:Team | (alice:Person), [sub | (bob:Person)]]";
+    client.open(&uri(), text);
+
+    let Some(DocumentSymbolResponse::Nested(symbols)) =
+        client.request::<DocumentSymbolRequest>(DocumentSymbolParams {
+            text_document: TextDocumentIdentifier::new(uri()),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        })
+    else {
+        panic!("expected nested symbols");
+    };
+
+    assert_eq!(symbols.len(), 1);
+    let children = symbols[0].children.as_ref().unwrap();
+    let names: Vec<_> = children.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, vec!["alice", "sub"]);
+    assert_eq!(children[1].children.as_ref().unwrap()[0].name, "bob");
+    client.shutdown();
+}
+
+#[test]
+fn completes_labels_and_property_keys() {
+    let mut client = Client::start();
+    client.open(&uri(), DOCUMENT);
+
+    let mut complete = |text: &str| -> Vec<String> {
+        client.change(&uri(), 2, text);
+        let end = text.lines().count() as u32 - 1;
+        let column = text.lines().last().unwrap().len() as u32;
+        let response = client.request::<Completion>(CompletionParams {
+            text_document_position: TextDocumentPositionParams::new(
+                TextDocumentIdentifier::new(uri()),
+                Position::new(end, column),
+            ),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+            context: None,
+        });
+        match response {
+            Some(CompletionResponse::Array(items)) => {
+                items.into_iter().map(|item| item.label).collect()
+            }
+            other => panic!("unexpected completion {:?}", other),
+        }
+    };
+
+    let labels = complete(&format!("{}(carol:", DOCUMENT));
+    assert_eq!(labels, vec!["Group", "KNOWS", "Person"]);
+
+    let keys = complete(&format!("{}(carol:Person {{name: \"Carol\", ", DOCUMENT));
+    assert_eq!(keys, vec!["age", "name", "team"]);
+
+    assert!(complete(&format!("{}(carol {{name: ", DOCUMENT)).is_empty());
+    client.shutdown();
+}
+
+#[test]
+fn serves_over_stdio() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_gram-lsp"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+
+    let mut send = |message: Message| {
+        message.write(&mut stdin).unwrap();
+        stdin.flush().unwrap();
+    };
+    send(
+        Request::new(
+            RequestId::from(1),
+            Initialize::METHOD.to_string(),
+            InitializeParams::default(),
+        )
+        .into(),
+    );
+    let Some(Message::Response(response)) = Message::read(&mut stdout).unwrap() else {
+        panic!("expected the initialize response");
+    };
+    let result: InitializeResult = serde_json::from_value(response.result.unwrap()).unwrap();
+    assert!(result.capabilities.definition_provider.is_some());
+
+    send(
+        Notification::new(
+            Initialized::METHOD.to_string(),
+            lsp_types::InitializedParams {},
+        )
+        .into(),
+    );
+    send(Request::new(RequestId::from(2), Shutdown::METHOD.to_string(), ()).into());
+    assert!(matches!(
+        Message::read(&mut stdout).unwrap(),
+        Some(Message::Response(_))
+    ));
+    send(Notification::new(Exit::METHOD.to_string(), ()).into());
+
+    assert!(child.wait().unwrap().success());
+}
@@ -0,0 +1,362 @@
+//! `IntoSubject`, `FromSubject` and `IntoRelationship` derives
+
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::spanned::Spanned;
+use syn::{Data, DeriveInput, Fields, GenericArgument, Ident, LitStr, PathArguments, Type};
+
+/// Which trait is being derived; decides which field roles are allowed.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    IntoSubject,
+    FromSubject,
+    IntoRelationship,
+}
+
+/// The role a field plays in the generated subject.
+enum Role {
+    Identity,
+    Labels,
+    Property { key: String, nested: bool },
+    Skip,
+    Source,
+    Target,
+}
+
+struct Field {
+    ident: Ident,
+    ty: Type,
+    role: Role,
+}
+
+/// A struct with its `#[gram(...)]` attributes resolved.
+struct Model {
+    input: DeriveInput,
+    labels: Vec<LitStr>,
+    fields: Vec<Field>,
+}
+
+pub fn expand(input: DeriveInput, target: Target) -> syn::Result<TokenStream> {
+    let model = Model::parse(input, target)?;
+    let name = &model.input.ident;
+    let (impl_generics, ty_generics, where_clause) = model.input.generics.split_for_impl();
+
+    Ok(match target {
+        Target::IntoSubject => {
+            let subject = model.subject_expr();
+            quote! {
+                // Identity and label conversions are spanned at user code and may be no-ops
+                #[allow(clippy::useless_conversion)]
+                impl #impl_generics ::pattern_core::IntoSubject for #name #ty_generics #where_clause {
+                    fn into_subject(self) -> ::pattern_core::Subject {
+                        #subject
+                    }
+                }
+            }
+        }
+        Target::FromSubject => {
+            let body = model.read_fields();
+            quote! {
+                #[allow(clippy::useless_conversion)]
+                impl #impl_generics ::pattern_core::FromSubject for #name #ty_generics #where_clause {
+                    fn from_subject(
+                        subject: &::pattern_core::Subject,
+                    ) -> ::core::result::Result<Self, ::pattern_core::PropertyError> {
+                        #body
+                    }
+                }
+            }
+        }
+        Target::IntoRelationship => {
+            let subject = model.subject_expr();
+            let endpoint = |role: fn(&Role) -> bool| {
+                let field = &model.fields.iter().find(|f| role(&f.role)).unwrap().ident;
+                quote! {
+                    ::pattern_core::Pattern::point(::pattern_core::IntoSubject::into_subject(self.#field))
+                }
+            };
+            let source = endpoint(|r| matches!(r, Role::Source));
+            let target = endpoint(|r| matches!(r, Role::Target));
+            quote! {
+                #[allow(clippy::useless_conversion)]
+                impl #impl_generics ::pattern_core::IntoRelationship for #name #ty_generics #where_clause {
+                    fn into_relationship(self) -> ::pattern_core::Pattern<::pattern_core::Subject> {
+                        let source = #source;
+                        let target = #target;
+                        ::pattern_core::Pattern::pattern(#subject, ::std::vec![source, target])
+                    }
+                }
+            }
+        }
+    })
+}
+
+impl Model {
+    fn parse(input: DeriveInput, target: Target) -> syn::Result<Self> {
+        let mut labels = Vec::new();
+        for attr in input.attrs.iter().filter(|a| a.path().is_ident("gram")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("label") {
+                    labels.push(meta.value()?.parse::<LitStr>()?);
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown gram attribute; expected `label = \"...\"`"))
+                }
+            })?;
+        }
+
+        let named = match &input.data {
+            Data::Struct(data) => match &data.fields {
+                Fields::Named(named) => Some(named.named.clone()),
+                _ => None,
+            },
+            _ => None,
+        };
+        let Some(named) = named else {
+            return Err(syn::Error::new(
+                input.ident.span(),
+                "gram derives require a struct with named fields",
+            ));
+        };
+
+        let mut fields = Vec::with_capacity(named.len());
+        for field in named {
+            let ident = field.ident.clone().unwrap();
+            let mut role = None;
+            let mut rename = None;
+            let mut nested = false;
+            for attr in field.attrs.iter().filter(|a| a.path().is_ident("gram")) {
+                attr.parse_nested_meta(|meta| {
+                    let span = meta.path.span();
+                    if meta.path.is_ident("identity") {
+                        set_role(&mut role, Role::Identity, span)
+                    } else if meta.path.is_ident("labels") {
+                        set_role(&mut role, Role::Labels, span)
+                    } else if meta.path.is_ident("skip") {
+                        set_role(&mut role, Role::Skip, span)
+                    } else if meta.path.is_ident("source") || meta.path.is_ident("target") {
+                        if target != Target::IntoRelationship {
+                            return Err(meta.error(
+                                "`source` and `target` are only valid with #[derive(IntoRelationship)]",
+                            ));
+                        }
+                        let endpoint = if meta.path.is_ident("source") {
+                            Role::Source
+                        } else {
+                            Role::Target
+                        };
+                        set_role(&mut role, endpoint, span)
+                    } else if meta.path.is_ident("rename") {
+                        rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                        Ok(())
+                    } else if meta.path.is_ident("nested") {
+                        nested = true;
+                        Ok(())
+                    } else {
+                        Err(meta.error(
+                            "unknown gram attribute; expected one of `identity`, `labels`, \
+                             `rename = \"...\"`, `skip`, `nested`, `source`, `target`",
+                        ))
+                    }
+                })?;
+            }
+            let role = match role {
+                None => Role::Property {
+                    key: rename.unwrap_or_else(|| ident.to_string()),
+                    nested,
+                },
+                Some(_) if rename.is_some() || nested => {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`rename` and `nested` only apply to property fields",
+                    ))
+                }
+                Some(role) => role,
+            };
+            fields.push(Field {
+                ident,
+                ty: field.ty,
+                role,
+            });
+        }
+
+        let model = Model {
+            input,
+            labels,
+            fields,
+        };
+        model.check_unique("identity", |r| matches!(r, Role::Identity))?;
+        model.check_unique("labels", |r| matches!(r, Role::Labels))?;
+        if target == Target::IntoRelationship {
+            model.check_required("source", |r| matches!(r, Role::Source))?;
+            model.check_required("target", |r| matches!(r, Role::Target))?;
+        }
+        Ok(model)
+    }
+
+    fn check_unique(&self, name: &str, role: fn(&Role) -> bool) -> syn::Result<()> {
+        match self.fields.iter().filter(|f| role(&f.role)).nth(1) {
+            Some(second) => Err(syn::Error::new(
+                second.ident.span(),
+                format!("only one field may be marked #[gram({})]", name),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    fn check_required(&self, name: &str, role: fn(&Role) -> bool) -> syn::Result<()> {
+        self.check_unique(name, role)?;
+        if self.fields.iter().any(|f| role(&f.role)) {
+            Ok(())
+        } else {
+            Err(syn::Error::new(
+                self.input.ident.span(),
+                format!(
+                    "#[derive(IntoRelationship)] requires a #[gram({})] field",
+                    name
+                ),
+            ))
+        }
+    }
+
+    /// Expression building the subject from `self`'s fields.
+    fn subject_expr(&self) -> TokenStream {
+        let mut identity = quote! { ::std::string::String::new() };
+        let mut inserts = Vec::new();
+        let static_labels = &self.labels;
+        let mut dynamic_labels = quote! {};
+
+        for field in &self.fields {
+            let ident = &field.ident;
+            match &field.role {
+                Role::Identity => {
+                    identity = quote_spanned! {field.ty.span()=>
+                        ::core::convert::Into::<::std::string::String>::into(self.#ident)
+                    };
+                }
+                Role::Labels => {
+                    dynamic_labels = quote_spanned! {field.ty.span()=>
+                        labels.extend(
+                            ::core::iter::IntoIterator::into_iter(self.#ident)
+                                .map(::core::convert::Into::<::std::string::String>::into),
+                        );
+                    };
+                }
+                Role::Property { key, nested } => {
+                    let convert = |value: TokenStream| {
+                        if *nested {
+                            quote! {
+                                ::pattern_core::Value::VMap(
+                                    ::pattern_core::IntoSubject::into_subject(#value).properties,
+                                )
+                            }
+                        } else {
+                            quote! { ::core::convert::Into::<::pattern_core::Value>::into(#value) }
+                        }
+                    };
+                    let insert = if option_inner(&field.ty).is_some() {
+                        let value = convert(quote! { value });
+                        quote_spanned! {field.ty.span()=>
+                            if let ::core::option::Option::Some(value) = self.#ident {
+                                properties.insert(::std::string::String::from(#key), #value);
+                            }
+                        }
+                    } else {
+                        let value = convert(quote! { self.#ident });
+                        quote_spanned! {field.ty.span()=>
+                            properties.insert(::std::string::String::from(#key), #value);
+                        }
+                    };
+                    inserts.push(insert);
+                }
+                Role::Skip | Role::Source | Role::Target => {}
+            }
+        }
+
+        quote! {
+            ::pattern_core::Subject {
+                identity: ::pattern_core::Symbol(#identity),
+                labels: {
+                    #[allow(unused_mut)]
+                    let mut labels = ::std::collections::HashSet::new();
+                    #(labels.insert(::std::string::String::from(#static_labels));)*
+                    #dynamic_labels
+                    labels
+                },
+                properties: {
+                    #[allow(unused_mut)]
+                    let mut properties = ::std::collections::HashMap::new();
+                    #(#inserts)*
+                    properties
+                },
+            }
+        }
+    }
+
+    /// Body of `from_subject`, reading each field back from `subject`.
+    fn read_fields(&self) -> TokenStream {
+        let static_labels = &self.labels;
+        let inits = self.fields.iter().map(|field| {
+            let ident = &field.ident;
+            let value = match &field.role {
+                Role::Identity => quote_spanned! {field.ty.span()=>
+                    ::core::convert::From::from(::core::clone::Clone::clone(&subject.identity.0))
+                },
+                Role::Labels => quote_spanned! {field.ty.span()=>
+                    {
+                        // Sorted so that ordered collections are deterministic
+                        let mut labels: ::std::vec::Vec<&::std::string::String> = subject
+                            .labels
+                            .iter()
+                            .filter(|label| ![#(#static_labels),*].contains(&label.as_str()))
+                            .collect();
+                        labels.sort();
+                        labels.into_iter().cloned().collect()
+                    }
+                },
+                Role::Property { key, nested } => {
+                    let optional = option_inner(&field.ty).is_some();
+                    match (nested, optional) {
+                        (false, false) => quote! { subject.require(#key)? },
+                        (false, true) => quote! { subject.try_get(#key)? },
+                        (true, false) => quote! { subject.require_nested(#key)? },
+                        (true, true) => quote! { subject.try_get_nested(#key)? },
+                    }
+                }
+                Role::Skip | Role::Source | Role::Target => quote_spanned! {field.ty.span()=>
+                    ::core::default::Default::default()
+                },
+            };
+            quote! { #ident: #value }
+        });
+        quote! {
+            ::core::result::Result::Ok(Self {
+                #(#inits,)*
+            })
+        }
+    }
+}
+
+fn set_role(slot: &mut Option<Role>, role: Role, span: proc_macro2::Span) -> syn::Result<()> {
+    if slot.is_some() {
+        return Err(syn::Error::new(span, "conflicting gram attributes"));
+    }
+    *slot = Some(role);
+    Ok(())
+}
+
+/// Returns `T` if `ty` is written as `Option<T>` (or a path ending in `Option<T>`).
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else { return None };
+    let last = path.path.segments.last()?;
+    if last.ident != "Option" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &last.arguments else {
+        return None;
+    };
+    match args.args.first()? {
+        GenericArgument::Type(inner) if args.args.len() == 1 => Some(inner),
+        _ => None,
+    }
+}
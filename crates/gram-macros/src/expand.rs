@@ -0,0 +1,169 @@
+//! Code generation for parsed patterns
+
+use crate::interpolate::Substituted;
+use pattern_core::{Pattern, RangeValue, Subject, Value};
+use proc_macro2::{Ident, Literal, TokenStream};
+use quote::quote;
+use std::collections::BTreeMap;
+
+/// Generates construction code for parsed patterns.
+pub struct Expander<'a> {
+    pub substituted: &'a Substituted,
+    /// Local binding holding the converted value of each placeholder, in placeholder order
+    pub placeholders: Vec<Ident>,
+}
+
+impl Expander<'_> {
+    pub fn pattern(&self, pattern: &Pattern<Subject>) -> Result<TokenStream, String> {
+        let subject = self.subject(&pattern.value)?;
+        let elements = pattern
+            .elements
+            .iter()
+            .map(|e| self.pattern(e))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(quote! {
+            ::pattern_core::Pattern::pattern(#subject, ::std::vec![#(#elements),*])
+        })
+    }
+
+    fn subject(&self, subject: &Subject) -> Result<TokenStream, String> {
+        let identity = &subject.identity.0;
+        self.reject_placeholder(identity, "identities")?;
+
+        // Sort labels and keys so the expansion is deterministic
+        let mut labels: Vec<&String> = subject.labels.iter().collect();
+        labels.sort();
+        for label in &labels {
+            self.reject_placeholder(label, "labels")?;
+        }
+
+        let properties: BTreeMap<&String, &Value> = subject.properties.iter().collect();
+        let mut inserts = Vec::with_capacity(properties.len());
+        for (key, value) in properties {
+            self.reject_placeholder(key, "property keys")?;
+            let value = self.value(value)?;
+            inserts.push(quote! {
+                properties.insert(::std::string::String::from(#key), #value);
+            });
+        }
+
+        Ok(quote! {
+            ::pattern_core::Subject {
+                identity: ::pattern_core::Symbol(::std::string::String::from(#identity)),
+                labels: {
+                    #[allow(unused_mut)]
+                    let mut labels = ::std::collections::HashSet::new();
+                    #(labels.insert(::std::string::String::from(#labels));)*
+                    labels
+                },
+                properties: {
+                    #[allow(unused_mut)]
+                    let mut properties = ::std::collections::HashMap::new();
+                    #(#inserts)*
+                    properties
+                },
+            }
+        })
+    }
+
+    fn value(&self, value: &Value) -> Result<TokenStream, String> {
+        Ok(match value {
+            Value::VString(s) => match self.substituted.placeholder(s) {
+                Some(n) => {
+                    let binding = &self.placeholders[n];
+                    quote! { ::core::clone::Clone::clone(&#binding) }
+                }
+                None => quote! { ::pattern_core::Value::VString(::std::string::String::from(#s)) },
+            },
+            Value::VInteger(i) => {
+                let i = Literal::i64_suffixed(*i);
+                quote! { ::pattern_core::Value::VInteger(#i) }
+            }
+            Value::VDecimal(d) => {
+                let d = float(*d);
+                quote! { ::pattern_core::Value::VDecimal(#d) }
+            }
+            Value::VBoolean(b) => quote! { ::pattern_core::Value::VBoolean(#b) },
+            Value::VSymbol(s) => {
+                self.reject_placeholder(s, "symbols")?;
+                quote! { ::pattern_core::Value::VSymbol(::std::string::String::from(#s)) }
+            }
+            Value::VTaggedString { tag, content } => quote! {
+                ::pattern_core::Value::VTaggedString {
+                    tag: ::std::string::String::from(#tag),
+                    content: ::std::string::String::from(#content),
+                }
+            },
+            Value::VArray(items) => {
+                let items = items
+                    .iter()
+                    .map(|v| self.value(v))
+                    .collect::<Result<Vec<_>, _>>()?;
+                quote! { ::pattern_core::Value::VArray(::std::vec![#(#items),*]) }
+            }
+            Value::VMap(map) => {
+                let entries: BTreeMap<&String, &Value> = map.iter().collect();
+                let mut inserts = Vec::with_capacity(entries.len());
+                for (key, value) in entries {
+                    self.reject_placeholder(key, "map keys")?;
+                    let value = self.value(value)?;
+                    inserts.push(quote! { map.insert(::std::string::String::from(#key), #value); });
+                }
+                quote! {
+                    ::pattern_core::Value::VMap({
+                        #[allow(unused_mut)]
+                        let mut map = ::std::collections::HashMap::new();
+                        #(#inserts)*
+                        map
+                    })
+                }
+            }
+            Value::VRange(RangeValue { lower, upper }) => {
+                let bound = |b: &Option<f64>| match b {
+                    Some(x) => {
+                        let x = float(*x);
+                        quote! { ::core::option::Option::Some(#x) }
+                    }
+                    None => quote! { ::core::option::Option::None },
+                };
+                let (lower, upper) = (bound(lower), bound(upper));
+                quote! {
+                    ::pattern_core::Value::VRange(::pattern_core::RangeValue {
+                        lower: #lower,
+                        upper: #upper,
+                    })
+                }
+            }
+            Value::VMeasurement { unit, value } => {
+                let value = float(*value);
+                quote! {
+                    ::pattern_core::Value::VMeasurement {
+                        unit: ::std::string::String::from(#unit),
+                        value: #value,
+                    }
+                }
+            }
+        })
+    }
+
+    fn reject_placeholder(&self, s: &str, position: &str) -> Result<(), String> {
+        if self.substituted.mentions_placeholder(s) {
+            Err(format!(
+                "interpolation is only supported in property values, not in {}",
+                position
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+fn float(x: f64) -> TokenStream {
+    if x.is_finite() {
+        let lit = Literal::f64_suffixed(x);
+        quote! { #lit }
+    } else {
+        let bits = x.to_bits();
+        quote! { f64::from_bits(#bits) }
+    }
+}
@@ -0,0 +1,167 @@
+//! Placeholder substitution for `$name` interpolations
+//!
+//! Gram notation has no syntax for host-language expressions, so before parsing
+//! each `$name` outside of quoted strings and comments is replaced with a string
+//! literal holding a unique sentinel. After parsing, property values equal to a
+//! sentinel are swapped for the corresponding Rust expression.
+
+/// Source text with placeholders replaced by sentinel strings.
+pub struct Substituted {
+    /// The text handed to the parser
+    pub text: String,
+    /// Prefix shared by every sentinel; guaranteed not to occur in the original source
+    pub prefix: String,
+    /// Placeholder names in order of appearance (sentinel `n` is `names[n]`)
+    pub names: Vec<String>,
+    /// `(offset in text, sentinel length, placeholder length)` for each substitution
+    edits: Vec<(usize, usize, usize)>,
+}
+
+impl Substituted {
+    /// Replaces every `$name` placeholder in `source`.
+    pub fn new(source: &str) -> Self {
+        let mut prefix = String::from("__gram_interpolation_");
+        while source.contains(&prefix) {
+            prefix.push('_');
+        }
+
+        let mut text = String::with_capacity(source.len());
+        let mut names = Vec::new();
+        let mut edits = Vec::new();
+        let bytes = source.as_bytes();
+        let mut i = 0;
+
+        while i < bytes.len() {
+            let rest = &source[i..];
+            let skip = if let Some(fenced) = rest.strip_prefix("```") {
+                fenced.find("```").map_or(rest.len(), |end| end + 6)
+            } else if rest.starts_with("//") {
+                rest.find('\n').unwrap_or(rest.len())
+            } else if matches!(bytes[i], b'"' | b'\'' | b'`') {
+                quoted_len(rest)
+            } else if bytes[i] == b'$' {
+                let name_len = rest[1..]
+                    .char_indices()
+                    .find(|(n, c)| {
+                        !(c.is_ascii_alphanumeric() || *c == '_') || (*n == 0 && c.is_ascii_digit())
+                    })
+                    .map_or(rest.len() - 1, |(n, _)| n);
+                if name_len > 0 {
+                    let sentinel = format!("\"{}{}\"", prefix, names.len());
+                    edits.push((text.len(), sentinel.len(), name_len + 1));
+                    names.push(rest[1..=name_len].to_string());
+                    text.push_str(&sentinel);
+                    i += name_len + 1;
+                    continue;
+                }
+                1
+            } else {
+                rest.chars().next().map_or(1, char::len_utf8)
+            };
+            text.push_str(&rest[..skip]);
+            i += skip;
+        }
+
+        Substituted {
+            text,
+            prefix,
+            names,
+            edits,
+        }
+    }
+
+    /// Returns the placeholder index if `s` is one of our sentinels.
+    pub fn placeholder(&self, s: &str) -> Option<usize> {
+        s.strip_prefix(&self.prefix)?
+            .parse::<usize>()
+            .ok()
+            .filter(|n| *n < self.names.len())
+    }
+
+    /// Returns `true` if `s` contains a sentinel (used to reject placeholders
+    /// outside property values).
+    pub fn mentions_placeholder(&self, s: &str) -> bool {
+        s.contains(&self.prefix)
+    }
+
+    /// Maps a byte offset in the substituted text back to the original source.
+    pub fn original_offset(&self, offset: usize) -> usize {
+        let mut delta: isize = 0;
+        for (at, new_len, old_len) in &self.edits {
+            if offset < *at {
+                break;
+            }
+            if offset < at + new_len {
+                return (*at as isize + delta) as usize;
+            }
+            delta += *old_len as isize - *new_len as isize;
+        }
+        (offset as isize + delta) as usize
+    }
+}
+
+/// Length of a quoted string starting at the first byte of `s`, including
+/// both quotes. Unterminated strings run to the end of the input.
+fn quoted_len(s: &str) -> usize {
+    let quote = s.as_bytes()[0];
+    let bytes = s.as_bytes();
+    let mut i = 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b if b == quote => return i + 1,
+            _ => i += 1,
+        }
+    }
+    s.len()
+}
+
+/// 1-indexed line and column of a byte offset.
+pub fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let mut offset = offset.min(source.len());
+    while !source.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let prefix = &source[..offset];
+    let line = prefix.matches('\n').count() + 1;
+    let column = prefix[prefix.rfind('\n').map_or(0, |p| p + 1)..]
+        .chars()
+        .count()
+        + 1;
+    (line, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_substitutes_outside_strings_only() {
+        let s = Substituted::new("(a {x: $x, y: '$y', z: $z_1}) // $c");
+        assert_eq!(s.names, vec!["x", "z_1"]);
+        assert!(s.text.contains("'$y'"));
+        assert!(s.text.ends_with("// $c"));
+        assert_eq!(s.placeholder(&format!("{}1", s.prefix)), Some(1));
+        assert_eq!(s.placeholder(&format!("{}2", s.prefix)), None);
+    }
+
+    #[test]
+    fn test_prefix_avoids_collisions() {
+        let s = Substituted::new("(a {x: '__gram_interpolation_0', y: $y})");
+        assert_eq!(s.prefix, "__gram_interpolation__");
+        assert_eq!(s.placeholder(&format!("{}0", s.prefix)), Some(0));
+    }
+
+    #[test]
+    fn test_offsets_map_back_to_source() {
+        let source = "(a {x: $x}) ]";
+        let s = Substituted::new(source);
+        let bracket = s.text.rfind(']').unwrap();
+        assert_eq!(s.original_offset(bracket), source.rfind(']').unwrap());
+        assert_eq!(
+            s.original_offset(s.text.find('"').unwrap()),
+            source.find('$').unwrap()
+        );
+        assert_eq!(line_column("ab\ncd", 4), (2, 2));
+    }
+}
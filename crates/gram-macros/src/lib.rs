@@ -0,0 +1,381 @@
+//! gram-macros - Compile-time gram notation
+//!
+//! This crate provides macros that turn gram notation into `Pattern<Subject>`
+//! construction code at compile time:
+//!
+//! - [`gram!`] expands to a single `Pattern<Subject>`
+//! - [`gram_patterns!`] expands to a `Vec<Pattern<Subject>>`
+//!
+//! The notation is checked with the same parser as `gram_codec::parse_gram`;
+//! syntax errors are reported as compile errors at the macro call site.
+//!
+//! It also provides derives mapping plain Rust structs to subjects:
+//!
+//! - [`IntoSubject`](macro@IntoSubject) and [`FromSubject`](macro@FromSubject)
+//!   implement the `pattern_core` traits of the same name
+//! - [`IntoRelationship`](macro@IntoRelationship) builds a two-element
+//!   `Pattern<Subject>` from a struct with source and target fields
+//!
+//! The expansions refer to `::pattern_core`, so the calling crate must depend on
+//! `pattern-core`.
+//!
+//! # Interpolation
+//!
+//! Property values may be written as `$name` placeholders. Each placeholder is
+//! bound either by a `name = expr` argument after the notation or, when no
+//! argument is given, by a variable called `name` in scope. Each distinct
+//! placeholder is evaluated once, moved, and converted with
+//! `Into<pattern_core::Value>`; repeated uses receive clones.
+//!
+//! ```rust
+//! use gram_macros::gram;
+//! use pattern_core::Value;
+//!
+//! let name = Value::VString("Alice".to_string());
+//! let pattern = gram!(
+//!     "(a:Person {name: $name, age: $age})-[:KNOWS]->(b)",
+//!     age = Value::VInteger(30),
+//! );
+//!
+//! assert_eq!(pattern.elements.len(), 2);
+//! assert_eq!(pattern.elements[0].value.properties["age"], Value::VInteger(30));
+//! ```
+
+mod derive;
+mod expand;
+mod interpolate;
+
+use expand::Expander;
+use interpolate::{line_column, Substituted};
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::{quote, ToTokens};
+use std::collections::HashMap;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{parse_macro_input, DeriveInput, Expr, Ident, LitStr, Token};
+
+/// Builds a single `Pattern<Subject>` from gram notation.
+///
+/// The notation must contain exactly one pattern; use [`gram_patterns!`] for
+/// several. See the [crate documentation](crate) for interpolation.
+///
+/// # Examples
+///
+/// ```rust
+/// use gram_macros::gram;
+///
+/// let knows = gram!("(alice:Person)-[:KNOWS]->(bob:Person)");
+/// assert!(knows.value.labels.contains("KNOWS"));
+/// assert_eq!(knows.elements[1].value.identity.0, "bob");
+/// ```
+#[proc_macro]
+pub fn gram(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as MacroInput);
+    match input.expand(Arity::One) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// Builds a `Vec<Pattern<Subject>>` from gram notation containing any number of patterns.
+///
+/// # Examples
+///
+/// ```rust
+/// use gram_macros::gram_patterns;
+///
+/// let doc = gram_patterns!("(a:Person) (b:Person) (a)-[:KNOWS]->(b)");
+/// assert_eq!(doc.len(), 3);
+/// ```
+#[proc_macro]
+pub fn gram_patterns(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as MacroInput);
+    match input.expand(Arity::Many) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// Derives `pattern_core::IntoSubject` for a struct with named fields.
+///
+/// # Attributes
+///
+/// On the struct:
+///
+/// - `#[gram(label = "Person")]` adds a static label (repeatable)
+///
+/// On fields:
+///
+/// - `#[gram(identity)]` uses the field as the identity (`Into<String>`); without
+///   one the identity is empty
+/// - `#[gram(labels)]` adds the field's items as dynamic labels
+///   (`IntoIterator` of `Into<String>`)
+/// - `#[gram(rename = "key")]` stores the property under another key
+/// - `#[gram(skip)]` leaves the field out
+/// - `#[gram(nested)]` stores a struct implementing `IntoSubject` as a `VMap` of
+///   its properties (its identity and labels are dropped)
+///
+/// Every other field becomes a property via `Into<pattern_core::Value>`. `Option`
+/// fields are omitted when `None`.
+///
+/// Generic structs are supported, but no bounds are inferred for type parameters;
+/// declare them on the struct.
+///
+/// # Examples
+///
+/// ```rust
+/// use gram_macros::{FromSubject, IntoSubject};
+/// use pattern_core::{FromSubject, IntoSubject, Value};
+///
+/// #[derive(IntoSubject, FromSubject, Debug, PartialEq)]
+/// #[gram(label = "Person")]
+/// struct Person {
+///     #[gram(identity)]
+///     id: String,
+///     #[gram(rename = "fullName")]
+///     name: String,
+///     age: Option<i64>,
+///     #[gram(skip)]
+///     cache: Vec<u8>,
+/// }
+///
+/// let alice = Person { id: "alice".into(), name: "Alice".into(), age: Some(30), cache: vec![1] };
+/// let subject = alice.into_subject();
+/// assert!(subject.labels.contains("Person"));
+/// assert_eq!(subject.properties["fullName"], Value::from("Alice"));
+///
+/// let back = Person::from_subject(&subject).unwrap();
+/// assert_eq!(back.age, Some(30));
+/// assert!(back.cache.is_empty());
+/// ```
+#[proc_macro_derive(IntoSubject, attributes(gram))]
+pub fn derive_into_subject(input: TokenStream) -> TokenStream {
+    derive_with(input, derive::Target::IntoSubject)
+}
+
+/// Derives `pattern_core::FromSubject` for a struct with named fields.
+///
+/// Accepts the same attributes as [`IntoSubject`](macro@IntoSubject):
+///
+/// - the identity field is built with `From<String>`
+/// - the labels field collects the subject's labels other than the static ones,
+///   in sorted order
+/// - properties are converted with `Subject::require`, or `Subject::try_get`
+///   for `Option` fields, so errors name the offending key
+/// - nested fields use `FromSubject` on the `VMap`
+/// - skipped fields are `Default::default()`
+///
+/// Static labels are not checked.
+#[proc_macro_derive(FromSubject, attributes(gram))]
+pub fn derive_from_subject(input: TokenStream) -> TokenStream {
+    derive_with(input, derive::Target::FromSubject)
+}
+
+/// Derives `pattern_core::IntoRelationship` for a struct describing a
+/// relationship.
+///
+/// One field must be marked `#[gram(source)]` and one `#[gram(target)]`; both
+/// must implement `pattern_core::IntoSubject`. The remaining fields and struct
+/// attributes describe the relationship subject, as for
+/// [`IntoSubject`](macro@IntoSubject).
+///
+/// # Examples
+///
+/// ```rust
+/// use gram_macros::{IntoRelationship, IntoSubject};
+/// use pattern_core::{IntoRelationship, Value};
+///
+/// #[derive(IntoSubject)]
+/// #[gram(label = "Person")]
+/// struct Person {
+///     #[gram(identity)]
+///     id: String,
+/// }
+///
+/// #[derive(IntoRelationship)]
+/// #[gram(label = "KNOWS")]
+/// struct Knows {
+///     #[gram(source)]
+///     from: Person,
+///     #[gram(target)]
+///     to: Person,
+///     since: i64,
+/// }
+///
+/// let knows = Knows {
+///     from: Person { id: "alice".into() },
+///     to: Person { id: "bob".into() },
+///     since: 2020,
+/// }
+/// .into_relationship();
+///
+/// assert!(knows.value.labels.contains("KNOWS"));
+/// assert_eq!(knows.value.properties["since"], Value::VInteger(2020));
+/// assert_eq!(knows.elements[1].value.identity.0, "bob");
+/// ```
+#[proc_macro_derive(IntoRelationship, attributes(gram))]
+pub fn derive_into_relationship(input: TokenStream) -> TokenStream {
+    derive_with(input, derive::Target::IntoRelationship)
+}
+
+fn derive_with(input: TokenStream, target: derive::Target) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match derive::expand(input, target) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+enum Arity {
+    One,
+    Many,
+}
+
+/// `"notation" (, name = expr)* ,?`
+struct MacroInput {
+    source: LitStr,
+    args: Vec<(Ident, Expr)>,
+}
+
+impl Parse for MacroInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let source: LitStr = input.parse()?;
+        let mut args = Vec::new();
+        if !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            let named = Punctuated::<NamedArg, Token![,]>::parse_terminated(input)?;
+            args = named.into_iter().map(|a| (a.name, a.expr)).collect();
+        }
+        Ok(MacroInput { source, args })
+    }
+}
+
+struct NamedArg {
+    name: Ident,
+    expr: Expr,
+}
+
+impl Parse for NamedArg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name = input.parse()?;
+        input.parse::<Token![=]>()?;
+        let expr = input.parse()?;
+        Ok(NamedArg { name, expr })
+    }
+}
+
+impl MacroInput {
+    fn expand(&self, arity: Arity) -> syn::Result<proc_macro2::TokenStream> {
+        let source = self.source.value();
+        let span = self.source.span();
+        let substituted = Substituted::new(&source);
+
+        let mut named: HashMap<String, &Expr> = HashMap::new();
+        for (name, expr) in &self.args {
+            if named.insert(name.to_string(), expr).is_some() {
+                return Err(syn::Error::new(
+                    name.span(),
+                    format!("duplicate interpolation argument `{}`", name),
+                ));
+            }
+        }
+        for (name, _) in &self.args {
+            if !substituted.names.iter().any(|n| name == n) {
+                return Err(syn::Error::new(
+                    name.span(),
+                    format!("argument `{}` is never used as `${}`", name, name),
+                ));
+            }
+        }
+        // Each distinct placeholder is evaluated once, before any construction
+        let mut unique: Vec<&String> = Vec::new();
+        for name in &substituted.names {
+            if !unique.contains(&name) {
+                unique.push(name);
+            }
+        }
+        let bindings: Vec<Ident> = unique
+            .iter()
+            .map(|name| Ident::new(&format!("__gram_{}", name), Span::mixed_site()))
+            .collect();
+        let lets = unique.iter().zip(&bindings).map(|(name, binding)| {
+            let expr = match named.get(*name) {
+                Some(expr) => expr.to_token_stream(),
+                None => Ident::new(name, span).to_token_stream(),
+            };
+            quote! {
+                let #binding: ::pattern_core::Value = ::core::convert::Into::into(#expr);
+            }
+        });
+        let lets: Vec<_> = lets.collect();
+        let placeholders = substituted
+            .names
+            .iter()
+            .map(|name| bindings[unique.iter().position(|u| *u == name).unwrap()].clone())
+            .collect();
+
+        let patterns = gram_codec::parse_gram(&substituted.text)
+            .map_err(|err| syn::Error::new(span, describe(&err, &source, &substituted)))?;
+
+        let expander = Expander {
+            substituted: &substituted,
+            placeholders,
+        };
+        let mut built = patterns
+            .iter()
+            .map(|p| expander.pattern(p))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|message| syn::Error::new(span, message))?;
+
+        match arity {
+            Arity::Many => Ok(quote! {{
+                #(#lets)*
+                let patterns: ::std::vec::Vec<::pattern_core::Pattern<::pattern_core::Subject>> =
+                    ::std::vec![#(#built),*];
+                patterns
+            }}),
+            Arity::One if built.len() == 1 => {
+                let pattern = built.remove(0);
+                Ok(quote! {{
+                    #(#lets)*
+                    #pattern
+                }})
+            }
+            Arity::One => Err(syn::Error::new(
+                span,
+                format!(
+                    "gram! expects exactly one pattern, found {}; use gram_patterns! for several",
+                    built.len()
+                ),
+            )),
+        }
+    }
+}
+
+/// Formats a parse error with its position in the original (unsubstituted) source.
+fn describe(err: &gram_codec::ParseError, source: &str, substituted: &Substituted) -> String {
+    use gram_codec::ParseError::*;
+
+    let detail = match err {
+        SyntaxError {
+            expected, found, ..
+        } => format!("expected {}, found '{}'", expected, found),
+        UnexpectedInput { snippet, .. } => format!("unexpected input '{}'", snippet),
+        InvalidValue { kind, reason, .. } => format!("invalid {}: {}", kind, reason),
+        UnmatchedDelimiter { delimiter, .. } => format!("unmatched '{}'", delimiter),
+        Internal { message } => message.clone(),
+        _ => err.message(),
+    };
+    match err.location() {
+        Some(location) => {
+            let offset = substituted.original_offset(location.offset);
+            let (line, column) = line_column(source, offset);
+            format!(
+                "invalid gram notation at line {}, column {}: {}",
+                line, column, detail
+            )
+        }
+        None => format!("invalid gram notation: {}", detail),
+    }
+}
@@ -0,0 +1,242 @@
+//! Tests for the IntoSubject, FromSubject and IntoRelationship derives
+
+use gram_macros::{FromSubject, IntoRelationship, IntoSubject};
+use pattern_core::{
+    FromSubject, IntoRelationship, IntoSubject, PropertyError, Subject, Symbol, Value,
+};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+#[derive(IntoSubject, FromSubject, Debug, Clone, PartialEq)]
+struct Address {
+    city: String,
+    zip: Option<String>,
+}
+
+#[derive(IntoSubject, FromSubject, Debug, Clone, PartialEq)]
+#[gram(label = "Person", label = "Entity")]
+struct Person {
+    #[gram(identity)]
+    id: String,
+    #[gram(labels)]
+    roles: Vec<String>,
+    #[gram(rename = "fullName")]
+    name: String,
+    age: u32,
+    email: Option<String>,
+    tags: Vec<String>,
+    #[gram(nested)]
+    address: Address,
+    #[gram(nested)]
+    previous: Option<Address>,
+    #[gram(skip)]
+    visits: usize,
+}
+
+fn alice() -> Person {
+    Person {
+        id: "alice".to_string(),
+        roles: vec!["Admin".to_string(), "Employee".to_string()],
+        name: "Alice Smith".to_string(),
+        age: 30,
+        email: None,
+        tags: vec!["ops".to_string()],
+        address: Address {
+            city: "Berlin".to_string(),
+            zip: Some("10115".to_string()),
+        },
+        previous: None,
+        visits: 7,
+    }
+}
+
+#[test]
+fn test_into_subject_maps_identity_labels_and_properties() {
+    let subject = alice().into_subject();
+
+    assert_eq!(subject.identity, Symbol("alice".to_string()));
+    let labels: BTreeSet<&str> = subject.labels.iter().map(String::as_str).collect();
+    assert_eq!(
+        labels,
+        BTreeSet::from(["Admin", "Employee", "Entity", "Person"])
+    );
+    assert_eq!(subject.properties["fullName"], Value::from("Alice Smith"));
+    assert_eq!(subject.properties["age"], Value::VInteger(30));
+    assert_eq!(subject.properties["tags"], Value::from(vec!["ops"]));
+    assert!(!subject.properties.contains_key("email"));
+    assert!(!subject.properties.contains_key("previous"));
+    assert!(!subject.properties.contains_key("visits"));
+    assert!(!subject.properties.contains_key("name"));
+}
+
+#[test]
+fn test_nested_struct_becomes_map() {
+    let subject = alice().into_subject();
+    assert_eq!(
+        subject.properties["address"],
+        Value::VMap(HashMap::from([
+            ("city".to_string(), Value::from("Berlin")),
+            ("zip".to_string(), Value::from("10115")),
+        ]))
+    );
+}
+
+#[test]
+fn test_round_trip() {
+    let mut person = alice();
+    person.email = Some("alice@example.com".to_string());
+    person.previous = Some(Address {
+        city: "Paris".to_string(),
+        zip: None,
+    });
+
+    let back = Person::from_subject(&person.clone().into_subject()).unwrap();
+
+    // Skipped fields come back as their default
+    assert_eq!(back.visits, 0);
+    assert_eq!(
+        back,
+        Person {
+            visits: 0,
+            ..person
+        }
+    );
+}
+
+#[test]
+fn test_dynamic_labels_exclude_static_labels() {
+    #[derive(FromSubject)]
+    #[gram(label = "Person")]
+    struct Labelled {
+        #[gram(labels)]
+        labels: HashSet<String>,
+    }
+
+    let subject = Subject {
+        identity: Symbol("n".to_string()),
+        labels: HashSet::from(["Person".to_string(), "Admin".to_string()]),
+        properties: HashMap::new(),
+    };
+    let labelled = Labelled::from_subject(&subject).unwrap();
+    assert_eq!(labelled.labels, HashSet::from(["Admin".to_string()]));
+}
+
+#[test]
+fn test_from_subject_errors_name_the_key() {
+    let mut subject = alice().into_subject();
+    subject.properties.remove("fullName");
+    assert_eq!(
+        Person::from_subject(&subject).unwrap_err(),
+        PropertyError::Missing {
+            key: "fullName".to_string()
+        }
+    );
+
+    let mut subject = alice().into_subject();
+    subject
+        .properties
+        .insert("age".to_string(), Value::from("thirty"));
+    assert_eq!(
+        Person::from_subject(&subject).unwrap_err().to_string(),
+        "property 'age': expected integer, found string"
+    );
+
+    let mut subject = alice().into_subject();
+    subject
+        .properties
+        .insert("address".to_string(), Value::from("Berlin"));
+    assert_eq!(
+        Person::from_subject(&subject).unwrap_err().to_string(),
+        "property 'address': expected map, found string"
+    );
+}
+
+#[test]
+fn test_nested_errors_name_the_key_path() {
+    let mut subject = alice().into_subject();
+    if let Some(Value::VMap(address)) = subject.properties.get_mut("address") {
+        address.remove("city");
+    }
+    assert_eq!(
+        Person::from_subject(&subject).unwrap_err(),
+        PropertyError::Missing {
+            key: "address.city".to_string()
+        }
+    );
+
+    let mut subject = alice().into_subject();
+    if let Some(Value::VMap(address)) = subject.properties.get_mut("address") {
+        address.insert("zip".to_string(), Value::VInteger(10115));
+    }
+    assert_eq!(
+        Person::from_subject(&subject).unwrap_err().to_string(),
+        "property 'address.zip': expected string, found integer"
+    );
+}
+
+#[test]
+fn test_struct_without_identity_has_empty_identity() {
+    let subject = Address {
+        city: "Oslo".to_string(),
+        zip: None,
+    }
+    .into_subject();
+    assert_eq!(subject.identity.0, "");
+    assert!(subject.labels.is_empty());
+}
+
+#[test]
+fn test_generic_struct() {
+    // The derives add no bounds of their own; the struct declares what its fields need
+    #[derive(IntoSubject, FromSubject, Debug, PartialEq)]
+    struct Measurement<T>
+    where
+        T: Into<Value> + for<'a> TryFrom<&'a Value, Error = PropertyError>,
+    {
+        value: T,
+    }
+
+    let subject = Measurement { value: 2.5 }.into_subject();
+    assert_eq!(subject.properties["value"], Value::VDecimal(2.5));
+    assert_eq!(
+        Measurement::<f64>::from_subject(&subject),
+        Ok(Measurement { value: 2.5 })
+    );
+}
+
+#[derive(IntoRelationship)]
+#[gram(label = "KNOWS")]
+struct Knows {
+    #[gram(identity)]
+    id: String,
+    #[gram(source)]
+    from: Person,
+    #[gram(target)]
+    to: Subject,
+    since: i64,
+}
+
+#[test]
+fn test_relationship_produces_two_element_pattern() {
+    let bob = Subject {
+        identity: Symbol("bob".to_string()),
+        labels: HashSet::new(),
+        properties: HashMap::new(),
+    };
+    let knows = Knows {
+        id: "k1".to_string(),
+        from: alice(),
+        to: bob.clone(),
+        since: 2015,
+    }
+    .into_relationship();
+
+    assert_eq!(knows.value.identity.0, "k1");
+    assert!(knows.value.labels.contains("KNOWS"));
+    assert_eq!(knows.value.properties.len(), 1);
+    assert_eq!(knows.value.properties["since"], Value::VInteger(2015));
+
+    assert_eq!(knows.elements.len(), 2);
+    assert!(knows.elements.iter().all(|e| e.elements.is_empty()));
+    assert_eq!(knows.elements[0].value, alice().into_subject());
+    assert_eq!(knows.elements[1].value, bob);
+}
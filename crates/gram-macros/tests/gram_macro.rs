@@ -0,0 +1,143 @@
+//! Tests for the gram! and gram_patterns! macros
+//!
+//! Each expansion is compared against `gram_codec::parse_gram` on the same
+//! notation, so the macros stay in step with the runtime parser.
+
+use gram_macros::{gram, gram_patterns};
+use pattern_core::{Pattern, RangeValue, Subject, Value};
+
+fn parsed(source: &str) -> Vec<Pattern<Subject>> {
+    gram_codec::parse_gram(source).expect("valid gram")
+}
+
+#[test]
+fn test_node_matches_parser() {
+    let pattern = gram!("(a:Person:Admin {name: 'Alice', age: 30})");
+    assert_eq!(
+        vec![pattern],
+        parsed("(a:Person:Admin {name: 'Alice', age: 30})")
+    );
+}
+
+#[test]
+fn test_relationship_matches_parser() {
+    let pattern = gram!("(a:Person {name: 'Alice'})-[:KNOWS]->(b)");
+    assert_eq!(
+        vec![pattern],
+        parsed("(a:Person {name: 'Alice'})-[:KNOWS]->(b)")
+    );
+}
+
+#[test]
+fn test_subject_pattern_matches_parser() {
+    let pattern = gram!("[team:Team {size: 2} | alice, bob]");
+    assert_eq!(vec![pattern], parsed("[team:Team {size: 2} | alice, bob]"));
+}
+
+#[test]
+fn test_value_kinds_match_parser() {
+    let source = "(v {s: \"x\", i: -7, d: 2.5, b: true, sym: foo, t: url`http://x`, \
+                  arr: [1, 'two'], r: 1..10, m: 5kg})";
+    let pattern = gram!(
+        "(v {s: \"x\", i: -7, d: 2.5, b: true, sym: foo, t: url`http://x`, \
+         arr: [1, 'two'], r: 1..10, m: 5kg})"
+    );
+    assert_eq!(vec![pattern], parsed(source));
+}
+
+#[test]
+fn test_multiline_notation_with_comments() {
+    let doc = gram_patterns!(
+        r#"
+        // people
+        (alice:Person)
+        (bob:Person)
+        (alice)-[:KNOWS]->(bob)
+        "#
+    );
+    assert_eq!(doc.len(), 3);
+    assert_eq!(doc[2].elements[0].value.identity.0, "alice");
+}
+
+#[test]
+fn test_empty_gram_patterns() {
+    let doc = gram_patterns!("");
+    assert!(doc.is_empty());
+}
+
+#[test]
+fn test_named_interpolation() {
+    let base = 40;
+    let pattern = gram!(
+        "(a:Person {name: $name, age: $age})",
+        name = Value::VString(format!("User {}", base)),
+        age = Value::VInteger(base + 2),
+    );
+    let props = &pattern.value.properties;
+    assert_eq!(props["name"], Value::VString("User 40".to_string()));
+    assert_eq!(props["age"], Value::VInteger(42));
+}
+
+#[test]
+fn test_implicit_interpolation_from_scope() {
+    let score = Value::VDecimal(0.5);
+    let pattern = gram!("(a {score: $score, again: $score})");
+    assert_eq!(pattern.value.properties["score"], Value::VDecimal(0.5));
+    assert_eq!(pattern.value.properties["again"], Value::VDecimal(0.5));
+}
+
+#[test]
+fn test_interpolation_inside_arrays_and_relationships() {
+    let first = Value::VInteger(1);
+    let pattern = gram!("(a)-[:R {weights: [$first, 2]}]->(b)");
+    assert_eq!(
+        pattern.value.properties["weights"],
+        Value::VArray(vec![Value::VInteger(1), Value::VInteger(2)])
+    );
+}
+
+#[test]
+fn test_interpolation_converts_rust_values() {
+    let tags = vec!["a", "b"];
+    let pattern = gram!(
+        "(n {name: $name, age: $age, tags: $tags})",
+        name = "Alice",
+        age = 30
+    );
+    assert_eq!(
+        pattern.value.get::<String>("name"),
+        Some("Alice".to_string())
+    );
+    assert_eq!(pattern.value.get::<u8>("age"), Some(30));
+    assert_eq!(
+        pattern.value.get::<Vec<String>>("tags").map(|t| t.len()),
+        Some(2)
+    );
+}
+
+#[test]
+fn test_dollar_inside_strings_is_literal() {
+    let pattern = gram!("(a {price: '$5'})");
+    assert_eq!(
+        pattern.value.properties["price"],
+        Value::VString("$5".to_string())
+    );
+}
+
+#[test]
+fn test_range_bounds() {
+    let pattern = gram!("(a {r: 3..})");
+    assert_eq!(
+        pattern.value.properties["r"],
+        Value::VRange(RangeValue {
+            lower: Some(3.0),
+            upper: None
+        })
+    );
+}
+
+#[test]
+fn compile_errors() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
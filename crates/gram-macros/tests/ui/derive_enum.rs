@@ -0,0 +1,8 @@
+use gram_macros::IntoSubject;
+
+#[derive(IntoSubject)]
+enum Shape {
+    Circle,
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use gram_macros::IntoRelationship;
+use pattern_core::Subject;
+
+#[derive(IntoRelationship)]
+struct Knows {
+    #[gram(source)]
+    from: Subject,
+    to: Subject,
+}
+
+fn main() {}
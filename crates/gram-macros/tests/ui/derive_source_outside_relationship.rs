@@ -0,0 +1,9 @@
+use gram_macros::IntoSubject;
+
+#[derive(IntoSubject)]
+struct Person {
+    #[gram(source)]
+    friend: String,
+}
+
+fn main() {}
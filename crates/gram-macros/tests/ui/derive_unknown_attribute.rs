@@ -0,0 +1,9 @@
+use gram_macros::IntoSubject;
+
+#[derive(IntoSubject)]
+struct Person {
+    #[gram(id)]
+    id: String,
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use gram_macros::gram;
+
+fn main() {
+    let id = 1;
+    let _ = gram!("($id)");
+}
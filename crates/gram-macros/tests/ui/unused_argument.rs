@@ -0,0 +1,5 @@
+use gram_macros::gram;
+
+fn main() {
+    let _ = gram!("(a {x: $x})", x = 1, y = 2);
+}
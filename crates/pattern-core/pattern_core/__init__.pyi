@@ -716,6 +716,20 @@ class PatternSubject:
         """Find first pattern matching predicate."""
         ...
     
+    def filter_expr(self, expr: str) -> List['PatternSubject']:
+        """Filter subpatterns whose Subject satisfies an expression such as ":Person AND age >= 30".
+
+        Raises ValueError if the expression is malformed or compares mismatched types.
+        """
+        ...
+    
+    def find_first_expr(self, expr: str) -> Optional['PatternSubject']:
+        """Find the first subpattern whose Subject satisfies an expression.
+
+        Raises ValueError if the expression is malformed or compares mismatched types.
+        """
+        ...
+    
     def matches(self, other: 'PatternSubject') -> bool:
         """Check if patterns have identical structure."""
         ...
@@ -0,0 +1,655 @@
+//! Typed conversions for `Value` and typed property access on `Subject`
+//!
+//! This module provides:
+//!
+//! - `From` impls turning Rust values into [`Value`] (integers, floats, `bool`,
+//!   strings, `Vec<T>`, `HashMap<String, T>`, `Option<T>`, [`RangeValue`])
+//! - `TryFrom<Value>` and `TryFrom<&Value>` impls going the other way, failing with
+//!   a [`PropertyError`] that names the expected and actual types
+//! - [`Subject`] helpers ([`get`](Subject::get), [`get_or`](Subject::get_or),
+//!   [`try_get`](Subject::try_get), [`require`](Subject::require)) that combine the
+//!   property lookup with the conversion
+//! - [`IntoSubject`], [`FromSubject`] and [`IntoRelationship`], the traits behind
+//!   the `gram-macros` derives for mapping Rust structs to subjects and patterns
+//!
+//! # Conversion Rules
+//!
+//! - Integer types convert from `VInteger`, failing with
+//!   [`PropertyError::OutOfRange`] if the value does not fit.
+//! - `f64`/`f32` convert from `VDecimal` and also from `VInteger` (widening).
+//! - `String` converts from `VString` and `VSymbol`; `&str` borrows from either.
+//! - `Vec<T>` converts from `VArray`, `HashMap<String, T>` from `VMap`; the first
+//!   element that fails to convert determines the error.
+//! - `Option<T>` has no null to map to: `None` converts to an empty `VArray` and
+//!   `Some(v)` to the value of `v`. Going back, an empty array is `None` and any
+//!   other value converts with `T`'s rules, so `Some` of an empty `Vec` reads
+//!   back as `None`. A missing property is best read with
+//!   [`try_get`](Subject::try_get), which returns `Ok(None)`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use pattern_core::{PropertyError, Subject, Symbol, Value};
+//! use std::collections::{HashMap, HashSet};
+//!
+//! let subject = Subject {
+//!     identity: Symbol("alice".to_string()),
+//!     labels: HashSet::new(),
+//!     properties: HashMap::from([
+//!         ("name".to_string(), Value::from("Alice")),
+//!         ("age".to_string(), Value::from(30)),
+//!         ("tags".to_string(), Value::from(vec!["admin", "ops"])),
+//!     ]),
+//! };
+//!
+//! let name: &str = subject.require("name").unwrap();
+//! let age: u8 = subject.require("age").unwrap();
+//! let tags: Vec<String> = subject.get("tags").unwrap();
+//! assert_eq!((name, age, tags.len()), ("Alice", 30, 2));
+//!
+//! assert_eq!(subject.get_or("score", 0.0), 0.0);
+//!
+//! let err = subject.require::<bool>("name").unwrap_err();
+//! assert_eq!(err.to_string(), "property 'name': expected boolean, found string");
+//! ```
+
+use crate::pattern::Pattern;
+use crate::subject::{RangeValue, Subject, Symbol, Value};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+// ============================================================================
+// PropertyError
+// ============================================================================
+
+/// Error returned when a property is missing or cannot be converted to the
+/// requested Rust type.
+///
+/// Type names are those returned by [`Value::type_name`]. `key` is `None` when
+/// the error comes from a bare `TryFrom` conversion rather than a `Subject`
+/// property lookup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PropertyError {
+    /// The subject has no property with this key
+    Missing {
+        /// The property key that was looked up
+        key: String,
+    },
+    /// The value has a different type than requested
+    TypeMismatch {
+        /// The property key, if known
+        key: Option<String>,
+        /// Name of the expected value type
+        expected: &'static str,
+        /// Name of the actual value type
+        actual: &'static str,
+    },
+    /// The value has the right type but does not fit the requested Rust type
+    OutOfRange {
+        /// The property key, if known
+        key: Option<String>,
+        /// Name of the requested Rust type (e.g. `"u8"`)
+        target: &'static str,
+        /// The value, rendered for the message
+        value: String,
+    },
+}
+
+impl PropertyError {
+    fn mismatch(expected: &'static str, actual: &Value) -> Self {
+        PropertyError::TypeMismatch {
+            key: None,
+            expected,
+            actual: actual.type_name(),
+        }
+    }
+
+    /// Returns the property key this error refers to, if known.
+    pub fn key(&self) -> Option<&str> {
+        match self {
+            PropertyError::Missing { key } => Some(key),
+            PropertyError::TypeMismatch { key, .. } | PropertyError::OutOfRange { key, .. } => {
+                key.as_deref()
+            }
+        }
+    }
+
+    /// Attaches a property key to an error produced by a bare conversion.
+    ///
+    /// Errors that already carry a key keep it.
+    pub fn with_key(self, property: &str) -> Self {
+        match self {
+            PropertyError::TypeMismatch {
+                key: None,
+                expected,
+                actual,
+            } => PropertyError::TypeMismatch {
+                key: Some(property.to_string()),
+                expected,
+                actual,
+            },
+            PropertyError::OutOfRange {
+                key: None,
+                target,
+                value,
+            } => PropertyError::OutOfRange {
+                key: Some(property.to_string()),
+                target,
+                value,
+            },
+            other => other,
+        }
+    }
+
+    /// Prefixes the key with `property`, for an error raised inside the nested
+    /// subject stored under `property`.
+    ///
+    /// `city` becomes `address.city`; an error without a key gets `address`.
+    pub fn within(self, property: &str) -> Self {
+        let path = |key: &str| format!("{}.{}", property, key);
+        match self {
+            PropertyError::Missing { key } => PropertyError::Missing { key: path(&key) },
+            PropertyError::TypeMismatch {
+                key,
+                expected,
+                actual,
+            } => PropertyError::TypeMismatch {
+                key: Some(key.map_or_else(|| property.to_string(), |k| path(&k))),
+                expected,
+                actual,
+            },
+            PropertyError::OutOfRange { key, target, value } => PropertyError::OutOfRange {
+                key: Some(key.map_or_else(|| property.to_string(), |k| path(&k))),
+                target,
+                value,
+            },
+        }
+    }
+}
+
+impl fmt::Display for PropertyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let prefix = |f: &mut fmt::Formatter<'_>, key: &Option<String>| match key {
+            Some(key) => write!(f, "property '{}': ", key),
+            None => Ok(()),
+        };
+        match self {
+            PropertyError::Missing { key } => write!(f, "missing property '{}'", key),
+            PropertyError::TypeMismatch {
+                key,
+                expected,
+                actual,
+            } => {
+                prefix(f, key)?;
+                write!(f, "expected {}, found {}", expected, actual)
+            }
+            PropertyError::OutOfRange { key, target, value } => {
+                prefix(f, key)?;
+                write!(f, "value {} is out of range for {}", value, target)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PropertyError {}
+
+// ============================================================================
+// Rust -> Value
+// ============================================================================
+
+macro_rules! from_integer {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Value {
+                fn from(value: $t) -> Self {
+                    Value::VInteger(i64::from(value))
+                }
+            }
+        )*
+    };
+}
+
+from_integer!(i8, i16, i32, i64, u8, u16, u32);
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::VDecimal(value)
+    }
+}
+
+impl From<f32> for Value {
+    fn from(value: f32) -> Self {
+        Value::VDecimal(f64::from(value))
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::VBoolean(value)
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::VString(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::VString(value.to_string())
+    }
+}
+
+impl From<RangeValue> for Value {
+    fn from(value: RangeValue) -> Self {
+        Value::VRange(value)
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(values: Vec<T>) -> Self {
+        Value::VArray(values.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<T: Into<Value>> From<HashMap<String, T>> for Value {
+    fn from(map: HashMap<String, T>) -> Self {
+        Value::VMap(map.into_iter().map(|(k, v)| (k, v.into())).collect())
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(value) => value.into(),
+            None => Value::VArray(Vec::new()),
+        }
+    }
+}
+
+// ============================================================================
+// Value -> Rust
+// ============================================================================
+
+macro_rules! try_into_integer {
+    ($($t:ident),*) => {
+        $(
+            impl TryFrom<&Value> for $t {
+                type Error = PropertyError;
+
+                fn try_from(value: &Value) -> Result<Self, Self::Error> {
+                    match value {
+                        Value::VInteger(i) => $t::try_from(*i).map_err(|_| PropertyError::OutOfRange {
+                            key: None,
+                            target: stringify!($t),
+                            value: i.to_string(),
+                        }),
+                        other => Err(PropertyError::mismatch("integer", other)),
+                    }
+                }
+            }
+
+            impl TryFrom<Value> for $t {
+                type Error = PropertyError;
+
+                fn try_from(value: Value) -> Result<Self, Self::Error> {
+                    $t::try_from(&value)
+                }
+            }
+        )*
+    };
+}
+
+try_into_integer!(i8, i16, i32, i64, u8, u16, u32, u64, usize);
+
+impl TryFrom<&Value> for f64 {
+    type Error = PropertyError;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::VDecimal(d) => Ok(*d),
+            Value::VInteger(i) => Ok(*i as f64),
+            other => Err(PropertyError::mismatch("decimal", other)),
+        }
+    }
+}
+
+impl TryFrom<&Value> for f32 {
+    type Error = PropertyError;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        f64::try_from(value).map(|d| d as f32)
+    }
+}
+
+impl TryFrom<&Value> for bool {
+    type Error = PropertyError;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::VBoolean(b) => Ok(*b),
+            other => Err(PropertyError::mismatch("boolean", other)),
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a Value> for &'a str {
+    type Error = PropertyError;
+
+    fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::VString(s) | Value::VSymbol(s) => Ok(s),
+            other => Err(PropertyError::mismatch("string", other)),
+        }
+    }
+}
+
+impl TryFrom<&Value> for String {
+    type Error = PropertyError;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        <&str>::try_from(value).map(str::to_string)
+    }
+}
+
+impl TryFrom<&Value> for RangeValue {
+    type Error = PropertyError;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::VRange(r) => Ok(r.clone()),
+            other => Err(PropertyError::mismatch("range", other)),
+        }
+    }
+}
+
+impl<'a, T> TryFrom<&'a Value> for Vec<T>
+where
+    T: TryFrom<&'a Value, Error = PropertyError>,
+{
+    type Error = PropertyError;
+
+    fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::VArray(items) => items.iter().map(T::try_from).collect(),
+            other => Err(PropertyError::mismatch("array", other)),
+        }
+    }
+}
+
+impl<'a, T> TryFrom<&'a Value> for HashMap<String, T>
+where
+    T: TryFrom<&'a Value, Error = PropertyError>,
+{
+    type Error = PropertyError;
+
+    fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::VMap(map) => map
+                .iter()
+                .map(|(k, v)| Ok((k.clone(), T::try_from(v)?)))
+                .collect(),
+            other => Err(PropertyError::mismatch("map", other)),
+        }
+    }
+}
+
+impl<'a, T> TryFrom<&'a Value> for Option<T>
+where
+    T: TryFrom<&'a Value, Error = PropertyError>,
+{
+    type Error = PropertyError;
+
+    fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::VArray(items) if items.is_empty() => Ok(None),
+            other => T::try_from(other).map(Some),
+        }
+    }
+}
+
+macro_rules! try_from_owned_via_ref {
+    ($($t:ty),*) => {
+        $(
+            impl TryFrom<Value> for $t {
+                type Error = PropertyError;
+
+                fn try_from(value: Value) -> Result<Self, Self::Error> {
+                    <$t>::try_from(&value)
+                }
+            }
+        )*
+    };
+}
+
+try_from_owned_via_ref!(f64, f32, bool, RangeValue);
+
+impl TryFrom<Value> for String {
+    type Error = PropertyError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::VString(s) | Value::VSymbol(s) => Ok(s),
+            other => Err(PropertyError::mismatch("string", &other)),
+        }
+    }
+}
+
+impl<T> TryFrom<Value> for Vec<T>
+where
+    T: TryFrom<Value, Error = PropertyError>,
+{
+    type Error = PropertyError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::VArray(items) => items.into_iter().map(T::try_from).collect(),
+            other => Err(PropertyError::mismatch("array", &other)),
+        }
+    }
+}
+
+impl<T> TryFrom<Value> for HashMap<String, T>
+where
+    T: TryFrom<Value, Error = PropertyError>,
+{
+    type Error = PropertyError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::VMap(map) => map
+                .into_iter()
+                .map(|(k, v)| Ok((k, T::try_from(v)?)))
+                .collect(),
+            other => Err(PropertyError::mismatch("map", &other)),
+        }
+    }
+}
+
+impl<T> TryFrom<Value> for Option<T>
+where
+    T: TryFrom<Value, Error = PropertyError>,
+{
+    type Error = PropertyError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::VArray(items) if items.is_empty() => Ok(None),
+            other => T::try_from(other).map(Some),
+        }
+    }
+}
+
+// ============================================================================
+// Subject Property Accessors
+// ============================================================================
+
+impl Subject {
+    /// Returns property `key` converted to `T`, or `None` if it is missing or has
+    /// the wrong type.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use pattern_core::{Subject, Symbol, Value};
+    /// use std::collections::{HashMap, HashSet};
+    ///
+    /// let subject = Subject {
+    ///     identity: Symbol("n".to_string()),
+    ///     labels: HashSet::new(),
+    ///     properties: HashMap::from([("age".to_string(), Value::VInteger(30))]),
+    /// };
+    /// assert_eq!(subject.get::<i64>("age"), Some(30));
+    /// assert_eq!(subject.get::<String>("age"), None);
+    /// assert_eq!(subject.get::<i64>("missing"), None);
+    /// ```
+    pub fn get<'a, T>(&'a self, key: &str) -> Option<T>
+    where
+        T: TryFrom<&'a Value, Error = PropertyError>,
+    {
+        self.try_get(key).ok().flatten()
+    }
+
+    /// Returns property `key` converted to `T`, or `default` if it is missing or
+    /// has the wrong type.
+    pub fn get_or<'a, T>(&'a self, key: &str, default: T) -> T
+    where
+        T: TryFrom<&'a Value, Error = PropertyError>,
+    {
+        self.get(key).unwrap_or(default)
+    }
+
+    /// Returns property `key` converted to `T`, `Ok(None)` if it is missing, or an
+    /// error if it is present with the wrong type.
+    ///
+    /// Use this for optional properties that must still be well-typed when present.
+    pub fn try_get<'a, T>(&'a self, key: &str) -> Result<Option<T>, PropertyError>
+    where
+        T: TryFrom<&'a Value, Error = PropertyError>,
+    {
+        self.properties
+            .get(key)
+            .map(|value| T::try_from(value).map_err(|e| e.with_key(key)))
+            .transpose()
+    }
+
+    /// Returns property `key` converted to `T`.
+    ///
+    /// # Errors
+    ///
+    /// - [`PropertyError::Missing`] if the subject has no property `key`
+    /// - [`PropertyError::TypeMismatch`] or [`PropertyError::OutOfRange`] (with the
+    ///   key filled in) if the value cannot be converted
+    pub fn require<'a, T>(&'a self, key: &str) -> Result<T, PropertyError>
+    where
+        T: TryFrom<&'a Value, Error = PropertyError>,
+    {
+        self.try_get(key)?.ok_or_else(|| PropertyError::Missing {
+            key: key.to_string(),
+        })
+    }
+}
+
+impl Subject {
+    /// Returns property `key`, a `VMap`, converted to `T` with [`FromSubject`];
+    /// `Ok(None)` if it is missing.
+    ///
+    /// The map's entries become the properties of a subject with an empty
+    /// identity and no labels, which is then passed to [`FromSubject::from_subject`].
+    /// This is how nested structs are read back by the `FromSubject` derive.
+    /// Errors from the nested conversion name the full key path, e.g.
+    /// `address.city` (see [`PropertyError::within`]).
+    pub fn try_get_nested<T: FromSubject>(&self, key: &str) -> Result<Option<T>, PropertyError> {
+        match self.properties.get(key) {
+            None => Ok(None),
+            Some(Value::VMap(map)) => {
+                let nested = Subject {
+                    identity: Symbol(String::new()),
+                    labels: HashSet::new(),
+                    properties: map.clone(),
+                };
+                T::from_subject(&nested)
+                    .map(Some)
+                    .map_err(|e| e.within(key))
+            }
+            Some(other) => Err(PropertyError::mismatch("map", other).with_key(key)),
+        }
+    }
+
+    /// Like [`try_get_nested`](Subject::try_get_nested), but a missing property is
+    /// a [`PropertyError::Missing`].
+    pub fn require_nested<T: FromSubject>(&self, key: &str) -> Result<T, PropertyError> {
+        self.try_get_nested(key)?
+            .ok_or_else(|| PropertyError::Missing {
+                key: key.to_string(),
+            })
+    }
+}
+
+// ============================================================================
+// Struct Mapping Traits
+// ============================================================================
+
+/// Types that can be converted into a [`Subject`].
+///
+/// Usually derived with `#[derive(IntoSubject)]` from the `gram-macros` crate,
+/// which maps an identity field, static and dynamic labels, and the remaining
+/// fields to properties.
+///
+/// # Examples
+///
+/// ```rust
+/// use pattern_core::{IntoSubject, Subject, Symbol, Value};
+/// use std::collections::{HashMap, HashSet};
+///
+/// struct Tag(String);
+///
+/// impl IntoSubject for Tag {
+///     fn into_subject(self) -> Subject {
+///         Subject {
+///             identity: Symbol(self.0),
+///             labels: HashSet::from(["Tag".to_string()]),
+///             properties: HashMap::new(),
+///         }
+///     }
+/// }
+///
+/// assert_eq!(Tag("rust".to_string()).into_subject().identity.0, "rust");
+/// ```
+pub trait IntoSubject {
+    /// Converts `self` into a subject.
+    fn into_subject(self) -> Subject;
+}
+
+/// Types that can be read back from a [`Subject`].
+///
+/// Usually derived with `#[derive(FromSubject)]` from the `gram-macros` crate.
+/// Errors name the offending property, as with [`Subject::require`].
+pub trait FromSubject: Sized {
+    /// Builds a value from the subject's identity, labels and properties.
+    fn from_subject(subject: &Subject) -> Result<Self, PropertyError>;
+}
+
+/// Types that describe a relationship between two subjects.
+///
+/// Usually derived with `#[derive(IntoRelationship)]` from the `gram-macros`
+/// crate. The result is a two-element pattern, the same shape the parser produces
+/// for `(source)-[relationship]->(target)`.
+pub trait IntoRelationship {
+    /// Converts `self` into a pattern whose value is the relationship subject and
+    /// whose elements are the source and target subjects, as atomic patterns.
+    fn into_relationship(self) -> Pattern<Subject>;
+}
+
+impl IntoSubject for Subject {
+    fn into_subject(self) -> Subject {
+        self
+    }
+}
+
+impl FromSubject for Subject {
+    fn from_subject(subject: &Subject) -> Result<Self, PropertyError> {
+        Ok(subject.clone())
+    }
+}
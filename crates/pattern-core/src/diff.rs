@@ -0,0 +1,936 @@
+//! Semantic, identity-aware diff between two `Pattern<Subject>` documents
+//!
+//! Text diffs of gram files are noisy: the labels and properties of a
+//! [`Subject`] are unordered, so a serializer may write them differently each
+//! time. [`diff`] compares two documents as graphs instead, keyed by identity,
+//! and reports:
+//!
+//! - nodes and relationships that were added or removed
+//! - label changes and per-property old/new values of subjects in both versions
+//! - elements that moved between container patterns
+//!
+//! # Graph View
+//!
+//! Each document is read the way the gram query engine reads it. A pattern with
+//! exactly two elements is a relationship from the endpoint of its first element
+//! to the endpoint of its second (so paths such as `(a)-->(b)-->(c)` yield two
+//! relationships). Every other pattern with an identity is a node, and a node
+//! with elements is a *container* of their identities. Subjects that occur
+//! several times with the same identity are combined as in
+//! `Combinable for Subject`.
+//!
+//! Relationships with an identity are matched by identity; a changed endpoint is
+//! reported as a removal and an addition. Anonymous relationships are matched by
+//! their endpoints, labels and properties, so they can only be added or removed.
+//! Anonymous nodes, and elements of anonymous containers, are not tracked.
+//!
+//! # Reports and Changesets
+//!
+//! A [`Diff`] displays as a line-oriented human report. [`Diff::changeset`]
+//! renders it as a document of change patterns, which gram-codec can write as
+//! gram text:
+//!
+//! | Change | Pattern |
+//! |--------|---------|
+//! | added node or relationship | `[:Added \| (bob:Person)]`, `[:Added \| (a)-[:KNOWS]->(b)]` |
+//! | removed node or relationship | `[:Removed \| (carol)]`, `[:Removed \| (a)-[:KNOWS]->(c)]` |
+//! | labels and properties | `(alice:Intern {age: 34})-[:Changed]->(alice:Employee {age: 35})` |
+//! | moved element | `[:Moved {from: ["t1"], to: ["t2"]} \| (alice)]` |
+//!
+//! The two ends of `Changed` hold the removed labels and old values, then the
+//! added labels and new values; a key missing on one side was added or removed.
+//! [`Diff::from_changeset`] reads such a document back, and [`Diff::apply`]
+//! applies a diff to the old version.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use pattern_core::{diff, Pattern, Subject, Symbol, Value};
+//! use std::collections::{HashMap, HashSet};
+//!
+//! let person = |id: &str, age: i64| {
+//!     Pattern::point(Subject {
+//!         identity: Symbol(id.to_string()),
+//!         labels: HashSet::from(["Person".to_string()]),
+//!         properties: HashMap::from([("age".to_string(), Value::from(age))]),
+//!     })
+//! };
+//!
+//! let old = vec![person("alice", 34), person("bob", 27)];
+//! let new = vec![person("alice", 35), person("carol", 45)];
+//!
+//! let changes = diff(&old, &new);
+//! assert_eq!(changes.added_nodes.len(), 1);
+//! assert_eq!(changes.removed_nodes.len(), 1);
+//! assert_eq!(changes.changed[0].properties[0].new, Some(Value::from(35)));
+//! assert_eq!(changes.to_string(), "+ (carol:Person {age: 45})\n- (bob:Person {age: 27})\n~ alice\n    age: 34 -> 35\n");
+//!
+//! // The changeset turns the old version into the new one
+//! let patched = changes.apply(&old);
+//! assert!(diff(&patched, &new).is_empty());
+//! ```
+
+use crate::merge::is_reference;
+use crate::pattern::Pattern;
+use crate::subject::{Subject, Symbol, Value};
+use crate::Combinable;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+// ============================================================================
+// Changes
+// ============================================================================
+
+/// A relationship: its subject and the identities of its endpoints.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Relationship {
+    /// The relationship subject (merged over all occurrences if it has an identity)
+    pub subject: Subject,
+    /// Identity of the source endpoint
+    pub source: Symbol,
+    /// Identity of the target endpoint
+    pub target: Symbol,
+}
+
+/// The old and new value of one property. `None` means the property is absent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyChange {
+    /// The property key
+    pub key: String,
+    /// Value in the old version
+    pub old: Option<Value>,
+    /// Value in the new version
+    pub new: Option<Value>,
+}
+
+/// Label and property changes of a subject present in both versions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubjectChange {
+    /// The subject's identity
+    pub identity: Symbol,
+    /// Labels only in the new version, sorted
+    pub labels_added: Vec<String>,
+    /// Labels only in the old version, sorted
+    pub labels_removed: Vec<String>,
+    /// Properties whose value differs, sorted by key
+    pub properties: Vec<PropertyChange>,
+}
+
+/// An element whose set of containers changed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Move {
+    /// Identity of the element
+    pub identity: Symbol,
+    /// Containers holding the element in the old version
+    pub from: Vec<Symbol>,
+    /// Containers holding the element in the new version
+    pub to: Vec<Symbol>,
+}
+
+/// Identity-aware differences between two documents.
+///
+/// Added entries and changes are listed in the order they first occur in the new
+/// version; removed entries in the order they first occur in the old version.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Diff {
+    /// Nodes only in the new version
+    pub added_nodes: Vec<Subject>,
+    /// Nodes only in the old version
+    pub removed_nodes: Vec<Subject>,
+    /// Relationships only in the new version
+    pub added_relationships: Vec<Relationship>,
+    /// Relationships only in the old version
+    pub removed_relationships: Vec<Relationship>,
+    /// Label and property changes of subjects in both versions
+    pub changed: Vec<SubjectChange>,
+    /// Elements of the new version whose containers changed
+    pub moved: Vec<Move>,
+}
+
+/// Error reading a changeset document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChangesetError {
+    /// A top-level pattern is not labelled `Added`, `Removed`, `Changed` or `Moved`
+    UnknownChange {
+        /// Index of the top-level pattern
+        index: usize,
+    },
+    /// A change pattern does not have the expected elements
+    Malformed {
+        /// Index of the top-level pattern
+        index: usize,
+        /// What is wrong with it
+        reason: String,
+    },
+}
+
+impl fmt::Display for ChangesetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChangesetError::UnknownChange { index } => write!(
+                f,
+                "change {}: expected a label Added, Removed, Changed or Moved",
+                index
+            ),
+            ChangesetError::Malformed { index, reason } => {
+                write!(f, "change {}: {}", index, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ChangesetError {}
+
+// ============================================================================
+// Computing a Diff
+// ============================================================================
+
+/// Computes the identity-aware differences from `old` to `new`.
+///
+/// See the [module documentation](self) for how documents are compared.
+pub fn diff(old: &[Pattern<Subject>], new: &[Pattern<Subject>]) -> Diff {
+    diff_snapshots(&Snapshot::build(old), &Snapshot::build(new))
+}
+
+pub(crate) fn diff_snapshots(old: &Snapshot, new: &Snapshot) -> Diff {
+    let mut result = Diff::default();
+    // Present in both versions as the same kind, with the same endpoints
+    let same = |identity: &Symbol| {
+        old.subjects.contains_key(identity)
+            && new.subjects.contains_key(identity)
+            && old.edges.get(identity) == new.edges.get(identity)
+    };
+
+    for identity in &new.order {
+        let subject = &new.subjects[identity];
+        if same(identity) {
+            if let Some(change) = compare(&old.subjects[identity], subject) {
+                result.changed.push(change);
+            }
+            continue;
+        }
+        match new.edges.get(identity) {
+            Some((source, target)) => result.added_relationships.push(Relationship {
+                subject: subject.clone(),
+                source: source.clone(),
+                target: target.clone(),
+            }),
+            None => result.added_nodes.push(subject.clone()),
+        }
+    }
+    for relationship in &new.anonymous {
+        if !old.has_anonymous(relationship) {
+            result.added_relationships.push(relationship.clone());
+        }
+    }
+
+    for identity in old.order.iter().filter(|identity| !same(identity)) {
+        let subject = &old.subjects[identity];
+        match old.edges.get(identity) {
+            Some((source, target)) => result.removed_relationships.push(Relationship {
+                subject: subject.clone(),
+                source: source.clone(),
+                target: target.clone(),
+            }),
+            None => result.removed_nodes.push(subject.clone()),
+        }
+    }
+    for relationship in &old.anonymous {
+        if !new.has_anonymous(relationship) {
+            result.removed_relationships.push(relationship.clone());
+        }
+    }
+
+    let no_containers = Vec::new();
+    for identity in &new.order {
+        let from = old.containers.get(identity).unwrap_or(&no_containers);
+        let to = new.containers.get(identity).unwrap_or(&no_containers);
+        let same = from.len() == to.len() && from.iter().all(|c| to.contains(c));
+        if !same {
+            result.moved.push(Move {
+                identity: identity.clone(),
+                from: from.clone(),
+                to: to.clone(),
+            });
+        }
+    }
+
+    result
+}
+
+/// Label and property differences between two versions of a subject.
+fn compare(old: &Subject, new: &Subject) -> Option<SubjectChange> {
+    let mut labels_added: Vec<String> = new.labels.difference(&old.labels).cloned().collect();
+    let mut labels_removed: Vec<String> = old.labels.difference(&new.labels).cloned().collect();
+    labels_added.sort();
+    labels_removed.sort();
+
+    let mut keys: Vec<&String> = old.properties.keys().chain(new.properties.keys()).collect();
+    keys.sort();
+    keys.dedup();
+    let properties: Vec<PropertyChange> = keys
+        .into_iter()
+        .filter_map(|key| {
+            let before = old.properties.get(key);
+            let after = new.properties.get(key);
+            (before != after).then(|| PropertyChange {
+                key: key.clone(),
+                old: before.cloned(),
+                new: after.cloned(),
+            })
+        })
+        .collect();
+
+    if labels_added.is_empty() && labels_removed.is_empty() && properties.is_empty() {
+        return None;
+    }
+    Some(SubjectChange {
+        identity: new.identity.clone(),
+        labels_added,
+        labels_removed,
+        properties,
+    })
+}
+
+/// The graph view of one document.
+#[derive(Default)]
+pub(crate) struct Snapshot {
+    /// Identities in order of first occurrence
+    pub(crate) order: Vec<Symbol>,
+    /// Merged subject of every identity
+    pub(crate) subjects: HashMap<Symbol, Subject>,
+    /// Endpoints of relationships with an identity (first occurrence wins)
+    pub(crate) edges: HashMap<Symbol, (Symbol, Symbol)>,
+    /// Anonymous relationships in order of first occurrence, without duplicates
+    pub(crate) anonymous: Vec<Relationship>,
+    /// The same relationships, for lookups
+    anonymous_set: HashSet<Relationship>,
+    /// Containers of each identity, in order of first occurrence
+    pub(crate) containers: HashMap<Symbol, Vec<Symbol>>,
+}
+
+impl Snapshot {
+    pub(crate) fn build(patterns: &[Pattern<Subject>]) -> Self {
+        let mut snapshot = Snapshot::default();
+        for pattern in patterns {
+            snapshot.add(pattern);
+        }
+        snapshot
+    }
+
+    /// Adds a pattern and returns the identity of its endpoint.
+    fn add(&mut self, pattern: &Pattern<Subject>) -> Symbol {
+        let subject = &pattern.value;
+        self.add_subject(subject);
+        if pattern.elements.len() == 2 {
+            let source = self.add(&pattern.elements[0]);
+            let target = self.add(&pattern.elements[1]);
+            if subject.identity.0.is_empty() {
+                let relationship = Relationship {
+                    subject: subject.clone(),
+                    source,
+                    target: target.clone(),
+                };
+                self.add_anonymous(relationship);
+            } else {
+                self.edges
+                    .entry(subject.identity.clone())
+                    .or_insert((source, target.clone()));
+            }
+            return target;
+        }
+        for element in &pattern.elements {
+            self.add(element);
+            let member = &element.value.identity;
+            if subject.identity.0.is_empty() || member.0.is_empty() {
+                continue;
+            }
+            let containers = self.containers.entry(member.clone()).or_default();
+            if !containers.contains(&subject.identity) {
+                containers.push(subject.identity.clone());
+            }
+        }
+        subject.identity.clone()
+    }
+
+    /// Adds an anonymous relationship unless the snapshot already has it.
+    pub(crate) fn add_anonymous(&mut self, relationship: Relationship) {
+        if self.anonymous_set.insert(relationship.clone()) {
+            self.anonymous.push(relationship);
+        }
+    }
+
+    /// Whether the snapshot has the anonymous relationship.
+    pub(crate) fn has_anonymous(&self, relationship: &Relationship) -> bool {
+        self.anonymous_set.contains(relationship)
+    }
+
+    fn add_subject(&mut self, subject: &Subject) {
+        if subject.identity.0.is_empty() {
+            return;
+        }
+        match self.subjects.get_mut(&subject.identity) {
+            Some(merged) => *merged = merged.clone().combine(subject.clone()),
+            None => {
+                self.order.push(subject.identity.clone());
+                self.subjects
+                    .insert(subject.identity.clone(), subject.clone());
+            }
+        }
+    }
+}
+
+// ============================================================================
+// Changesets
+// ============================================================================
+
+const ADDED: &str = "Added";
+const REMOVED: &str = "Removed";
+const CHANGED: &str = "Changed";
+const MOVED: &str = "Moved";
+
+impl Diff {
+    /// Returns `true` if the two versions are equivalent.
+    pub fn is_empty(&self) -> bool {
+        self.added_nodes.is_empty()
+            && self.removed_nodes.is_empty()
+            && self.added_relationships.is_empty()
+            && self.removed_relationships.is_empty()
+            && self.changed.is_empty()
+            && self.moved.is_empty()
+    }
+
+    /// Renders the diff as a changeset document.
+    ///
+    /// Removals come first, then additions, changes and moves. See the
+    /// [module documentation](self) for the change patterns.
+    pub fn changeset(&self) -> Vec<Pattern<Subject>> {
+        let change = |label: &str, elements| Pattern::pattern(marker(label), elements);
+        let mut patterns = Vec::new();
+        for node in &self.removed_nodes {
+            patterns.push(change(REMOVED, vec![Pattern::point(node.clone())]));
+        }
+        for relationship in &self.removed_relationships {
+            patterns.push(change(REMOVED, vec![relationship.to_pattern()]));
+        }
+        for node in &self.added_nodes {
+            patterns.push(change(ADDED, vec![Pattern::point(node.clone())]));
+        }
+        for relationship in &self.added_relationships {
+            patterns.push(change(ADDED, vec![relationship.to_pattern()]));
+        }
+        for changed in &self.changed {
+            let (before, after) = changed.sides();
+            patterns.push(change(
+                CHANGED,
+                vec![Pattern::point(before), Pattern::point(after)],
+            ));
+        }
+        for moved in &self.moved {
+            let mut subject = marker(MOVED);
+            let names = |containers: &[Symbol]| {
+                Value::VArray(
+                    containers
+                        .iter()
+                        .map(|c| Value::VString(c.0.clone()))
+                        .collect(),
+                )
+            };
+            subject
+                .properties
+                .insert("from".to_string(), names(&moved.from));
+            subject
+                .properties
+                .insert("to".to_string(), names(&moved.to));
+            patterns.push(Pattern::pattern(
+                subject,
+                vec![Pattern::point(reference(&moved.identity))],
+            ));
+        }
+        patterns
+    }
+
+    /// Reads a changeset document written by [`Diff::changeset`].
+    pub fn from_changeset(changeset: &[Pattern<Subject>]) -> Result<Self, ChangesetError> {
+        let mut result = Diff::default();
+        for (index, pattern) in changeset.iter().enumerate() {
+            let malformed = |reason: &str| ChangesetError::Malformed {
+                index,
+                reason: reason.to_string(),
+            };
+            let labels = &pattern.value.labels;
+            if labels.contains(ADDED) || labels.contains(REMOVED) {
+                let [element] = pattern.elements.as_slice() else {
+                    return Err(malformed("expected one node or relationship"));
+                };
+                let added = labels.contains(ADDED);
+                match element.elements.as_slice() {
+                    [] if added => result.added_nodes.push(element.value.clone()),
+                    [] => result.removed_nodes.push(element.value.clone()),
+                    [source, target] if source.is_atomic() && target.is_atomic() => {
+                        let relationship = Relationship {
+                            subject: element.value.clone(),
+                            source: source.value.identity.clone(),
+                            target: target.value.identity.clone(),
+                        };
+                        if added {
+                            result.added_relationships.push(relationship);
+                        } else {
+                            result.removed_relationships.push(relationship);
+                        }
+                    }
+                    _ => return Err(malformed("expected one node or relationship")),
+                }
+            } else if labels.contains(CHANGED) {
+                let [before, after] = pattern.elements.as_slice() else {
+                    return Err(malformed("expected the old and new subject"));
+                };
+                let (before, after) = (&before.value, &after.value);
+                if before.identity != after.identity || before.identity.0.is_empty() {
+                    return Err(malformed("old and new subject must share an identity"));
+                }
+                let change = compare(before, after).unwrap_or_else(|| SubjectChange {
+                    identity: after.identity.clone(),
+                    labels_added: Vec::new(),
+                    labels_removed: Vec::new(),
+                    properties: Vec::new(),
+                });
+                result.changed.push(change);
+            } else if labels.contains(MOVED) {
+                let [element] = pattern.elements.as_slice() else {
+                    return Err(malformed("expected the moved element"));
+                };
+                let names = |key: &str| match pattern.value.properties.get(key) {
+                    Some(Value::VArray(items)) => items
+                        .iter()
+                        .map(|item| match item {
+                            Value::VString(s) | Value::VSymbol(s) => Ok(Symbol(s.clone())),
+                            _ => Err(malformed("containers must be strings")),
+                        })
+                        .collect::<Result<Vec<_>, _>>(),
+                    _ => Err(malformed("expected `from` and `to` arrays")),
+                };
+                result.moved.push(Move {
+                    identity: element.value.identity.clone(),
+                    from: names("from")?,
+                    to: names("to")?,
+                });
+            } else {
+                return Err(ChangesetError::UnknownChange { index });
+            }
+        }
+        Ok(result)
+    }
+
+    /// Applies the diff to the old version of a document.
+    ///
+    /// Removed relationships and nodes are taken out wherever they occur (a
+    /// relationship inside a path is replaced by its endpoint), label and
+    /// property changes are made to every occurrence, added nodes and
+    /// relationships are appended, and moved elements are taken out of their old
+    /// containers and appended to their new ones.
+    ///
+    /// The result is equivalent to the new version: `diff(&diff.apply(&old), &new)`
+    /// is empty, although patterns may be ordered differently. A container is
+    /// never given exactly two elements, which would read as a relationship; it
+    /// is written as several patterns instead, such as `[g | a] [g | b]`.
+    pub fn apply(&self, old: &[Pattern<Subject>]) -> Vec<Pattern<Subject>> {
+        let mut patterns = old.to_vec();
+
+        let removed_edges: HashSet<&Symbol> = self
+            .removed_relationships
+            .iter()
+            .filter(|r| !r.subject.identity.0.is_empty())
+            .map(|r| &r.subject.identity)
+            .collect();
+        let removed_anonymous: HashSet<&Relationship> = self
+            .removed_relationships
+            .iter()
+            .filter(|r| r.subject.identity.0.is_empty())
+            .collect();
+        let (mut orphans, mut split) = (Vec::new(), Vec::new());
+        patterns = retain(
+            patterns,
+            None,
+            &mut orphans,
+            &mut split,
+            &mut |_, pattern| {
+                if pattern.elements.len() != 2 {
+                    return false;
+                }
+                if pattern.value.identity.0.is_empty() {
+                    let relationship = Relationship {
+                        subject: pattern.value.clone(),
+                        source: endpoint(&pattern.elements[0]).clone(),
+                        target: endpoint(&pattern.elements[1]).clone(),
+                    };
+                    removed_anonymous.contains(&&relationship)
+                } else {
+                    removed_edges.contains(&pattern.value.identity)
+                }
+            },
+        );
+        patterns.append(&mut split);
+        settle(&mut patterns, orphans);
+
+        let removed_nodes: HashSet<&Symbol> =
+            self.removed_nodes.iter().map(|n| &n.identity).collect();
+        let (mut orphans, mut split) = (Vec::new(), Vec::new());
+        patterns = retain(
+            patterns,
+            None,
+            &mut orphans,
+            &mut split,
+            &mut |_, pattern| {
+                pattern.elements.len() != 2 && removed_nodes.contains(&pattern.value.identity)
+            },
+        );
+        patterns.append(&mut split);
+        settle(&mut patterns, orphans);
+
+        let changes: HashMap<&Symbol, &SubjectChange> =
+            self.changed.iter().map(|c| (&c.identity, c)).collect();
+        let mut defined: HashSet<Symbol> = HashSet::new();
+        for pattern in &patterns {
+            for subject in pattern.values() {
+                if changes.contains_key(&subject.identity) && !is_reference(subject) {
+                    defined.insert(subject.identity.clone());
+                }
+            }
+        }
+        let mut applied: HashSet<Symbol> = HashSet::new();
+        for pattern in &mut patterns {
+            update(pattern, &changes, &defined, &mut applied);
+        }
+
+        for node in &self.added_nodes {
+            patterns.push(Pattern::point(node.clone()));
+        }
+        for relationship in &self.added_relationships {
+            patterns.push(relationship.to_pattern());
+        }
+
+        // Take moved elements out of the containers they leave, then add them to
+        // the containers they join
+        let mut taken: HashMap<Symbol, Pattern<Subject>> = HashMap::new();
+        for moved in &self.moved {
+            let leaving: Vec<&Symbol> = moved
+                .from
+                .iter()
+                .filter(|c| !moved.to.contains(c))
+                .collect();
+            // The whole element is kept in `taken`, so its parts are not orphans
+            let (mut parts, mut split) = (Vec::new(), Vec::new());
+            patterns = retain(
+                patterns,
+                None,
+                &mut parts,
+                &mut split,
+                &mut |parent, pattern| {
+                    let out = parent.is_some_and(|p| leaving.contains(&p))
+                        && pattern.value.identity == moved.identity;
+                    if out {
+                        taken
+                            .entry(moved.identity.clone())
+                            .or_insert_with(|| pattern.clone());
+                    }
+                    out
+                },
+            );
+            patterns.append(&mut split);
+        }
+        for moved in &self.moved {
+            for container in moved.to.iter().filter(|c| !moved.from.contains(c)) {
+                let element = taken
+                    .remove(&moved.identity)
+                    .unwrap_or_else(|| Pattern::point(reference(&moved.identity)));
+                // A container with two elements would read as a relationship,
+                // so a second element goes into a pattern of its own
+                match patterns
+                    .iter_mut()
+                    .find_map(|pattern| find_container(pattern, container))
+                    .filter(|target| target.elements.len() != 1)
+                {
+                    Some(target) => target.elements.push(element),
+                    None => patterns.push(Pattern::pattern(reference(container), vec![element])),
+                }
+            }
+        }
+        settle(&mut patterns, taken.into_values().collect());
+
+        patterns
+    }
+}
+
+/// Reads `changeset` and applies it to `old`.
+///
+/// # Examples
+///
+/// ```rust
+/// use pattern_core::{apply_changeset, diff, Pattern, Subject, Symbol};
+/// use std::collections::{HashMap, HashSet};
+///
+/// let node = |id: &str| Pattern::point(Subject {
+///     identity: Symbol(id.to_string()),
+///     labels: HashSet::new(),
+///     properties: HashMap::new(),
+/// });
+///
+/// let old = vec![node("a")];
+/// let new = vec![node("a"), node("b")];
+/// let changeset = diff(&old, &new).changeset();
+///
+/// let patched = apply_changeset(&old, &changeset).unwrap();
+/// assert_eq!(patched, new);
+/// ```
+pub fn apply_changeset(
+    old: &[Pattern<Subject>],
+    changeset: &[Pattern<Subject>],
+) -> Result<Vec<Pattern<Subject>>, ChangesetError> {
+    Ok(Diff::from_changeset(changeset)?.apply(old))
+}
+
+impl Relationship {
+    /// The relationship as a pattern between bare references to its endpoints.
+    fn to_pattern(&self) -> Pattern<Subject> {
+        Pattern::pattern(
+            self.subject.clone(),
+            vec![
+                Pattern::point(reference(&self.source)),
+                Pattern::point(reference(&self.target)),
+            ],
+        )
+    }
+}
+
+impl SubjectChange {
+    /// The old and new side of the change, as written in a changeset.
+    fn sides(&self) -> (Subject, Subject) {
+        let mut before = reference(&self.identity);
+        let mut after = reference(&self.identity);
+        before.labels.extend(self.labels_removed.iter().cloned());
+        after.labels.extend(self.labels_added.iter().cloned());
+        for change in &self.properties {
+            if let Some(old) = &change.old {
+                before.properties.insert(change.key.clone(), old.clone());
+            }
+            if let Some(new) = &change.new {
+                after.properties.insert(change.key.clone(), new.clone());
+            }
+        }
+        (before, after)
+    }
+}
+
+fn marker(label: &str) -> Subject {
+    Subject {
+        identity: Symbol(String::new()),
+        labels: HashSet::from([label.to_string()]),
+        properties: HashMap::new(),
+    }
+}
+
+fn reference(identity: &Symbol) -> Subject {
+    Subject {
+        identity: identity.clone(),
+        labels: HashSet::new(),
+        properties: HashMap::new(),
+    }
+}
+
+fn endpoint(pattern: &Pattern<Subject>) -> &Symbol {
+    if pattern.elements.len() == 2 {
+        endpoint(&pattern.elements[1])
+    } else {
+        &pattern.value.identity
+    }
+}
+
+/// Removes every pattern for which `remove(parent, pattern)` holds, at any depth.
+///
+/// A removed element of a relationship is replaced by a reference to its
+/// endpoint, so that the relationship keeps its shape. The elements of removed
+/// patterns are collected in `orphans`, to be kept by [`settle`] if needed.
+///
+/// A container left with two elements, which would read as a relationship,
+/// keeps the first; the second is collected in `split`, in a pattern of the
+/// container's own.
+fn retain(
+    patterns: Vec<Pattern<Subject>>,
+    parent: Option<&Symbol>,
+    orphans: &mut Vec<Pattern<Subject>>,
+    split: &mut Vec<Pattern<Subject>>,
+    remove: &mut impl FnMut(Option<&Symbol>, &Pattern<Subject>) -> bool,
+) -> Vec<Pattern<Subject>> {
+    let relationship = patterns.len() == 2 && parent.is_some();
+    let mut kept = patterns
+        .into_iter()
+        .filter_map(|pattern| {
+            let removed = remove(parent, &pattern);
+            let end = endpoint(&pattern).clone();
+            let Pattern { value, elements } = pattern;
+            let elements = retain(elements, Some(&value.identity), orphans, split, remove);
+            if removed {
+                orphans.extend(elements);
+                return relationship.then(|| Pattern::point(reference(&end)));
+            }
+            Some(Pattern::pattern(value, elements))
+        })
+        .collect::<Vec<_>>();
+    if let (Some(container), false, 2) = (parent, relationship, kept.len()) {
+        let second = kept.pop().expect("two elements");
+        split.push(Pattern::pattern(reference(container), vec![second]));
+    }
+    kept
+}
+
+/// Appends the orphans that still carry information to the top level: nested
+/// patterns, definitions, and references to identities that no longer occur.
+fn settle(patterns: &mut Vec<Pattern<Subject>>, orphans: Vec<Pattern<Subject>>) {
+    for orphan in orphans {
+        let identity = &orphan.value.identity;
+        let keep = !orphan.elements.is_empty()
+            || (!identity.0.is_empty()
+                && (!is_reference(&orphan.value)
+                    || !patterns
+                        .iter()
+                        .any(|p| p.values().iter().any(|s| &s.identity == identity))));
+        if keep {
+            patterns.push(orphan);
+        }
+    }
+}
+
+/// Applies label and property changes to every occurrence under `pattern`.
+///
+/// Removed labels and changed keys are cleared everywhere; added labels and new
+/// values go to the first occurrence that defines the subject, or the first
+/// occurrence if none does.
+fn update(
+    pattern: &mut Pattern<Subject>,
+    changes: &HashMap<&Symbol, &SubjectChange>,
+    defined: &HashSet<Symbol>,
+    applied: &mut HashSet<Symbol>,
+) {
+    let subject = &mut pattern.value;
+    if let Some(change) = changes.get(&subject.identity) {
+        let definition = !is_reference(subject) || !defined.contains(&subject.identity);
+        for label in &change.labels_removed {
+            subject.labels.remove(label);
+        }
+        for property in &change.properties {
+            subject.properties.remove(&property.key);
+        }
+        if definition && applied.insert(subject.identity.clone()) {
+            subject.labels.extend(change.labels_added.iter().cloned());
+            for property in &change.properties {
+                if let Some(new) = &property.new {
+                    subject.properties.insert(property.key.clone(), new.clone());
+                }
+            }
+        }
+    }
+    for element in &mut pattern.elements {
+        update(element, changes, defined, applied);
+    }
+}
+
+/// The first pattern with identity `container`, in pre-order.
+fn find_container<'p>(
+    pattern: &'p mut Pattern<Subject>,
+    container: &Symbol,
+) -> Option<&'p mut Pattern<Subject>> {
+    if &pattern.value.identity == container {
+        return Some(pattern);
+    }
+    pattern
+        .elements
+        .iter_mut()
+        .find_map(|element| find_container(element, container))
+}
+
+// ============================================================================
+// Human Report
+// ============================================================================
+
+/// Writes one line per change:
+///
+/// ```text
+/// + (bob:Person {name: "Bob"})
+/// - (carol:Person)
+/// + (alice)-[:KNOWS]->(bob)
+/// ~ alice
+///     + :Employee
+///     - :Intern
+///     age: 34 -> 35
+///     email: (none) -> "alice@example.com"
+/// > alice: [team1] -> [team2]
+/// ```
+impl fmt::Display for Diff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for node in &self.added_nodes {
+            writeln!(f, "+ ({})", node)?;
+        }
+        for node in &self.removed_nodes {
+            writeln!(f, "- ({})", node)?;
+        }
+        for relationship in &self.added_relationships {
+            writeln!(f, "+ {}", relationship)?;
+        }
+        for relationship in &self.removed_relationships {
+            writeln!(f, "- {}", relationship)?;
+        }
+        for change in &self.changed {
+            writeln!(f, "~ {}", change.identity)?;
+            for label in &change.labels_added {
+                writeln!(f, "    + :{}", label)?;
+            }
+            for label in &change.labels_removed {
+                writeln!(f, "    - :{}", label)?;
+            }
+            for property in &change.properties {
+                writeln!(
+                    f,
+                    "    {}: {} -> {}",
+                    property.key,
+                    optional(&property.old),
+                    optional(&property.new)
+                )?;
+            }
+        }
+        for moved in &self.moved {
+            writeln!(
+                f,
+                "> {}: [{}] -> [{}]",
+                moved.identity,
+                names(&moved.from),
+                names(&moved.to)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Relationship {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if is_reference(&self.subject) && self.subject.identity.0.is_empty() {
+            write!(f, "({})-->({})", self.source, self.target)
+        } else {
+            write!(f, "({})-[{}]->({})", self.source, self.subject, self.target)
+        }
+    }
+}
+
+fn optional(value: &Option<Value>) -> String {
+    value
+        .as_ref()
+        .map_or_else(|| "(none)".to_string(), |v| v.to_string())
+}
+
+fn names(symbols: &[Symbol]) -> String {
+    symbols
+        .iter()
+        .map(|s| s.0.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,998 @@
+//! Property predicate expressions over `Subject` values.
+//!
+//! [`Pattern::filter`] and [`Pattern::find_first`] take Rust closures, which cannot be
+//! stored in configuration files or sent over the wire. An [`Expr`] is the same kind of
+//! predicate written as text:
+//!
+//! ```text
+//! :Person AND age >= 30 AND name =~ 'Al.*'
+//! ```
+//!
+//! # Syntax
+//!
+//! | Form | Meaning |
+//! |------|---------|
+//! | `:Person`, `:Person:Employee` | the subject carries every listed label |
+//! | `age`, `address.city` | a property; dots read into nested map values |
+//! | `` `first name` `` | a property key that is not a plain identifier |
+//! | `=`, `!=` (or `<>`), `<`, `<=`, `>`, `>=` | comparison |
+//! | `name =~ 'Al.*'` | the whole string matches a regular expression |
+//! | `role IN ['admin', 'owner']` | equal to an element of a list |
+//! | `age IN 18..65`, `age IN 18..` | within an inclusive range |
+//! | `EXISTS email`, `EXISTS(address.zip)` | the property is present |
+//! | `NOT`, `AND`, `OR`, `( ... )` | boolean logic, in increasing order of looseness |
+//! | `active` | a boolean property used as a condition |
+//!
+//! Literals are integers, decimals, single- or double-quoted strings, `true`/`false`,
+//! lists (`[1, 2]`) and ranges (`1..10`, `1..`, `..10`). Keywords are case-insensitive.
+//!
+//! # Evaluation
+//!
+//! Integers and decimals compare numerically; strings and symbols compare by text.
+//! Equality follows IEEE 754 for NaN at any depth: `x = NaN` and `[NaN] = [NaN]`
+//! are both `false`, unlike `Value`'s own `Eq`. A comparison involving a missing
+//! property is `false` (so `NOT x = 1` holds when `x` is absent). Ordering values that have no common order, such as a string and a
+//! number, is a [`ExprError::Type`] error, as is ordering against a NaN decimal;
+//! [`ExprError::span`] points at the offending comparison in the expression text.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use pattern_core::{Expr, Pattern, Subject, Symbol, Value};
+//! use std::collections::{HashMap, HashSet};
+//!
+//! let person = |id: &str, age: i64| Pattern::point(Subject {
+//!     identity: Symbol(id.to_string()),
+//!     labels: HashSet::from(["Person".to_string()]),
+//!     properties: HashMap::from([("age".to_string(), Value::VInteger(age))]),
+//! });
+//! let group = Pattern::pattern(
+//!     Subject {
+//!         identity: Symbol("team".to_string()),
+//!         labels: HashSet::new(),
+//!         properties: HashMap::new(),
+//!     },
+//!     vec![person("alice", 34), person("bob", 27)],
+//! );
+//!
+//! let adults = group.filter_expr(":Person AND age >= 30").unwrap();
+//! assert_eq!(adults.len(), 1);
+//! assert_eq!(adults[0].value.identity.0, "alice");
+//!
+//! let expr = Expr::parse("age IN 20..30").unwrap();
+//! assert!(expr.evaluate(&group.elements[1].value).unwrap());
+//! ```
+
+use crate::{Pattern, RangeValue, Subject, Value};
+use regex::Regex;
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::Range;
+
+// ============================================================================
+// Errors
+// ============================================================================
+
+/// Error parsing or evaluating an [`Expr`].
+///
+/// Every error carries the byte range of the expression text it refers to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExprError {
+    /// The expression text is malformed.
+    Syntax { message: String, span: Range<usize> },
+    /// A comparison was applied to values of incompatible types.
+    Type { message: String, span: Range<usize> },
+}
+
+impl ExprError {
+    /// Returns the byte range of the expression text the error refers to.
+    pub fn span(&self) -> Range<usize> {
+        match self {
+            ExprError::Syntax { span, .. } | ExprError::Type { span, .. } => span.clone(),
+        }
+    }
+
+    /// Returns the error message without location.
+    pub fn message(&self) -> &str {
+        match self {
+            ExprError::Syntax { message, .. } | ExprError::Type { message, .. } => message,
+        }
+    }
+
+    fn syntax(message: impl Into<String>, span: Range<usize>) -> Self {
+        ExprError::Syntax {
+            message: message.into(),
+            span,
+        }
+    }
+}
+
+impl fmt::Display for ExprError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            ExprError::Syntax { .. } => "syntax error",
+            ExprError::Type { .. } => "type error",
+        };
+        let span = self.span();
+        write!(
+            f,
+            "{} at {}..{}: {}",
+            kind,
+            span.start,
+            span.end,
+            self.message()
+        )
+    }
+}
+
+impl std::error::Error for ExprError {}
+
+// ============================================================================
+// Expression Tree
+// ============================================================================
+
+/// A parsed predicate expression.
+///
+/// See the [module documentation](self) for the syntax. Parse once with
+/// [`Expr::parse`] and evaluate against any number of subjects.
+#[derive(Debug, Clone)]
+pub struct Expr {
+    node: Node,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    And(Box<Node>, Box<Node>),
+    Or(Box<Node>, Box<Node>),
+    Not(Box<Node>),
+    Labels(Vec<String>),
+    Exists(Vec<String>),
+    /// A bare operand used as a condition
+    Truthy(Operand, Range<usize>),
+    Compare {
+        left: Operand,
+        op: CompareOp,
+        right: Operand,
+        span: Range<usize>,
+    },
+    In {
+        item: Operand,
+        set: Operand,
+        span: Range<usize>,
+    },
+    Regex {
+        operand: Operand,
+        regex: Regex,
+        span: Range<usize>,
+    },
+}
+
+#[derive(Debug, Clone)]
+enum Operand {
+    Property(Vec<String>),
+    Literal(Value),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Expr {
+    /// Parses expression text.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ExprError::Syntax`] with the span of the offending token if the text
+    /// is malformed, including invalid regular expressions after `=~`.
+    pub fn parse(input: &str) -> Result<Self, ExprError> {
+        let tokens = lex(input)?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            len: input.len(),
+        };
+        let node = parser.or()?;
+        match parser.peek() {
+            None => Ok(Expr { node }),
+            Some(token) => Err(ExprError::syntax(
+                format!("unexpected {}", token.kind),
+                token.span.clone(),
+            )),
+        }
+    }
+
+    /// Evaluates the expression against a subject.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ExprError::Type`] if a comparison is applied to values that cannot
+    /// be compared, such as ordering a string against a number or against NaN.
+    pub fn evaluate(&self, subject: &Subject) -> Result<bool, ExprError> {
+        self.node.evaluate(subject)
+    }
+
+    /// Evaluates the expression, treating type errors as `false`.
+    pub fn matches(&self, subject: &Subject) -> bool {
+        self.evaluate(subject).unwrap_or(false)
+    }
+}
+
+impl std::str::FromStr for Expr {
+    type Err = ExprError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Expr::parse(s)
+    }
+}
+
+// ============================================================================
+// Pattern Entry Points
+// ============================================================================
+
+impl Pattern<Subject> {
+    /// Filters subpatterns whose subject satisfies a predicate expression.
+    ///
+    /// Equivalent to [`filter`](Pattern::filter) with a closure evaluating `expr`
+    /// against each pattern's value, in the same pre-order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `expr` does not parse, or the first type error raised while
+    /// evaluating it.
+    pub fn filter_expr(&self, expr: &str) -> Result<Vec<&Pattern<Subject>>, ExprError> {
+        self.filter_by_expr(&Expr::parse(expr)?)
+    }
+
+    /// Filters subpatterns whose subject satisfies a parsed expression.
+    ///
+    /// Like [`filter_expr`](Pattern::filter_expr), for an expression that is
+    /// parsed once and applied to many patterns.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first type error raised while evaluating `expr`.
+    pub fn filter_by_expr(&self, expr: &Expr) -> Result<Vec<&Pattern<Subject>>, ExprError> {
+        let mut result = Vec::new();
+        self.filter_expr_recursive(expr, &mut result)?;
+        Ok(result)
+    }
+
+    fn filter_expr_recursive<'a>(
+        &'a self,
+        expr: &Expr,
+        result: &mut Vec<&'a Pattern<Subject>>,
+    ) -> Result<(), ExprError> {
+        if expr.evaluate(&self.value)? {
+            result.push(self);
+        }
+        for element in &self.elements {
+            element.filter_expr_recursive(expr, result)?;
+        }
+        Ok(())
+    }
+
+    /// Finds the first subpattern (including self) whose subject satisfies a predicate
+    /// expression.
+    ///
+    /// Equivalent to [`find_first`](Pattern::find_first) with a closure evaluating
+    /// `expr`; the search stops at the first match.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `expr` does not parse, or if evaluation raises a type error
+    /// before a match is found.
+    pub fn find_first_expr(&self, expr: &str) -> Result<Option<&Pattern<Subject>>, ExprError> {
+        self.find_first_by_expr(&Expr::parse(expr)?)
+    }
+
+    /// Finds the first subpattern (including self) whose subject satisfies a parsed
+    /// expression.
+    ///
+    /// Like [`find_first_expr`](Pattern::find_first_expr), for an expression that
+    /// is parsed once and applied to many patterns.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if evaluation raises a type error before a match is found.
+    pub fn find_first_by_expr(&self, expr: &Expr) -> Result<Option<&Pattern<Subject>>, ExprError> {
+        self.find_first_expr_recursive(expr)
+    }
+
+    fn find_first_expr_recursive(
+        &self,
+        expr: &Expr,
+    ) -> Result<Option<&Pattern<Subject>>, ExprError> {
+        if expr.evaluate(&self.value)? {
+            return Ok(Some(self));
+        }
+        for element in &self.elements {
+            if let Some(found) = element.find_first_expr_recursive(expr)? {
+                return Ok(Some(found));
+            }
+        }
+        Ok(None)
+    }
+}
+
+// ============================================================================
+// Evaluation
+// ============================================================================
+
+impl Node {
+    fn evaluate(&self, subject: &Subject) -> Result<bool, ExprError> {
+        match self {
+            Node::And(left, right) => Ok(left.evaluate(subject)? && right.evaluate(subject)?),
+            Node::Or(left, right) => Ok(left.evaluate(subject)? || right.evaluate(subject)?),
+            Node::Not(inner) => Ok(!inner.evaluate(subject)?),
+            Node::Labels(labels) => Ok(labels.iter().all(|l| subject.labels.contains(l))),
+            Node::Exists(path) => Ok(resolve(subject, path).is_some()),
+            Node::Truthy(operand, span) => match operand.resolve(subject) {
+                None => Ok(false),
+                Some(Value::VBoolean(b)) => Ok(*b),
+                Some(other) => Err(type_error(
+                    format!("expected a boolean condition, found {}", other.type_name()),
+                    span,
+                )),
+            },
+            Node::Compare {
+                left,
+                op,
+                right,
+                span,
+            } => {
+                let (Some(left), Some(right)) = (left.resolve(subject), right.resolve(subject))
+                else {
+                    return Ok(false);
+                };
+                match op {
+                    CompareOp::Eq => Ok(values_equal(left, right)),
+                    CompareOp::Ne => Ok(!values_equal(left, right)),
+                    _ => {
+                        let ordering = compare(left, right)
+                            .ok_or_else(|| type_error(unordered(left, right), span))?;
+                        Ok(match op {
+                            CompareOp::Lt => ordering == Ordering::Less,
+                            CompareOp::Le => ordering != Ordering::Greater,
+                            CompareOp::Gt => ordering == Ordering::Greater,
+                            _ => ordering != Ordering::Less,
+                        })
+                    }
+                }
+            }
+            Node::In { item, set, span } => {
+                let (Some(item), Some(set)) = (item.resolve(subject), set.resolve(subject)) else {
+                    return Ok(false);
+                };
+                match set {
+                    Value::VArray(values) => Ok(values.iter().any(|v| values_equal(item, v))),
+                    Value::VRange(range) => match numeric(item) {
+                        Some(n) => Ok(in_range(range, n)),
+                        None => Err(type_error(
+                            format!("cannot test {} for range membership", item.type_name()),
+                            span,
+                        )),
+                    },
+                    other => Err(type_error(
+                        format!("IN expects a list or range, found {}", other.type_name()),
+                        span,
+                    )),
+                }
+            }
+            Node::Regex {
+                operand,
+                regex,
+                span,
+            } => match operand.resolve(subject) {
+                None => Ok(false),
+                Some(Value::VString(s) | Value::VSymbol(s)) => Ok(regex.is_match(s)),
+                Some(other) => Err(type_error(
+                    format!("=~ expects a string, found {}", other.type_name()),
+                    span,
+                )),
+            },
+        }
+    }
+}
+
+impl Operand {
+    fn resolve<'a>(&'a self, subject: &'a Subject) -> Option<&'a Value> {
+        match self {
+            Operand::Property(path) => resolve(subject, path),
+            Operand::Literal(value) => Some(value),
+        }
+    }
+}
+
+fn resolve<'a>(subject: &'a Subject, path: &[String]) -> Option<&'a Value> {
+    let (first, rest) = path.split_first()?;
+    rest.iter()
+        .try_fold(subject.properties.get(first)?, |value, key| match value {
+            Value::VMap(map) => map.get(key),
+            _ => None,
+        })
+}
+
+fn type_error(message: String, span: &Range<usize>) -> ExprError {
+    ExprError::Type {
+        message,
+        span: span.clone(),
+    }
+}
+
+fn numeric(value: &Value) -> Option<f64> {
+    match value {
+        Value::VInteger(i) => Some(*i as f64),
+        Value::VDecimal(d) => Some(*d),
+        _ => None,
+    }
+}
+
+fn text(value: &Value) -> Option<&str> {
+    match value {
+        Value::VString(s) | Value::VSymbol(s) => Some(s),
+        _ => None,
+    }
+}
+
+/// Equality for `=`, `!=` and `IN`: numbers compare across integer and decimal,
+/// and NaN equals nothing, itself included, inside arrays and maps too.
+fn values_equal(left: &Value, right: &Value) -> bool {
+    if let (Some(l), Some(r)) = (numeric(left), numeric(right)) {
+        return l == r;
+    }
+    if let (Some(l), Some(r)) = (text(left), text(right)) {
+        return l == r;
+    }
+    match (left, right) {
+        (Value::VArray(l), Value::VArray(r)) => {
+            l.len() == r.len() && l.iter().zip(r).all(|(l, r)| values_equal(l, r))
+        }
+        (Value::VMap(l), Value::VMap(r)) => {
+            l.len() == r.len()
+                && l.iter()
+                    .all(|(key, l)| r.get(key).is_some_and(|r| values_equal(l, r)))
+        }
+        (Value::VRange(l), Value::VRange(r)) => l.lower == r.lower && l.upper == r.upper,
+        (
+            Value::VMeasurement { unit: lu, value: l },
+            Value::VMeasurement { unit: ru, value: r },
+        ) => lu == ru && l == r,
+        _ => left == right,
+    }
+}
+
+fn compare(left: &Value, right: &Value) -> Option<Ordering> {
+    if let (Some(l), Some(r)) = (numeric(left), numeric(right)) {
+        return l.partial_cmp(&r);
+    }
+    if let (Some(l), Some(r)) = (text(left), text(right)) {
+        return Some(l.cmp(r));
+    }
+    match (left, right) {
+        (Value::VBoolean(l), Value::VBoolean(r)) => Some(l.cmp(r)),
+        (
+            Value::VMeasurement { unit: lu, value: l },
+            Value::VMeasurement { unit: ru, value: r },
+        ) if lu == ru => l.partial_cmp(r),
+        _ => None,
+    }
+}
+
+/// Describes why `left` and `right` have no order: a NaN operand of a type
+/// that would otherwise order, or types that do not compare.
+fn unordered(left: &Value, right: &Value) -> String {
+    let nan = match (left, right) {
+        (
+            Value::VMeasurement { unit: lu, value: l },
+            Value::VMeasurement { unit: ru, value: r },
+        ) => lu == ru && (l.is_nan() || r.is_nan()),
+        _ => match (numeric(left), numeric(right)) {
+            (Some(l), Some(r)) => l.is_nan() || r.is_nan(),
+            _ => false,
+        },
+    };
+    if nan {
+        "cannot order NaN".to_string()
+    } else {
+        format!(
+            "cannot order {} against {}",
+            left.type_name(),
+            right.type_name()
+        )
+    }
+}
+
+fn in_range(range: &RangeValue, n: f64) -> bool {
+    range.lower.map_or(true, |lower| lower <= n) && range.upper.map_or(true, |upper| n <= upper)
+}
+
+// ============================================================================
+// Lexer
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Ident(String),
+    /// Backtick-quoted property key; never a keyword
+    QuotedIdent(String),
+    Str(String),
+    Int(i64),
+    Dec(f64),
+    Colon,
+    Dot,
+    DotDot,
+    Comma,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Compare(CompareOp),
+    Match,
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenKind::Ident(s) => write!(f, "'{}'", s),
+            TokenKind::QuotedIdent(s) => write!(f, "`{}`", s),
+            TokenKind::Str(_) => write!(f, "string"),
+            TokenKind::Int(i) => write!(f, "'{}'", i),
+            TokenKind::Dec(d) => write!(f, "'{}'", d),
+            TokenKind::Colon => write!(f, "':'"),
+            TokenKind::Dot => write!(f, "'.'"),
+            TokenKind::DotDot => write!(f, "'..'"),
+            TokenKind::Comma => write!(f, "','"),
+            TokenKind::LParen => write!(f, "'('"),
+            TokenKind::RParen => write!(f, "')'"),
+            TokenKind::LBracket => write!(f, "'['"),
+            TokenKind::RBracket => write!(f, "']'"),
+            TokenKind::Compare(_) => write!(f, "comparison operator"),
+            TokenKind::Match => write!(f, "'=~'"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    span: Range<usize>,
+}
+
+fn lex(input: &str) -> Result<Vec<Token>, ExprError> {
+    let bytes = input.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let c = bytes[i];
+        if c.is_ascii_whitespace() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        let two = bytes.get(i..i + 2);
+        let kind = match c {
+            b'(' => single(&mut i, TokenKind::LParen),
+            b')' => single(&mut i, TokenKind::RParen),
+            b'[' => single(&mut i, TokenKind::LBracket),
+            b']' => single(&mut i, TokenKind::RBracket),
+            b',' => single(&mut i, TokenKind::Comma),
+            b':' => single(&mut i, TokenKind::Colon),
+            b'.' if two == Some(b"..") => {
+                i += 2;
+                TokenKind::DotDot
+            }
+            b'.' => single(&mut i, TokenKind::Dot),
+            b'=' if two == Some(b"=~") => {
+                i += 2;
+                TokenKind::Match
+            }
+            b'=' if two == Some(b"==") => {
+                i += 2;
+                TokenKind::Compare(CompareOp::Eq)
+            }
+            b'=' => single(&mut i, TokenKind::Compare(CompareOp::Eq)),
+            b'!' if two == Some(b"!=") => {
+                i += 2;
+                TokenKind::Compare(CompareOp::Ne)
+            }
+            b'<' => {
+                i += 1;
+                match bytes.get(i) {
+                    Some(b'=') => single(&mut i, TokenKind::Compare(CompareOp::Le)),
+                    Some(b'>') => single(&mut i, TokenKind::Compare(CompareOp::Ne)),
+                    _ => TokenKind::Compare(CompareOp::Lt),
+                }
+            }
+            b'>' => {
+                i += 1;
+                match bytes.get(i) {
+                    Some(b'=') => single(&mut i, TokenKind::Compare(CompareOp::Ge)),
+                    _ => TokenKind::Compare(CompareOp::Gt),
+                }
+            }
+            b'\'' | b'"' => TokenKind::Str(lex_string(input, &mut i)?),
+            b'`' => {
+                let end = input[i + 1..]
+                    .find('`')
+                    .map(|n| i + 1 + n)
+                    .ok_or_else(|| ExprError::syntax("unterminated quoted key", i..input.len()))?;
+                let key = input[i + 1..end].to_string();
+                i = end + 1;
+                TokenKind::QuotedIdent(key)
+            }
+            b'-' | b'0'..=b'9' => lex_number(input, &mut i)?,
+            c if c == b'_' || c.is_ascii_alphabetic() => {
+                while i < bytes.len() && (bytes[i] == b'_' || bytes[i].is_ascii_alphanumeric()) {
+                    i += 1;
+                }
+                TokenKind::Ident(input[start..i].to_string())
+            }
+            _ => {
+                let ch = input[i..].chars().next().unwrap_or_default();
+                return Err(ExprError::syntax(
+                    format!("unexpected character '{}'", ch),
+                    i..i + ch.len_utf8(),
+                ));
+            }
+        };
+        tokens.push(Token {
+            kind,
+            span: start..i,
+        });
+    }
+    Ok(tokens)
+}
+
+fn single(i: &mut usize, kind: TokenKind) -> TokenKind {
+    *i += 1;
+    kind
+}
+
+fn lex_string(input: &str, i: &mut usize) -> Result<String, ExprError> {
+    let start = *i;
+    let mut chars = input[start..].char_indices();
+    let (_, quote) = chars.next().expect("called on a quote");
+    let mut value = String::new();
+    while let Some((offset, c)) = chars.next() {
+        match c {
+            '\\' => {
+                let (_, escaped) = chars
+                    .next()
+                    .ok_or_else(|| ExprError::syntax("unterminated string", start..input.len()))?;
+                value.push(match escaped {
+                    'n' => '\n',
+                    't' => '\t',
+                    'r' => '\r',
+                    other => other,
+                });
+            }
+            c if c == quote => {
+                *i = start + offset + 1;
+                return Ok(value);
+            }
+            c => value.push(c),
+        }
+    }
+    Err(ExprError::syntax("unterminated string", start..input.len()))
+}
+
+fn lex_number(input: &str, i: &mut usize) -> Result<TokenKind, ExprError> {
+    let bytes = input.as_bytes();
+    let start = *i;
+    if bytes[*i] == b'-' {
+        *i += 1;
+    }
+    let digits = *i;
+    while *i < bytes.len() && bytes[*i].is_ascii_digit() {
+        *i += 1;
+    }
+    if *i == digits {
+        return Err(ExprError::syntax("expected a number after '-'", start..*i));
+    }
+    // A single '.' followed by a digit is a decimal point; '..' starts a range
+    let decimal = bytes.get(*i) == Some(&b'.') && bytes.get(*i + 1).is_some_and(u8::is_ascii_digit);
+    if decimal {
+        *i += 1;
+        while *i < bytes.len() && bytes[*i].is_ascii_digit() {
+            *i += 1;
+        }
+    }
+    let text = &input[start..*i];
+    let invalid = || ExprError::syntax(format!("invalid number '{}'", text), start..*i);
+    if decimal {
+        text.parse().map(TokenKind::Dec).map_err(|_| invalid())
+    } else {
+        text.parse().map(TokenKind::Int).map_err(|_| invalid())
+    }
+}
+
+// ============================================================================
+// Parser
+// ============================================================================
+
+/// Recursive-descent parser; one method per precedence level.
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// Input length, for the span of "unexpected end" errors
+    len: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, kind: &TokenKind) -> bool {
+        if self.peek().is_some_and(|t| &t.kind == kind) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if self.peek_keyword(keyword) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token { kind: TokenKind::Ident(s), .. }) if s.eq_ignore_ascii_case(keyword))
+    }
+
+    fn expect(&mut self, kind: TokenKind) -> Result<Range<usize>, ExprError> {
+        match self.next() {
+            Some(token) if token.kind == kind => Ok(token.span),
+            Some(token) => Err(ExprError::syntax(
+                format!("expected {}, found {}", kind, token.kind),
+                token.span,
+            )),
+            None => Err(self.unexpected_end(&kind.to_string())),
+        }
+    }
+
+    fn unexpected_end(&self, expected: &str) -> ExprError {
+        ExprError::syntax(
+            format!("expected {}, found end of expression", expected),
+            self.len..self.len,
+        )
+    }
+
+    /// Span from the token at `start` to the last consumed token.
+    fn span_from(&self, start: usize) -> Range<usize> {
+        let first = &self.tokens[start].span;
+        let last = &self.tokens[self.pos - 1].span;
+        first.start..last.end
+    }
+
+    fn or(&mut self) -> Result<Node, ExprError> {
+        let mut node = self.and()?;
+        while self.eat_keyword("OR") {
+            node = Node::Or(Box::new(node), Box::new(self.and()?));
+        }
+        Ok(node)
+    }
+
+    fn and(&mut self) -> Result<Node, ExprError> {
+        let mut node = self.not()?;
+        while self.eat_keyword("AND") {
+            node = Node::And(Box::new(node), Box::new(self.not()?));
+        }
+        Ok(node)
+    }
+
+    fn not(&mut self) -> Result<Node, ExprError> {
+        if self.eat_keyword("NOT") {
+            Ok(Node::Not(Box::new(self.not()?)))
+        } else {
+            self.primary()
+        }
+    }
+
+    fn primary(&mut self) -> Result<Node, ExprError> {
+        let Some(token) = self.peek().cloned() else {
+            return Err(self.unexpected_end("a condition"));
+        };
+        match token.kind {
+            TokenKind::LParen => {
+                self.pos += 1;
+                let node = self.or()?;
+                self.expect(TokenKind::RParen)?;
+                Ok(node)
+            }
+            TokenKind::Colon => {
+                let mut labels = Vec::new();
+                while self.eat(&TokenKind::Colon) {
+                    labels.push(self.key("a label")?);
+                }
+                Ok(Node::Labels(labels))
+            }
+            TokenKind::Ident(ref s) if s.eq_ignore_ascii_case("EXISTS") => {
+                self.pos += 1;
+                let parenthesized = self.eat(&TokenKind::LParen);
+                let path = self.path()?;
+                if parenthesized {
+                    self.expect(TokenKind::RParen)?;
+                }
+                Ok(Node::Exists(path))
+            }
+            _ => self.comparison(),
+        }
+    }
+
+    fn comparison(&mut self) -> Result<Node, ExprError> {
+        let start = self.pos;
+        let left = self.operand()?;
+
+        if self.eat_keyword("IN") {
+            let set = self.operand()?;
+            return Ok(Node::In {
+                item: left,
+                set,
+                span: self.span_from(start),
+            });
+        }
+
+        match self.peek().map(|t| t.kind.clone()) {
+            Some(TokenKind::Compare(op)) => {
+                self.pos += 1;
+                let right = self.operand()?;
+                Ok(Node::Compare {
+                    left,
+                    op,
+                    right,
+                    span: self.span_from(start),
+                })
+            }
+            Some(TokenKind::Match) => {
+                self.pos += 1;
+                let pattern = match self.next() {
+                    Some(Token {
+                        kind: TokenKind::Str(pattern),
+                        span,
+                    }) => (pattern, span),
+                    Some(token) => {
+                        return Err(ExprError::syntax(
+                            format!("expected a regular expression string, found {}", token.kind),
+                            token.span,
+                        ))
+                    }
+                    None => return Err(self.unexpected_end("a regular expression string")),
+                };
+                // `=~` matches the whole string
+                let regex = Regex::new(&format!("^(?:{})$", pattern.0)).map_err(|e| {
+                    ExprError::syntax(format!("invalid regular expression: {}", e), pattern.1)
+                })?;
+                Ok(Node::Regex {
+                    operand: left,
+                    regex,
+                    span: self.span_from(start),
+                })
+            }
+            _ => Ok(Node::Truthy(left, self.span_from(start))),
+        }
+    }
+
+    fn operand(&mut self) -> Result<Operand, ExprError> {
+        match self.peek().map(|t| &t.kind) {
+            Some(TokenKind::Ident(s)) if s.eq_ignore_ascii_case("true") => {
+                self.pos += 1;
+                Ok(Operand::Literal(Value::VBoolean(true)))
+            }
+            Some(TokenKind::Ident(s)) if s.eq_ignore_ascii_case("false") => {
+                self.pos += 1;
+                Ok(Operand::Literal(Value::VBoolean(false)))
+            }
+            Some(TokenKind::Ident(_) | TokenKind::QuotedIdent(_)) => {
+                Ok(Operand::Property(self.path()?))
+            }
+            _ => self.literal().map(Operand::Literal),
+        }
+    }
+
+    fn path(&mut self) -> Result<Vec<String>, ExprError> {
+        let mut path = vec![self.key("a property name")?];
+        while self.eat(&TokenKind::Dot) {
+            path.push(self.key("a property name")?);
+        }
+        Ok(path)
+    }
+
+    fn key(&mut self, expected: &str) -> Result<String, ExprError> {
+        match self.next() {
+            Some(Token {
+                kind: TokenKind::Ident(s) | TokenKind::QuotedIdent(s),
+                ..
+            }) => Ok(s),
+            Some(token) => Err(ExprError::syntax(
+                format!("expected {}, found {}", expected, token.kind),
+                token.span,
+            )),
+            None => Err(self.unexpected_end(expected)),
+        }
+    }
+
+    fn literal(&mut self) -> Result<Value, ExprError> {
+        let Some(token) = self.next() else {
+            return Err(self.unexpected_end("a value"));
+        };
+        let value = match token.kind {
+            TokenKind::Str(s) => return Ok(Value::VString(s)),
+            TokenKind::Int(i) => Value::VInteger(i),
+            TokenKind::Dec(d) => Value::VDecimal(d),
+            TokenKind::DotDot => {
+                let upper = self.number("an upper bound")?;
+                return Ok(Value::VRange(RangeValue {
+                    lower: None,
+                    upper: Some(upper),
+                }));
+            }
+            TokenKind::LBracket => {
+                let mut values = Vec::new();
+                if !self.eat(&TokenKind::RBracket) {
+                    loop {
+                        values.push(self.literal()?);
+                        if self.eat(&TokenKind::RBracket) {
+                            break;
+                        }
+                        self.expect(TokenKind::Comma)?;
+                    }
+                }
+                return Ok(Value::VArray(values));
+            }
+            other => {
+                return Err(ExprError::syntax(
+                    format!("expected a value, found {}", other),
+                    token.span,
+                ))
+            }
+        };
+
+        // A number may start a range: `1..10` or `1..`
+        if !self.eat(&TokenKind::DotDot) {
+            return Ok(value);
+        }
+        let lower = numeric(&value);
+        let upper = match self.peek().map(|t| &t.kind) {
+            Some(TokenKind::Int(_) | TokenKind::Dec(_)) => Some(self.number("an upper bound")?),
+            _ => None,
+        };
+        Ok(Value::VRange(RangeValue { lower, upper }))
+    }
+
+    fn number(&mut self, expected: &str) -> Result<f64, ExprError> {
+        match self.next() {
+            Some(Token {
+                kind: TokenKind::Int(i),
+                ..
+            }) => Ok(i as f64),
+            Some(Token {
+                kind: TokenKind::Dec(d),
+                ..
+            }) => Ok(d),
+            Some(token) => Err(ExprError::syntax(
+                format!("expected {}, found {}", expected, token.kind),
+                token.span,
+            )),
+            None => Err(self.unexpected_end(expected)),
+        }
+    }
+}
@@ -0,0 +1,396 @@
+//! Identity, label and property indexes for `Pattern<Subject>` documents.
+//!
+//! The query functions on [`Pattern`] (`find_first`, `filter`, `any_value`) are linear
+//! scans. For large documents that are queried repeatedly, a [`PatternIndex`] answers the
+//! common lookups directly:
+//!
+//! - identity → every occurrence of that identity (with its path)
+//! - label → every subject carrying that label
+//! - (property key, value) → every subject with that exact property value
+//! - property key + numeric range → every subject whose numeric property falls in the range
+//!
+//! # Paths
+//!
+//! Occurrences are reported as [`PatternPath`]s: the first index selects the top-level
+//! pattern (its *slot* in the index), the remaining indices walk down through `elements`.
+//! Paths are kept in sorted sets, so lookups return occurrences in document (pre-order)
+//! order. Use [`PatternIndex::resolve`] to turn a path back into a pattern.
+//!
+//! # Incremental Updates
+//!
+//! The index does not own the patterns. Top-level patterns are added with
+//! [`PatternIndex::insert`], which assigns the next free slot, and removed with
+//! [`PatternIndex::remove`]. Slots are stable: removing a pattern never renumbers
+//! the others.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use pattern_core::{Pattern, PatternIndex, Subject, Symbol, Value};
+//! use std::collections::{HashMap, HashSet};
+//!
+//! let person = |id: &str, age: i64| Subject {
+//!     identity: Symbol(id.to_string()),
+//!     labels: HashSet::from(["Person".to_string()]),
+//!     properties: HashMap::from([("age".to_string(), Value::VInteger(age))]),
+//! };
+//!
+//! let patterns = vec![
+//!     Pattern::point(person("alice", 30)),
+//!     Pattern::point(person("bob", 42)),
+//! ];
+//!
+//! let index = PatternIndex::build(&patterns);
+//!
+//! assert_eq!(index.occurrences("bob").len(), 1);
+//! assert_eq!(index.with_label("Person").count(), 2);
+//! assert_eq!(index.with_property("age", &Value::VInteger(30)).count(), 1);
+//! assert_eq!(index.property_range("age", 40.0..).len(), 1);
+//!
+//! let bob = &index.occurrences("bob")[0];
+//! assert_eq!(PatternIndex::resolve(&patterns, bob).unwrap().value.identity.0, "bob");
+//! ```
+
+use crate::{Pattern, Subject, Symbol, Value};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ops::{Bound, RangeBounds};
+
+/// Path to a subpattern within an indexed document.
+///
+/// The first index is the slot of the top-level pattern; the remaining indices select
+/// elements at each nesting level. `[2]` is the third top-level pattern itself, `[2, 0]`
+/// is its first element.
+pub type PatternPath = Vec<usize>;
+
+/// Indexes over a collection of top-level `Pattern<Subject>` values.
+///
+/// See the [module documentation](self) for an overview.
+#[derive(Debug, Clone, Default)]
+pub struct PatternIndex {
+    identities: HashMap<Symbol, BTreeSet<PatternPath>>,
+    labels: HashMap<String, BTreeSet<PatternPath>>,
+    properties: HashMap<String, HashMap<Value, BTreeSet<PatternPath>>>,
+    numeric: HashMap<String, BTreeMap<NumericKey, BTreeSet<PatternPath>>>,
+    next_slot: usize,
+    len: usize,
+}
+
+impl PatternIndex {
+    /// Creates an empty index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds an index over a slice of top-level patterns.
+    ///
+    /// Pattern `i` of the slice is assigned slot `i`, so paths returned by the index
+    /// can be resolved directly against `patterns`.
+    ///
+    /// # Complexity
+    ///
+    /// O(n) in the total number of subjects (amortized hash-map inserts), plus
+    /// O(log n) per numeric property.
+    pub fn build(patterns: &[Pattern<Subject>]) -> Self {
+        let mut index = Self::new();
+        for pattern in patterns {
+            index.insert(pattern);
+        }
+        index
+    }
+
+    /// Indexes a new top-level pattern and returns the slot assigned to it.
+    ///
+    /// Slots are handed out sequentially and never reused, so appending to the
+    /// indexed `Vec` and calling `insert` keeps slots and positions aligned.
+    pub fn insert(&mut self, pattern: &Pattern<Subject>) -> usize {
+        let slot = self.next_slot;
+        self.next_slot += 1;
+        self.len += 1;
+
+        let mut path = vec![slot];
+        self.insert_recursive(pattern, &mut path);
+        slot
+    }
+
+    /// Removes a previously indexed top-level pattern.
+    ///
+    /// `pattern` must be the pattern that was indexed at `slot`; it is walked to find
+    /// the entries to remove. Other slots are unaffected.
+    pub fn remove(&mut self, slot: usize, pattern: &Pattern<Subject>) {
+        let mut path = vec![slot];
+        self.remove_recursive(pattern, &mut path);
+        self.len = self.len.saturating_sub(1);
+    }
+
+    /// Returns the number of top-level patterns currently indexed.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if no patterns are indexed.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns every occurrence of a subject with the given identity, in document order.
+    ///
+    /// Anonymous subjects (empty identity) are not indexed by identity.
+    pub fn occurrences(&self, identity: &str) -> Vec<&PatternPath> {
+        self.identities
+            .get(&Symbol(identity.to_string()))
+            .map(|paths| paths.iter().collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns `true` if at least one subject with the given identity is indexed.
+    pub fn contains_identity(&self, identity: &str) -> bool {
+        self.identities.contains_key(&Symbol(identity.to_string()))
+    }
+
+    /// Iterates over all indexed identities (in no particular order).
+    pub fn identities(&self) -> impl Iterator<Item = &Symbol> {
+        self.identities.keys()
+    }
+
+    /// Iterates over the paths of all subjects carrying `label`, in document order.
+    pub fn with_label<'a>(&'a self, label: &str) -> impl Iterator<Item = &'a PatternPath> + 'a {
+        self.labels.get(label).into_iter().flatten()
+    }
+
+    /// Iterates over all indexed labels (in no particular order).
+    pub fn labels(&self) -> impl Iterator<Item = &String> {
+        self.labels.keys()
+    }
+
+    /// Iterates over the paths of all subjects whose property `key` equals `value`.
+    ///
+    /// Equality is structural and variant-exact: `VInteger(1)` does not match
+    /// `VDecimal(1.0)`. Use [`property_range`](Self::property_range) for numeric
+    /// comparisons across integer and decimal values.
+    pub fn with_property<'a>(
+        &'a self,
+        key: &str,
+        value: &Value,
+    ) -> impl Iterator<Item = &'a PatternPath> + 'a {
+        self.properties
+            .get(key)
+            .and_then(|values| values.get(value))
+            .into_iter()
+            .flatten()
+    }
+
+    /// Iterates over the paths of all subjects that have property `key` (any value).
+    pub fn with_property_key<'a>(
+        &'a self,
+        key: &str,
+    ) -> impl Iterator<Item = &'a PatternPath> + 'a {
+        let mut paths: Vec<&PatternPath> = self
+            .properties
+            .get(key)
+            .into_iter()
+            .flat_map(|values| values.values().flatten())
+            .collect();
+        paths.sort();
+        paths.into_iter()
+    }
+
+    /// Returns the paths of subjects whose numeric property `key` lies within `range`.
+    ///
+    /// Integer, decimal and measurement values are compared as `f64`. Results are
+    /// ordered by property value, then by document order.
+    ///
+    /// # Complexity
+    ///
+    /// O(log n + k) where k is the number of matches.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use pattern_core::{Pattern, PatternIndex, Subject, Symbol, Value};
+    /// use std::collections::{HashMap, HashSet};
+    ///
+    /// let node = |id: &str, score: f64| Pattern::point(Subject {
+    ///     identity: Symbol(id.to_string()),
+    ///     labels: HashSet::new(),
+    ///     properties: HashMap::from([("score".to_string(), Value::VDecimal(score))]),
+    /// });
+    ///
+    /// let patterns = vec![node("a", 0.5), node("b", 1.5), node("c", 2.5)];
+    /// let index = PatternIndex::build(&patterns);
+    ///
+    /// let hits = index.property_range("score", 1.0..=2.5);
+    /// assert_eq!(hits, vec![&vec![1], &vec![2]]);
+    /// ```
+    pub fn property_range<R>(&self, key: &str, range: R) -> Vec<&PatternPath>
+    where
+        R: RangeBounds<f64>,
+    {
+        let Some(values) = self.numeric.get(key) else {
+            return Vec::new();
+        };
+        let bound = |b: Bound<&f64>| match b {
+            Bound::Included(x) => Bound::Included(NumericKey(*x)),
+            Bound::Excluded(x) => Bound::Excluded(NumericKey(*x)),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        let (start, end) = (bound(range.start_bound()), bound(range.end_bound()));
+        if let (Bound::Included(s) | Bound::Excluded(s), Bound::Included(e) | Bound::Excluded(e)) =
+            (&start, &end)
+        {
+            // BTreeMap::range panics on inverted or empty-exclusive bounds
+            match s.cmp(e) {
+                Ordering::Greater => return Vec::new(),
+                Ordering::Equal
+                    if matches!(start, Bound::Excluded(_)) || matches!(end, Bound::Excluded(_)) =>
+                {
+                    return Vec::new()
+                }
+                _ => {}
+            }
+        }
+        values
+            .range((start, end))
+            .flat_map(|(_, paths)| paths.iter())
+            .collect()
+    }
+
+    /// Resolves a path returned by this index against the indexed patterns.
+    ///
+    /// Returns `None` if the path does not point at an existing subpattern.
+    pub fn resolve<'a>(
+        patterns: &'a [Pattern<Subject>],
+        path: &[usize],
+    ) -> Option<&'a Pattern<Subject>> {
+        let (first, rest) = path.split_first()?;
+        rest.iter()
+            .try_fold(patterns.get(*first)?, |p, i| p.elements.get(*i))
+    }
+
+    fn insert_recursive(&mut self, pattern: &Pattern<Subject>, path: &mut PatternPath) {
+        let subject = &pattern.value;
+
+        if !subject.identity.0.is_empty() {
+            self.identities
+                .entry(subject.identity.clone())
+                .or_default()
+                .insert(path.clone());
+        }
+        for label in &subject.labels {
+            self.labels
+                .entry(label.clone())
+                .or_default()
+                .insert(path.clone());
+        }
+        for (key, value) in &subject.properties {
+            self.properties
+                .entry(key.clone())
+                .or_default()
+                .entry(value.clone())
+                .or_default()
+                .insert(path.clone());
+            if let Some(n) = numeric_value(value) {
+                self.numeric
+                    .entry(key.clone())
+                    .or_default()
+                    .entry(NumericKey(n))
+                    .or_default()
+                    .insert(path.clone());
+            }
+        }
+
+        for (i, element) in pattern.elements.iter().enumerate() {
+            path.push(i);
+            self.insert_recursive(element, path);
+            path.pop();
+        }
+    }
+
+    fn remove_recursive(&mut self, pattern: &Pattern<Subject>, path: &mut PatternPath) {
+        let subject = &pattern.value;
+
+        if !subject.identity.0.is_empty() {
+            remove_entry(&mut self.identities, &subject.identity, path);
+        }
+        for label in &subject.labels {
+            remove_entry(&mut self.labels, label, path);
+        }
+        for (key, value) in &subject.properties {
+            if let Some(values) = self.properties.get_mut(key) {
+                remove_entry(values, value, path);
+                if values.is_empty() {
+                    self.properties.remove(key);
+                }
+            }
+            if let Some(n) = numeric_value(value) {
+                if let Some(values) = self.numeric.get_mut(key) {
+                    let k = NumericKey(n);
+                    if let Some(paths) = values.get_mut(&k) {
+                        paths.remove(path.as_slice());
+                        if paths.is_empty() {
+                            values.remove(&k);
+                        }
+                    }
+                    if values.is_empty() {
+                        self.numeric.remove(key);
+                    }
+                }
+            }
+        }
+
+        for (i, element) in pattern.elements.iter().enumerate() {
+            path.push(i);
+            self.remove_recursive(element, path);
+            path.pop();
+        }
+    }
+}
+
+/// Removes `path` from the set stored under `key`, dropping the key once its set is empty.
+fn remove_entry<K, Q>(map: &mut HashMap<K, BTreeSet<PatternPath>>, key: &Q, path: &[usize])
+where
+    K: std::borrow::Borrow<Q> + std::hash::Hash + Eq,
+    Q: std::hash::Hash + Eq + ?Sized,
+{
+    if let Some(paths) = map.get_mut(key) {
+        paths.remove(path);
+        if paths.is_empty() {
+            map.remove(key);
+        }
+    }
+}
+
+/// Numeric view of a property value used by the range index.
+fn numeric_value(value: &Value) -> Option<f64> {
+    match value {
+        Value::VInteger(i) => Some(*i as f64),
+        Value::VDecimal(d) if !d.is_nan() => Some(*d),
+        Value::VMeasurement { value, .. } if !value.is_nan() => Some(*value),
+        _ => None,
+    }
+}
+
+/// Totally ordered `f64` key for the range index (NaN is never stored).
+#[derive(Debug, Clone, Copy)]
+struct NumericKey(f64);
+
+impl PartialEq for NumericKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for NumericKey {}
+
+impl PartialOrd for NumericKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for NumericKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // -0.0 and 0.0 must land on the same key
+        (self.0 + 0.0).total_cmp(&(other.0 + 0.0))
+    }
+}
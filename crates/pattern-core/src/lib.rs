@@ -155,6 +155,72 @@
 //! assert_eq!(map.get(&Pattern::point(1)), Some(&"first"));
 //! ```
 //!
+//! # Typed Properties
+//!
+//! [`Value`](subject::Value) converts to and from Rust primitives, `Vec<T>` and
+//! `HashMap<String, T>` via `From`/`TryFrom`, and [`Subject`](subject::Subject) has
+//! typed accessors that report missing or mistyped properties as a
+//! [`PropertyError`](convert::PropertyError):
+//!
+//! ```rust
+//! use pattern_core::{Subject, Symbol, Value};
+//! use std::collections::{HashMap, HashSet};
+//!
+//! let person = Subject {
+//!     identity: Symbol("alice".to_string()),
+//!     labels: HashSet::new(),
+//!     properties: HashMap::from([("age".to_string(), Value::from(30))]),
+//! };
+//!
+//! assert_eq!(person.get::<i64>("age"), Some(30));
+//! assert_eq!(person.get_or("name", String::from("unknown")), "unknown");
+//! assert!(person.require::<String>("age").is_err());
+//! ```
+//!
+//! # Predicate Expressions
+//!
+//! [`Expr`](expr::Expr) is a small text language for subject predicates, so filters can
+//! live in configuration or come from a UI instead of Rust closures:
+//!
+//! ```rust
+//! use pattern_core::{Pattern, Subject, Symbol, Value};
+//! use std::collections::{HashMap, HashSet};
+//!
+//! let alice = Pattern::point(Subject {
+//!     identity: Symbol("alice".to_string()),
+//!     labels: HashSet::from(["Person".to_string()]),
+//!     properties: HashMap::from([("name".to_string(), Value::from("Alice"))]),
+//! });
+//!
+//! let found = alice.find_first_expr(":Person AND name =~ 'Al.*'").unwrap();
+//! assert!(found.is_some());
+//! ```
+//!
+//! # Comparing Documents
+//!
+//! [`diff()`] compares two documents as graphs keyed by identity rather than as text,
+//! reporting added and removed nodes and relationships, label and property changes and
+//! moved elements. A [`Diff`] prints as a human report and converts to a changeset
+//! document that [`apply_changeset`] applies to the old version.
+//! [`three_way_merge`] combines the changes two branches made to a common base, and
+//! reports overlapping changes as structured conflicts.
+//!
+//! # Persistent Patterns
+//!
+//! [`SharedPattern<V>`](shared::SharedPattern) is an `Arc`-based, immutable variant of
+//! `Pattern<V>` with O(1) clones and path-copying updates, for undo histories and
+//! snapshots shared between threads.
+//!
+//! ```rust
+//! use pattern_core::{Pattern, SharedPattern};
+//!
+//! let v1 = SharedPattern::from(Pattern::pattern(1, vec![Pattern::point(2), Pattern::point(3)]));
+//! let v2 = v1.update_value(&[0], |n| n * 10).unwrap();
+//!
+//! assert_eq!(v1.to_pattern(), Pattern::pattern(1, vec![Pattern::point(2), Pattern::point(3)]));
+//! assert_eq!(v2.to_pattern(), Pattern::pattern(1, vec![Pattern::point(20), Pattern::point(3)]));
+//! ```
+//!
 //! # WASM Compatibility
 //!
 //! All types in this crate are fully compatible with WebAssembly targets. Compile for WASM with:
@@ -170,15 +236,34 @@
 //! - Subject: `../gram-hs/libs/subject/src/Subject/Core.hs`
 //! - Feature Spec: `../gram-hs/specs/001-pattern-data-structure/`
 
+pub mod convert;
+pub mod diff;
+pub mod expr;
+pub mod index;
+pub mod merge;
 pub mod pattern;
+pub mod shared;
 pub mod subject;
 pub mod test_utils;
+pub mod three_way;
 
 #[cfg(feature = "python")]
 pub mod python;
 
+pub use convert::{FromSubject, IntoRelationship, IntoSubject, PropertyError};
+pub use diff::{
+    apply_changeset, diff, ChangesetError, Diff, Move, PropertyChange, Relationship, SubjectChange,
+};
+pub use expr::{Expr, ExprError};
+pub use index::{PatternIndex, PatternPath};
+pub use merge::{
+    merge_documents, Conflict, DocumentMerge, IdentityRule, MergeError, MergePolicy, MergeReport,
+    MergeRule, Merged, Merging,
+};
 pub use pattern::{Pattern, StructureAnalysis, ValidationError, ValidationRules};
-pub use subject::{PropertyRecord, RangeValue, Subject, Symbol, Value};
+pub use shared::SharedPattern;
+pub use subject::{canonical_f64, PropertyRecord, RangeValue, Subject, Symbol, Value};
+pub use three_way::{three_way_merge, MergeConflict, Side, ThreeWayMerge};
 
 // Re-export comonad operations for convenient access
 // These are defined in pattern::comonad and pattern::comonad_helpers modules
@@ -405,6 +490,12 @@ impl Combinable for FirstSubject {
     }
 }
 
+impl From<FirstSubject> for Subject {
+    fn from(wrapper: FirstSubject) -> Self {
+        wrapper.0
+    }
+}
+
 /// Newtype wrapper for "last wins" combination strategy.
 ///
 /// When combining two LastSubject instances, the second subject is returned
@@ -446,6 +537,12 @@ impl Combinable for LastSubject {
     }
 }
 
+impl From<LastSubject> for Subject {
+    fn from(wrapper: LastSubject) -> Self {
+        wrapper.0
+    }
+}
+
 /// Newtype wrapper for "empty" combination strategy that creates anonymous subjects.
 ///
 /// When combining two EmptySubject instances, the result is always an anonymous
@@ -503,6 +600,12 @@ impl Combinable for EmptySubject {
     }
 }
 
+impl From<EmptySubject> for Subject {
+    fn from(wrapper: EmptySubject) -> Self {
+        wrapper.0
+    }
+}
+
 impl Default for EmptySubject {
     fn default() -> Self {
         EmptySubject(Subject {
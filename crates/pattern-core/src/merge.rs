@@ -0,0 +1,849 @@
+//! Configurable merge policies for combining `Subject` values
+//!
+//! `impl Combinable for Subject` keeps the left identity and lets right-hand
+//! properties overwrite; [`FirstSubject`](crate::FirstSubject) and
+//! [`LastSubject`](crate::LastSubject) discard one side entirely. A [`MergePolicy`]
+//! decides per property key how two values are combined, and how differing
+//! identities are handled, and records every conflict it resolves so that merges
+//! of data from several sources can be audited.
+//!
+//! # Rules
+//!
+//! A [`MergeRule`] applies when both subjects have a property. If only one side
+//! has it, that value is kept.
+//!
+//! | Rule | Result |
+//! |------|--------|
+//! | [`KeepLeft`](MergeRule::KeepLeft) | the left value |
+//! | [`KeepRight`](MergeRule::KeepRight) | the right value (the default, as in `Combinable for Subject`) |
+//! | [`Error`](MergeRule::Error) | fails with [`MergeError::PropertyConflict`] if the values differ |
+//! | [`Accumulate`](MergeRule::Accumulate) | a `VArray` of both values; arrays are concatenated |
+//! | [`Sum`](MergeRule::Sum) | the numeric sum, a decimal if an integer sum overflows |
+//! | [`Max`](MergeRule::Max) / [`Min`](MergeRule::Min) | the larger / smaller number |
+//! | [`UnionMaps`](MergeRule::UnionMaps) | the union of two `VMap`s, recursively; right wins on scalar keys |
+//!
+//! Labels are always unioned. Identities are compared when both are non-empty and
+//! resolved by an [`IdentityRule`].
+//!
+//! # Conflicts
+//!
+//! A [`Conflict`] is recorded whenever both sides have a property with different
+//! values, or two different non-empty identities, and the policy resolved it.
+//! Conflicts are reported in key order.
+//!
+//! # As a `Combinable` Strategy
+//!
+//! [`MergePolicy::strategy`] wraps a subject in a [`Merging`] value, which
+//! implements [`Combinable`] and accumulates conflicts (and the first error) as
+//! it is combined. Every rule is associative except [`Sum`](MergeRule::Sum) once
+//! decimals are involved: decimal addition rounds, and integer sums that
+//! overflow `i64` become decimals, so such sums can depend on grouping. Otherwise
+//! the merged subject does not depend on grouping; which intermediate values are
+//! reported as conflicts may.
+//!
+//! # Merging Documents
+//!
+//! [`merge_documents`] unifies several documents (`Vec<Pattern<Subject>>`) by
+//! identity with any `Combinable` subject strategy, removes duplicate
+//! relationships and returns a [`MergeReport`] of merged, conflicting and new
+//! identities. [`MergePolicy::merge_documents`] does the same with a policy.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use pattern_core::{MergePolicy, MergeRule, Subject, Symbol, Value};
+//! use std::collections::{HashMap, HashSet};
+//!
+//! let source = |visits: i64, email: &str| Subject {
+//!     identity: Symbol("alice".to_string()),
+//!     labels: HashSet::from(["Person".to_string()]),
+//!     properties: HashMap::from([
+//!         ("visits".to_string(), Value::from(visits)),
+//!         ("email".to_string(), Value::from(email)),
+//!     ]),
+//! };
+//!
+//! let policy = MergePolicy::new()
+//!     .rule("visits", MergeRule::Sum)
+//!     .rule("email", MergeRule::Accumulate);
+//!
+//! let merged = policy
+//!     .merge(source(3, "alice@a.org"), source(4, "alice@b.org"))
+//!     .unwrap();
+//!
+//! assert_eq!(merged.subject.get::<i64>("visits"), Some(7));
+//! assert_eq!(merged.subject.get::<Vec<String>>("email").unwrap().len(), 2);
+//! assert_eq!(merged.conflicts.len(), 2);
+//!
+//! let strict = MergePolicy::new().default_rule(MergeRule::Error);
+//! assert!(strict.merge(source(3, "a"), source(4, "a")).is_err());
+//! ```
+
+use crate::pattern::Pattern;
+use crate::subject::{Subject, Symbol, Value};
+use crate::Combinable;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+// ============================================================================
+// Rules
+// ============================================================================
+
+/// How two values of the same property are combined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MergeRule {
+    /// Keep the left (earlier) value
+    KeepLeft,
+    /// Keep the right (later) value
+    KeepRight,
+    /// Fail if the values differ
+    Error,
+    /// Collect both values into a `VArray`, concatenating arrays
+    Accumulate,
+    /// Add numeric values; an integer sum that overflows `i64` is a decimal
+    Sum,
+    /// Keep the larger numeric value
+    Max,
+    /// Keep the smaller numeric value
+    Min,
+    /// Union `VMap` values key by key
+    UnionMaps,
+}
+
+/// How two different non-empty identities are handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IdentityRule {
+    /// Keep the left identity (as in `Combinable for Subject`)
+    KeepLeft,
+    /// Keep the right identity
+    KeepRight,
+    /// Fail with [`MergeError::IdentityConflict`]
+    Error,
+}
+
+// ============================================================================
+// Results and Errors
+// ============================================================================
+
+/// A difference between two merged subjects and how it was resolved.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Conflict {
+    /// The subjects had different non-empty identities
+    Identity {
+        /// Left identity
+        left: Symbol,
+        /// Right identity
+        right: Symbol,
+        /// Identity of the merged subject
+        kept: Symbol,
+    },
+    /// Both subjects had the property, with different values
+    Property {
+        /// The property key
+        key: String,
+        /// Left value
+        left: Value,
+        /// Right value
+        right: Value,
+        /// The rule that resolved the conflict
+        rule: MergeRule,
+        /// Value of the merged property
+        merged: Value,
+    },
+}
+
+/// The result of a successful merge.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Merged {
+    /// The merged subject
+    pub subject: Subject,
+    /// Conflicts resolved by the policy
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Error returned when a merge policy cannot combine two subjects.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MergeError {
+    /// Identities differ and the policy uses [`IdentityRule::Error`]
+    IdentityConflict {
+        /// Left identity
+        left: Symbol,
+        /// Right identity
+        right: Symbol,
+    },
+    /// Property values differ and the key's rule is [`MergeRule::Error`]
+    PropertyConflict {
+        /// The property key
+        key: String,
+        /// Left value
+        left: Box<Value>,
+        /// Right value
+        right: Box<Value>,
+    },
+    /// A value has the wrong type for the key's rule (`Sum`, `Max`, `Min`, `UnionMaps`)
+    TypeMismatch {
+        /// The property key
+        key: String,
+        /// The rule being applied
+        rule: MergeRule,
+        /// Name of the offending value's type
+        actual: &'static str,
+    },
+}
+
+impl fmt::Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MergeError::IdentityConflict { left, right } => {
+                write!(f, "conflicting identities '{}' and '{}'", left, right)
+            }
+            MergeError::PropertyConflict { key, left, right } => write!(
+                f,
+                "property '{}': conflicting values {} and {}",
+                key, left, right
+            ),
+            MergeError::TypeMismatch { key, rule, actual } => {
+                let expected = match rule {
+                    MergeRule::UnionMaps => "map",
+                    _ => "number",
+                };
+                write!(
+                    f,
+                    "property '{}': {:?} expects a {}, found {}",
+                    key, rule, expected, actual
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for MergeError {}
+
+// ============================================================================
+// MergePolicy
+// ============================================================================
+
+/// Per-key rules for merging two subjects.
+///
+/// Built with chained methods starting from [`MergePolicy::new`], which behaves like
+/// `Combinable for Subject`: left identity, right-hand properties win.
+///
+/// See the [module documentation](self) for the rules and an example.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergePolicy {
+    identity: IdentityRule,
+    default: MergeRule,
+    rules: HashMap<String, MergeRule>,
+}
+
+impl Default for MergePolicy {
+    fn default() -> Self {
+        MergePolicy {
+            identity: IdentityRule::KeepLeft,
+            default: MergeRule::KeepRight,
+            rules: HashMap::new(),
+        }
+    }
+}
+
+impl MergePolicy {
+    /// Creates a policy equivalent to `Combinable for Subject`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how differing identities are handled.
+    pub fn identity(mut self, rule: IdentityRule) -> Self {
+        self.identity = rule;
+        self
+    }
+
+    /// Sets the rule for properties without a key-specific rule.
+    pub fn default_rule(mut self, rule: MergeRule) -> Self {
+        self.default = rule;
+        self
+    }
+
+    /// Sets the rule for one property key.
+    pub fn rule(mut self, key: impl Into<String>, rule: MergeRule) -> Self {
+        self.rules.insert(key.into(), rule);
+        self
+    }
+
+    /// Returns the rule applied to `key`.
+    pub fn rule_for(&self, key: &str) -> MergeRule {
+        self.rules.get(key).copied().unwrap_or(self.default)
+    }
+
+    /// Merges two subjects, reporting the conflicts that were resolved.
+    ///
+    /// # Errors
+    ///
+    /// Fails on the first conflict governed by an `Error` rule, or if a numeric or
+    /// map rule meets a value of another type.
+    pub fn merge(&self, left: Subject, right: Subject) -> Result<Merged, MergeError> {
+        let mut conflicts = Vec::new();
+
+        let identity = if right.identity.0.is_empty() || left.identity == right.identity {
+            left.identity
+        } else if left.identity.0.is_empty() {
+            right.identity
+        } else {
+            let kept = match self.identity {
+                IdentityRule::KeepLeft => left.identity.clone(),
+                IdentityRule::KeepRight => right.identity.clone(),
+                IdentityRule::Error => {
+                    return Err(MergeError::IdentityConflict {
+                        left: left.identity,
+                        right: right.identity,
+                    })
+                }
+            };
+            conflicts.push(Conflict::Identity {
+                left: left.identity,
+                right: right.identity,
+                kept: kept.clone(),
+            });
+            kept
+        };
+
+        let labels: HashSet<String> = left.labels.union(&right.labels).cloned().collect();
+
+        let mut properties = left.properties;
+        let mut right_properties: Vec<(String, Value)> = right.properties.into_iter().collect();
+        right_properties.sort_by(|a, b| a.0.cmp(&b.0));
+        for (key, r) in right_properties {
+            let Some(l) = properties.remove(&key) else {
+                properties.insert(key, r);
+                continue;
+            };
+            let rule = self.rule_for(&key);
+            let merged = merge_values(&key, rule, &l, &r)?;
+            if l != r {
+                conflicts.push(Conflict::Property {
+                    key: key.clone(),
+                    left: l,
+                    right: r,
+                    rule,
+                    merged: merged.clone(),
+                });
+            }
+            properties.insert(key, merged);
+        }
+
+        Ok(Merged {
+            subject: Subject {
+                identity,
+                labels,
+                properties,
+            },
+            conflicts,
+        })
+    }
+
+    /// Wraps `subject` for use as a [`Combinable`] value governed by this policy.
+    pub fn strategy(&self, subject: Subject) -> Merging<'_> {
+        Merging {
+            policy: self,
+            subject,
+            conflicts: Vec::new(),
+            error: None,
+        }
+    }
+}
+
+fn merge_values(
+    key: &str,
+    rule: MergeRule,
+    left: &Value,
+    right: &Value,
+) -> Result<Value, MergeError> {
+    let mismatch = |value: &Value| MergeError::TypeMismatch {
+        key: key.to_string(),
+        rule,
+        actual: value.type_name(),
+    };
+    match rule {
+        MergeRule::KeepLeft => Ok(left.clone()),
+        MergeRule::KeepRight => Ok(right.clone()),
+        MergeRule::Error if left == right => Ok(left.clone()),
+        MergeRule::Error => Err(MergeError::PropertyConflict {
+            key: key.to_string(),
+            left: Box::new(left.clone()),
+            right: Box::new(right.clone()),
+        }),
+        MergeRule::Accumulate => {
+            let items = |value: &Value| match value {
+                Value::VArray(items) => items.clone(),
+                other => vec![other.clone()],
+            };
+            let mut merged = items(left);
+            merged.extend(items(right));
+            Ok(Value::VArray(merged))
+        }
+        MergeRule::Sum | MergeRule::Max | MergeRule::Min => {
+            if let Some(other) = [left, right].into_iter().find(|v| !is_numeric(v)) {
+                return Err(mismatch(other));
+            }
+            Ok(match (rule, left, right) {
+                (MergeRule::Sum, Value::VInteger(l), Value::VInteger(r)) => match l.checked_add(*r)
+                {
+                    Some(sum) => Value::VInteger(sum),
+                    None => Value::VDecimal(*l as f64 + *r as f64),
+                },
+                (MergeRule::Sum, l, r) => Value::VDecimal(as_f64(l) + as_f64(r)),
+                // Ties keep the left value, so integer/decimal variants are stable
+                (MergeRule::Max, l, r) if as_f64(r) > as_f64(l) => r.clone(),
+                (MergeRule::Min, l, r) if as_f64(r) < as_f64(l) => r.clone(),
+                (_, l, _) => l.clone(),
+            })
+        }
+        MergeRule::UnionMaps => match (left, right) {
+            (Value::VMap(l), Value::VMap(r)) => Ok(Value::VMap(union_maps(l, r))),
+            (Value::VMap(_), other) | (other, _) => Err(mismatch(other)),
+        },
+    }
+}
+
+fn is_numeric(value: &Value) -> bool {
+    matches!(value, Value::VInteger(_) | Value::VDecimal(_))
+}
+
+fn as_f64(value: &Value) -> f64 {
+    match value {
+        Value::VInteger(i) => *i as f64,
+        Value::VDecimal(d) => *d,
+        _ => 0.0,
+    }
+}
+
+fn union_maps(
+    left: &HashMap<String, Value>,
+    right: &HashMap<String, Value>,
+) -> HashMap<String, Value> {
+    let mut merged = left.clone();
+    for (key, r) in right {
+        let value = match (merged.get(key), r) {
+            (Some(Value::VMap(l)), Value::VMap(r)) => Value::VMap(union_maps(l, r)),
+            _ => r.clone(),
+        };
+        merged.insert(key.clone(), value);
+    }
+    merged
+}
+
+// ============================================================================
+// Merging (Combinable strategy)
+// ============================================================================
+
+/// A subject being merged under a [`MergePolicy`], usable wherever a
+/// [`Combinable`] value is expected.
+///
+/// Created by [`MergePolicy::strategy`]. Combining two `Merging` values merges their
+/// subjects, keeps the conflicts reported so far, and remembers the first error;
+/// after an error the left subject is carried through unchanged. Call
+/// [`finish`](Merging::finish) to get the result.
+///
+/// Both values must come from the same policy; the left value's policy is used.
+///
+/// # Examples
+///
+/// ```rust
+/// use pattern_core::{Combinable, MergePolicy, MergeRule, Subject, Symbol, Value};
+/// use std::collections::{HashMap, HashSet};
+///
+/// let count = |n: i64| Subject {
+///     identity: Symbol("page".to_string()),
+///     labels: HashSet::new(),
+///     properties: HashMap::from([("views".to_string(), Value::from(n))]),
+/// };
+///
+/// let policy = MergePolicy::new().rule("views", MergeRule::Sum);
+/// let merged = [count(1), count(2), count(3)]
+///     .into_iter()
+///     .map(|s| policy.strategy(s))
+///     .reduce(Combinable::combine)
+///     .unwrap()
+///     .finish()
+///     .unwrap();
+///
+/// assert_eq!(merged.subject.get::<i64>("views"), Some(6));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Merging<'p> {
+    policy: &'p MergePolicy,
+    subject: Subject,
+    conflicts: Vec<Conflict>,
+    error: Option<MergeError>,
+}
+
+impl<'p> Merging<'p> {
+    /// Returns the subject merged so far.
+    pub fn subject(&self) -> &Subject {
+        &self.subject
+    }
+
+    /// Returns the conflicts resolved so far.
+    pub fn conflicts(&self) -> &[Conflict] {
+        &self.conflicts
+    }
+
+    /// Returns the merged subject and its conflicts, or the first error.
+    pub fn finish(self) -> Result<Merged, MergeError> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(Merged {
+                subject: self.subject,
+                conflicts: self.conflicts,
+            }),
+        }
+    }
+}
+
+impl<'p> Combinable for Merging<'p> {
+    fn combine(mut self, other: Self) -> Self {
+        self.conflicts.extend(other.conflicts);
+        if self.error.is_some() {
+            return self;
+        }
+        if let Some(error) = other.error {
+            self.error = Some(error);
+            return self;
+        }
+        // Keep a copy so the left subject survives a failed merge
+        match self.policy.merge(self.subject.clone(), other.subject) {
+            Ok(merged) => {
+                self.subject = merged.subject;
+                self.conflicts.extend(merged.conflicts);
+            }
+            Err(error) => self.error = Some(error),
+        }
+        self
+    }
+}
+
+// ============================================================================
+// Document Merge
+// ============================================================================
+
+/// Summary of a [`merge_documents`] run.
+///
+/// Identities are listed in the order they first occur across the inputs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MergeReport {
+    /// Identities that occur in more than one input document
+    pub merged: Vec<Symbol>,
+    /// Identities whose occurrences give some property different values
+    pub conflicting: Vec<Symbol>,
+    /// Identities that do not occur in the first document
+    pub new: Vec<Symbol>,
+    /// Number of top-level patterns not written on their own: anonymous
+    /// patterns equal to an earlier one, and identified patterns folded into
+    /// the earlier pattern with their identity, whether or not they added
+    /// elements to it
+    pub folded: usize,
+}
+
+/// The result of merging several documents: the unified patterns and a report.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocumentMerge {
+    /// The merged document
+    pub patterns: Vec<Pattern<Subject>>,
+    /// What the merge unified, and where sources disagreed
+    pub report: MergeReport,
+}
+
+/// Merges several gram documents into one, unifying subjects by identity.
+///
+/// Every occurrence of a non-empty identity, in any document, contributes to a
+/// single merged subject. Occurrences are combined in document order using the
+/// `Combinable` strategy that `strategy` wraps each subject in, e.g. `Subject`
+/// itself (via [`std::convert::identity`]), [`FirstSubject`](crate::FirstSubject)
+/// or [`LastSubject`](crate::LastSubject). The merged subject always keeps the
+/// identity it was grouped by. To merge with a [`MergePolicy`], use
+/// [`MergePolicy::merge_documents`].
+///
+/// The output contains the top-level patterns of all documents in order, with
+/// duplicates removed:
+///
+/// - Patterns whose top-level subject has an identity are merged by that
+///   identity: the first occurrence is kept and elements of later ones are
+///   appended unless already present.
+/// - Anonymous patterns, such as `(a)-->(b)`, are dropped if an earlier pattern
+///   has the same structure, with identified subjects compared by identity and
+///   anonymous ones by labels and properties.
+///
+/// Each merged subject is written out once, at its first occurrence that was a
+/// definition (had labels or properties) in its input; every other occurrence
+/// becomes a bare reference to its identity.
+///
+/// # Examples
+///
+/// ```rust
+/// use pattern_core::{merge_documents, Pattern, Subject, Symbol, Value};
+/// use std::collections::{HashMap, HashSet};
+///
+/// let node = |id: &str, label: &str, props: Vec<(&str, Value)>| {
+///     Pattern::point(Subject {
+///         identity: Symbol(id.to_string()),
+///         labels: HashSet::from([label.to_string()]),
+///         properties: props.into_iter().map(|(k, v)| (k.to_string(), v)).collect(),
+///     })
+/// };
+///
+/// let first = vec![node("alice", "Person", vec![("age", Value::from(34))])];
+/// let second = vec![
+///     node("alice", "Employee", vec![("age", Value::from(35))]),
+///     node("bob", "Person", vec![]),
+/// ];
+///
+/// let merge = merge_documents(&[first, second], std::convert::identity);
+///
+/// assert_eq!(merge.patterns.len(), 2);
+/// let alice = &merge.patterns[0].value;
+/// assert_eq!(alice.labels.len(), 2);
+/// assert_eq!(alice.get::<i64>("age"), Some(35));
+/// assert_eq!(merge.report.merged, vec![Symbol("alice".to_string())]);
+/// assert_eq!(merge.report.conflicting, vec![Symbol("alice".to_string())]);
+/// assert_eq!(merge.report.new, vec![Symbol("bob".to_string())]);
+/// ```
+pub fn merge_documents<S>(
+    documents: &[Vec<Pattern<Subject>>],
+    strategy: impl Fn(Subject) -> S,
+) -> DocumentMerge
+where
+    S: Combinable + Into<Subject>,
+{
+    let merge: Result<DocumentMerge, std::convert::Infallible> =
+        merge_documents_with(documents, strategy, |merged| Ok(merged.into()));
+    match merge {
+        Ok(merge) => merge,
+        Err(never) => match never {},
+    }
+}
+
+impl MergePolicy {
+    /// Merges several gram documents into one, combining subjects that share an
+    /// identity with this policy.
+    ///
+    /// See [`merge_documents`] for how patterns are unified. Fails with the
+    /// first error the policy raises.
+    pub fn merge_documents(
+        &self,
+        documents: &[Vec<Pattern<Subject>>],
+    ) -> Result<DocumentMerge, MergeError> {
+        merge_documents_with(
+            documents,
+            |subject| self.strategy(subject),
+            |merging| merging.finish().map(|merged| merged.subject),
+        )
+    }
+}
+
+/// Every occurrence of one identity across the input documents.
+struct Occurrences<S> {
+    merged: Option<S>,
+    documents: Vec<usize>,
+    values: HashMap<String, Value>,
+    conflicting: bool,
+}
+
+/// Identity-aware structural key of a pattern, used to detect duplicates.
+#[derive(PartialEq, Eq, Hash)]
+struct Key {
+    root: Root,
+    elements: Vec<Key>,
+}
+
+#[derive(PartialEq, Eq, Hash)]
+enum Root {
+    Identity(Symbol),
+    Anonymous(Vec<String>, Vec<(String, Value)>),
+}
+
+/// A top-level pattern of the output, with the keys of its elements.
+struct Entry {
+    pattern: Pattern<Subject>,
+    elements: HashSet<Key>,
+}
+
+fn merge_documents_with<S, E>(
+    documents: &[Vec<Pattern<Subject>>],
+    wrap: impl Fn(Subject) -> S,
+    finish: impl Fn(S) -> Result<Subject, E>,
+) -> Result<DocumentMerge, E>
+where
+    S: Combinable,
+{
+    // Combine every occurrence of each identity, in document order
+    let mut order: Vec<Symbol> = Vec::new();
+    let mut occurrences: HashMap<Symbol, Occurrences<S>> = HashMap::new();
+    for (index, document) in documents.iter().enumerate() {
+        for pattern in document {
+            for subject in pattern.values() {
+                if subject.identity.0.is_empty() {
+                    continue;
+                }
+                let entry = occurrences
+                    .entry(subject.identity.clone())
+                    .or_insert_with(|| {
+                        order.push(subject.identity.clone());
+                        Occurrences {
+                            merged: None,
+                            documents: Vec::new(),
+                            values: HashMap::new(),
+                            conflicting: false,
+                        }
+                    });
+                let wrapped = wrap(subject.clone());
+                entry.merged = Some(match entry.merged.take() {
+                    Some(merged) => merged.combine(wrapped),
+                    None => wrapped,
+                });
+                if entry.documents.last() != Some(&index) {
+                    entry.documents.push(index);
+                }
+                for (key, value) in &subject.properties {
+                    match entry.values.get(key) {
+                        Some(seen) => entry.conflicting |= seen != value,
+                        None => {
+                            entry.values.insert(key.clone(), value.clone());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let mut report = MergeReport::default();
+    let mut subjects: HashMap<Symbol, Subject> = HashMap::new();
+    for identity in order {
+        let occurrence = occurrences
+            .remove(&identity)
+            .expect("every identity in order has occurrences");
+        if occurrence.documents.len() > 1 {
+            report.merged.push(identity.clone());
+        }
+        if occurrence.conflicting {
+            report.conflicting.push(identity.clone());
+        }
+        if occurrence.documents[0] > 0 {
+            report.new.push(identity.clone());
+        }
+        let merged = occurrence
+            .merged
+            .expect("every identity has at least one occurrence");
+        let mut subject = finish(merged)?;
+        subject.identity = identity.clone();
+        subjects.insert(identity, subject);
+    }
+
+    // Unify top-level patterns by identity and drop anonymous duplicates
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut by_identity: HashMap<Symbol, usize> = HashMap::new();
+    let mut anonymous: HashSet<Key> = HashSet::new();
+    for pattern in documents.iter().flatten() {
+        let identity = &pattern.value.identity;
+        if identity.0.is_empty() {
+            if anonymous.insert(key(pattern)) {
+                entries.push(entry(pattern));
+            } else {
+                report.folded += 1;
+            }
+            continue;
+        }
+        match by_identity.get(identity) {
+            Some(&index) => {
+                let existing = &mut entries[index];
+                for element in &pattern.elements {
+                    if existing.elements.insert(key(element)) {
+                        existing.pattern.elements.push(element.clone());
+                    }
+                }
+                report.folded += 1;
+            }
+            None => {
+                by_identity.insert(identity.clone(), entries.len());
+                entries.push(entry(pattern));
+            }
+        }
+    }
+
+    // Write each merged subject once, preferably where a definition was
+    let defined: HashSet<&Symbol> = entries
+        .iter()
+        .flat_map(|entry| entry.pattern.values())
+        .filter(|subject| !is_reference(subject))
+        .map(|subject| &subject.identity)
+        .collect();
+    let mut written: HashSet<Symbol> = HashSet::new();
+    let patterns = entries
+        .iter()
+        .map(|entry| rewrite(&entry.pattern, &subjects, &defined, &mut written))
+        .collect();
+
+    Ok(DocumentMerge { patterns, report })
+}
+
+fn entry(pattern: &Pattern<Subject>) -> Entry {
+    Entry {
+        pattern: pattern.clone(),
+        elements: pattern.elements.iter().map(key).collect(),
+    }
+}
+
+fn key(pattern: &Pattern<Subject>) -> Key {
+    let subject = &pattern.value;
+    let root = if subject.identity.0.is_empty() {
+        let mut labels: Vec<String> = subject.labels.iter().cloned().collect();
+        labels.sort();
+        let mut properties: Vec<(String, Value)> = subject
+            .properties
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        properties.sort();
+        Root::Anonymous(labels, properties)
+    } else {
+        Root::Identity(subject.identity.clone())
+    };
+    Key {
+        root,
+        elements: pattern.elements.iter().map(key).collect(),
+    }
+}
+
+/// Whether `subject` only refers to its identity, without defining anything.
+pub(crate) fn is_reference(subject: &Subject) -> bool {
+    subject.labels.is_empty() && subject.properties.is_empty()
+}
+
+fn rewrite(
+    pattern: &Pattern<Subject>,
+    subjects: &HashMap<Symbol, Subject>,
+    defined: &HashSet<&Symbol>,
+    written: &mut HashSet<Symbol>,
+) -> Pattern<Subject> {
+    let subject = &pattern.value;
+    let value = match subjects.get(&subject.identity) {
+        Some(merged)
+            if !written.contains(&subject.identity)
+                && (!is_reference(subject) || !defined.contains(&subject.identity)) =>
+        {
+            written.insert(subject.identity.clone());
+            merged.clone()
+        }
+        Some(_) => Subject {
+            identity: subject.identity.clone(),
+            labels: HashSet::new(),
+            properties: HashMap::new(),
+        },
+        None => subject.clone(),
+    };
+    let elements = pattern
+        .elements
+        .iter()
+        .map(|element| rewrite(element, subjects, defined, written))
+        .collect();
+    Pattern::pattern(value, elements)
+}
@@ -108,7 +108,8 @@ use std::hash::{Hash, Hasher};
 /// - `Hash`: Patterns can be hashed when `V: Hash` for use in HashMap/HashSet
 ///   - Enables pattern deduplication and caching
 ///   - Structure-preserving: different structures produce different hashes
-///   - Note: `Pattern<Subject>` is NOT hashable (Subject contains f64)
+///   - `Pattern<Subject>` is hashable and ordered: `Subject` and `Value` use canonical float
+///     semantics (see [`Value`](crate::Value))
 /// - `Debug`: Structured representation for debugging (with truncation for deep nesting)
 /// - `Display`: Human-readable representation
 ///
@@ -3034,11 +3035,13 @@ where
 /// - ✅ `Pattern<String>` is hashable (String implements Hash)
 /// - ✅ `Pattern<Symbol>` is hashable (Symbol implements Hash)
 /// - ✅ `Pattern<i32>` is hashable (integers implement Hash)
-/// - ❌ `Pattern<Subject>` is NOT hashable (Subject contains f64)
+/// - ✅ `Pattern<Subject>` is hashable (Subject and Value hash floats canonically)
 /// - ❌ `Pattern<f64>` is NOT hashable (floats don't implement Hash)
 ///
 /// This is correct behavior - the type system prevents hashing types that
-/// shouldn't be hashed due to problematic equality semantics (e.g., NaN != NaN for floats).
+/// shouldn't be hashed due to problematic equality semantics (e.g., NaN != NaN for raw
+/// floats). `Value` avoids the problem by defining a total float equality in which
+/// all NaNs are equal and `-0.0 == 0.0`.
 ///
 /// # Examples
 ///
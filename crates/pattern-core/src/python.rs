@@ -1260,6 +1260,45 @@ impl PyPatternSubject {
         Ok(found.map(|p| PyPatternSubject { pattern: p.clone() }))
     }
 
+    /// Filter subpatterns whose Subject satisfies a predicate expression.
+    ///
+    /// Args:
+    ///     expr (str): Expression such as ":Person AND age >= 30"
+    ///
+    /// Returns:
+    ///     List[PatternSubject]: Matching subpatterns in pre-order
+    ///
+    /// Raises:
+    ///     ValueError: If the expression is malformed or a comparison has mismatched types
+    fn filter_expr(&self, expr: &str) -> PyResult<Vec<PyPatternSubject>> {
+        let filtered = self
+            .pattern
+            .filter_expr(expr)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(filtered
+            .into_iter()
+            .map(|p| PyPatternSubject { pattern: p.clone() })
+            .collect())
+    }
+
+    /// Find the first subpattern whose Subject satisfies a predicate expression.
+    ///
+    /// Args:
+    ///     expr (str): Expression such as "name =~ 'Al.*'"
+    ///
+    /// Returns:
+    ///     Optional[PatternSubject]: First match in pre-order, or None
+    ///
+    /// Raises:
+    ///     ValueError: If the expression is malformed or a comparison has mismatched types
+    fn find_first_expr(&self, expr: &str) -> PyResult<Option<PyPatternSubject>> {
+        let found = self
+            .pattern
+            .find_first_expr(expr)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(found.map(|p| PyPatternSubject { pattern: p.clone() }))
+    }
+
     /// Check if patterns have identical structure.
     fn matches(&self, other: &PyPatternSubject) -> bool {
         self.pattern.matches(&other.pattern)
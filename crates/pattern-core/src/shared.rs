@@ -0,0 +1,287 @@
+//! Persistent pattern with structural sharing
+//!
+//! This module provides [`SharedPattern<V>`], an immutable counterpart to
+//! [`Pattern<V>`] whose nodes are reference-counted with [`Arc`].
+//!
+//! # Why a separate type
+//!
+//! `Pattern<V>` owns its elements in a `Vec`, so keeping an old version of a
+//! large pattern around while editing a copy requires cloning the whole tree.
+//! `SharedPattern<V>` instead:
+//!
+//! - clones in O(1) (one reference count increment),
+//! - updates by *path copying*: only the nodes on the path from the root to the
+//!   edited node are rebuilt, every other subtree is shared with the previous
+//!   version,
+//! - is `Send + Sync` whenever `V` is, so snapshots can be handed to concurrent
+//!   readers.
+//!
+//! This makes it suitable for undo histories and snapshot isolation.
+//!
+//! # Paths
+//!
+//! Nodes are addressed by paths of element indices relative to the pattern the
+//! method is called on: `&[]` is the pattern itself, `&[1]` its second element,
+//! `&[1, 0]` the first element of that, and so on.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use pattern_core::{Pattern, SharedPattern};
+//!
+//! let original = SharedPattern::from(Pattern::pattern("root", vec![
+//!     Pattern::point("a"),
+//!     Pattern::point("b"),
+//! ]));
+//!
+//! let edited = original.update_value(&[1], |_| "B").unwrap();
+//!
+//! // The old version is untouched...
+//! assert_eq!(original.get(&[1]).unwrap().value(), &"b");
+//! // ...and the unchanged element is shared, not copied
+//! assert!(SharedPattern::ptr_eq(&original.elements()[0], &edited.elements()[0]));
+//! ```
+
+use crate::pattern::Pattern;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// An immutable, reference-counted pattern supporting cheap snapshots.
+///
+/// See the [module documentation](self) for an overview.
+///
+/// # Complexity
+///
+/// - `clone`: O(1)
+/// - `get`: O(d) for a path of length d
+/// - `set`, `update`, `update_value`: O(d · w), where w is the number of
+///   elements of each node on the path (their `Arc`s are copied, not their contents)
+pub struct SharedPattern<V> {
+    node: Arc<Node<V>>,
+}
+
+struct Node<V> {
+    value: V,
+    elements: Vec<SharedPattern<V>>,
+}
+
+impl<V> SharedPattern<V> {
+    /// Creates an atomic pattern (no elements).
+    pub fn point(value: V) -> Self {
+        Self::pattern(value, Vec::new())
+    }
+
+    /// Creates a pattern from a value and its elements.
+    pub fn pattern(value: V, elements: Vec<SharedPattern<V>>) -> Self {
+        SharedPattern {
+            node: Arc::new(Node { value, elements }),
+        }
+    }
+
+    /// Returns a reference to the pattern's value.
+    pub fn value(&self) -> &V {
+        &self.node.value
+    }
+
+    /// Returns the pattern's elements.
+    pub fn elements(&self) -> &[SharedPattern<V>] {
+        &self.node.elements
+    }
+
+    /// Returns the number of direct elements.
+    pub fn length(&self) -> usize {
+        self.node.elements.len()
+    }
+
+    /// Returns the total number of nodes, counting shared subtrees once per occurrence.
+    pub fn size(&self) -> usize {
+        1 + self.elements().iter().map(|e| e.size()).sum::<usize>()
+    }
+
+    /// Returns the maximum nesting depth. Atomic patterns have depth 0.
+    pub fn depth(&self) -> usize {
+        self.elements()
+            .iter()
+            .map(|e| 1 + e.depth())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Checks if the pattern has no elements.
+    pub fn is_atomic(&self) -> bool {
+        self.node.elements.is_empty()
+    }
+
+    /// Returns `true` if both patterns are the same allocation.
+    ///
+    /// This is how callers can observe which subtrees two versions share.
+    /// Pointer-equal patterns are equal unless a value is not equal to itself,
+    /// such as a NaN.
+    pub fn ptr_eq(a: &Self, b: &Self) -> bool {
+        Arc::ptr_eq(&a.node, &b.node)
+    }
+
+    /// Returns the subpattern at `path`, or `None` if the path is out of range.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use pattern_core::SharedPattern;
+    ///
+    /// let p = SharedPattern::pattern(0, vec![
+    ///     SharedPattern::pattern(1, vec![SharedPattern::point(2)]),
+    /// ]);
+    /// assert_eq!(p.get(&[0, 0]).map(|n| *n.value()), Some(2));
+    /// assert!(p.get(&[1]).is_none());
+    /// ```
+    pub fn get(&self, path: &[usize]) -> Option<&SharedPattern<V>> {
+        path.iter()
+            .try_fold(self, |node, index| node.elements().get(*index))
+    }
+}
+
+impl<V: Clone> SharedPattern<V> {
+    /// Returns a new version with the subpattern at `path` replaced by `replacement`.
+    ///
+    /// Returns `None` if the path is out of range. An empty path replaces the
+    /// whole pattern.
+    pub fn set(&self, path: &[usize], replacement: SharedPattern<V>) -> Option<Self> {
+        self.update(path, |_| replacement)
+    }
+
+    /// Returns a new version with the subpattern at `path` replaced by `f(old)`.
+    ///
+    /// Only the nodes along `path` are rebuilt; all other subtrees are shared with
+    /// `self`. Returns `None` if the path is out of range.
+    pub fn update<F>(&self, path: &[usize], f: F) -> Option<Self>
+    where
+        F: FnOnce(&SharedPattern<V>) -> SharedPattern<V>,
+    {
+        match path.split_first() {
+            None => Some(f(self)),
+            Some((index, rest)) => {
+                let updated = self.elements().get(*index)?.update(rest, f)?;
+                let mut elements = self.node.elements.clone();
+                elements[*index] = updated;
+                Some(Self::pattern(self.node.value.clone(), elements))
+            }
+        }
+    }
+
+    /// Returns a new version with the value at `path` replaced by `f(old)`.
+    ///
+    /// The elements of the edited node are shared, not copied.
+    pub fn update_value<F>(&self, path: &[usize], f: F) -> Option<Self>
+    where
+        F: FnOnce(&V) -> V,
+    {
+        self.update(path, |node| {
+            Self::pattern(f(node.value()), node.node.elements.clone())
+        })
+    }
+
+    /// Returns a new version with `element` inserted at position `index` of the
+    /// node at `path`.
+    ///
+    /// Returns `None` if the path is out of range or `index` exceeds the node's length.
+    pub fn insert_element(
+        &self,
+        path: &[usize],
+        index: usize,
+        element: SharedPattern<V>,
+    ) -> Option<Self> {
+        let target = self.get(path)?;
+        if index > target.length() {
+            return None;
+        }
+        self.update(path, |node| {
+            let mut elements = node.node.elements.clone();
+            elements.insert(index, element);
+            Self::pattern(node.value().clone(), elements)
+        })
+    }
+
+    /// Returns a new version with the element at position `index` of the node at
+    /// `path` removed.
+    ///
+    /// Returns `None` if the path or index is out of range.
+    pub fn remove_element(&self, path: &[usize], index: usize) -> Option<Self> {
+        let target = self.get(path)?;
+        if index >= target.length() {
+            return None;
+        }
+        self.update(path, |node| {
+            let mut elements = node.node.elements.clone();
+            elements.remove(index);
+            Self::pattern(node.value().clone(), elements)
+        })
+    }
+
+    /// Converts to an owned [`Pattern<V>`], cloning every value.
+    pub fn to_pattern(&self) -> Pattern<V> {
+        Pattern::pattern(
+            self.node.value.clone(),
+            self.elements().iter().map(|e| e.to_pattern()).collect(),
+        )
+    }
+}
+
+impl<V> Clone for SharedPattern<V> {
+    fn clone(&self) -> Self {
+        SharedPattern {
+            node: Arc::clone(&self.node),
+        }
+    }
+}
+
+impl<V> From<Pattern<V>> for SharedPattern<V> {
+    fn from(pattern: Pattern<V>) -> Self {
+        SharedPattern::pattern(
+            pattern.value,
+            pattern
+                .elements
+                .into_iter()
+                .map(SharedPattern::from)
+                .collect(),
+        )
+    }
+}
+
+impl<V: Clone> From<&SharedPattern<V>> for Pattern<V> {
+    fn from(shared: &SharedPattern<V>) -> Self {
+        shared.to_pattern()
+    }
+}
+
+impl<V: Clone> From<SharedPattern<V>> for Pattern<V> {
+    fn from(shared: SharedPattern<V>) -> Self {
+        shared.to_pattern()
+    }
+}
+
+impl<V: PartialEq> PartialEq for SharedPattern<V> {
+    fn eq(&self, other: &Self) -> bool {
+        // No shortcut for pointer-equal patterns: with a `V` such as `f64`, a
+        // pattern holding NaN must not equal itself any more than a copy of it
+        self.node.value == other.node.value && self.node.elements == other.node.elements
+    }
+}
+
+impl<V: Eq> Eq for SharedPattern<V> {}
+
+impl<V: Hash> Hash for SharedPattern<V> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.node.value.hash(state);
+        self.node.elements.hash(state);
+    }
+}
+
+impl<V: fmt::Debug> fmt::Debug for SharedPattern<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedPattern")
+            .field("value", &self.node.value)
+            .field("elements", &self.node.elements)
+            .finish()
+    }
+}
@@ -3,7 +3,11 @@
 //! This module provides the Subject type and related types (Symbol, Value, RangeValue, PropertyRecord)
 //! for use as pattern values in `Pattern<Subject>`.
 
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
+use std::hash::{Hash, Hasher};
 
 /// Symbol identifier that uniquely identifies the subject.
 ///
@@ -18,7 +22,7 @@ use std::fmt;
 /// let symbol = Symbol("n".to_string());
 /// assert_eq!(symbol.0, "n");
 /// ```
-#[derive(Clone, PartialEq, Eq, Hash)]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Symbol(pub String);
 
 impl fmt::Debug for Symbol {
@@ -37,8 +41,9 @@ impl fmt::Display for Symbol {
 ///
 /// Used in `Value::VRange` to represent numeric ranges with optional bounds.
 ///
-/// Note: This type only implements `PartialEq`, not `Eq`, because `f64` doesn't implement `Eq`
-/// (due to NaN != NaN).
+/// `RangeValue` implements `Eq`, `Ord` and `Hash` using the canonical float semantics
+/// described on [`Value`]. Bounds are compared lower first; an unbounded side (`None`)
+/// sorts before any bound.
 ///
 /// # Examples
 ///
@@ -63,7 +68,7 @@ impl fmt::Display for Symbol {
 ///     upper: Some(10.0),
 /// };
 /// ```
-#[derive(Clone, PartialEq)]
+#[derive(Clone)]
 pub struct RangeValue {
     /// Lower bound of the range (inclusive), `None` means unbounded below
     pub lower: Option<f64>,
@@ -97,8 +102,24 @@ impl fmt::Display for RangeValue {
 /// It supports standard types (integers, decimals, booleans, strings, symbols) and extended types
 /// (tagged strings, arrays, maps, ranges, measurements).
 ///
-/// Note: This type only implements `PartialEq`, not `Eq`, because it contains `RangeValue`
-/// which uses `f64` (`f64` doesn't implement `Eq` due to NaN != NaN).
+/// # Equality, Ordering and Hashing
+///
+/// `Value` implements `Eq`, `Ord` and `Hash` so that values (and subjects and patterns
+/// containing them) can be used in `HashSet`, `HashMap`, `BTreeSet` and `BTreeMap`.
+/// The three are consistent with each other: `a == b` exactly when
+/// `a.cmp(&b) == Ordering::Equal`, and equal values hash equally.
+///
+/// Floats (in `VDecimal`, `VRange` and `VMeasurement`) are compared canonically:
+///
+/// - `-0.0` equals `0.0`
+/// - every NaN equals every other NaN (so equality is reflexive)
+/// - the order is `-inf < ... < 0.0 < ... < +inf < NaN`
+///
+/// Values of different variants are never equal (`VInteger(1) != VDecimal(1.0)`), and
+/// are ordered by variant in declaration order: `VInteger < VDecimal < VBoolean <
+/// VString < VSymbol < VTaggedString < VArray < VMap < VRange < VMeasurement`.
+/// Maps compare by their entries sorted by key, and hash independently of
+/// iteration order.
 ///
 /// # Examples
 ///
@@ -122,7 +143,7 @@ impl fmt::Display for RangeValue {
 ///     Value::VInteger(2),
 /// ]);
 /// ```
-#[derive(Clone, PartialEq)]
+#[derive(Clone)]
 pub enum Value {
     /// Integer value (i64)
     VInteger(i64),
@@ -245,8 +266,11 @@ pub type PropertyRecord = std::collections::HashMap<String, Value>;
 /// - **Labels**: A set of label strings that categorize or classify the subject
 /// - **Properties**: A key-value map storing properties with rich value types
 ///
-/// Note: This type only implements `PartialEq`, not `Eq`, because it contains `Value`
-/// which uses `f64` (`f64` doesn't implement `Eq` due to NaN != NaN).
+/// `Subject` implements `Eq`, `Ord` and `Hash`, so `Pattern<Subject>` can be hashed,
+/// sorted and deduplicated. Subjects are ordered by identity, then by their sorted
+/// labels, then by their properties sorted by key (values use the canonical ordering
+/// described on [`Value`]). Hashing does not depend on `HashSet`/`HashMap`
+/// iteration order.
 ///
 /// # Examples
 ///
@@ -287,7 +311,7 @@ pub type PropertyRecord = std::collections::HashMap<String, Value>;
 ///     elements: vec![],
 /// };
 /// ```
-#[derive(Clone, PartialEq)]
+#[derive(Clone, PartialEq, Eq)]
 pub struct Subject {
     /// Symbol identifier that uniquely identifies the subject.
     ///
@@ -352,3 +376,266 @@ impl fmt::Display for Subject {
         Ok(())
     }
 }
+
+// ============================================================================
+// Total Equality, Ordering and Hashing
+// ============================================================================
+
+/// Maps every NaN to one NaN and `-0.0` to `0.0`, so that `total_cmp` and the bit
+/// pattern agree with the documented float equality.
+///
+/// Floats that are equal as [`Value`]s have the same canonical bits, so code
+/// keying values by `to_bits` should canonicalize them first.
+pub fn canonical_f64(x: f64) -> f64 {
+    if x.is_nan() {
+        f64::NAN
+    } else if x == 0.0 {
+        0.0
+    } else {
+        x
+    }
+}
+
+fn cmp_f64(a: f64, b: f64) -> Ordering {
+    canonical_f64(a).total_cmp(&canonical_f64(b))
+}
+
+fn hash_f64<H: Hasher>(x: f64, state: &mut H) {
+    canonical_f64(x).to_bits().hash(state);
+}
+
+fn cmp_opt_f64(a: Option<f64>, b: Option<f64>) -> Ordering {
+    match (a, b) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Less,
+        (Some(_), None) => Ordering::Greater,
+        (Some(a), Some(b)) => cmp_f64(a, b),
+    }
+}
+
+/// Hashes a collection without depending on its iteration order, by summing the
+/// independent hashes of its items.
+fn hash_unordered<T, I, H>(items: I, state: &mut H)
+where
+    T: Hash,
+    I: ExactSizeIterator<Item = T>,
+    H: Hasher,
+{
+    let len = items.len();
+    let sum = items.fold(0u64, |acc, item| {
+        let mut hasher = DefaultHasher::new();
+        item.hash(&mut hasher);
+        acc.wrapping_add(hasher.finish())
+    });
+    len.hash(state);
+    sum.hash(state);
+}
+
+fn sorted_labels(labels: &HashSet<String>) -> Vec<&String> {
+    let mut labels: Vec<&String> = labels.iter().collect();
+    labels.sort_unstable();
+    labels
+}
+
+fn sorted_entries<V>(map: &HashMap<String, V>) -> Vec<(&String, &V)> {
+    let mut entries: Vec<_> = map.iter().collect();
+    entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+    entries
+}
+
+impl PartialEq for RangeValue {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for RangeValue {}
+
+impl PartialOrd for RangeValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for RangeValue {
+    fn cmp(&self, other: &Self) -> Ordering {
+        cmp_opt_f64(self.lower, other.lower).then_with(|| cmp_opt_f64(self.upper, other.upper))
+    }
+}
+
+impl Hash for RangeValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for bound in [self.lower, self.upper] {
+            bound.is_some().hash(state);
+            if let Some(x) = bound {
+                hash_f64(x, state);
+            }
+        }
+    }
+}
+
+impl Value {
+    /// Returns a human-readable name for the value's type, used in error messages.
+    ///
+    /// Names match `gram_codec::Value::type_name` for the variants both types
+    /// share (`"string"`, `"integer"`, `"decimal"`, `"boolean"`, `"array"`,
+    /// `"range"`, `"tagged string"`); gram-codec depends on this crate, so the
+    /// function cannot be reused directly.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use pattern_core::Value;
+    ///
+    /// assert_eq!(Value::VInteger(1).type_name(), "integer");
+    /// assert_eq!(Value::VArray(vec![]).type_name(), "array");
+    /// ```
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::VInteger(_) => "integer",
+            Value::VDecimal(_) => "decimal",
+            Value::VBoolean(_) => "boolean",
+            Value::VString(_) => "string",
+            Value::VSymbol(_) => "symbol",
+            Value::VTaggedString { .. } => "tagged string",
+            Value::VArray(_) => "array",
+            Value::VMap(_) => "map",
+            Value::VRange(_) => "range",
+            Value::VMeasurement { .. } => "measurement",
+        }
+    }
+
+    /// Position of the variant in declaration order, used to order values of
+    /// different variants.
+    fn variant_rank(&self) -> u8 {
+        match self {
+            Value::VInteger(_) => 0,
+            Value::VDecimal(_) => 1,
+            Value::VBoolean(_) => 2,
+            Value::VString(_) => 3,
+            Value::VSymbol(_) => 4,
+            Value::VTaggedString { .. } => 5,
+            Value::VArray(_) => 6,
+            Value::VMap(_) => 7,
+            Value::VRange(_) => 8,
+            Value::VMeasurement { .. } => 9,
+        }
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::VInteger(a), Value::VInteger(b)) => a == b,
+            (Value::VDecimal(a), Value::VDecimal(b)) => cmp_f64(*a, *b).is_eq(),
+            (Value::VBoolean(a), Value::VBoolean(b)) => a == b,
+            (Value::VString(a), Value::VString(b)) => a == b,
+            (Value::VSymbol(a), Value::VSymbol(b)) => a == b,
+            (
+                Value::VTaggedString { tag, content },
+                Value::VTaggedString {
+                    tag: other_tag,
+                    content: other_content,
+                },
+            ) => tag == other_tag && content == other_content,
+            (Value::VArray(a), Value::VArray(b)) => a == b,
+            // HashMap equality is order-independent and uses `Value::eq` for values
+            (Value::VMap(a), Value::VMap(b)) => a == b,
+            (Value::VRange(a), Value::VRange(b)) => a == b,
+            (
+                Value::VMeasurement { unit, value },
+                Value::VMeasurement {
+                    unit: other_unit,
+                    value: other_value,
+                },
+            ) => unit == other_unit && cmp_f64(*value, *other_value).is_eq(),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Value {}
+
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Value {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Value::VInteger(a), Value::VInteger(b)) => a.cmp(b),
+            (Value::VDecimal(a), Value::VDecimal(b)) => cmp_f64(*a, *b),
+            (Value::VBoolean(a), Value::VBoolean(b)) => a.cmp(b),
+            (Value::VString(a), Value::VString(b)) => a.cmp(b),
+            (Value::VSymbol(a), Value::VSymbol(b)) => a.cmp(b),
+            (
+                Value::VTaggedString { tag, content },
+                Value::VTaggedString {
+                    tag: other_tag,
+                    content: other_content,
+                },
+            ) => tag.cmp(other_tag).then_with(|| content.cmp(other_content)),
+            (Value::VArray(a), Value::VArray(b)) => a.cmp(b),
+            (Value::VMap(a), Value::VMap(b)) => sorted_entries(a).cmp(&sorted_entries(b)),
+            (Value::VRange(a), Value::VRange(b)) => a.cmp(b),
+            (
+                Value::VMeasurement { unit, value },
+                Value::VMeasurement {
+                    unit: other_unit,
+                    value: other_value,
+                },
+            ) => unit
+                .cmp(other_unit)
+                .then_with(|| cmp_f64(*value, *other_value)),
+            _ => self.variant_rank().cmp(&other.variant_rank()),
+        }
+    }
+}
+
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.variant_rank().hash(state);
+        match self {
+            Value::VInteger(i) => i.hash(state),
+            Value::VDecimal(d) => hash_f64(*d, state),
+            Value::VBoolean(b) => b.hash(state),
+            Value::VString(s) | Value::VSymbol(s) => s.hash(state),
+            Value::VTaggedString { tag, content } => {
+                tag.hash(state);
+                content.hash(state);
+            }
+            Value::VArray(items) => items.hash(state),
+            Value::VMap(map) => hash_unordered(map.iter(), state),
+            Value::VRange(r) => r.hash(state),
+            Value::VMeasurement { unit, value } => {
+                unit.hash(state);
+                hash_f64(*value, state);
+            }
+        }
+    }
+}
+
+impl PartialOrd for Subject {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Subject {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.identity
+            .cmp(&other.identity)
+            .then_with(|| sorted_labels(&self.labels).cmp(&sorted_labels(&other.labels)))
+            .then_with(|| sorted_entries(&self.properties).cmp(&sorted_entries(&other.properties)))
+    }
+}
+
+impl Hash for Subject {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.identity.hash(state);
+        hash_unordered(self.labels.iter(), state);
+        hash_unordered(self.properties.iter(), state);
+    }
+}
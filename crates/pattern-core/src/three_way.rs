@@ -0,0 +1,592 @@
+//! Three-way merge of `Pattern<Subject>` documents
+//!
+//! When two branches edit the same gram dataset, a line-based merge of the
+//! files can interleave half-written patterns or duplicate subjects. A
+//! [`three_way_merge`] compares both branches with their common base the way
+//! [`diff`](crate::diff::diff) does, by identity, and combines the changes:
+//!
+//! - labels added or removed on either side are applied
+//! - a property changed on one side only takes that side's value
+//! - nodes and relationships added or removed on either side are added or removed
+//! - elements moved into or out of containers on either side are moved
+//!
+//! A change made identically on both sides is applied once.
+//!
+//! # Conflicts
+//!
+//! Changes that overlap are reported as [`MergeConflict`]s and left unresolved
+//! in the merged document:
+//!
+//! | Conflict | Merged document keeps |
+//! |----------|-----------------------|
+//! | [`Property`](MergeConflict::Property): both sides set a property to different values | the base value (absent if the base had none) |
+//! | [`Endpoints`](MergeConflict::Endpoints): both sides rewired a relationship differently | the base endpoints (ours if both sides added it) |
+//! | [`RemovedAndModified`](MergeConflict::RemovedAndModified): one side removed what the other changed or still refers to | the base version |
+//!
+//! [`ThreeWayMerge::annotated`] appends one `Conflict` pattern per conflict to
+//! the merged document, so that conflicts can be written out as gram for
+//! resolution, e.g. `[:Conflict {kind: "property", key: "age", base: 34, ours: 35, theirs: 36} | (alice)]`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use pattern_core::{three_way_merge, MergeConflict, Pattern, Subject, Symbol, Value};
+//! use std::collections::{HashMap, HashSet};
+//!
+//! let person = |labels: &[&str], age: i64, city: &str| {
+//!     vec![Pattern::point(Subject {
+//!         identity: Symbol("alice".to_string()),
+//!         labels: labels.iter().map(|l| l.to_string()).collect(),
+//!         properties: HashMap::from([
+//!             ("age".to_string(), Value::from(age)),
+//!             ("city".to_string(), Value::from(city)),
+//!         ]),
+//!     })]
+//! };
+//!
+//! let base = person(&["Person"], 34, "Auckland");
+//! let ours = person(&["Person", "Employee"], 35, "Auckland");
+//! let theirs = person(&["Person"], 34, "Wellington");
+//!
+//! let merge = three_way_merge(&base, &ours, &theirs);
+//! assert!(merge.is_clean());
+//! let alice = &merge.patterns[0].value;
+//! assert!(alice.labels.contains("Employee"));
+//! assert_eq!(alice.get::<i64>("age"), Some(35));
+//! assert_eq!(alice.get::<String>("city"), Some("Wellington".to_string()));
+//!
+//! let conflicting = person(&["Person"], 36, "Auckland");
+//! let merge = three_way_merge(&base, &ours, &conflicting);
+//! assert!(matches!(&merge.conflicts[0], MergeConflict::Property { key, .. } if key == "age"));
+//! assert_eq!(merge.patterns[0].value.get::<i64>("age"), Some(34));
+//! ```
+
+use crate::diff::{diff_snapshots, Snapshot};
+use crate::pattern::Pattern;
+use crate::subject::{Subject, Symbol, Value};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+// ============================================================================
+// Results
+// ============================================================================
+
+/// One of the two branches being merged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    /// The first changed version
+    Ours,
+    /// The second changed version
+    Theirs,
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Side::Ours => write!(f, "ours"),
+            Side::Theirs => write!(f, "theirs"),
+        }
+    }
+}
+
+/// Endpoints of a relationship: source and target identity.
+pub type Endpoints = (Symbol, Symbol);
+
+/// Overlapping changes that a [`three_way_merge`] could not combine.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MergeConflict {
+    /// Both sides changed a property to different values. `None` means absent.
+    Property {
+        /// The subject's identity
+        identity: Symbol,
+        /// The property key
+        key: String,
+        /// Value in the base version
+        base: Option<Value>,
+        /// Value in our version
+        ours: Option<Value>,
+        /// Value in their version
+        theirs: Option<Value>,
+    },
+    /// Both sides gave a relationship different endpoints, or one made it a node.
+    /// `None` means the identity is not a relationship in that version.
+    Endpoints {
+        /// The relationship's identity
+        identity: Symbol,
+        /// Endpoints in the base version
+        base: Option<Endpoints>,
+        /// Endpoints in our version
+        ours: Option<Endpoints>,
+        /// Endpoints in their version
+        theirs: Option<Endpoints>,
+    },
+    /// One side removed a node or relationship that the other side changed, or
+    /// still uses as an endpoint or container.
+    RemovedAndModified {
+        /// The removed identity
+        identity: Symbol,
+        /// The side that removed it
+        removed_by: Side,
+    },
+}
+
+impl MergeConflict {
+    /// Returns the identity of the conflicting node or relationship.
+    pub fn identity(&self) -> &Symbol {
+        match self {
+            MergeConflict::Property { identity, .. }
+            | MergeConflict::Endpoints { identity, .. }
+            | MergeConflict::RemovedAndModified { identity, .. } => identity,
+        }
+    }
+
+    /// Returns the conflict as an annotated `Conflict` pattern around a reference
+    /// to the conflicting subject.
+    ///
+    /// The pattern's `kind` property is `"property"`, `"endpoints"` or
+    /// `"removed"`; the remaining properties hold the versions, and are left out
+    /// where a version is absent.
+    pub fn to_pattern(&self) -> Pattern<Subject> {
+        let mut properties = HashMap::new();
+        let mut set = |key: &str, value: Option<Value>| {
+            if let Some(value) = value {
+                properties.insert(key.to_string(), value);
+            }
+        };
+        let endpoints = |endpoints: &Option<Endpoints>| {
+            endpoints.as_ref().map(|(source, target)| {
+                Value::VArray(vec![
+                    Value::VString(source.0.clone()),
+                    Value::VString(target.0.clone()),
+                ])
+            })
+        };
+        match self {
+            MergeConflict::Property {
+                key,
+                base,
+                ours,
+                theirs,
+                ..
+            } => {
+                set("kind", Some(Value::from("property")));
+                set("key", Some(Value::from(key.as_str())));
+                set("base", base.clone());
+                set("ours", ours.clone());
+                set("theirs", theirs.clone());
+            }
+            MergeConflict::Endpoints {
+                base, ours, theirs, ..
+            } => {
+                set("kind", Some(Value::from("endpoints")));
+                set("base", endpoints(base));
+                set("ours", endpoints(ours));
+                set("theirs", endpoints(theirs));
+            }
+            MergeConflict::RemovedAndModified { removed_by, .. } => {
+                set("kind", Some(Value::from("removed")));
+                set("removed_by", Some(Value::from(removed_by.to_string())));
+            }
+        }
+        Pattern::pattern(
+            Subject {
+                identity: Symbol(String::new()),
+                labels: HashSet::from(["Conflict".to_string()]),
+                properties,
+            },
+            vec![Pattern::point(Subject {
+                identity: self.identity().clone(),
+                labels: HashSet::new(),
+                properties: HashMap::new(),
+            })],
+        )
+    }
+}
+
+impl fmt::Display for MergeConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = |value: &Option<Value>| {
+            value
+                .as_ref()
+                .map_or_else(|| "(none)".to_string(), |v| v.to_string())
+        };
+        let endpoints = |endpoints: &Option<Endpoints>| {
+            endpoints.as_ref().map_or_else(
+                || "(none)".to_string(),
+                |(source, target)| format!("({})->({})", source, target),
+            )
+        };
+        match self {
+            MergeConflict::Property {
+                identity,
+                key,
+                base,
+                ours,
+                theirs,
+            } => write!(
+                f,
+                "{}.{}: base {}, ours {}, theirs {}",
+                identity,
+                key,
+                value(base),
+                value(ours),
+                value(theirs)
+            ),
+            MergeConflict::Endpoints {
+                identity,
+                base,
+                ours,
+                theirs,
+            } => write!(
+                f,
+                "{}: endpoints base {}, ours {}, theirs {}",
+                identity,
+                endpoints(base),
+                endpoints(ours),
+                endpoints(theirs)
+            ),
+            MergeConflict::RemovedAndModified {
+                identity,
+                removed_by,
+            } => write!(
+                f,
+                "{}: removed by {}, modified by {}",
+                identity,
+                removed_by,
+                match removed_by {
+                    Side::Ours => Side::Theirs,
+                    Side::Theirs => Side::Ours,
+                }
+            ),
+        }
+    }
+}
+
+/// The result of a [`three_way_merge`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThreeWayMerge {
+    /// The merged document, with conflicts left at their base version
+    pub patterns: Vec<Pattern<Subject>>,
+    /// Changes that could not be combined, in identity order of first occurrence
+    pub conflicts: Vec<MergeConflict>,
+}
+
+impl ThreeWayMerge {
+    /// Returns `true` if the merge had no conflicts.
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+
+    /// Returns the merged document followed by one `Conflict` pattern per
+    /// conflict (see [`MergeConflict::to_pattern`]).
+    pub fn annotated(&self) -> Vec<Pattern<Subject>> {
+        self.patterns
+            .iter()
+            .cloned()
+            .chain(self.conflicts.iter().map(MergeConflict::to_pattern))
+            .collect()
+    }
+}
+
+// ============================================================================
+// Merging
+// ============================================================================
+
+/// Merges the changes that `ours` and `theirs` each made to `base`.
+///
+/// The merged document is `base` with the combined changes applied, so its
+/// layout follows the base version. See the [module documentation](self) for
+/// how changes are combined and conflicts are resolved.
+pub fn three_way_merge(
+    base: &[Pattern<Subject>],
+    ours: &[Pattern<Subject>],
+    theirs: &[Pattern<Subject>],
+) -> ThreeWayMerge {
+    let versions = [
+        Snapshot::build(base),
+        Snapshot::build(ours),
+        Snapshot::build(theirs),
+    ];
+    let [b, o, t] = &versions;
+    let mut identities: Vec<&Symbol> = Vec::new();
+    let mut seen: HashSet<&Symbol> = HashSet::new();
+    for identity in versions.iter().flat_map(|v| v.order.iter()) {
+        if seen.insert(identity) {
+            identities.push(identity);
+        }
+    }
+
+    let mut merged = Snapshot::default();
+    let mut conflicts = Vec::new();
+    for &identity in &identities {
+        let entity = match (
+            b.subjects.get(identity),
+            o.subjects.get(identity),
+            t.subjects.get(identity),
+        ) {
+            (Some(_), None, None) => continue,
+            (Some(_), None, Some(_)) => {
+                if !modified(identity, b, t) {
+                    continue;
+                }
+                conflicts.push(MergeConflict::RemovedAndModified {
+                    identity: identity.clone(),
+                    removed_by: Side::Ours,
+                });
+                entity(identity, b)
+            }
+            (Some(_), Some(_), None) => {
+                if !modified(identity, b, o) {
+                    continue;
+                }
+                conflicts.push(MergeConflict::RemovedAndModified {
+                    identity: identity.clone(),
+                    removed_by: Side::Theirs,
+                });
+                entity(identity, b)
+            }
+            (_, Some(_), Some(_)) => merge_entity(identity, &versions, &mut conflicts),
+            (None, Some(_), None) => entity(identity, o),
+            (None, None, Some(_)) => entity(identity, t),
+            (None, None, None) => unreachable!("identity occurs in some version"),
+        };
+        insert(&mut merged, identity, entity);
+    }
+
+    for relationship in versions.iter().flat_map(|v| v.anonymous.iter()) {
+        let present = |v: &Snapshot| v.has_anonymous(relationship);
+        if pick(present(b), present(o), present(t)) {
+            merged.add_anonymous(relationship.clone());
+        }
+    }
+
+    for &member in &identities {
+        if merged.subjects.contains_key(member) {
+            add_containers(&mut merged, member, &versions);
+        }
+    }
+
+    // Keep removed identities that the merged document still refers to
+    loop {
+        let dangling: Vec<Symbol> = references(&merged)
+            .into_iter()
+            .filter(|identity| !identity.0.is_empty() && !merged.subjects.contains_key(identity))
+            .cloned()
+            .collect();
+        if dangling.is_empty() {
+            break;
+        }
+        for identity in dangling {
+            if merged.subjects.contains_key(&identity) {
+                continue;
+            }
+            let removed_by = if o.subjects.contains_key(&identity) {
+                Side::Theirs
+            } else {
+                Side::Ours
+            };
+            if !conflicts
+                .iter()
+                .any(|c| matches!(c, MergeConflict::RemovedAndModified { identity: i, .. } if *i == identity))
+            {
+                conflicts.push(MergeConflict::RemovedAndModified {
+                    identity: identity.clone(),
+                    removed_by,
+                });
+            }
+            let source = if b.subjects.contains_key(&identity) {
+                b
+            } else if o.subjects.contains_key(&identity) {
+                o
+            } else {
+                t
+            };
+            insert(&mut merged, &identity, entity(&identity, source));
+            add_containers(&mut merged, &identity, &versions);
+        }
+    }
+
+    // Order conflicts by identity, as they occur across the versions
+    let position: HashMap<&Symbol, usize> = identities
+        .iter()
+        .enumerate()
+        .map(|(i, &id)| (id, i))
+        .collect();
+    conflicts.sort_by_key(|conflict| position.get(conflict.identity()).copied());
+
+    let patterns = diff_snapshots(b, &merged).apply(base);
+    ThreeWayMerge {
+        patterns,
+        conflicts,
+    }
+}
+
+/// A subject with its endpoints, if it is a relationship.
+type Entity = (Subject, Option<Endpoints>);
+
+fn entity(identity: &Symbol, version: &Snapshot) -> Entity {
+    (
+        version.subjects[identity].clone(),
+        version.edges.get(identity).cloned(),
+    )
+}
+
+fn insert(snapshot: &mut Snapshot, identity: &Symbol, (subject, edge): Entity) {
+    snapshot.order.push(identity.clone());
+    snapshot.subjects.insert(identity.clone(), subject);
+    if let Some(edge) = edge {
+        snapshot.edges.insert(identity.clone(), edge);
+    }
+}
+
+/// Three-way merge of the containers that hold `member`.
+fn add_containers(merged: &mut Snapshot, member: &Symbol, [b, o, t]: &[Snapshot; 3]) {
+    let containers_of = |v: &Snapshot| v.containers.get(member).cloned().unwrap_or_default();
+    let (in_b, in_o, in_t) = (containers_of(b), containers_of(o), containers_of(t));
+    let mut containers: Vec<Symbol> = Vec::new();
+    for container in in_b.iter().chain(&in_o).chain(&in_t) {
+        if !containers.contains(container)
+            && pick(
+                in_b.contains(container),
+                in_o.contains(container),
+                in_t.contains(container),
+            )
+        {
+            containers.push(container.clone());
+        }
+    }
+    if !containers.is_empty() {
+        merged.containers.insert(member.clone(), containers);
+    }
+}
+
+/// Whether `changed` differs from `base` in the subject, endpoints or containers
+/// of `identity`.
+fn modified(identity: &Symbol, base: &Snapshot, changed: &Snapshot) -> bool {
+    let containers = |v: &Snapshot| -> HashSet<Symbol> {
+        v.containers
+            .get(identity)
+            .map(|c| c.iter().cloned().collect())
+            .unwrap_or_default()
+    };
+    base.subjects.get(identity) != changed.subjects.get(identity)
+        || base.edges.get(identity) != changed.edges.get(identity)
+        || containers(base) != containers(changed)
+}
+
+/// Three-way merge of an identity present in both changed versions.
+fn merge_entity(
+    identity: &Symbol,
+    [b, o, t]: &[Snapshot; 3],
+    conflicts: &mut Vec<MergeConflict>,
+) -> Entity {
+    let in_base = b.subjects.contains_key(identity);
+    let (base_edge, our_edge, their_edge) = (
+        b.edges.get(identity),
+        o.edges.get(identity),
+        t.edges.get(identity),
+    );
+    let edge = match choose(base_edge, our_edge, their_edge) {
+        Some(edge) => edge.cloned(),
+        None => {
+            conflicts.push(MergeConflict::Endpoints {
+                identity: identity.clone(),
+                base: base_edge.cloned(),
+                ours: our_edge.cloned(),
+                theirs: their_edge.cloned(),
+            });
+            if in_base {
+                base_edge.cloned()
+            } else {
+                our_edge.cloned()
+            }
+        }
+    };
+
+    let empty = Subject {
+        identity: identity.clone(),
+        labels: HashSet::new(),
+        properties: HashMap::new(),
+    };
+    let base = b.subjects.get(identity).unwrap_or(&empty);
+    let ours = &o.subjects[identity];
+    let theirs = &t.subjects[identity];
+
+    let mut subject = empty.clone();
+    for label in base.labels.iter().chain(&ours.labels).chain(&theirs.labels) {
+        if pick(
+            base.labels.contains(label),
+            ours.labels.contains(label),
+            theirs.labels.contains(label),
+        ) {
+            subject.labels.insert(label.clone());
+        }
+    }
+
+    let mut keys: Vec<&String> = base
+        .properties
+        .keys()
+        .chain(ours.properties.keys())
+        .chain(theirs.properties.keys())
+        .collect();
+    keys.sort();
+    keys.dedup();
+    for key in keys {
+        let (in_base, in_ours, in_theirs) = (
+            base.properties.get(key),
+            ours.properties.get(key),
+            theirs.properties.get(key),
+        );
+        let value = match choose(in_base, in_ours, in_theirs) {
+            Some(value) => value,
+            None => {
+                conflicts.push(MergeConflict::Property {
+                    identity: identity.clone(),
+                    key: key.clone(),
+                    base: in_base.cloned(),
+                    ours: in_ours.cloned(),
+                    theirs: in_theirs.cloned(),
+                });
+                in_base
+            }
+        };
+        if let Some(value) = value {
+            subject.properties.insert(key.clone(), value.clone());
+        }
+    }
+
+    (subject, edge)
+}
+
+/// Three-way choice: the side that changed, or `None` if both changed differently.
+fn choose<T: PartialEq>(base: T, ours: T, theirs: T) -> Option<T> {
+    if ours == theirs || theirs == base {
+        Some(ours)
+    } else if ours == base {
+        Some(theirs)
+    } else {
+        None
+    }
+}
+
+/// Three-way choice of a presence flag, which cannot conflict.
+fn pick(base: bool, ours: bool, theirs: bool) -> bool {
+    if ours == base {
+        theirs
+    } else {
+        ours
+    }
+}
+
+/// Identities the snapshot refers to as endpoints or containers.
+fn references(snapshot: &Snapshot) -> Vec<&Symbol> {
+    let mut references = Vec::new();
+    for (source, target) in snapshot.edges.values() {
+        references.extend([source, target]);
+    }
+    for relationship in &snapshot.anonymous {
+        references.extend([&relationship.source, &relationship.target]);
+    }
+    references.extend(snapshot.containers.values().flatten());
+    references
+}
@@ -0,0 +1,292 @@
+//! Tests for the identity-aware document diff
+//!
+//! Covers node, relationship, label, property and move detection, the human
+//! report, and applying a diff or its changeset to the old version.
+
+mod common;
+
+use common::{node, reference, subject};
+use pattern_core::{
+    apply_changeset, diff, ChangesetError, Diff, Pattern, PropertyChange, Subject, Symbol, Value,
+};
+
+fn edge(id: &str, label: &str, from: &str, to: &str) -> Pattern<Subject> {
+    Pattern::pattern(
+        subject(id, &[label], vec![]),
+        vec![reference(from), reference(to)],
+    )
+}
+
+fn container(id: &str, members: &[&str]) -> Pattern<Subject> {
+    Pattern::pattern(
+        subject(id, &["Team"], vec![]),
+        members.iter().map(|m| reference(m)).collect(),
+    )
+}
+
+fn ids(subjects: &[Subject]) -> Vec<&str> {
+    subjects.iter().map(|s| s.identity.0.as_str()).collect()
+}
+
+fn assert_applies(old: &[Pattern<Subject>], new: &[Pattern<Subject>]) {
+    let changes = diff(old, new);
+    let patched = changes.apply(old);
+    assert!(diff(&patched, new).is_empty(), "{}", diff(&patched, new));
+
+    let from_changeset = apply_changeset(old, &changes.changeset()).unwrap();
+    assert!(diff(&from_changeset, new).is_empty());
+}
+
+#[test]
+fn test_identical_documents_have_no_diff() {
+    let doc = vec![
+        node(
+            "a",
+            &["Person", "Employee"],
+            vec![("x", Value::from(1)), ("y", Value::from("y"))],
+        ),
+        edge("r", "KNOWS", "a", "b"),
+    ];
+    // Label and property order does not matter, nor does where a subject is defined
+    let reordered = vec![
+        edge("r", "KNOWS", "a", "b"),
+        node(
+            "a",
+            &["Employee", "Person"],
+            vec![("y", Value::from("y")), ("x", Value::from(1))],
+        ),
+    ];
+
+    let changes = diff(&doc, &reordered);
+    assert!(changes.is_empty());
+    assert_eq!(changes.to_string(), "");
+}
+
+#[test]
+fn test_added_and_removed_nodes() {
+    let old = vec![node("a", &[], vec![]), node("b", &["Person"], vec![])];
+    let new = vec![node("a", &[], vec![]), node("c", &["Person"], vec![])];
+
+    let changes = diff(&old, &new);
+    assert_eq!(ids(&changes.added_nodes), vec!["c"]);
+    assert_eq!(ids(&changes.removed_nodes), vec!["b"]);
+    assert!(changes.changed.is_empty());
+    assert_applies(&old, &new);
+}
+
+#[test]
+fn test_label_and_property_changes() {
+    let old = vec![node(
+        "alice",
+        &["Person", "Intern"],
+        vec![("age", Value::from(34)), ("phone", Value::from("555"))],
+    )];
+    let new = vec![node(
+        "alice",
+        &["Person", "Employee"],
+        vec![
+            ("age", Value::from(35)),
+            ("email", Value::from("a@example.com")),
+        ],
+    )];
+
+    let changes = diff(&old, &new);
+    assert_eq!(changes.changed.len(), 1);
+    let change = &changes.changed[0];
+    assert_eq!(change.labels_added, vec!["Employee"]);
+    assert_eq!(change.labels_removed, vec!["Intern"]);
+    assert_eq!(
+        change.properties,
+        vec![
+            PropertyChange {
+                key: "age".to_string(),
+                old: Some(Value::from(34)),
+                new: Some(Value::from(35)),
+            },
+            PropertyChange {
+                key: "email".to_string(),
+                old: None,
+                new: Some(Value::from("a@example.com")),
+            },
+            PropertyChange {
+                key: "phone".to_string(),
+                old: Some(Value::from("555")),
+                new: None,
+            },
+        ]
+    );
+    assert_eq!(
+        changes.to_string(),
+        "~ alice\n    + :Employee\n    - :Intern\n    age: 34 -> 35\n    email: (none) -> \"a@example.com\"\n    phone: \"555\" -> (none)\n"
+    );
+    assert_applies(&old, &new);
+}
+
+#[test]
+fn test_relationships_are_matched_by_identity_and_endpoints() {
+    let old = vec![
+        edge("r1", "KNOWS", "a", "b"),
+        edge("r2", "KNOWS", "a", "b"),
+        Pattern::pattern(
+            subject("", &["LIKES"], vec![]),
+            vec![reference("a"), reference("b")],
+        ),
+    ];
+    let new = vec![
+        edge("r1", "KNOWS", "a", "b"),
+        edge("r2", "KNOWS", "a", "c"),
+        Pattern::pattern(
+            subject("", &["LIKES"], vec![]),
+            vec![reference("b"), reference("a")],
+        ),
+    ];
+
+    let changes = diff(&old, &new);
+    // r2 was rewired; the anonymous LIKES was reversed
+    assert_eq!(changes.added_relationships.len(), 2);
+    assert_eq!(changes.removed_relationships.len(), 2);
+    assert_eq!(changes.added_relationships[0].target.0, "c");
+    assert_eq!(ids(&changes.added_nodes), vec!["c"]);
+    assert_eq!(changes.to_string().lines().next(), Some("+ (c)"));
+    assert!(changes.to_string().contains("- (a)-[r2:KNOWS]->(b)\n"));
+    assert_applies(&old, &new);
+}
+
+#[test]
+fn test_relationships_inside_paths() {
+    // (a)-[r1]->(b)-[r2]->(c)
+    let path = |second: &str| {
+        Pattern::pattern(
+            subject("r2", &["NEXT"], vec![]),
+            vec![edge("r1", "NEXT", "a", "b"), reference(second)],
+        )
+    };
+    let old = vec![path("c")];
+    let new = vec![path("d")];
+
+    let changes = diff(&old, &new);
+    assert_eq!(changes.removed_relationships.len(), 1);
+    assert_eq!(changes.removed_relationships[0].source.0, "b");
+    assert_eq!(ids(&changes.removed_nodes), vec!["c"]);
+    assert_applies(&old, &new);
+}
+
+#[test]
+fn test_moved_elements() {
+    // Containers with exactly two elements would read as relationships
+    let old = vec![
+        container("t1", &["alice", "bob", "dave", "frank"]),
+        container("t2", &["carol"]),
+    ];
+    let new = vec![
+        container("t1", &["bob", "dave", "frank"]),
+        container("t2", &["carol", "erin", "alice"]),
+    ];
+
+    let changes = diff(&old, &new);
+    assert_eq!(changes.moved.len(), 2);
+    assert_eq!(changes.moved[1].identity.0, "alice");
+    assert_eq!(changes.moved[1].from, vec![Symbol("t1".to_string())]);
+    assert_eq!(changes.moved[1].to, vec![Symbol("t2".to_string())]);
+    assert_eq!(ids(&changes.added_nodes), vec!["erin"]);
+    assert!(changes
+        .to_string()
+        .ends_with("> erin: [] -> [t2]\n> alice: [t1] -> [t2]\n"));
+    assert_applies(&old, &new);
+}
+
+#[test]
+fn test_new_container_receives_elements() {
+    let old = vec![node("alice", &[], vec![])];
+    let new = vec![container("t1", &["alice", "bob", "carol"])];
+
+    let changes = diff(&old, &new);
+    assert_eq!(ids(&changes.added_nodes), vec!["t1", "bob", "carol"]);
+    assert_eq!(changes.moved.len(), 3);
+    assert_applies(&old, &new);
+}
+
+#[test]
+fn test_two_element_containers_are_not_applied_as_relationships() {
+    let new = vec![container("g", &["c"]), container("g", &["a"])];
+    assert_applies(&[], &new);
+
+    let old = vec![container("g", &["a", "b", "c"])];
+    let new = vec![container("g", &["a"]), container("g", &["b"])];
+    assert_applies(&old, &new);
+
+    let old = vec![container("g", &["a", "b", "c"]), node("d", &[], vec![])];
+    let new = vec![
+        container("g", &["a"]),
+        container("g", &["b"]),
+        container("h", &["c"]),
+        container("h", &["d"]),
+    ];
+    let patched = diff(&old, &new).apply(&old);
+    assert!(patched.iter().all(|pattern| pattern.elements.len() != 2));
+    assert_applies(&old, &new);
+
+    let old = vec![container("g", &["a", "b", "c"])];
+    let new = vec![container("g", &["a"]), container("h", &["b", "c", "d"])];
+    assert_applies(&old, &new);
+}
+
+#[test]
+fn test_changes_apply_to_every_occurrence() {
+    let old = vec![
+        edge("r", "KNOWS", "a", "b"),
+        node("a", &["Person"], vec![("age", Value::from(1))]),
+    ];
+    let new = vec![
+        edge("r", "KNOWS", "a", "b"),
+        node("a", &["Person"], vec![("age", Value::from(2))]),
+    ];
+
+    let patched = diff(&old, &new).apply(&old);
+    // The new value lands on the definition, not on the bare reference
+    assert_eq!(patched, new);
+}
+
+#[test]
+fn test_changeset_round_trip() {
+    let old = vec![
+        container("t1", &["alice"]),
+        node("alice", &["Intern"], vec![("age", Value::from(34))]),
+        edge("r", "KNOWS", "alice", "bob"),
+    ];
+    let new = vec![
+        container("t2", &["alice"]),
+        node("alice", &["Employee"], vec![("age", Value::from(35))]),
+        node("carol", &[], vec![]),
+    ];
+
+    let changes = diff(&old, &new);
+    let changeset = changes.changeset();
+    assert!(changeset
+        .iter()
+        .all(|change| change.value.identity.0.is_empty() && change.value.labels.len() == 1));
+    assert_eq!(Diff::from_changeset(&changeset).unwrap(), changes);
+    assert_applies(&old, &new);
+}
+
+#[test]
+fn test_invalid_changesets() {
+    let unknown = vec![Pattern::pattern(
+        subject("", &["Renamed"], vec![]),
+        vec![reference("a")],
+    )];
+    assert_eq!(
+        Diff::from_changeset(&unknown),
+        Err(ChangesetError::UnknownChange { index: 0 })
+    );
+
+    let mismatched = vec![Pattern::pattern(
+        subject("", &["Changed"], vec![]),
+        vec![reference("a"), reference("b")],
+    )];
+    let err = Diff::from_changeset(&mismatched).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "change 0: old and new subject must share an identity"
+    );
+}
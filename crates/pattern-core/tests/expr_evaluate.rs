@@ -0,0 +1,236 @@
+//! Tests for predicate expression evaluation
+//!
+//! Evaluates expressions against subjects and through the `filter_expr` and
+//! `find_first_expr` entry points on `Pattern<Subject>`.
+
+use pattern_core::{Expr, ExprError, Pattern, Subject, Symbol, Value};
+use std::collections::{HashMap, HashSet};
+
+fn person(id: &str, name: &str, age: i64) -> Pattern<Subject> {
+    Pattern::point(Subject {
+        identity: Symbol(id.to_string()),
+        labels: HashSet::from(["Person".to_string()]),
+        properties: HashMap::from([
+            ("name".to_string(), Value::VString(name.to_string())),
+            ("age".to_string(), Value::VInteger(age)),
+            (
+                "address".to_string(),
+                Value::VMap(HashMap::from([(
+                    "city".to_string(),
+                    Value::VString("Wellington".to_string()),
+                )])),
+            ),
+        ]),
+    })
+}
+
+fn team() -> Pattern<Subject> {
+    Pattern::pattern(
+        Subject {
+            identity: Symbol("team".to_string()),
+            labels: HashSet::from(["Team".to_string()]),
+            properties: HashMap::from([("size".to_string(), Value::VInteger(3))]),
+        },
+        vec![
+            person("alice", "Alice", 34),
+            person("albert", "Albert", 61),
+            person("bob", "Bob", 27),
+        ],
+    )
+}
+
+fn ids(patterns: &[&Pattern<Subject>]) -> Vec<String> {
+    patterns
+        .iter()
+        .map(|p| p.value.identity.0.clone())
+        .collect()
+}
+
+#[test]
+fn test_filter_expr_combines_labels_comparisons_and_regex() {
+    let team = team();
+    let found = team
+        .filter_expr(":Person AND age >= 30 AND name =~ 'Al.*'")
+        .unwrap();
+    assert_eq!(ids(&found), vec!["alice", "albert"]);
+}
+
+#[test]
+fn test_regex_matches_whole_string() {
+    let team = team();
+    assert!(team.filter_expr("name =~ 'Al'").unwrap().is_empty());
+    assert_eq!(team.filter_expr("name =~ '.*b'").unwrap().len(), 1);
+}
+
+#[test]
+fn test_nested_property_access() {
+    let team = team();
+    assert_eq!(
+        team.filter_expr("address.city = 'Wellington'")
+            .unwrap()
+            .len(),
+        3
+    );
+    assert!(team.filter_expr("EXISTS address.zip").unwrap().is_empty());
+    assert_eq!(team.filter_expr("EXISTS(address)").unwrap().len(), 3);
+}
+
+#[test]
+fn test_in_lists_and_ranges() {
+    let team = team();
+    assert_eq!(
+        ids(&team.filter_expr("name IN ['Bob', 'Alice']").unwrap()),
+        vec!["alice", "bob"]
+    );
+    assert_eq!(
+        ids(&team.filter_expr("age IN 30..61").unwrap()),
+        vec!["alice", "albert"]
+    );
+    assert_eq!(
+        ids(&team.filter_expr("age IN 60..").unwrap()),
+        vec!["albert"]
+    );
+}
+
+#[test]
+fn test_numbers_compare_across_integer_and_decimal() {
+    let team = team();
+    assert_eq!(ids(&team.filter_expr("age = 34.0").unwrap()), vec!["alice"]);
+    assert_eq!(
+        ids(&team.filter_expr("age > 60.5").unwrap()),
+        vec!["albert"]
+    );
+}
+
+#[test]
+fn test_missing_properties_compare_false() {
+    let team = team();
+    // Only the team has `size`; people are skipped rather than erroring
+    assert_eq!(ids(&team.filter_expr("size = 3").unwrap()), vec!["team"]);
+    assert_eq!(
+        ids(&team.filter_expr("size != 3").unwrap()),
+        Vec::<String>::new()
+    );
+    assert_eq!(team.filter_expr("NOT size = 3").unwrap().len(), 3);
+}
+
+#[test]
+fn test_find_first_expr_stops_at_first_match() {
+    let team = team();
+    let found = team.find_first_expr(":Person AND age < 30").unwrap();
+    assert_eq!(found.unwrap().value.identity.0, "bob");
+    assert!(team.find_first_expr(":Robot").unwrap().is_none());
+}
+
+#[test]
+fn test_type_errors_report_the_comparison_span() {
+    let team = team();
+    let err = team.filter_expr(":Person AND name > 3").unwrap_err();
+    assert_eq!(err.span(), 12..20);
+    assert!(matches!(err, ExprError::Type { .. }));
+    assert_eq!(err.message(), "cannot order string against integer");
+
+    let err = Expr::parse("age =~ 'x'")
+        .unwrap()
+        .evaluate(&team.elements[0].value)
+        .unwrap_err();
+    assert_eq!(err.span(), 0..10);
+}
+
+#[test]
+fn test_ordering_against_nan_reports_nan() {
+    let subject = Subject {
+        identity: Symbol("n".to_string()),
+        labels: HashSet::new(),
+        properties: HashMap::from([("score".to_string(), Value::VDecimal(f64::NAN))]),
+    };
+    let err = Expr::parse("score < 1.5")
+        .unwrap()
+        .evaluate(&subject)
+        .unwrap_err();
+    assert!(matches!(err, ExprError::Type { .. }));
+    assert_eq!(err.message(), "cannot order NaN");
+    assert_eq!(err.span(), 0..11);
+}
+
+#[test]
+fn test_ordering_nan_against_another_type_reports_the_types() {
+    let subject = Subject {
+        identity: Symbol("n".to_string()),
+        labels: HashSet::new(),
+        properties: HashMap::from([("score".to_string(), Value::VDecimal(f64::NAN))]),
+    };
+    let err = Expr::parse("score < 'x'")
+        .unwrap()
+        .evaluate(&subject)
+        .unwrap_err();
+    assert_eq!(err.message(), "cannot order decimal against string");
+}
+
+#[test]
+fn test_nan_is_never_equal() {
+    let subject = Subject {
+        identity: Symbol("n".to_string()),
+        labels: HashSet::new(),
+        properties: HashMap::from([
+            ("x".to_string(), Value::VDecimal(f64::NAN)),
+            (
+                "xs".to_string(),
+                Value::VArray(vec![Value::VDecimal(f64::NAN)]),
+            ),
+            (
+                "m".to_string(),
+                Value::VMap(HashMap::from([(
+                    "k".to_string(),
+                    Value::VDecimal(f64::NAN),
+                )])),
+            ),
+        ]),
+    };
+    let eval = |text: &str| Expr::parse(text).unwrap().evaluate(&subject).unwrap();
+    assert!(!eval("x = x"));
+    assert!(eval("x != x"));
+    assert!(!eval("xs = xs"));
+    assert!(!eval("m = m"));
+    assert!(!eval("x IN xs"));
+}
+
+#[test]
+fn test_arrays_compare_numbers_across_integer_and_decimal() {
+    let subject = Subject {
+        identity: Symbol("n".to_string()),
+        labels: HashSet::new(),
+        properties: HashMap::from([("xs".to_string(), Value::VArray(vec![Value::VInteger(1)]))]),
+    };
+    assert!(Expr::parse("xs = [1.0]")
+        .unwrap()
+        .evaluate(&subject)
+        .unwrap());
+}
+
+#[test]
+fn test_boolean_properties_as_conditions() {
+    let active = Subject {
+        identity: Symbol("a".to_string()),
+        labels: HashSet::new(),
+        properties: HashMap::from([("active".to_string(), Value::VBoolean(true))]),
+    };
+    assert!(Expr::parse("active").unwrap().evaluate(&active).unwrap());
+    assert!(!Expr::parse("NOT active").unwrap().matches(&active));
+    assert!(!Expr::parse("archived").unwrap().evaluate(&active).unwrap());
+    assert!(Expr::parse("active = 1").unwrap().evaluate(&active).is_ok());
+}
+
+#[test]
+fn test_filter_expr_agrees_with_filter() {
+    let team = team();
+    let expr = Expr::parse("age < 40 OR :Team").unwrap();
+    let by_closure = team.filter(|p| expr.matches(&p.value));
+    let by_text = team.filter_expr("age < 40 OR :Team").unwrap();
+    assert_eq!(ids(&by_closure), ids(&by_text));
+    assert_eq!(ids(&team.filter_by_expr(&expr).unwrap()), ids(&by_text));
+    assert_eq!(
+        team.find_first_by_expr(&expr).unwrap(),
+        team.find_first_expr("age < 40 OR :Team").unwrap()
+    );
+}
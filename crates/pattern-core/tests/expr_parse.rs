@@ -0,0 +1,116 @@
+//! Tests for predicate expression parsing
+//!
+//! Checks operator precedence, literal forms and the spans reported for
+//! malformed expressions.
+
+use pattern_core::{Expr, ExprError, Subject, Symbol, Value};
+use std::collections::{HashMap, HashSet};
+
+fn subject(labels: &[&str], properties: &[(&str, Value)]) -> Subject {
+    Subject {
+        identity: Symbol("s".to_string()),
+        labels: labels.iter().map(|l| l.to_string()).collect::<HashSet<_>>(),
+        properties: properties
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.clone()))
+            .collect::<HashMap<_, _>>(),
+    }
+}
+
+fn syntax_span(input: &str) -> std::ops::Range<usize> {
+    match Expr::parse(input) {
+        Err(ExprError::Syntax { span, .. }) => span,
+        other => panic!("expected a syntax error for {:?}, got {:?}", input, other),
+    }
+}
+
+#[test]
+fn test_and_binds_tighter_than_or() {
+    let expr = Expr::parse(":A OR :B AND :C").unwrap();
+    assert!(expr.evaluate(&subject(&["A"], &[])).unwrap());
+    assert!(!expr.evaluate(&subject(&["B"], &[])).unwrap());
+
+    let grouped = Expr::parse("(:A OR :B) AND :C").unwrap();
+    assert!(!grouped.evaluate(&subject(&["A"], &[])).unwrap());
+    assert!(grouped.evaluate(&subject(&["B", "C"], &[])).unwrap());
+}
+
+#[test]
+fn test_keywords_are_case_insensitive() {
+    let expr = Expr::parse("not :A and exists x or FALSE").unwrap();
+    assert!(expr
+        .evaluate(&subject(&[], &[("x", Value::VInteger(1))]))
+        .unwrap());
+}
+
+#[test]
+fn test_literal_forms() {
+    let s = subject(
+        &[],
+        &[
+            ("n", Value::VInteger(-3)),
+            ("d", Value::VDecimal(2.5)),
+            ("s", Value::VString("it's".to_string())),
+            ("b", Value::VBoolean(true)),
+        ],
+    );
+    for input in [
+        "n = -3",
+        "d = 2.5",
+        r#"s = "it's""#,
+        r"s = 'it\'s'",
+        "b = true",
+        "n IN [1, -3]",
+        "d IN 2..3",
+        "d IN ..2.5",
+        "n IN -5..",
+        "`n` < 0",
+    ] {
+        assert!(
+            Expr::parse(input).unwrap().evaluate(&s).unwrap(),
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn test_operator_spellings() {
+    let s = subject(&[], &[("n", Value::VInteger(1))]);
+    for input in [
+        "n == 1", "n != 2", "n <> 2", "n >= 1", "n <= 1", "n > 0", "n < 2",
+    ] {
+        assert!(
+            Expr::parse(input).unwrap().evaluate(&s).unwrap(),
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn test_syntax_error_spans() {
+    assert_eq!(syntax_span("age >= "), 7..7);
+    assert_eq!(syntax_span("age >= 30 AND"), 13..13);
+    assert_eq!(syntax_span("age >= 30 30"), 10..12);
+    assert_eq!(syntax_span("name = 'Al"), 7..10);
+    assert_eq!(syntax_span("a # b"), 2..3);
+    assert_eq!(syntax_span("(:A OR :B"), 9..9);
+    assert_eq!(syntax_span("name =~ 30"), 8..10);
+}
+
+#[test]
+fn test_invalid_regex_is_a_syntax_error() {
+    let err = Expr::parse("name =~ 'Al(.*'").unwrap_err();
+    assert_eq!(err.span(), 8..15);
+    assert!(err.message().starts_with("invalid regular expression"));
+}
+
+#[test]
+fn test_error_display_includes_span() {
+    let err = Expr::parse("age >=").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "syntax error at 6..6: expected a value, found end of expression"
+    );
+}
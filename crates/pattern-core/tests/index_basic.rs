@@ -0,0 +1,232 @@
+//! Tests for PatternIndex lookups
+//!
+//! Covers identity, label, property-equality and numeric range lookups over
+//! `Pattern<Subject>` documents, and checks results against linear scans.
+
+mod common;
+
+use common::{node, rel};
+use pattern_core::{Pattern, PatternIndex, Subject, Value};
+use std::collections::{HashMap, HashSet};
+
+fn sample_document() -> Vec<Pattern<Subject>> {
+    let alice = || {
+        node(
+            "alice",
+            &["Person"],
+            vec![
+                ("name", Value::VString("Alice".to_string())),
+                ("age", Value::VInteger(30)),
+            ],
+        )
+    };
+    let bob = || {
+        node(
+            "bob",
+            &["Person", "Admin"],
+            vec![
+                ("name", Value::VString("Bob".to_string())),
+                ("age", Value::VInteger(42)),
+            ],
+        )
+    };
+    let acme = || {
+        node(
+            "acme",
+            &["Company"],
+            vec![
+                ("country", Value::VString("NZ".to_string())),
+                ("revenue", Value::VDecimal(12.5)),
+            ],
+        )
+    };
+
+    vec![
+        alice(),
+        bob(),
+        acme(),
+        rel("KNOWS", alice(), bob()),
+        rel("WORKS_AT", bob(), acme()),
+    ]
+}
+
+#[test]
+fn test_identity_occurrences_with_paths() {
+    let doc = sample_document();
+    let index = PatternIndex::build(&doc);
+
+    let bob = index.occurrences("bob");
+    assert_eq!(bob, vec![&vec![1], &vec![3, 1], &vec![4, 0]]);
+
+    for path in bob {
+        let resolved = PatternIndex::resolve(&doc, path).unwrap();
+        assert_eq!(resolved.value.identity.0, "bob");
+    }
+}
+
+#[test]
+fn test_unknown_identity_has_no_occurrences() {
+    let index = PatternIndex::build(&sample_document());
+    assert!(index.occurrences("carol").is_empty());
+    assert!(!index.contains_identity("carol"));
+    assert!(index.contains_identity("alice"));
+}
+
+#[test]
+fn test_anonymous_subjects_not_indexed_by_identity() {
+    let index = PatternIndex::build(&sample_document());
+    assert!(index.occurrences("").is_empty());
+    assert_eq!(index.identities().count(), 3);
+}
+
+#[test]
+fn test_label_lookup_in_document_order() {
+    let index = PatternIndex::build(&sample_document());
+
+    let people: Vec<_> = index.with_label("Person").cloned().collect();
+    assert_eq!(
+        people,
+        vec![vec![0], vec![1], vec![3, 0], vec![3, 1], vec![4, 0]]
+    );
+
+    assert_eq!(index.with_label("KNOWS").count(), 1);
+    assert_eq!(index.with_label("Missing").count(), 0);
+}
+
+#[test]
+fn test_label_lookup_matches_linear_scan() {
+    let doc = sample_document();
+    let index = PatternIndex::build(&doc);
+
+    let scanned: usize = doc
+        .iter()
+        .map(|p| p.filter(|sub| sub.value.labels.contains("Person")).len())
+        .sum();
+    assert_eq!(index.with_label("Person").count(), scanned);
+}
+
+#[test]
+fn test_property_equality_lookup() {
+    let index = PatternIndex::build(&sample_document());
+
+    let nz: Vec<_> = index
+        .with_property("country", &Value::VString("NZ".to_string()))
+        .collect();
+    assert_eq!(nz.len(), 2);
+
+    let none: Vec<_> = index
+        .with_property("country", &Value::VString("AU".to_string()))
+        .collect();
+    assert!(none.is_empty());
+}
+
+#[test]
+fn test_property_equality_is_variant_exact() {
+    let doc = vec![node("n", &[], vec![("x", Value::VInteger(1))])];
+    let index = PatternIndex::build(&doc);
+
+    assert_eq!(index.with_property("x", &Value::VInteger(1)).count(), 1);
+    assert_eq!(index.with_property("x", &Value::VDecimal(1.0)).count(), 0);
+}
+
+#[test]
+fn test_property_equality_on_maps_ignores_entry_order() {
+    let mut m1 = HashMap::new();
+    m1.insert("a".to_string(), Value::VInteger(1));
+    m1.insert("b".to_string(), Value::VInteger(2));
+    let mut m2 = HashMap::new();
+    m2.insert("b".to_string(), Value::VInteger(2));
+    m2.insert("a".to_string(), Value::VInteger(1));
+
+    let doc = vec![node("n", &[], vec![("meta", Value::VMap(m1))])];
+    let index = PatternIndex::build(&doc);
+
+    assert_eq!(index.with_property("meta", &Value::VMap(m2)).count(), 1);
+}
+
+#[test]
+fn test_property_key_lookup() {
+    let index = PatternIndex::build(&sample_document());
+    let with_age: Vec<_> = index.with_property_key("age").cloned().collect();
+    assert_eq!(
+        with_age,
+        vec![vec![0], vec![1], vec![3, 0], vec![3, 1], vec![4, 0]]
+    );
+}
+
+#[test]
+fn test_numeric_range_lookup() {
+    let index = PatternIndex::build(&sample_document());
+
+    assert_eq!(index.property_range("age", 40.0..).len(), 3);
+    assert_eq!(index.property_range("age", ..40.0).len(), 2);
+    assert_eq!(index.property_range("age", 30.0..=42.0).len(), 5);
+    assert_eq!(index.property_range("age", 31.0..42.0).len(), 0);
+    assert_eq!(index.property_range("revenue", 10.0..20.0).len(), 2);
+}
+
+#[test]
+fn test_numeric_range_ordered_by_value() {
+    let doc = vec![
+        node("a", &[], vec![("n", Value::VInteger(3))]),
+        node("b", &[], vec![("n", Value::VDecimal(1.5))]),
+        node("c", &[], vec![("n", Value::VInteger(2))]),
+    ];
+    let index = PatternIndex::build(&doc);
+
+    let hits = index.property_range("n", ..);
+    assert_eq!(hits, vec![&vec![1], &vec![2], &vec![0]]);
+}
+
+#[test]
+fn test_numeric_range_degenerate_bounds() {
+    let doc = vec![node("a", &[], vec![("n", Value::VInteger(3))])];
+    let index = PatternIndex::build(&doc);
+
+    #[allow(clippy::reversed_empty_ranges)]
+    let inverted = index.property_range("n", 5.0..1.0);
+    assert!(inverted.is_empty());
+    assert!(index.property_range("n", 3.0..3.0).is_empty());
+    assert_eq!(index.property_range("n", 3.0..=3.0).len(), 1);
+    assert!(index.property_range("missing", ..).is_empty());
+}
+
+#[test]
+fn test_numeric_range_ignores_non_numeric_and_nan() {
+    let doc = vec![
+        node("a", &[], vec![("n", Value::VString("10".to_string()))]),
+        node("b", &[], vec![("n", Value::VDecimal(f64::NAN))]),
+        node(
+            "c",
+            &[],
+            vec![(
+                "n",
+                Value::VMeasurement {
+                    unit: "kg".to_string(),
+                    value: 10.0,
+                },
+            )],
+        ),
+    ];
+    let index = PatternIndex::build(&doc);
+
+    assert_eq!(index.property_range("n", ..), vec![&vec![2]]);
+}
+
+#[test]
+fn test_resolve_invalid_paths() {
+    let doc = sample_document();
+    assert!(PatternIndex::resolve(&doc, &[]).is_none());
+    assert!(PatternIndex::resolve(&doc, &[99]).is_none());
+    assert!(PatternIndex::resolve(&doc, &[0, 0]).is_none());
+    assert!(PatternIndex::resolve(&doc, &[3, 1]).is_some());
+}
+
+#[test]
+fn test_empty_index() {
+    let index = PatternIndex::build(&[]);
+    assert!(index.is_empty());
+    assert_eq!(index.labels().count(), 0);
+    let empty: HashSet<&String> = index.labels().collect();
+    assert!(empty.is_empty());
+}
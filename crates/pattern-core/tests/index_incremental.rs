@@ -0,0 +1,127 @@
+//! Tests for incremental PatternIndex maintenance
+//!
+//! Inserting and removing top-level patterns must leave the index in the same
+//! state as rebuilding it from scratch.
+
+use pattern_core::{Pattern, PatternIndex, Subject, Symbol, Value};
+use std::collections::{HashMap, HashSet};
+
+fn node(id: &str, label: &str, age: i64) -> Pattern<Subject> {
+    Pattern::point(Subject {
+        identity: Symbol(id.to_string()),
+        labels: HashSet::from([label.to_string()]),
+        properties: HashMap::from([("age".to_string(), Value::VInteger(age))]),
+    })
+}
+
+#[test]
+fn test_insert_assigns_sequential_slots() {
+    let mut index = PatternIndex::new();
+    assert_eq!(index.insert(&node("a", "Person", 1)), 0);
+    assert_eq!(index.insert(&node("b", "Person", 2)), 1);
+    assert_eq!(index.len(), 2);
+    assert_eq!(index.occurrences("b"), vec![&vec![1]]);
+}
+
+#[test]
+fn test_insert_after_build_continues_slots() {
+    let doc = vec![node("a", "Person", 1), node("b", "Person", 2)];
+    let mut index = PatternIndex::build(&doc);
+
+    let slot = index.insert(&node("c", "Robot", 3));
+    assert_eq!(slot, 2);
+    assert_eq!(index.with_label("Robot").count(), 1);
+    assert_eq!(index.property_range("age", 2.5..).len(), 1);
+}
+
+#[test]
+fn test_remove_drops_all_entries() {
+    let doc = vec![node("a", "Person", 1), node("b", "Robot", 2)];
+    let mut index = PatternIndex::build(&doc);
+
+    index.remove(1, &doc[1]);
+
+    assert_eq!(index.len(), 1);
+    assert!(!index.contains_identity("b"));
+    assert_eq!(index.with_label("Robot").count(), 0);
+    assert_eq!(index.labels().count(), 1);
+    assert_eq!(index.with_property("age", &Value::VInteger(2)).count(), 0);
+    assert!(index.property_range("age", 2.0..).is_empty());
+}
+
+#[test]
+fn test_remove_keeps_other_slots_stable() {
+    let doc = vec![
+        node("a", "Person", 1),
+        node("b", "Person", 2),
+        node("c", "Person", 3),
+    ];
+    let mut index = PatternIndex::build(&doc);
+
+    index.remove(0, &doc[0]);
+
+    assert_eq!(index.occurrences("c"), vec![&vec![2]]);
+    let remaining: Vec<_> = index.with_label("Person").cloned().collect();
+    assert_eq!(remaining, vec![vec![1], vec![2]]);
+}
+
+#[test]
+fn test_remove_shared_identity_keeps_other_occurrences() {
+    let a = node("a", "Person", 1);
+    let rel = Pattern::pattern(
+        Subject {
+            identity: Symbol(String::new()),
+            labels: HashSet::new(),
+            properties: HashMap::new(),
+        },
+        vec![a.clone(), node("b", "Person", 2)],
+    );
+    let doc = vec![a, rel];
+    let mut index = PatternIndex::build(&doc);
+    assert_eq!(index.occurrences("a").len(), 2);
+
+    index.remove(1, &doc[1]);
+    assert_eq!(index.occurrences("a"), vec![&vec![0]]);
+    assert!(!index.contains_identity("b"));
+}
+
+#[test]
+fn test_incremental_matches_rebuild() {
+    let doc: Vec<_> = (0..50)
+        .map(|i| {
+            node(
+                &format!("n{}", i),
+                if i % 2 == 0 { "Even" } else { "Odd" },
+                i,
+            )
+        })
+        .collect();
+
+    let mut incremental = PatternIndex::new();
+    for p in &doc {
+        incremental.insert(p);
+    }
+    // Remove every third pattern
+    for (slot, p) in doc.iter().enumerate().filter(|(i, _)| i % 3 == 0) {
+        incremental.remove(slot, p);
+    }
+
+    let kept: Vec<usize> = (0..doc.len()).filter(|i| i % 3 != 0).collect();
+
+    let evens: Vec<usize> = incremental.with_label("Even").map(|p| p[0]).collect();
+    let expected: Vec<usize> = kept.iter().copied().filter(|i| i % 2 == 0).collect();
+    assert_eq!(evens, expected);
+
+    let in_range: Vec<usize> = incremental
+        .property_range("age", 10.0..20.0)
+        .into_iter()
+        .map(|p| p[0])
+        .collect();
+    let expected: Vec<usize> = kept
+        .iter()
+        .copied()
+        .filter(|i| (10..20).contains(i))
+        .collect();
+    assert_eq!(in_range, expected);
+    assert_eq!(incremental.len(), kept.len());
+}
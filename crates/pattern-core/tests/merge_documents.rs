@@ -0,0 +1,192 @@
+//! Tests for merging several documents by identity
+//!
+//! Covers subject unification across documents, relationship deduplication,
+//! placement of merged definitions, the merge report and pluggable strategies.
+
+mod common;
+
+use common::{node, reference, subject};
+use pattern_core::{
+    merge_documents, FirstSubject, LastSubject, MergeError, MergePolicy, MergeRule, Pattern,
+    Subject, Symbol, Value,
+};
+
+fn relationship(edge: Subject, from: Pattern<Subject>, to: Pattern<Subject>) -> Pattern<Subject> {
+    Pattern::pattern(edge, vec![from, to])
+}
+
+fn symbols(ids: &[&str]) -> Vec<Symbol> {
+    ids.iter().map(|id| Symbol(id.to_string())).collect()
+}
+
+#[test]
+fn test_subjects_are_unified_across_documents() {
+    let first = vec![node(
+        "alice",
+        &["Person"],
+        vec![("name", Value::from("Alice"))],
+    )];
+    let second = vec![node(
+        "alice",
+        &["Employee"],
+        vec![("role", Value::from("dev"))],
+    )];
+
+    let merge = merge_documents(&[first, second], std::convert::identity);
+
+    assert_eq!(merge.patterns.len(), 1);
+    let alice = &merge.patterns[0].value;
+    assert_eq!(alice.labels.len(), 2);
+    assert_eq!(alice.properties.len(), 2);
+    assert_eq!(merge.report.merged, symbols(&["alice"]));
+    assert!(merge.report.conflicting.is_empty());
+    assert!(merge.report.new.is_empty());
+    assert_eq!(merge.report.folded, 1);
+}
+
+#[test]
+fn test_definition_is_written_once() {
+    let first = vec![relationship(
+        subject("", &["KNOWS"], vec![]),
+        reference("alice"),
+        reference("bob"),
+    )];
+    let second = vec![
+        node("alice", &["Person"], vec![]),
+        node("bob", &["Person"], vec![]),
+    ];
+
+    let merge = merge_documents(&[first, second], std::convert::identity);
+
+    assert_eq!(merge.patterns.len(), 3);
+    // The relationship keeps bare references; the declarations carry the labels
+    assert!(merge.patterns[0]
+        .elements
+        .iter()
+        .all(|e| e.value.labels.is_empty()));
+    assert!(merge.patterns[1].value.labels.contains("Person"));
+    assert!(merge.patterns[2].value.labels.contains("Person"));
+    assert_eq!(merge.report.new, Vec::<Symbol>::new());
+}
+
+#[test]
+fn test_definition_moves_to_first_occurrence_when_no_declaration_survives() {
+    let knows = || subject("", &["KNOWS"], vec![]);
+    let first = vec![relationship(knows(), reference("a"), reference("b"))];
+    let second = vec![relationship(
+        knows(),
+        node("a", &["Person"], vec![]),
+        reference("b"),
+    )];
+
+    let merge = merge_documents(&[first, second], std::convert::identity);
+
+    // The second relationship is a duplicate, so `a` is defined inside the first
+    assert_eq!(merge.patterns.len(), 1);
+    assert_eq!(merge.report.folded, 1);
+    assert!(merge.patterns[0].elements[0]
+        .value
+        .labels
+        .contains("Person"));
+}
+
+#[test]
+fn test_relationships_are_deduplicated() {
+    let knows = |since: i64| subject("", &["KNOWS"], vec![("since", Value::from(since))]);
+    let first = vec![
+        relationship(knows(2020), reference("a"), reference("b")),
+        relationship(knows(2020), reference("b"), reference("a")),
+    ];
+    let second = vec![
+        relationship(knows(2020), reference("a"), reference("b")),
+        relationship(knows(2021), reference("a"), reference("b")),
+    ];
+
+    let merge = merge_documents(&[first, second], std::convert::identity);
+
+    // Same endpoints and edge subject is a duplicate; reversed or different edges are not
+    assert_eq!(merge.patterns.len(), 3);
+    assert_eq!(merge.report.folded, 1);
+}
+
+#[test]
+fn test_identified_patterns_union_their_elements() {
+    let first = vec![Pattern::pattern(
+        subject("team", &["Team"], vec![]),
+        vec![reference("alice"), reference("bob")],
+    )];
+    let second = vec![Pattern::pattern(
+        subject("team", &[], vec![]),
+        vec![reference("bob"), reference("carol")],
+    )];
+
+    let merge = merge_documents(&[first, second], std::convert::identity);
+
+    assert_eq!(merge.patterns.len(), 1);
+    let members: Vec<&str> = merge.patterns[0]
+        .elements
+        .iter()
+        .map(|e| e.value.identity.0.as_str())
+        .collect();
+    assert_eq!(members, vec!["alice", "bob", "carol"]);
+    assert_eq!(merge.report.new, symbols(&["carol"]));
+    // Folded into the first, although it added an element
+    assert_eq!(merge.report.folded, 1);
+}
+
+#[test]
+fn test_report_lists_conflicting_and_new_identities() {
+    let first = vec![
+        node("a", &[], vec![("x", Value::from(1))]),
+        node("b", &[], vec![("x", Value::from(1))]),
+    ];
+    let second = vec![
+        node("b", &[], vec![("x", Value::from(2))]),
+        node("c", &[], vec![]),
+        node("a", &[], vec![("x", Value::from(1))]),
+    ];
+    let third = vec![node("d", &[], vec![])];
+
+    let merge = merge_documents(&[first, second, third], std::convert::identity);
+
+    assert_eq!(merge.report.merged, symbols(&["a", "b"]));
+    assert_eq!(merge.report.conflicting, symbols(&["b"]));
+    assert_eq!(merge.report.new, symbols(&["c", "d"]));
+}
+
+#[test]
+fn test_strategy_decides_merged_subject() {
+    let docs = [
+        vec![node("a", &["First"], vec![("v", Value::from(1))])],
+        vec![node("a", &["Last"], vec![("v", Value::from(2))])],
+    ];
+
+    let first = merge_documents(&docs, FirstSubject);
+    assert!(first.patterns[0].value.labels.contains("First"));
+    assert_eq!(first.patterns[0].value.get::<i64>("v"), Some(1));
+
+    let last = merge_documents(&docs, LastSubject);
+    assert!(!last.patterns[0].value.labels.contains("First"));
+    assert_eq!(last.patterns[0].value.get::<i64>("v"), Some(2));
+}
+
+#[test]
+fn test_merge_with_policy() {
+    let docs = [
+        vec![node("a", &[], vec![("visits", Value::from(2))])],
+        vec![node("a", &[], vec![("visits", Value::from(3))])],
+    ];
+
+    let summed = MergePolicy::new()
+        .rule("visits", MergeRule::Sum)
+        .merge_documents(&docs)
+        .unwrap();
+    assert_eq!(summed.patterns[0].value.get::<i64>("visits"), Some(5));
+    assert_eq!(summed.report.conflicting, symbols(&["a"]));
+
+    let strict = MergePolicy::new().default_rule(MergeRule::Error);
+    assert!(matches!(
+        strict.merge_documents(&docs),
+        Err(MergeError::PropertyConflict { .. })
+    ));
+}
@@ -0,0 +1,290 @@
+//! Tests for MergePolicy and the Merging combination strategy
+//!
+//! Covers each per-key rule, identity handling, conflict reporting and use of
+//! a policy through `Combinable` (including inside `Pattern::combine`).
+
+mod common;
+
+use common::subject;
+use pattern_core::{
+    Combinable, Conflict, IdentityRule, MergeError, MergePolicy, MergeRule, Pattern, Symbol, Value,
+};
+use std::collections::HashMap;
+
+fn merge_one(rule: MergeRule, left: Value, right: Value) -> Result<Value, MergeError> {
+    let policy = MergePolicy::new().rule("x", rule);
+    let merged = policy.merge(
+        subject("a", &[], vec![("x", left)]),
+        subject("a", &[], vec![("x", right)]),
+    )?;
+    Ok(merged.subject.properties["x"].clone())
+}
+
+#[test]
+fn test_default_policy_matches_subject_combine() {
+    let left = subject(
+        "a",
+        &["Person"],
+        vec![("name", Value::from("Al")), ("age", Value::from(3))],
+    );
+    let right = subject("b", &["Employee"], vec![("name", Value::from("Alice"))]);
+
+    let merged = MergePolicy::new()
+        .merge(left.clone(), right.clone())
+        .unwrap();
+    assert_eq!(merged.subject, left.combine(right));
+}
+
+#[test]
+fn test_keep_left_and_keep_right() {
+    assert_eq!(
+        merge_one(MergeRule::KeepLeft, Value::from(1), Value::from(2)).unwrap(),
+        Value::from(1)
+    );
+    assert_eq!(
+        merge_one(MergeRule::KeepRight, Value::from(1), Value::from(2)).unwrap(),
+        Value::from(2)
+    );
+}
+
+#[test]
+fn test_error_rule_fails_only_on_differing_values() {
+    assert_eq!(
+        merge_one(MergeRule::Error, Value::from("x"), Value::from("x")).unwrap(),
+        Value::from("x")
+    );
+    let err = merge_one(MergeRule::Error, Value::from("x"), Value::from("y")).unwrap_err();
+    assert_eq!(
+        err,
+        MergeError::PropertyConflict {
+            key: "x".to_string(),
+            left: Box::new(Value::from("x")),
+            right: Box::new(Value::from("y")),
+        }
+    );
+}
+
+#[test]
+fn test_accumulate_concatenates_arrays() {
+    assert_eq!(
+        merge_one(MergeRule::Accumulate, Value::from(1), Value::from(2)).unwrap(),
+        Value::from(vec![1, 2])
+    );
+    assert_eq!(
+        merge_one(
+            MergeRule::Accumulate,
+            Value::from(vec![1, 2]),
+            Value::from(3)
+        )
+        .unwrap(),
+        Value::from(vec![1, 2, 3])
+    );
+}
+
+#[test]
+fn test_numeric_rules() {
+    assert_eq!(
+        merge_one(MergeRule::Sum, Value::from(2), Value::from(3)).unwrap(),
+        Value::from(5)
+    );
+    assert_eq!(
+        merge_one(MergeRule::Sum, Value::from(2), Value::from(0.5)).unwrap(),
+        Value::VDecimal(2.5)
+    );
+    assert_eq!(
+        merge_one(MergeRule::Sum, Value::from(i64::MAX), Value::from(1)).unwrap(),
+        Value::VDecimal(i64::MAX as f64 + 1.0)
+    );
+    assert_eq!(
+        merge_one(MergeRule::Max, Value::from(2), Value::from(3.5)).unwrap(),
+        Value::VDecimal(3.5)
+    );
+    assert_eq!(
+        merge_one(MergeRule::Min, Value::from(2), Value::from(3.5)).unwrap(),
+        Value::from(2)
+    );
+
+    let err = merge_one(MergeRule::Sum, Value::from(2), Value::from("3")).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "property 'x': Sum expects a number, found string"
+    );
+}
+
+#[test]
+fn test_union_maps_is_recursive() {
+    let left = Value::VMap(HashMap::from([
+        ("a".to_string(), Value::from(1)),
+        (
+            "nested".to_string(),
+            Value::VMap(HashMap::from([("x".to_string(), Value::from(1))])),
+        ),
+    ]));
+    let right = Value::VMap(HashMap::from([
+        ("a".to_string(), Value::from(2)),
+        (
+            "nested".to_string(),
+            Value::VMap(HashMap::from([("y".to_string(), Value::from(2))])),
+        ),
+    ]));
+
+    let Value::VMap(merged) = merge_one(MergeRule::UnionMaps, left, right).unwrap() else {
+        panic!("expected a map");
+    };
+    assert_eq!(merged["a"], Value::from(2));
+    let Value::VMap(nested) = &merged["nested"] else {
+        panic!("expected a nested map");
+    };
+    assert_eq!(nested.len(), 2);
+
+    assert!(matches!(
+        merge_one(
+            MergeRule::UnionMaps,
+            Value::VMap(HashMap::new()),
+            Value::from(1)
+        ),
+        Err(MergeError::TypeMismatch {
+            actual: "integer",
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_identity_rules() {
+    let left = subject("a", &[], vec![]);
+    let right = subject("b", &[], vec![]);
+
+    let merged = MergePolicy::new()
+        .identity(IdentityRule::KeepRight)
+        .merge(left.clone(), right.clone())
+        .unwrap();
+    assert_eq!(merged.subject.identity.0, "b");
+    assert_eq!(
+        merged.conflicts,
+        vec![Conflict::Identity {
+            left: Symbol("a".to_string()),
+            right: Symbol("b".to_string()),
+            kept: Symbol("b".to_string()),
+        }]
+    );
+
+    let err = MergePolicy::new()
+        .identity(IdentityRule::Error)
+        .merge(left.clone(), right)
+        .unwrap_err();
+    assert_eq!(err.to_string(), "conflicting identities 'a' and 'b'");
+
+    // An anonymous side never conflicts
+    let merged = MergePolicy::new()
+        .identity(IdentityRule::Error)
+        .merge(subject("", &[], vec![]), left)
+        .unwrap();
+    assert_eq!(merged.subject.identity.0, "a");
+    assert!(merged.conflicts.is_empty());
+}
+
+#[test]
+fn test_conflicts_are_reported_in_key_order() {
+    let policy = MergePolicy::new()
+        .default_rule(MergeRule::KeepLeft)
+        .rule("score", MergeRule::Max);
+    let merged = policy
+        .merge(
+            subject(
+                "a",
+                &[],
+                vec![
+                    ("score", Value::from(1)),
+                    ("name", Value::from("A")),
+                    ("same", Value::from(1)),
+                ],
+            ),
+            subject(
+                "a",
+                &[],
+                vec![
+                    ("score", Value::from(5)),
+                    ("name", Value::from("B")),
+                    ("same", Value::from(1)),
+                ],
+            ),
+        )
+        .unwrap();
+
+    let keys: Vec<&str> = merged
+        .conflicts
+        .iter()
+        .map(|c| match c {
+            Conflict::Property { key, .. } => key.as_str(),
+            Conflict::Identity { .. } => "<identity>",
+        })
+        .collect();
+    assert_eq!(keys, vec!["name", "score"]);
+    assert!(matches!(
+        &merged.conflicts[1],
+        Conflict::Property { rule: MergeRule::Max, merged, .. } if *merged == Value::from(5)
+    ));
+}
+
+#[test]
+fn test_merging_strategy_is_associative() {
+    let policy = MergePolicy::new()
+        .rule("views", MergeRule::Sum)
+        .rule("tags", MergeRule::Accumulate);
+    let s = |n: i64, tag: &str| {
+        subject(
+            "p",
+            &[],
+            vec![("views", Value::from(n)), ("tags", Value::from(tag))],
+        )
+    };
+
+    let left = policy
+        .strategy(s(1, "a"))
+        .combine(policy.strategy(s(2, "b")))
+        .combine(policy.strategy(s(3, "c")));
+    let right = policy.strategy(s(1, "a")).combine(
+        policy
+            .strategy(s(2, "b"))
+            .combine(policy.strategy(s(3, "c"))),
+    );
+
+    let left = left.finish().unwrap();
+    let right = right.finish().unwrap();
+    assert_eq!(left.subject, right.subject);
+    assert_eq!(left.subject.get::<i64>("views"), Some(6));
+}
+
+#[test]
+fn test_merging_strategy_keeps_first_error() {
+    let policy = MergePolicy::new().default_rule(MergeRule::Error);
+    let s = |n: i64| subject("p", &[], vec![("v", Value::from(n))]);
+
+    let merging = policy
+        .strategy(s(1))
+        .combine(policy.strategy(s(2)))
+        .combine(policy.strategy(s(3)));
+    assert_eq!(merging.subject().get::<i64>("v"), Some(1));
+    assert!(matches!(
+        merging.finish(),
+        Err(MergeError::PropertyConflict { right, .. }) if *right == Value::from(2)
+    ));
+}
+
+#[test]
+fn test_merging_inside_pattern_combine() {
+    let policy = MergePolicy::new().rule("n", MergeRule::Sum);
+    let a = Pattern::pattern(
+        policy.strategy(subject("g", &[], vec![("n", Value::from(1))])),
+        vec![Pattern::point(policy.strategy(subject("x", &[], vec![])))],
+    );
+    let b = Pattern::pattern(
+        policy.strategy(subject("g", &[], vec![("n", Value::from(2))])),
+        vec![Pattern::point(policy.strategy(subject("y", &[], vec![])))],
+    );
+
+    let combined = a.combine(b);
+    assert_eq!(combined.elements.len(), 2);
+    assert_eq!(combined.value.subject().get::<i64>("n"), Some(3));
+}
@@ -184,3 +184,35 @@ def test_pattern_subject_operations():
     
     mapped = pattern.map(add_label)
     assert mapped.get_value().has_label("Employee") is True
+
+
+def test_pattern_subject_expression_queries():
+    """Test filter_expr and find_first_expr on PatternSubject"""
+    alice = pattern_core.Subject(
+        identity="alice",
+        labels={"Person"},
+        properties={"name": pattern_core.Value.string("Alice"), "age": pattern_core.Value.int(34)}
+    )
+    bob = pattern_core.Subject(
+        identity="bob",
+        labels={"Person"},
+        properties={"name": pattern_core.Value.string("Bob"), "age": pattern_core.Value.int(27)}
+    )
+    team = pattern_core.Subject(identity="team", labels={"Team"}, properties={})
+    pattern = pattern_core.PatternSubject.pattern(
+        team,
+        [pattern_core.PatternSubject.point(alice), pattern_core.PatternSubject.point(bob)]
+    )
+
+    found = pattern.filter_expr(":Person AND age >= 30")
+    assert [p.get_value().identity for p in found] == ["alice"]
+
+    first = pattern.find_first_expr("name =~ 'B.*'")
+    assert first.get_value().identity == "bob"
+    assert pattern.find_first_expr(":Robot") is None
+
+    try:
+        pattern.filter_expr("age >=")
+        assert False, "expected ValueError"
+    except ValueError as e:
+        assert "syntax error" in str(e)
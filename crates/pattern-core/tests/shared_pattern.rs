@@ -0,0 +1,170 @@
+//! Tests for SharedPattern persistence and structural sharing
+
+use pattern_core::{Pattern, SharedPattern};
+use std::sync::Arc;
+use std::thread;
+
+fn sample() -> Pattern<i32> {
+    Pattern::pattern(
+        0,
+        vec![
+            Pattern::pattern(1, vec![Pattern::point(10), Pattern::point(11)]),
+            Pattern::pattern(2, vec![Pattern::point(20)]),
+            Pattern::point(3),
+        ],
+    )
+}
+
+#[test]
+fn test_conversion_round_trip() {
+    let shared = SharedPattern::from(sample());
+    assert_eq!(shared.to_pattern(), sample());
+    assert_eq!(Pattern::from(&shared), sample());
+    assert_eq!(shared.size(), sample().size());
+    assert_eq!(shared.depth(), sample().depth());
+    assert_eq!(shared.length(), 3);
+}
+
+#[test]
+fn test_clone_shares_root() {
+    let a = SharedPattern::from(sample());
+    let b = a.clone();
+    assert!(SharedPattern::ptr_eq(&a, &b));
+}
+
+#[test]
+fn test_get_by_path() {
+    let shared = SharedPattern::from(sample());
+    assert_eq!(shared.get(&[]).map(|p| *p.value()), Some(0));
+    assert_eq!(shared.get(&[0, 1]).map(|p| *p.value()), Some(11));
+    assert!(shared.get(&[0, 2]).is_none());
+    assert!(shared.get(&[2, 0]).is_none());
+}
+
+#[test]
+fn test_update_copies_only_the_path() {
+    let v1 = SharedPattern::from(sample());
+    let v2 = v1.update_value(&[0, 1], |v| v + 100).unwrap();
+
+    assert_eq!(*v1.get(&[0, 1]).unwrap().value(), 11);
+    assert_eq!(*v2.get(&[0, 1]).unwrap().value(), 111);
+
+    // Nodes on the path are new
+    assert!(!SharedPattern::ptr_eq(&v1, &v2));
+    assert!(!SharedPattern::ptr_eq(
+        v1.get(&[0]).unwrap(),
+        v2.get(&[0]).unwrap()
+    ));
+    // Everything off the path is shared
+    for path in [&[0, 0][..], &[1], &[2]] {
+        assert!(SharedPattern::ptr_eq(
+            v1.get(path).unwrap(),
+            v2.get(path).unwrap()
+        ));
+    }
+}
+
+#[test]
+fn test_update_value_shares_edited_nodes_elements() {
+    let v1 = SharedPattern::from(sample());
+    let v2 = v1.update_value(&[1], |_| 42).unwrap();
+    assert!(SharedPattern::ptr_eq(
+        v1.get(&[1, 0]).unwrap(),
+        v2.get(&[1, 0]).unwrap()
+    ));
+}
+
+#[test]
+fn test_set_and_invalid_paths() {
+    let v1 = SharedPattern::from(sample());
+    let v2 = v1.set(&[2], SharedPattern::point(99)).unwrap();
+    assert_eq!(*v2.get(&[2]).unwrap().value(), 99);
+    assert!(v1.set(&[5], SharedPattern::point(0)).is_none());
+    assert!(v1.update_value(&[0, 0, 0], |v| *v).is_none());
+
+    let replaced = v1.set(&[], SharedPattern::point(7)).unwrap();
+    assert_eq!(replaced.to_pattern(), Pattern::point(7));
+}
+
+#[test]
+fn test_insert_and_remove_elements() {
+    let v1 = SharedPattern::from(sample());
+
+    let v2 = v1
+        .insert_element(&[1], 0, SharedPattern::point(19))
+        .unwrap();
+    let values: Vec<i32> = v2
+        .get(&[1])
+        .unwrap()
+        .elements()
+        .iter()
+        .map(|e| *e.value())
+        .collect();
+    assert_eq!(values, vec![19, 20]);
+
+    let v3 = v2.remove_element(&[], 0).unwrap();
+    assert_eq!(v3.length(), 2);
+    assert_eq!(*v3.elements()[0].value(), 2);
+
+    assert!(v1
+        .insert_element(&[2], 2, SharedPattern::point(0))
+        .is_none());
+    assert!(v1.remove_element(&[2], 0).is_none());
+    assert_eq!(v1.to_pattern(), sample());
+}
+
+#[test]
+fn test_equality_is_structural() {
+    let a = SharedPattern::from(sample());
+    let b = SharedPattern::from(sample());
+    assert!(!SharedPattern::ptr_eq(&a, &b));
+    assert_eq!(a, b);
+    assert_ne!(a, a.update_value(&[2], |v| v + 1).unwrap());
+}
+
+#[test]
+fn test_equality_of_clones_matches_copies() {
+    let nan = SharedPattern::from(Pattern::pattern(0.0, vec![Pattern::point(f64::NAN)]));
+    let clone = nan.clone();
+    let copy = SharedPattern::from(nan.to_pattern());
+    assert!(SharedPattern::ptr_eq(&nan, &clone));
+    assert_ne!(nan, clone);
+    assert_ne!(nan, copy);
+    assert_eq!(nan == clone, nan.to_pattern() == clone.to_pattern());
+}
+
+#[test]
+fn test_undo_history() {
+    let mut history = vec![SharedPattern::from(sample())];
+    for i in 0..10 {
+        let next = history
+            .last()
+            .unwrap()
+            .update_value(&[2], |v| v + i)
+            .unwrap();
+        history.push(next);
+    }
+    assert_eq!(*history[0].get(&[2]).unwrap().value(), 3);
+    assert_eq!(*history[10].get(&[2]).unwrap().value(), 3 + 45);
+    // Untouched subtree is shared across every version
+    assert!(history
+        .iter()
+        .all(|v| SharedPattern::ptr_eq(v.get(&[0]).unwrap(), history[0].get(&[0]).unwrap())));
+}
+
+#[test]
+fn test_send_sync_snapshots() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<SharedPattern<String>>();
+
+    let snapshot = Arc::new(SharedPattern::from(sample()));
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let snapshot = Arc::clone(&snapshot);
+            thread::spawn(move || snapshot.size())
+        })
+        .collect();
+    for handle in handles {
+        assert_eq!(handle.join().unwrap(), 7);
+    }
+}
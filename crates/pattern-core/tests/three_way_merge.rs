@@ -0,0 +1,153 @@
+//! Tests for three-way merges of `Pattern<Subject>` documents
+//!
+//! Covers combining additions, removals and moves from both sides, conflicts
+//! on subjects both sides added, and conflict patterns.
+
+mod common;
+
+use common::{node, reference, subject};
+use pattern_core::{diff, three_way_merge, MergeConflict, Pattern, Side, Subject, Symbol, Value};
+
+fn knows(from: &str, to: &str) -> Pattern<Subject> {
+    Pattern::pattern(
+        subject("", &["KNOWS"], vec![]),
+        vec![reference(from), reference(to)],
+    )
+}
+
+fn team(id: &str, members: &[&str]) -> Pattern<Subject> {
+    Pattern::pattern(
+        subject(id, &["Team"], vec![]),
+        members.iter().map(|m| reference(m)).collect(),
+    )
+}
+
+#[test]
+fn test_additions_and_removals_from_both_sides() {
+    let base = vec![reference("a"), reference("b"), knows("a", "b")];
+    let ours = vec![
+        reference("a"),
+        reference("b"),
+        reference("c"),
+        knows("a", "b"),
+    ];
+    let theirs = vec![reference("a"), reference("b"), knows("b", "a")];
+
+    let merged = three_way_merge(&base, &ours, &theirs);
+
+    assert!(merged.is_clean());
+    let expected = vec![
+        reference("a"),
+        reference("b"),
+        reference("c"),
+        knows("b", "a"),
+    ];
+    assert!(diff(&merged.patterns, &expected).is_empty());
+}
+
+#[test]
+fn test_moves_from_both_sides() {
+    let base = vec![team("t1", &["a", "b", "c", "d"]), team("t2", &["x"])];
+    let ours = vec![team("t1", &["b", "c", "d"]), team("t2", &["x", "a"])];
+    let theirs = vec![team("t1", &["a", "b", "c", "d", "e"]), team("t2", &["x"])];
+
+    let merged = three_way_merge(&base, &ours, &theirs);
+
+    assert!(merged.is_clean());
+    let expected = vec![team("t1", &["b", "c", "d", "e"]), team("t2", &["x", "a"])];
+    assert!(
+        diff(&merged.patterns, &expected).is_empty(),
+        "{}",
+        diff(&merged.patterns, &expected)
+    );
+}
+
+#[test]
+fn test_both_sides_add_the_same_identity() {
+    let base = vec![];
+    let ours = vec![Pattern::point(subject(
+        "n",
+        &["A"],
+        vec![("x", Value::from(1)), ("y", Value::from(1))],
+    ))];
+    let theirs = vec![Pattern::point(subject(
+        "n",
+        &["B"],
+        vec![("x", Value::from(1)), ("y", Value::from(2))],
+    ))];
+
+    let merged = three_way_merge(&base, &ours, &theirs);
+
+    assert_eq!(
+        merged.conflicts,
+        vec![MergeConflict::Property {
+            identity: Symbol("n".to_string()),
+            key: "y".to_string(),
+            base: None,
+            ours: Some(Value::from(1)),
+            theirs: Some(Value::from(2)),
+        }]
+    );
+    let n = &merged.patterns[0].value;
+    assert_eq!(n.labels.len(), 2);
+    assert_eq!(n.get::<i64>("x"), Some(1));
+    assert!(!n.properties.contains_key("y"));
+}
+
+#[test]
+fn test_removal_by_both_sides() {
+    let base = vec![reference("a"), reference("b")];
+    let both = vec![reference("a")];
+
+    let merged = three_way_merge(&base, &both, &both);
+
+    assert!(merged.is_clean());
+    assert_eq!(merged.patterns, both);
+}
+
+#[test]
+fn test_removed_node_still_used_by_other_side() {
+    let base = vec![reference("a"), node("b", &[], vec![("v", Value::from(1))])];
+    let ours = vec![reference("a")];
+    let theirs = vec![
+        reference("a"),
+        node("b", &[], vec![("v", Value::from(1))]),
+        knows("a", "b"),
+    ];
+
+    let merged = three_way_merge(&base, &ours, &theirs);
+
+    assert_eq!(
+        merged.conflicts,
+        vec![MergeConflict::RemovedAndModified {
+            identity: Symbol("b".to_string()),
+            removed_by: Side::Ours,
+        }]
+    );
+    assert!(diff(&merged.patterns, &theirs).is_empty());
+}
+
+#[test]
+fn test_conflict_patterns() {
+    let conflict = MergeConflict::Endpoints {
+        identity: Symbol("r".to_string()),
+        base: Some((Symbol("a".to_string()), Symbol("b".to_string()))),
+        ours: None,
+        theirs: Some((Symbol("a".to_string()), Symbol("c".to_string()))),
+    };
+
+    let pattern = conflict.to_pattern();
+
+    assert!(pattern.value.identity.0.is_empty());
+    assert!(pattern.value.labels.contains("Conflict"));
+    assert_eq!(
+        pattern.value.get::<String>("kind"),
+        Some("endpoints".to_string())
+    );
+    assert_eq!(
+        pattern.value.get::<Vec<String>>("theirs"),
+        Some(vec!["a".to_string(), "c".to_string()])
+    );
+    assert!(!pattern.value.properties.contains_key("ours"));
+    assert_eq!(pattern.elements[0].value.identity.0, "r");
+}
@@ -0,0 +1,246 @@
+//! Tests for typed Value conversions and Subject property accessors
+//!
+//! Covers `From`/`TryFrom` between `Value` and Rust types, and the
+//! `get`/`get_or`/`try_get`/`require` helpers with their `PropertyError`s.
+
+use pattern_core::{PropertyError, RangeValue, Subject, Symbol, Value};
+use std::collections::{HashMap, HashSet};
+
+// ============================================================================
+// Helper Functions
+// ============================================================================
+
+fn person() -> Subject {
+    Subject {
+        identity: Symbol("alice".to_string()),
+        labels: HashSet::from(["Person".to_string()]),
+        properties: HashMap::from([
+            ("name".to_string(), Value::from("Alice")),
+            ("age".to_string(), Value::from(30u8)),
+            ("height".to_string(), Value::from(1.68)),
+            ("active".to_string(), Value::from(true)),
+            ("big".to_string(), Value::from(i64::MAX)),
+            ("tags".to_string(), Value::from(vec!["admin", "ops"])),
+            (
+                "scores".to_string(),
+                Value::from(HashMap::from([
+                    ("math".to_string(), 90),
+                    ("art".to_string(), 75),
+                ])),
+            ),
+            ("kind".to_string(), Value::VSymbol("employee".to_string())),
+        ]),
+    }
+}
+
+// ============================================================================
+// Value Conversions
+// ============================================================================
+
+#[test]
+fn test_from_primitives() {
+    assert_eq!(Value::from(-3i8), Value::VInteger(-3));
+    assert_eq!(Value::from(u32::MAX), Value::VInteger(u32::MAX as i64));
+    assert_eq!(Value::from(0.5f32), Value::VDecimal(0.5));
+    assert_eq!(Value::from(false), Value::VBoolean(false));
+    assert_eq!(Value::from("x"), Value::VString("x".to_string()));
+    assert_eq!(
+        Value::from(String::from("y")),
+        Value::VString("y".to_string())
+    );
+    let range = RangeValue {
+        lower: Some(1.0),
+        upper: None,
+    };
+    assert_eq!(Value::from(range.clone()), Value::VRange(range));
+}
+
+#[test]
+fn test_from_collections() {
+    assert_eq!(
+        Value::from(vec![1, 2]),
+        Value::VArray(vec![Value::VInteger(1), Value::VInteger(2)])
+    );
+    let nested = Value::from(vec![vec![true]]);
+    assert_eq!(
+        nested,
+        Value::VArray(vec![Value::VArray(vec![Value::VBoolean(true)])])
+    );
+    let map = Value::from(HashMap::from([("k".to_string(), "v")]));
+    assert_eq!(
+        map,
+        Value::VMap(HashMap::from([("k".to_string(), Value::from("v"))]))
+    );
+}
+
+#[test]
+fn test_try_from_round_trips() {
+    assert_eq!(i32::try_from(Value::from(-7)), Ok(-7));
+    assert_eq!(f64::try_from(&Value::from(2.5)), Ok(2.5));
+    assert_eq!(bool::try_from(Value::from(true)), Ok(true));
+    assert_eq!(String::try_from(Value::from("s")), Ok("s".to_string()));
+    assert_eq!(
+        Vec::<u16>::try_from(Value::from(vec![1u16, 2])),
+        Ok(vec![1, 2])
+    );
+    let map = HashMap::from([("a".to_string(), 1.5)]);
+    assert_eq!(
+        HashMap::<String, f64>::try_from(Value::from(map.clone())),
+        Ok(map)
+    );
+}
+
+#[test]
+fn test_options_map_none_to_an_empty_array() {
+    assert_eq!(Value::from(Some(3)), Value::VInteger(3));
+    assert_eq!(Value::from(None::<i64>), Value::VArray(vec![]));
+    assert_eq!(Option::<i64>::try_from(Value::from(Some(3))), Ok(Some(3)));
+    assert_eq!(Option::<i64>::try_from(&Value::from(None::<i64>)), Ok(None));
+    assert_eq!(
+        Option::<String>::try_from(&Value::from("s")),
+        Ok(Some("s".to_string()))
+    );
+    assert_eq!(
+        Option::<bool>::try_from(Value::from(1))
+            .unwrap_err()
+            .to_string(),
+        "expected boolean, found integer"
+    );
+    // An empty array is always `None`, even for an optional array
+    assert_eq!(
+        Option::<Vec<i64>>::try_from(Value::from(Some(Vec::<i64>::new()))),
+        Ok(None)
+    );
+    assert_eq!(
+        Option::<Vec<i64>>::try_from(Value::from(Some(vec![1]))),
+        Ok(Some(vec![1]))
+    );
+}
+
+#[test]
+fn test_try_from_widens_integers_to_decimals() {
+    assert_eq!(f64::try_from(&Value::VInteger(3)), Ok(3.0));
+    assert_eq!(f32::try_from(&Value::VInteger(3)), Ok(3.0));
+    assert!(i64::try_from(&Value::VDecimal(3.0)).is_err());
+}
+
+#[test]
+fn test_try_from_borrows_strings_and_symbols() {
+    let value = Value::VSymbol("sym".to_string());
+    let s: &str = (&value).try_into().unwrap();
+    assert_eq!(s, "sym");
+}
+
+#[test]
+fn test_try_from_reports_type_mismatch() {
+    assert_eq!(
+        bool::try_from(&Value::VInteger(1)),
+        Err(PropertyError::TypeMismatch {
+            key: None,
+            expected: "boolean",
+            actual: "integer",
+        })
+    );
+    // Element errors surface from inside collections
+    let err =
+        Vec::<i64>::try_from(&Value::from(vec![Value::from(1), Value::from("x")])).unwrap_err();
+    assert_eq!(err.to_string(), "expected integer, found string");
+}
+
+#[test]
+fn test_try_from_reports_out_of_range() {
+    let err = u8::try_from(Value::VInteger(300)).unwrap_err();
+    assert_eq!(
+        err,
+        PropertyError::OutOfRange {
+            key: None,
+            target: "u8",
+            value: "300".to_string(),
+        }
+    );
+    assert_eq!(err.to_string(), "value 300 is out of range for u8");
+    assert!(u64::try_from(Value::VInteger(-1)).is_err());
+}
+
+// ============================================================================
+// Subject Accessors
+// ============================================================================
+
+#[test]
+fn test_get_returns_typed_values() {
+    let alice = person();
+    assert_eq!(alice.get::<String>("name"), Some("Alice".to_string()));
+    assert_eq!(alice.get::<&str>("kind"), Some("employee"));
+    assert_eq!(alice.get::<u8>("age"), Some(30));
+    assert_eq!(alice.get::<f64>("height"), Some(1.68));
+    assert_eq!(alice.get::<bool>("active"), Some(true));
+    assert_eq!(
+        alice.get::<Vec<String>>("tags"),
+        Some(vec!["admin".to_string(), "ops".to_string()])
+    );
+    assert_eq!(
+        alice.get::<HashMap<String, i64>>("scores").unwrap()["math"],
+        90
+    );
+}
+
+#[test]
+fn test_get_is_none_for_missing_or_mistyped() {
+    let alice = person();
+    assert_eq!(alice.get::<i64>("missing"), None);
+    assert_eq!(alice.get::<i64>("name"), None);
+    assert_eq!(alice.get::<i32>("big"), None);
+}
+
+#[test]
+fn test_get_or_falls_back_to_default() {
+    let alice = person();
+    assert_eq!(alice.get_or("age", 0u8), 30);
+    assert_eq!(alice.get_or("email", "none"), "none");
+    assert!(!alice.get_or("name", false));
+}
+
+#[test]
+fn test_try_get_distinguishes_missing_from_mistyped() {
+    let alice = person();
+    assert_eq!(alice.try_get::<i64>("age"), Ok(Some(30)));
+    assert_eq!(alice.try_get::<i64>("missing"), Ok(None));
+    assert_eq!(
+        alice.try_get::<i64>("name"),
+        Err(PropertyError::TypeMismatch {
+            key: Some("name".to_string()),
+            expected: "integer",
+            actual: "string",
+        })
+    );
+}
+
+#[test]
+fn test_require_errors_name_the_key() {
+    let alice = person();
+    assert_eq!(alice.require::<i64>("age"), Ok(30));
+
+    let missing = alice.require::<i64>("email").unwrap_err();
+    assert_eq!(missing.key(), Some("email"));
+    assert_eq!(missing.to_string(), "missing property 'email'");
+
+    let mistyped = alice.require::<Vec<i64>>("tags").unwrap_err();
+    assert_eq!(
+        mistyped.to_string(),
+        "property 'tags': expected integer, found string"
+    );
+
+    let overflow = alice.require::<i16>("big").unwrap_err();
+    assert_eq!(
+        overflow.to_string(),
+        format!("property 'big': value {} is out of range for i16", i64::MAX)
+    );
+}
+
+#[test]
+fn test_property_error_is_std_error() {
+    fn boxed() -> Result<i64, Box<dyn std::error::Error>> {
+        Ok(person().require::<i64>("name")?)
+    }
+    assert!(boxed().is_err());
+}
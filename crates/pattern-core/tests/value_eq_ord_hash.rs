@@ -0,0 +1,253 @@
+//! Tests for total Eq/Ord/Hash on Value, RangeValue and Subject
+//!
+//! Checks the canonical float semantics (NaN, -0.0), order-independent map and
+//! label hashing, and that equality, ordering and hashing agree.
+
+mod common;
+
+use common::subject;
+use pattern_core::{Pattern, RangeValue, Value};
+use proptest::prelude::*;
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+// ============================================================================
+// Helper Functions
+// ============================================================================
+
+fn hash_of<T: Hash>(value: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn assert_consistent(a: &Value, b: &Value) {
+    assert_eq!(a == b, a.cmp(b) == Ordering::Equal, "{:?} vs {:?}", a, b);
+    assert_eq!(a.cmp(b), b.cmp(a).reverse(), "{:?} vs {:?}", a, b);
+    if a == b {
+        assert_eq!(hash_of(a), hash_of(b), "{:?} vs {:?}", a, b);
+    }
+}
+
+// ============================================================================
+// Floats
+// ============================================================================
+
+#[test]
+fn test_nan_equals_itself() {
+    let nan = Value::VDecimal(f64::NAN);
+    assert_eq!(nan, nan.clone());
+    assert_eq!(nan, Value::VDecimal(-f64::NAN));
+    assert_eq!(hash_of(&nan), hash_of(&Value::VDecimal(-f64::NAN)));
+}
+
+#[test]
+fn test_negative_zero_equals_zero() {
+    let zero = Value::VDecimal(0.0);
+    let neg = Value::VDecimal(-0.0);
+    assert_eq!(zero, neg);
+    assert_eq!(zero.cmp(&neg), Ordering::Equal);
+    assert_eq!(hash_of(&zero), hash_of(&neg));
+}
+
+#[test]
+fn test_float_order() {
+    let mut values = [
+        Value::VDecimal(f64::NAN),
+        Value::VDecimal(f64::INFINITY),
+        Value::VDecimal(1.5),
+        Value::VDecimal(-0.0),
+        Value::VDecimal(f64::NEG_INFINITY),
+        Value::VDecimal(-2.0),
+    ];
+    values.sort();
+    let rendered: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+    assert_eq!(rendered, vec!["-inf", "-2", "-0", "1.5", "inf", "NaN"]);
+}
+
+#[test]
+fn test_measurement_and_range_use_canonical_floats() {
+    let m1 = Value::VMeasurement {
+        unit: "kg".to_string(),
+        value: -0.0,
+    };
+    let m2 = Value::VMeasurement {
+        unit: "kg".to_string(),
+        value: 0.0,
+    };
+    assert_eq!(m1, m2);
+    assert_eq!(hash_of(&m1), hash_of(&m2));
+
+    let r1 = RangeValue {
+        lower: Some(f64::NAN),
+        upper: None,
+    };
+    let r2 = RangeValue {
+        lower: Some(f64::NAN),
+        upper: None,
+    };
+    assert_eq!(r1, r2);
+    assert_eq!(hash_of(&r1), hash_of(&r2));
+}
+
+#[test]
+fn test_unbounded_range_sorts_first() {
+    let unbounded = RangeValue {
+        lower: None,
+        upper: Some(1.0),
+    };
+    let bounded = RangeValue {
+        lower: Some(f64::NEG_INFINITY),
+        upper: Some(1.0),
+    };
+    assert!(unbounded < bounded);
+}
+
+// ============================================================================
+// Variants and Collections
+// ============================================================================
+
+#[test]
+fn test_variants_are_distinct_and_ordered() {
+    assert_ne!(Value::VInteger(1), Value::VDecimal(1.0));
+    assert_ne!(
+        Value::VString("a".to_string()),
+        Value::VSymbol("a".to_string())
+    );
+    assert!(Value::VInteger(i64::MAX) < Value::VDecimal(f64::NEG_INFINITY));
+    assert!(Value::VBoolean(true) < Value::VString(String::new()));
+    assert!(
+        Value::VArray(vec![]) < Value::VMap(HashMap::new()),
+        "arrays before maps"
+    );
+}
+
+#[test]
+fn test_map_equality_and_hash_ignore_insertion_order() {
+    let mut a = HashMap::new();
+    let mut b = HashMap::new();
+    for i in 0..50 {
+        a.insert(format!("k{}", i), Value::VInteger(i));
+    }
+    for i in (0..50).rev() {
+        b.insert(format!("k{}", i), Value::VInteger(i));
+    }
+    let (a, b) = (Value::VMap(a), Value::VMap(b));
+    assert_eq!(a, b);
+    assert_eq!(a.cmp(&b), Ordering::Equal);
+    assert_eq!(hash_of(&a), hash_of(&b));
+}
+
+#[test]
+fn test_maps_order_by_sorted_entries() {
+    let map = |entries: &[(&str, i64)]| {
+        Value::VMap(
+            entries
+                .iter()
+                .map(|(k, v)| (k.to_string(), Value::VInteger(*v)))
+                .collect(),
+        )
+    };
+    assert!(map(&[("a", 1)]) < map(&[("a", 2)]));
+    assert!(map(&[("a", 9)]) < map(&[("b", 0)]));
+    assert!(map(&[("a", 1)]) < map(&[("a", 1), ("b", 0)]));
+}
+
+// ============================================================================
+// Subject and Pattern<Subject>
+// ============================================================================
+
+#[test]
+fn test_subject_hash_set_deduplicates() {
+    let mut set = HashSet::new();
+    set.insert(subject("a", &["X", "Y"], vec![("n", Value::VDecimal(0.0))]));
+    set.insert(subject(
+        "a",
+        &["Y", "X"],
+        vec![("n", Value::VDecimal(-0.0))],
+    ));
+    set.insert(subject("b", &[], vec![]));
+    assert_eq!(set.len(), 2);
+}
+
+#[test]
+fn test_subject_order_identity_labels_properties() {
+    let a = subject("a", &["Z"], vec![]);
+    let b = subject("b", &["A"], vec![]);
+    assert!(a < b);
+
+    let fewer_labels = subject("a", &["A"], vec![]);
+    let more_labels = subject("a", &["A", "B"], vec![]);
+    assert!(fewer_labels < more_labels);
+
+    let small = subject("a", &["A"], vec![("x", Value::VInteger(1))]);
+    let large = subject("a", &["A"], vec![("x", Value::VInteger(2))]);
+    assert!(small < large);
+}
+
+#[test]
+fn test_pattern_subject_in_ordered_and_hashed_collections() {
+    let p = |id: &str| Pattern::point(subject(id, &["Person"], vec![]));
+    let doc = vec![p("c"), p("a"), p("b"), p("a")];
+
+    let sorted: BTreeSet<_> = doc.iter().cloned().collect();
+    let ids: Vec<_> = sorted.iter().map(|p| p.value.identity.0.as_str()).collect();
+    assert_eq!(ids, vec!["a", "b", "c"]);
+
+    let unique: HashSet<_> = doc.into_iter().collect();
+    assert_eq!(unique.len(), 3);
+}
+
+// ============================================================================
+// Property-Based Consistency
+// ============================================================================
+
+fn float() -> impl Strategy<Value = f64> {
+    prop_oneof![
+        Just(f64::NAN),
+        Just(-0.0),
+        Just(0.0),
+        Just(f64::INFINITY),
+        (-5i32..5).prop_map(|i| i as f64 / 2.0),
+    ]
+}
+
+fn value() -> impl Strategy<Value = Value> {
+    let leaf = prop_oneof![
+        (-3i64..3).prop_map(Value::VInteger),
+        float().prop_map(Value::VDecimal),
+        any::<bool>().prop_map(Value::VBoolean),
+        "[ab]{0,2}".prop_map(Value::VString),
+        "[ab]{0,2}".prop_map(Value::VSymbol),
+        (proptest::option::of(float()), proptest::option::of(float()))
+            .prop_map(|(lower, upper)| Value::VRange(RangeValue { lower, upper })),
+        float().prop_map(|value| Value::VMeasurement {
+            unit: "kg".to_string(),
+            value
+        }),
+    ];
+    leaf.prop_recursive(2, 8, 3, |inner| {
+        prop_oneof![
+            proptest::collection::vec(inner.clone(), 0..3).prop_map(Value::VArray),
+            proptest::collection::hash_map("[ab]", inner, 0..3).prop_map(Value::VMap),
+        ]
+    })
+}
+
+proptest! {
+    #[test]
+    fn prop_eq_ord_hash_agree(a in value(), b in value()) {
+        assert_consistent(&a, &b);
+        assert_consistent(&a, &a.clone());
+    }
+
+    #[test]
+    fn prop_ord_is_transitive(a in value(), b in value(), c in value()) {
+        let mut sorted = [a, b, c];
+        sorted.sort();
+        prop_assert!(sorted[0] <= sorted[1] && sorted[1] <= sorted[2]);
+        prop_assert!(sorted[0] <= sorted[2]);
+    }
+}
@@ -0,0 +1,167 @@
+//! Fixed-length bitmaps used as column masks
+
+use std::ops::{BitAnd, BitOr};
+
+/// A fixed-length set of node positions, stored as packed 64-bit words.
+///
+/// Bitmaps are the result type of columnar queries and can be combined with
+/// `&` and `|` to build conjunctions and disjunctions.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Bitmap {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl Bitmap {
+    /// Creates a bitmap of `len` positions, all unset.
+    pub fn new(len: usize) -> Self {
+        Bitmap {
+            words: vec![0; (len + 63) / 64],
+            len,
+        }
+    }
+
+    /// Creates a bitmap of `len` positions, all set.
+    pub fn full(len: usize) -> Self {
+        let mut bitmap = Bitmap {
+            words: vec![u64::MAX; (len + 63) / 64],
+            len,
+        };
+        bitmap.clear_tail();
+        bitmap
+    }
+
+    /// Number of positions (set or unset).
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the bitmap has no positions.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns whether position `i` is set. Out-of-range positions are unset.
+    pub fn get(&self, i: usize) -> bool {
+        i < self.len && self.words[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    /// Sets or clears position `i`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is out of range.
+    pub fn set(&mut self, i: usize, value: bool) {
+        assert!(i < self.len, "bitmap index {} out of range {}", i, self.len);
+        let mask = 1 << (i % 64);
+        if value {
+            self.words[i / 64] |= mask;
+        } else {
+            self.words[i / 64] &= !mask;
+        }
+    }
+
+    /// Number of set positions.
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Returns `true` if any position is set.
+    pub fn any(&self) -> bool {
+        self.words.iter().any(|w| *w != 0)
+    }
+
+    /// Iterates over set positions in ascending order.
+    pub fn ones(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(w, word)| {
+            let mut bits = *word;
+            std::iter::from_fn(move || {
+                if bits == 0 {
+                    return None;
+                }
+                let bit = bits.trailing_zeros() as usize;
+                bits &= bits - 1;
+                Some(w * 64 + bit)
+            })
+        })
+    }
+
+    /// Returns the complement of this bitmap.
+    pub fn complement(&self) -> Bitmap {
+        let mut bitmap = Bitmap {
+            words: self.words.iter().map(|w| !w).collect(),
+            len: self.len,
+        };
+        bitmap.clear_tail();
+        bitmap
+    }
+
+    fn clear_tail(&mut self) {
+        if self.len % 64 != 0 {
+            if let Some(last) = self.words.last_mut() {
+                *last &= (1 << (self.len % 64)) - 1;
+            }
+        }
+    }
+
+    fn zip_with(&self, other: &Bitmap, f: impl Fn(u64, u64) -> u64) -> Bitmap {
+        assert_eq!(self.len, other.len, "bitmap length mismatch");
+        Bitmap {
+            words: self
+                .words
+                .iter()
+                .zip(&other.words)
+                .map(|(a, b)| f(*a, *b))
+                .collect(),
+            len: self.len,
+        }
+    }
+}
+
+impl BitAnd for &Bitmap {
+    type Output = Bitmap;
+
+    /// # Panics
+    ///
+    /// Panics if the bitmaps have different lengths.
+    fn bitand(self, other: &Bitmap) -> Bitmap {
+        self.zip_with(other, |a, b| a & b)
+    }
+}
+
+impl BitOr for &Bitmap {
+    type Output = Bitmap;
+
+    /// # Panics
+    ///
+    /// Panics if the bitmaps have different lengths.
+    fn bitor(self, other: &Bitmap) -> Bitmap {
+        self.zip_with(other, |a, b| a | b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_get_and_ones() {
+        let mut bitmap = Bitmap::new(130);
+        bitmap.set(0, true);
+        bitmap.set(64, true);
+        bitmap.set(129, true);
+        assert!(bitmap.get(64));
+        assert!(!bitmap.get(65));
+        assert!(!bitmap.get(500));
+        assert_eq!(bitmap.ones().collect::<Vec<_>>(), vec![0, 64, 129]);
+        assert_eq!(bitmap.count_ones(), 3);
+    }
+
+    #[test]
+    fn test_full_and_not_respect_length() {
+        let full = Bitmap::full(70);
+        assert_eq!(full.count_ones(), 70);
+        assert_eq!(full.complement().count_ones(), 0);
+        assert_eq!(Bitmap::new(70).complement(), full);
+    }
+}
@@ -0,0 +1,565 @@
+//! Columnar layout for `Pattern<Subject>` documents
+//!
+//! [`ColumnarDocument`] flattens a document into struct-of-arrays form:
+//!
+//! - **Structure**: nodes are numbered in breadth-first order, so the children of
+//!   every node occupy a contiguous range of node ids. `parent` and
+//!   `child_offsets` arrays replace the nested `Vec`s.
+//! - **Identities**: one interned [`Sym`] per node.
+//! - **Labels**: one [`Bitmap`] per distinct label.
+//! - **Properties**: one [`PropertyColumn`] per distinct key, with a presence
+//!   bitmap and typed storage ([`ColumnData`]) when every value under that key has
+//!   the same primitive type.
+//!
+//! Queries such as [`ColumnarDocument::where_numeric`] and
+//! [`ColumnarDocument::sum_numeric`] scan the columns directly and return
+//! [`Bitmap`] masks that can be combined with `&` and `|`.
+
+use crate::bitmap::Bitmap;
+use crate::interner::{Interner, Sym};
+use pattern_core::{Pattern, Subject, Symbol, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+
+const NO_PARENT: u32 = u32::MAX;
+
+/// Typed storage for one property column.
+///
+/// Slots for nodes without the property hold a default value; consult the
+/// column's presence bitmap before reading them.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColumnData {
+    /// Every value is a `VInteger`
+    Integer(Vec<i64>),
+    /// Every value is a `VDecimal`
+    Decimal(Vec<f64>),
+    /// Every value is a `VBoolean`
+    Boolean(Bitmap),
+    /// Every value is a `VString` (interned)
+    String(Vec<Sym>),
+    /// Values of mixed or non-primitive types
+    Mixed(Vec<Option<Value>>),
+}
+
+/// All values of one property key across a document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyColumn {
+    key: Sym,
+    present: Bitmap,
+    data: ColumnData,
+}
+
+impl PropertyColumn {
+    /// Interned key of this column.
+    pub fn key(&self) -> Sym {
+        self.key
+    }
+
+    /// Nodes that have this property.
+    pub fn present(&self) -> &Bitmap {
+        &self.present
+    }
+
+    /// Typed values, indexed by node id.
+    pub fn data(&self) -> &ColumnData {
+        &self.data
+    }
+}
+
+/// A `Pattern<Subject>` document in columnar (struct-of-arrays) form.
+///
+/// Conversion to and from `Vec<Pattern<Subject>>` is lossless: labels,
+/// properties (including float bit patterns) and element order are preserved.
+///
+/// # Examples
+///
+/// ```rust
+/// use pattern_core::{Pattern, Subject, Symbol, Value};
+/// use pattern_store::ColumnarDocument;
+/// use std::collections::{HashMap, HashSet};
+///
+/// let person = |id: &str, age: i64| Pattern::point(Subject {
+///     identity: Symbol(id.to_string()),
+///     labels: HashSet::from(["Person".to_string()]),
+///     properties: HashMap::from([("age".to_string(), Value::VInteger(age))]),
+/// });
+/// let doc = vec![person("alice", 30), person("bob", 42)];
+///
+/// let columns = ColumnarDocument::from_patterns(&doc);
+/// let older = &columns.where_numeric("age", |age| age > 35.0) & &columns.label_mask("Person");
+/// assert_eq!(older.ones().collect::<Vec<_>>(), vec![1]);
+/// assert_eq!(columns.sum_numeric("age"), 72.0);
+///
+/// assert_eq!(columns.to_patterns(), doc);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ColumnarDocument {
+    strings: Interner,
+    root_count: usize,
+    parent: Vec<u32>,
+    child_offsets: Vec<u32>,
+    identity: Vec<Sym>,
+    labels: Vec<(Sym, Bitmap)>,
+    label_index: HashMap<Sym, usize>,
+    columns: Vec<PropertyColumn>,
+    column_index: HashMap<Sym, usize>,
+}
+
+/// Read-only view of one node of a [`ColumnarDocument`].
+#[derive(Debug, Clone, Copy)]
+pub struct NodeView<'a> {
+    doc: &'a ColumnarDocument,
+    id: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Integer,
+    Decimal,
+    Boolean,
+    String,
+    Mixed,
+}
+
+impl Kind {
+    fn of(value: &Value) -> Kind {
+        match value {
+            Value::VInteger(_) => Kind::Integer,
+            Value::VDecimal(_) => Kind::Decimal,
+            Value::VBoolean(_) => Kind::Boolean,
+            Value::VString(_) => Kind::String,
+            _ => Kind::Mixed,
+        }
+    }
+}
+
+impl ColumnarDocument {
+    /// Builds the columnar form of a document.
+    ///
+    /// # Complexity
+    ///
+    /// O(n + p) where n is the number of nodes and p the number of properties.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the document has more than `u32::MAX - 1` nodes.
+    pub fn from_patterns(patterns: &[Pattern<Subject>]) -> Self {
+        // Breadth-first numbering keeps each node's children contiguous
+        let mut order: Vec<&Pattern<Subject>> = patterns.iter().collect();
+        let mut parent = vec![NO_PARENT; order.len()];
+        let mut child_offsets = Vec::with_capacity(order.len() + 1);
+        let mut i = 0;
+        while i < order.len() {
+            child_offsets.push(to_u32(order.len()));
+            let node = order[i];
+            for element in &node.elements {
+                order.push(element);
+                parent.push(to_u32(i));
+            }
+            i += 1;
+        }
+        child_offsets.push(to_u32(order.len()));
+        let n = order.len();
+
+        let mut strings = Interner::new();
+        let empty = strings.intern("");
+        let identity = order
+            .iter()
+            .map(|p| strings.intern(&p.value.identity.0))
+            .collect();
+
+        let mut labels: Vec<(Sym, Bitmap)> = Vec::new();
+        let mut label_index = HashMap::new();
+        let mut kinds: BTreeMap<&str, Kind> = BTreeMap::new();
+        for (id, node) in order.iter().enumerate() {
+            for label in &node.value.labels {
+                let sym = strings.intern(label);
+                let slot = *label_index.entry(sym).or_insert_with(|| {
+                    labels.push((sym, Bitmap::new(n)));
+                    labels.len() - 1
+                });
+                labels[slot].1.set(id, true);
+            }
+            for (key, value) in &node.value.properties {
+                let kind = Kind::of(value);
+                kinds
+                    .entry(key)
+                    .and_modify(|k| {
+                        if *k != kind {
+                            *k = Kind::Mixed
+                        }
+                    })
+                    .or_insert(kind);
+            }
+        }
+
+        let mut columns = Vec::with_capacity(kinds.len());
+        let mut column_index = HashMap::new();
+        for (key, kind) in kinds {
+            let data = match kind {
+                Kind::Integer => ColumnData::Integer(vec![0; n]),
+                Kind::Decimal => ColumnData::Decimal(vec![0.0; n]),
+                Kind::Boolean => ColumnData::Boolean(Bitmap::new(n)),
+                Kind::String => ColumnData::String(vec![empty; n]),
+                Kind::Mixed => ColumnData::Mixed(vec![None; n]),
+            };
+            let key = strings.intern(key);
+            column_index.insert(key, columns.len());
+            columns.push(PropertyColumn {
+                key,
+                present: Bitmap::new(n),
+                data,
+            });
+        }
+
+        for (id, node) in order.iter().enumerate() {
+            for (key, value) in &node.value.properties {
+                let key = strings.get(key).expect("key interned above");
+                let column = &mut columns[column_index[&key]];
+                column.present.set(id, true);
+                match (&mut column.data, value) {
+                    (ColumnData::Integer(v), Value::VInteger(x)) => v[id] = *x,
+                    (ColumnData::Decimal(v), Value::VDecimal(x)) => v[id] = *x,
+                    (ColumnData::Boolean(v), Value::VBoolean(x)) => v.set(id, *x),
+                    (ColumnData::String(v), Value::VString(x)) => v[id] = strings.intern(x),
+                    (ColumnData::Mixed(v), x) => v[id] = Some(x.clone()),
+                    _ => unreachable!("column kind agrees with every value"),
+                }
+            }
+        }
+
+        ColumnarDocument {
+            strings,
+            root_count: patterns.len(),
+            parent,
+            child_offsets,
+            identity,
+            labels,
+            label_index,
+            columns,
+            column_index,
+        }
+    }
+
+    /// Rebuilds the document as owned patterns, in the original order.
+    pub fn to_patterns(&self) -> Vec<Pattern<Subject>> {
+        self.roots().map(|id| self.to_pattern(id)).collect()
+    }
+
+    /// Rebuilds the subtree rooted at node `id`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is out of range.
+    pub fn to_pattern(&self, id: usize) -> Pattern<Subject> {
+        Pattern::pattern(
+            self.subject(id),
+            self.children(id).map(|c| self.to_pattern(c)).collect(),
+        )
+    }
+
+    /// Rebuilds the subject of node `id`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is out of range.
+    pub fn subject(&self, id: usize) -> Subject {
+        let node = self.node(id);
+        Subject {
+            identity: Symbol(node.identity().to_string()),
+            labels: node.labels().map(str::to_string).collect(),
+            properties: self
+                .columns
+                .iter()
+                .filter(|c| c.present.get(id))
+                .map(|c| (self.resolve(c.key).to_string(), self.column_value(c, id)))
+                .collect(),
+        }
+    }
+
+    /// Total number of nodes (top-level patterns and all nested elements).
+    pub fn len(&self) -> usize {
+        self.identity.len()
+    }
+
+    /// Returns `true` if the document has no patterns.
+    pub fn is_empty(&self) -> bool {
+        self.identity.is_empty()
+    }
+
+    /// Node ids of the top-level patterns.
+    pub fn roots(&self) -> Range<usize> {
+        0..self.root_count
+    }
+
+    /// Node ids of the direct elements of node `id`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is out of range.
+    pub fn children(&self, id: usize) -> Range<usize> {
+        self.child_offsets[id] as usize..self.child_offsets[id + 1] as usize
+    }
+
+    /// Parent of node `id`, or `None` for top-level patterns.
+    pub fn parent(&self, id: usize) -> Option<usize> {
+        match self.parent.get(id) {
+            Some(&p) if p != NO_PARENT => Some(p as usize),
+            _ => None,
+        }
+    }
+
+    /// Returns a view of node `id`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is out of range.
+    pub fn node(&self, id: usize) -> NodeView<'_> {
+        assert!(id < self.len(), "node {} out of range {}", id, self.len());
+        NodeView { doc: self, id }
+    }
+
+    /// Iterates over all nodes in breadth-first order.
+    pub fn nodes(&self) -> impl Iterator<Item = NodeView<'_>> {
+        (0..self.len()).map(move |id| NodeView { doc: self, id })
+    }
+
+    /// Resolves an interned string handle.
+    pub fn resolve(&self, sym: Sym) -> &str {
+        self.strings.resolve(sym)
+    }
+
+    /// The property column for `key`, if any node has that property.
+    pub fn column(&self, key: &str) -> Option<&PropertyColumn> {
+        let sym = self.strings.get(key)?;
+        self.column_index.get(&sym).map(|i| &self.columns[*i])
+    }
+
+    /// All property columns, sorted by key.
+    pub fn columns(&self) -> &[PropertyColumn] {
+        &self.columns
+    }
+
+    /// Distinct labels in first-seen order.
+    pub fn labels(&self) -> impl Iterator<Item = &str> {
+        self.labels.iter().map(move |(sym, _)| self.resolve(*sym))
+    }
+
+    /// Nodes carrying `label` (an empty mask if no node does).
+    pub fn label_mask(&self, label: &str) -> Bitmap {
+        self.strings
+            .get(label)
+            .and_then(|sym| self.label_index.get(&sym))
+            .map(|i| self.labels[*i].1.clone())
+            .unwrap_or_else(|| Bitmap::new(self.len()))
+    }
+
+    /// Nodes whose identity is `identity`.
+    pub fn identity_mask(&self, identity: &str) -> Bitmap {
+        let mut mask = Bitmap::new(self.len());
+        if let Some(sym) = self.strings.get(identity) {
+            for (id, s) in self.identity.iter().enumerate() {
+                if *s == sym {
+                    mask.set(id, true);
+                }
+            }
+        }
+        mask
+    }
+
+    /// Nodes with a numeric (`VInteger` or `VDecimal`) value under `key` that satisfies `predicate`.
+    pub fn where_numeric<F>(&self, key: &str, predicate: F) -> Bitmap
+    where
+        F: Fn(f64) -> bool,
+    {
+        let mut mask = Bitmap::new(self.len());
+        for (id, value) in self.numeric_values(key) {
+            if predicate(value) {
+                mask.set(id, true);
+            }
+        }
+        mask
+    }
+
+    /// Nodes with a `VString` value under `key` that satisfies `predicate`.
+    pub fn where_string<F>(&self, key: &str, predicate: F) -> Bitmap
+    where
+        F: Fn(&str) -> bool,
+    {
+        let mut mask = Bitmap::new(self.len());
+        let Some(column) = self.column(key) else {
+            return mask;
+        };
+        for id in column.present.ones() {
+            let hit = match &column.data {
+                ColumnData::String(v) => predicate(self.resolve(v[id])),
+                ColumnData::Mixed(v) => matches!(&v[id], Some(Value::VString(s)) if predicate(s)),
+                _ => false,
+            };
+            if hit {
+                mask.set(id, true);
+            }
+        }
+        mask
+    }
+
+    /// Nodes with a `VBoolean` value under `key` equal to `expected`.
+    pub fn where_bool(&self, key: &str, expected: bool) -> Bitmap {
+        let mut mask = Bitmap::new(self.len());
+        let Some(column) = self.column(key) else {
+            return mask;
+        };
+        for id in column.present.ones() {
+            let hit = match &column.data {
+                ColumnData::Boolean(v) => v.get(id) == expected,
+                ColumnData::Mixed(v) => v[id] == Some(Value::VBoolean(expected)),
+                _ => false,
+            };
+            if hit {
+                mask.set(id, true);
+            }
+        }
+        mask
+    }
+
+    /// Nodes for which `predicate` holds, evaluated row by row.
+    ///
+    /// Prefer the column-specific `where_*` methods for large documents; this is
+    /// the fallback for predicates that need several fields at once.
+    pub fn filter<F>(&self, predicate: F) -> Bitmap
+    where
+        F: Fn(NodeView<'_>) -> bool,
+    {
+        let mut mask = Bitmap::new(self.len());
+        for node in self.nodes() {
+            if predicate(node) {
+                mask.set(node.id, true);
+            }
+        }
+        mask
+    }
+
+    /// Folds over the numeric values of `key` in node order.
+    pub fn fold_numeric<B, F>(&self, key: &str, init: B, f: F) -> B
+    where
+        F: FnMut(B, f64) -> B,
+    {
+        self.numeric_values(key).map(|(_, v)| v).fold(init, f)
+    }
+
+    /// Sum of the numeric values of `key` (0.0 if there are none).
+    pub fn sum_numeric(&self, key: &str) -> f64 {
+        self.fold_numeric(key, 0.0, |acc, v| acc + v)
+    }
+
+    /// Folds over the nodes selected by `mask`, in node order.
+    pub fn fold_masked<B, F>(&self, mask: &Bitmap, init: B, f: F) -> B
+    where
+        F: FnMut(B, NodeView<'_>) -> B,
+    {
+        mask.ones()
+            .filter(|id| *id < self.len())
+            .map(|id| NodeView { doc: self, id })
+            .fold(init, f)
+    }
+
+    fn numeric_values<'a>(&'a self, key: &str) -> Box<dyn Iterator<Item = (usize, f64)> + 'a> {
+        let Some(column) = self.column(key) else {
+            return Box::new(std::iter::empty());
+        };
+        let present = column.present.ones();
+        match &column.data {
+            ColumnData::Integer(v) => Box::new(present.map(move |id| (id, v[id] as f64))),
+            ColumnData::Decimal(v) => Box::new(present.map(move |id| (id, v[id]))),
+            ColumnData::Mixed(v) => Box::new(present.filter_map(move |id| match &v[id] {
+                Some(Value::VInteger(i)) => Some((id, *i as f64)),
+                Some(Value::VDecimal(d)) => Some((id, *d)),
+                _ => None,
+            })),
+            _ => Box::new(std::iter::empty()),
+        }
+    }
+
+    fn column_value(&self, column: &PropertyColumn, id: usize) -> Value {
+        match &column.data {
+            ColumnData::Integer(v) => Value::VInteger(v[id]),
+            ColumnData::Decimal(v) => Value::VDecimal(v[id]),
+            ColumnData::Boolean(v) => Value::VBoolean(v.get(id)),
+            ColumnData::String(v) => Value::VString(self.resolve(v[id]).to_string()),
+            ColumnData::Mixed(v) => v[id].clone().expect("present slot holds a value"),
+        }
+    }
+}
+
+impl From<&[Pattern<Subject>]> for ColumnarDocument {
+    fn from(patterns: &[Pattern<Subject>]) -> Self {
+        ColumnarDocument::from_patterns(patterns)
+    }
+}
+
+impl From<&ColumnarDocument> for Vec<Pattern<Subject>> {
+    fn from(doc: &ColumnarDocument) -> Self {
+        doc.to_patterns()
+    }
+}
+
+impl<'a> NodeView<'a> {
+    /// Node id.
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Identity of this node (empty for anonymous subjects).
+    pub fn identity(&self) -> &'a str {
+        self.doc.resolve(self.doc.identity[self.id])
+    }
+
+    /// Returns `true` if the node carries `label`.
+    pub fn has_label(&self, label: &str) -> bool {
+        self.doc
+            .strings
+            .get(label)
+            .and_then(|sym| self.doc.label_index.get(&sym))
+            .is_some_and(|i| self.doc.labels[*i].1.get(self.id))
+    }
+
+    /// Labels of this node.
+    pub fn labels(&self) -> impl Iterator<Item = &'a str> + 'a {
+        let (doc, id) = (self.doc, self.id);
+        doc.labels
+            .iter()
+            .filter(move |(_, bitmap)| bitmap.get(id))
+            .map(move |(sym, _)| doc.resolve(*sym))
+    }
+
+    /// Value of property `key`, if present.
+    pub fn property(&self, key: &str) -> Option<Value> {
+        let column = self.doc.column(key)?;
+        column
+            .present
+            .get(self.id)
+            .then(|| self.doc.column_value(column, self.id))
+    }
+
+    /// Parent node id, or `None` for top-level patterns.
+    pub fn parent(&self) -> Option<usize> {
+        self.doc.parent(self.id)
+    }
+
+    /// Node ids of the direct elements.
+    pub fn children(&self) -> Range<usize> {
+        self.doc.children(self.id)
+    }
+
+    /// Returns `true` if the node has no elements.
+    pub fn is_atomic(&self) -> bool {
+        self.children().is_empty()
+    }
+}
+
+fn to_u32(n: usize) -> u32 {
+    match u32::try_from(n) {
+        Ok(v) if v != NO_PARENT => v,
+        _ => panic!("columnar document capacity exceeded"),
+    }
+}
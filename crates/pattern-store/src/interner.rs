@@ -0,0 +1,105 @@
+//! String interning for identities, labels and property keys
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Handle to a string stored in an [`Interner`].
+///
+/// Two handles from the same interner are equal exactly when the strings are equal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Sym(u32);
+
+impl Sym {
+    /// Returns the position of this string in the interner's table.
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Deduplicating string table.
+///
+/// Each distinct string is stored once; the lookup map and the table share the
+/// same allocation.
+#[derive(Debug, Clone, Default)]
+pub struct Interner {
+    lookup: HashMap<Arc<str>, Sym>,
+    strings: Vec<Arc<str>>,
+}
+
+impl Interner {
+    /// Creates an empty interner.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Interns `s`, returning the existing handle if it was seen before.
+    ///
+    /// # Panics
+    ///
+    /// Panics if more than `u32::MAX` distinct strings are interned.
+    pub fn intern(&mut self, s: &str) -> Sym {
+        if let Some(sym) = self.lookup.get(s) {
+            return *sym;
+        }
+        let sym = Sym(u32::try_from(self.strings.len()).expect("interner capacity exceeded"));
+        let shared: Arc<str> = Arc::from(s);
+        self.strings.push(shared.clone());
+        self.lookup.insert(shared, sym);
+        sym
+    }
+
+    /// Returns the handle for `s` without interning it.
+    pub fn get(&self, s: &str) -> Option<Sym> {
+        self.lookup.get(s).copied()
+    }
+
+    /// Returns the string for a handle.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sym` was not produced by this interner.
+    pub fn resolve(&self, sym: Sym) -> &str {
+        &self.strings[sym.index()]
+    }
+
+    /// Returns the number of distinct strings.
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    /// Returns `true` if nothing has been interned.
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// Total bytes of string data held by the table.
+    pub fn string_bytes(&self) -> usize {
+        self.strings.iter().map(|s| s.len()).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intern_deduplicates() {
+        let mut interner = Interner::new();
+        let a = interner.intern("Person");
+        let b = interner.intern("Person");
+        let c = interner.intern("Company");
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_eq!(interner.len(), 2);
+        assert_eq!(interner.resolve(c), "Company");
+    }
+
+    #[test]
+    fn test_get_does_not_intern() {
+        let mut interner = Interner::new();
+        assert_eq!(interner.get("x"), None);
+        let x = interner.intern("x");
+        assert_eq!(interner.get("x"), Some(x));
+        assert_eq!(interner.len(), 1);
+    }
+}
@@ -0,0 +1,55 @@
+//! pattern-store - Interned, hash-consed storage for patterns
+//!
+//! Graph documents tend to repeat themselves: the same node appears as a
+//! top-level pattern and again inside every relationship that references it,
+//! and the same labels and property keys appear on thousands of subjects.
+//! [`PatternStore`] exploits that repetition:
+//!
+//! - **Hash-consing**: structurally identical subpatterns are stored once and
+//!   referenced by [`PatternId`].
+//! - **Interning**: identities, labels, property keys and string values are
+//!   stored once in an [`Interner`] and referenced by [`Sym`].
+//!
+//! For scan-heavy workloads, [`ColumnarDocument`] lays a document out as
+//! struct-of-arrays columns (structure offsets, label bitmaps and typed property
+//! columns) and answers filter/fold queries directly on those columns.
+//!
+//! Stored patterns are immutable. Use [`PatternStore::materialize`] to get an
+//! owned `Pattern<Subject>` back, and [`PatternStore::stats`] to see how much
+//! sharing a document achieved.
+//!
+//! # Example
+//!
+//! ```rust
+//! use pattern_core::{Pattern, Subject, Symbol};
+//! use pattern_store::PatternStore;
+//! use std::collections::{HashMap, HashSet};
+//!
+//! let subject = |id: &str, label: &str| Subject {
+//!     identity: Symbol(id.to_string()),
+//!     labels: HashSet::from([label.to_string()]),
+//!     properties: HashMap::new(),
+//! };
+//! let alice = Pattern::point(subject("alice", "Person"));
+//! let bob = Pattern::point(subject("bob", "Person"));
+//! let knows = Pattern::pattern(subject("", "KNOWS"), vec![alice.clone(), bob.clone()]);
+//!
+//! let mut store = PatternStore::new();
+//! let ids = store.insert_all(&[alice, bob, knows]);
+//!
+//! // `alice` and `bob` inside the relationship share the top-level nodes
+//! assert_eq!(store.get(ids[2]).unwrap().elements.as_ref(), &[ids[0], ids[1]]);
+//! assert_eq!(store.len(), 3);
+//! ```
+
+mod bitmap;
+mod columnar;
+mod interner;
+mod store;
+
+pub use bitmap::Bitmap;
+pub use columnar::{ColumnData, ColumnarDocument, NodeView, PropertyColumn};
+pub use interner::{Interner, Sym};
+pub use store::{
+    PatternId, PatternStore, StoreStats, StoredPattern, StoredSubject, StoredValue, SubjectId,
+};
@@ -0,0 +1,536 @@
+//! Hash-consed storage for `Pattern<Subject>`
+
+use crate::interner::{Interner, Sym};
+use pattern_core::{canonical_f64, Pattern, RangeValue, Subject, Symbol, Value};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::mem::size_of;
+
+/// Identifier of a structurally unique pattern in a [`PatternStore`].
+///
+/// Two patterns inserted into the same store receive the same `PatternId`
+/// exactly when they are structurally identical (same subject, same elements
+/// in the same order).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PatternId(u32);
+
+impl PatternId {
+    /// Returns the position of this pattern in the store's pattern table.
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Identifier of a unique subject (identity + labels + properties) in a [`PatternStore`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SubjectId(u32);
+
+impl SubjectId {
+    /// Returns the position of this subject in the store's subject table.
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Interned form of a property [`Value`].
+///
+/// Strings are interned and floats are kept as the bit patterns of their
+/// [`canonical_f64`] forms, so values that are equal as `Value`s are stored, and
+/// deduplicated, as one. The conversion back to `Value` is lossless up to that
+/// equality: `-0.0` comes back as `0.0`, and every NaN as the same NaN.
+/// Map entries are sorted by key handle to make the representation canonical.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum StoredValue {
+    Integer(i64),
+    Decimal(u64),
+    Boolean(bool),
+    String(Sym),
+    Symbol(Sym),
+    TaggedString {
+        tag: Sym,
+        content: Sym,
+    },
+    Array(Box<[StoredValue]>),
+    Map(Box<[(Sym, StoredValue)]>),
+    Range {
+        lower: Option<u64>,
+        upper: Option<u64>,
+    },
+    Measurement {
+        unit: Sym,
+        value: u64,
+    },
+}
+
+/// Interned form of a [`Subject`].
+///
+/// Labels and properties are sorted by handle, so equal subjects have equal
+/// stored forms regardless of `HashSet`/`HashMap` iteration order.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StoredSubject {
+    /// Interned identity (the empty string for anonymous subjects)
+    pub identity: Sym,
+    /// Interned labels, sorted
+    pub labels: Box<[Sym]>,
+    /// Interned property keys and values, sorted by key
+    pub properties: Box<[(Sym, StoredValue)]>,
+}
+
+/// A hash-consed pattern node: a subject plus references to element patterns.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StoredPattern {
+    /// The subject decorating this pattern
+    pub subject: SubjectId,
+    /// The elements, as references to other stored patterns
+    pub elements: Box<[PatternId]>,
+}
+
+/// Memory and sharing statistics for a [`PatternStore`].
+///
+/// Byte counts are estimates: they include inline sizes and string/element
+/// payloads but not allocator or hash-table bucket overhead. They are intended
+/// for comparing the store against the equivalent `Pattern<Subject>` trees.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct StoreStats {
+    /// Pattern nodes passed to `insert`, counting repeats
+    pub inserted_nodes: usize,
+    /// Distinct pattern nodes actually stored
+    pub unique_patterns: usize,
+    /// Distinct subjects actually stored
+    pub unique_subjects: usize,
+    /// Distinct interned strings (identities, labels, keys and string values)
+    pub interned_strings: usize,
+    /// Estimated size of the inserted patterns as `Pattern<Subject>` trees
+    pub logical_bytes: usize,
+    /// Estimated size of the store's tables
+    pub stored_bytes: usize,
+}
+
+impl StoreStats {
+    /// Estimated bytes saved compared to holding the inserted trees directly.
+    pub fn bytes_saved(&self) -> usize {
+        self.logical_bytes.saturating_sub(self.stored_bytes)
+    }
+
+    /// Ratio of logical size to stored size (higher is better; 1.0 means no savings).
+    pub fn compression_ratio(&self) -> f64 {
+        if self.stored_bytes == 0 {
+            1.0
+        } else {
+            self.logical_bytes as f64 / self.stored_bytes as f64
+        }
+    }
+}
+
+impl fmt::Display for StoreStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} nodes inserted, {} unique patterns, {} unique subjects, {} strings; \
+             ~{} bytes as trees vs ~{} bytes stored ({:.1}x)",
+            self.inserted_nodes,
+            self.unique_patterns,
+            self.unique_subjects,
+            self.interned_strings,
+            self.logical_bytes,
+            self.stored_bytes,
+            self.compression_ratio()
+        )
+    }
+}
+
+/// Interned, hash-consed store of `Pattern<Subject>` values.
+///
+/// Structurally identical subpatterns are stored once and shared by id, and all
+/// identities, labels, property keys and string values are interned. Patterns are
+/// immutable once stored; inserting returns an id that can be used with
+/// [`get`](Self::get) or turned back into an owned tree with
+/// [`materialize`](Self::materialize).
+///
+/// # Examples
+///
+/// ```rust
+/// use pattern_core::{Pattern, Subject, Symbol};
+/// use pattern_store::PatternStore;
+/// use std::collections::{HashMap, HashSet};
+///
+/// let node = |id: &str| Pattern::point(Subject {
+///     identity: Symbol(id.to_string()),
+///     labels: HashSet::from(["Person".to_string()]),
+///     properties: HashMap::new(),
+/// });
+///
+/// let mut store = PatternStore::new();
+/// let a = store.insert(&node("a"));
+/// let b = store.insert(&node("a"));
+/// assert_eq!(a, b);
+///
+/// assert_eq!(store.materialize(a), Some(node("a")));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PatternStore {
+    strings: Interner,
+    subjects: Table<StoredSubject>,
+    patterns: Table<StoredPattern>,
+    inserted_nodes: usize,
+    logical_bytes: usize,
+}
+
+impl PatternStore {
+    /// Creates an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stores a pattern, sharing any subpatterns already present, and returns its id.
+    ///
+    /// # Complexity
+    ///
+    /// O(n) expected in the number of nodes of `pattern`.
+    pub fn insert(&mut self, pattern: &Pattern<Subject>) -> PatternId {
+        self.logical_bytes += logical_size(pattern);
+        self.insert_node(pattern)
+    }
+
+    /// Stores each pattern of a document and returns their ids in order.
+    pub fn insert_all(&mut self, patterns: &[Pattern<Subject>]) -> Vec<PatternId> {
+        patterns.iter().map(|p| self.insert(p)).collect()
+    }
+
+    /// Returns the id of a structurally identical stored pattern, without inserting.
+    pub fn find(&self, pattern: &Pattern<Subject>) -> Option<PatternId> {
+        let elements = pattern
+            .elements
+            .iter()
+            .map(|e| self.find(e))
+            .collect::<Option<Box<[_]>>>()?;
+        let subject = encode_subject(&pattern.value, &mut |s| self.strings.get(s))?;
+        let subject = SubjectId(self.subjects.find(&subject)?);
+        self.patterns
+            .find(&StoredPattern { subject, elements })
+            .map(PatternId)
+    }
+
+    /// Returns the stored node for an id.
+    pub fn get(&self, id: PatternId) -> Option<&StoredPattern> {
+        self.patterns.get(id.0)
+    }
+
+    /// Returns the stored subject for an id.
+    pub fn subject(&self, id: SubjectId) -> Option<&StoredSubject> {
+        self.subjects.get(id.0)
+    }
+
+    /// Resolves an interned string handle.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sym` does not belong to this store.
+    pub fn resolve(&self, sym: Sym) -> &str {
+        self.strings.resolve(sym)
+    }
+
+    /// Returns the interned handle for a string, if it occurs anywhere in the store.
+    pub fn lookup(&self, s: &str) -> Option<Sym> {
+        self.strings.get(s)
+    }
+
+    /// Rebuilds an owned `Pattern<Subject>` from a stored id.
+    ///
+    /// Shared subpatterns are copied into each position where they occur.
+    pub fn materialize(&self, id: PatternId) -> Option<Pattern<Subject>> {
+        let node = self.get(id)?;
+        let value = self.materialize_subject(node.subject)?;
+        let elements = node
+            .elements
+            .iter()
+            .map(|e| self.materialize(*e))
+            .collect::<Option<Vec<_>>>()?;
+        Some(Pattern::pattern(value, elements))
+    }
+
+    /// Rebuilds an owned `Subject` from a stored subject id.
+    pub fn materialize_subject(&self, id: SubjectId) -> Option<Subject> {
+        let stored = self.subject(id)?;
+        Some(Subject {
+            identity: Symbol(self.resolve(stored.identity).to_string()),
+            labels: stored
+                .labels
+                .iter()
+                .map(|l| self.resolve(*l).to_string())
+                .collect(),
+            properties: stored
+                .properties
+                .iter()
+                .map(|(k, v)| (self.resolve(*k).to_string(), self.decode_value(v)))
+                .collect(),
+        })
+    }
+
+    /// Number of distinct pattern nodes stored.
+    pub fn len(&self) -> usize {
+        self.patterns.items.len()
+    }
+
+    /// Returns `true` if nothing has been stored.
+    pub fn is_empty(&self) -> bool {
+        self.patterns.items.is_empty()
+    }
+
+    /// Reports sharing and estimated memory savings.
+    pub fn stats(&self) -> StoreStats {
+        let strings = self.strings.string_bytes()
+            + self.strings.len() * (2 * size_of::<std::sync::Arc<str>>() + size_of::<Sym>());
+        let subjects: usize = self
+            .subjects
+            .items
+            .iter()
+            .map(|s| {
+                size_of::<StoredSubject>()
+                    + s.labels.len() * size_of::<Sym>()
+                    + s.properties
+                        .iter()
+                        .map(|(_, v)| size_of::<Sym>() + stored_value_size(v))
+                        .sum::<usize>()
+            })
+            .sum();
+        let patterns: usize = self
+            .patterns
+            .items
+            .iter()
+            .map(|p| size_of::<StoredPattern>() + p.elements.len() * size_of::<PatternId>())
+            .sum();
+
+        StoreStats {
+            inserted_nodes: self.inserted_nodes,
+            unique_patterns: self.patterns.items.len(),
+            unique_subjects: self.subjects.items.len(),
+            interned_strings: self.strings.len(),
+            logical_bytes: self.logical_bytes,
+            stored_bytes: strings
+                + subjects
+                + patterns
+                + self.subjects.index_bytes()
+                + self.patterns.index_bytes(),
+        }
+    }
+
+    fn insert_node(&mut self, pattern: &Pattern<Subject>) -> PatternId {
+        self.inserted_nodes += 1;
+        let elements = pattern
+            .elements
+            .iter()
+            .map(|e| self.insert_node(e))
+            .collect::<Box<[_]>>();
+        let strings = &mut self.strings;
+        let subject = encode_subject(&pattern.value, &mut |s| Some(strings.intern(s)))
+            .expect("interning never fails");
+        let subject = SubjectId(self.subjects.intern(subject));
+        PatternId(self.patterns.intern(StoredPattern { subject, elements }))
+    }
+
+    fn decode_value(&self, value: &StoredValue) -> Value {
+        let s = |sym: &Sym| self.resolve(*sym).to_string();
+        match value {
+            StoredValue::Integer(i) => Value::VInteger(*i),
+            StoredValue::Decimal(bits) => Value::VDecimal(f64::from_bits(*bits)),
+            StoredValue::Boolean(b) => Value::VBoolean(*b),
+            StoredValue::String(sym) => Value::VString(s(sym)),
+            StoredValue::Symbol(sym) => Value::VSymbol(s(sym)),
+            StoredValue::TaggedString { tag, content } => Value::VTaggedString {
+                tag: s(tag),
+                content: s(content),
+            },
+            StoredValue::Array(items) => {
+                Value::VArray(items.iter().map(|v| self.decode_value(v)).collect())
+            }
+            StoredValue::Map(entries) => Value::VMap(
+                entries
+                    .iter()
+                    .map(|(k, v)| (s(k), self.decode_value(v)))
+                    .collect(),
+            ),
+            StoredValue::Range { lower, upper } => Value::VRange(RangeValue {
+                lower: lower.map(f64::from_bits),
+                upper: upper.map(f64::from_bits),
+            }),
+            StoredValue::Measurement { unit, value } => Value::VMeasurement {
+                unit: s(unit),
+                value: f64::from_bits(*value),
+            },
+        }
+    }
+}
+
+/// Encodes a subject, resolving strings through `sym`.
+///
+/// Returns `None` as soon as `sym` does (used for lookups that must not intern).
+fn encode_subject(
+    subject: &Subject,
+    sym: &mut impl FnMut(&str) -> Option<Sym>,
+) -> Option<StoredSubject> {
+    let identity = sym(&subject.identity.0)?;
+    let mut labels = subject
+        .labels
+        .iter()
+        .map(|l| sym(l))
+        .collect::<Option<Vec<_>>>()?;
+    labels.sort_unstable();
+    let mut properties = subject
+        .properties
+        .iter()
+        .map(|(k, v)| Some((sym(k)?, encode_value(v, sym)?)))
+        .collect::<Option<Vec<_>>>()?;
+    properties.sort_unstable_by_key(|(k, _)| *k);
+    Some(StoredSubject {
+        identity,
+        labels: labels.into(),
+        properties: properties.into(),
+    })
+}
+
+fn encode_value(value: &Value, sym: &mut impl FnMut(&str) -> Option<Sym>) -> Option<StoredValue> {
+    Some(match value {
+        Value::VInteger(i) => StoredValue::Integer(*i),
+        Value::VDecimal(d) => StoredValue::Decimal(canonical_bits(*d)),
+        Value::VBoolean(b) => StoredValue::Boolean(*b),
+        Value::VString(s) => StoredValue::String(sym(s)?),
+        Value::VSymbol(s) => StoredValue::Symbol(sym(s)?),
+        Value::VTaggedString { tag, content } => StoredValue::TaggedString {
+            tag: sym(tag)?,
+            content: sym(content)?,
+        },
+        Value::VArray(items) => StoredValue::Array(
+            items
+                .iter()
+                .map(|v| encode_value(v, sym))
+                .collect::<Option<Box<[_]>>>()?,
+        ),
+        Value::VMap(map) => {
+            let mut entries = map
+                .iter()
+                .map(|(k, v)| Some((sym(k)?, encode_value(v, sym)?)))
+                .collect::<Option<Vec<_>>>()?;
+            entries.sort_unstable_by_key(|(k, _)| *k);
+            StoredValue::Map(entries.into())
+        }
+        Value::VRange(r) => StoredValue::Range {
+            lower: r.lower.map(canonical_bits),
+            upper: r.upper.map(canonical_bits),
+        },
+        Value::VMeasurement { unit, value } => StoredValue::Measurement {
+            unit: sym(unit)?,
+            value: canonical_bits(*value),
+        },
+    })
+}
+
+fn canonical_bits(x: f64) -> u64 {
+    canonical_f64(x).to_bits()
+}
+
+/// Estimated size of a `Pattern<Subject>` tree (inline sizes plus string payloads).
+fn logical_size(pattern: &Pattern<Subject>) -> usize {
+    let subject = &pattern.value;
+    let own = size_of::<Pattern<Subject>>()
+        + subject.identity.0.len()
+        + subject
+            .labels
+            .iter()
+            .map(|l| size_of::<String>() + l.len())
+            .sum::<usize>()
+        + subject
+            .properties
+            .iter()
+            .map(|(k, v)| size_of::<String>() + k.len() + value_size(v))
+            .sum::<usize>();
+    own + pattern.elements.iter().map(logical_size).sum::<usize>()
+}
+
+fn value_size(value: &Value) -> usize {
+    size_of::<Value>()
+        + match value {
+            Value::VString(s) | Value::VSymbol(s) => s.len(),
+            Value::VTaggedString { tag, content } => tag.len() + content.len(),
+            Value::VMeasurement { unit, .. } => unit.len(),
+            Value::VArray(items) => items.iter().map(value_size).sum(),
+            Value::VMap(map) => map
+                .iter()
+                .map(|(k, v)| size_of::<String>() + k.len() + value_size(v))
+                .sum(),
+            _ => 0,
+        }
+}
+
+fn stored_value_size(value: &StoredValue) -> usize {
+    size_of::<StoredValue>()
+        + match value {
+            StoredValue::Array(items) => items.iter().map(stored_value_size).sum(),
+            StoredValue::Map(entries) => entries
+                .iter()
+                .map(|(_, v)| size_of::<Sym>() + stored_value_size(v))
+                .sum(),
+            _ => 0,
+        }
+}
+
+/// Append-only deduplicating table.
+///
+/// Items are bucketed by hash so each item is stored once (in `items`), rather
+/// than once in a `Vec` and again as a `HashMap` key.
+#[derive(Debug, Clone)]
+struct Table<T> {
+    items: Vec<T>,
+    buckets: HashMap<u64, Vec<u32>>,
+}
+
+impl<T> Default for Table<T> {
+    fn default() -> Self {
+        Self {
+            items: Vec::new(),
+            buckets: HashMap::new(),
+        }
+    }
+}
+
+impl<T: Hash + Eq> Table<T> {
+    fn intern(&mut self, item: T) -> u32 {
+        let hash = hash_of(&item);
+        if let Some(id) = self.find_hashed(hash, &item) {
+            return id;
+        }
+        let id = u32::try_from(self.items.len()).expect("pattern store capacity exceeded");
+        self.items.push(item);
+        self.buckets.entry(hash).or_default().push(id);
+        id
+    }
+
+    fn find(&self, item: &T) -> Option<u32> {
+        self.find_hashed(hash_of(item), item)
+    }
+
+    fn find_hashed(&self, hash: u64, item: &T) -> Option<u32> {
+        self.buckets
+            .get(&hash)?
+            .iter()
+            .copied()
+            .find(|id| &self.items[*id as usize] == item)
+    }
+
+    fn get(&self, id: u32) -> Option<&T> {
+        self.items.get(id as usize)
+    }
+
+    fn index_bytes(&self) -> usize {
+        self.buckets.len() * (size_of::<u64>() + size_of::<Vec<u32>>())
+            + self.items.len() * size_of::<u32>()
+    }
+}
+
+fn hash_of<T: Hash>(item: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    item.hash(&mut hasher);
+    hasher.finish()
+}
@@ -0,0 +1,139 @@
+//! Tests for filter and fold queries over ColumnarDocument columns
+//!
+//! Each columnar query is checked against the equivalent scan over the
+//! original `Pattern<Subject>` trees.
+
+use pattern_core::{Pattern, Subject, Symbol, Value};
+use pattern_store::ColumnarDocument;
+use std::collections::{HashMap, HashSet};
+
+fn person(i: i64) -> Pattern<Subject> {
+    let mut properties = HashMap::from([
+        ("age".to_string(), Value::VInteger(20 + i % 50)),
+        (
+            "team".to_string(),
+            Value::VString(if i % 3 == 0 { "red" } else { "blue" }.to_string()),
+        ),
+        ("active".to_string(), Value::VBoolean(i % 2 == 0)),
+    ]);
+    if i % 10 == 0 {
+        properties.insert("score".to_string(), Value::VDecimal(i as f64 / 4.0));
+    }
+    Pattern::point(Subject {
+        identity: Symbol(format!("p{}", i)),
+        labels: HashSet::from(["Person".to_string()]),
+        properties,
+    })
+}
+
+fn document() -> Vec<Pattern<Subject>> {
+    let mut doc: Vec<_> = (0..200).map(person).collect();
+    doc.push(Pattern::pattern(
+        Subject {
+            identity: Symbol("k".to_string()),
+            labels: HashSet::from(["KNOWS".to_string()]),
+            properties: HashMap::from([("age".to_string(), Value::VString("n/a".to_string()))]),
+        },
+        vec![person(1), person(2)],
+    ));
+    doc
+}
+
+fn scan<F: Fn(&Subject) -> bool>(doc: &[Pattern<Subject>], f: F) -> usize {
+    doc.iter().map(|p| p.filter(|q| f(&q.value)).len()).sum()
+}
+
+#[test]
+fn test_label_mask_matches_scan() {
+    let doc = document();
+    let columns = ColumnarDocument::from_patterns(&doc);
+    assert_eq!(
+        columns.label_mask("Person").count_ones(),
+        scan(&doc, |s| s.labels.contains("Person"))
+    );
+    assert_eq!(columns.label_mask("Nope").count_ones(), 0);
+}
+
+#[test]
+fn test_numeric_filter_handles_mixed_columns() {
+    let doc = document();
+    let columns = ColumnarDocument::from_patterns(&doc);
+
+    let expected = scan(
+        &doc,
+        |s| matches!(s.properties.get("age"), Some(Value::VInteger(a)) if *a >= 60),
+    );
+    assert_eq!(
+        columns.where_numeric("age", |a| a >= 60.0).count_ones(),
+        expected
+    );
+    assert_eq!(columns.where_string("age", |a| a == "n/a").count_ones(), 1);
+}
+
+#[test]
+fn test_combined_masks() {
+    let doc = document();
+    let columns = ColumnarDocument::from_patterns(&doc);
+
+    let red_and_active =
+        &columns.where_string("team", |t| t == "red") & &columns.where_bool("active", true);
+    let expected = scan(&doc, |s| {
+        s.properties.get("team") == Some(&Value::VString("red".to_string()))
+            && s.properties.get("active") == Some(&Value::VBoolean(true))
+    });
+    assert_eq!(red_and_active.count_ones(), expected);
+
+    let either = &columns.label_mask("KNOWS") | &columns.identity_mask("p7");
+    assert_eq!(either.count_ones(), 2);
+}
+
+#[test]
+fn test_row_filter_matches_column_filter() {
+    let columns = ColumnarDocument::from_patterns(&document());
+    let by_row = columns.filter(|n| {
+        n.has_label("Person") && matches!(n.property("age"), Some(Value::VInteger(a)) if a < 25)
+    });
+    let by_column = &columns.label_mask("Person") & &columns.where_numeric("age", |a| a < 25.0);
+    assert_eq!(by_row, by_column);
+}
+
+#[test]
+fn test_folds() {
+    let doc = document();
+    let columns = ColumnarDocument::from_patterns(&doc);
+
+    let expected: f64 = doc
+        .iter()
+        .flat_map(|p| p.values())
+        .filter_map(|s| match s.properties.get("score") {
+            Some(Value::VDecimal(d)) => Some(*d),
+            _ => None,
+        })
+        .sum();
+    assert_eq!(columns.sum_numeric("score"), expected);
+    assert_eq!(columns.fold_numeric("score", 0, |n, _| n + 1), 20);
+    assert_eq!(columns.sum_numeric("missing"), 0.0);
+
+    let red = columns.where_string("team", |t| t == "red");
+    let ids = columns.fold_masked(&red, Vec::new(), |mut acc, n| {
+        acc.push(n.identity().to_string());
+        acc
+    });
+    assert_eq!(ids.len(), red.count_ones());
+    assert!(ids.iter().all(|id| id.starts_with('p')));
+}
+
+#[test]
+fn test_node_view_accessors() {
+    let columns = ColumnarDocument::from_patterns(&document());
+    let knows = columns.identity_mask("k").ones().next().unwrap();
+    let node = columns.node(knows);
+
+    assert_eq!(node.labels().collect::<Vec<_>>(), vec!["KNOWS"]);
+    assert!(!node.is_atomic());
+    for child in node.children() {
+        assert_eq!(columns.node(child).parent(), Some(knows));
+        assert!(columns.node(child).is_atomic());
+    }
+    assert_eq!(node.property("team"), None);
+}
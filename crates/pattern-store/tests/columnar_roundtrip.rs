@@ -0,0 +1,134 @@
+//! Tests for lossless conversion between patterns and ColumnarDocument
+
+mod common;
+
+use common::{node, subject};
+use pattern_core::{Pattern, RangeValue, Value};
+use pattern_store::{ColumnData, ColumnarDocument};
+use std::collections::HashMap;
+
+#[test]
+fn test_empty_document() {
+    let doc = ColumnarDocument::from_patterns(&[]);
+    assert!(doc.is_empty());
+    assert!(doc.to_patterns().is_empty());
+    assert!(doc.columns().is_empty());
+}
+
+#[test]
+fn test_nested_structure_round_trips() {
+    let a = node("a", &["Person"], vec![("age", Value::VInteger(1))]);
+    let b = node("b", &["Person"], vec![]);
+    let path = Pattern::pattern(
+        subject("", &["PATH"], vec![]),
+        vec![
+            Pattern::pattern(
+                subject("r1", &["KNOWS"], vec![]),
+                vec![a.clone(), b.clone()],
+            ),
+            Pattern::pattern(
+                subject("r2", &["KNOWS"], vec![]),
+                vec![b.clone(), a.clone()],
+            ),
+        ],
+    );
+    let patterns = vec![a, path, b];
+
+    let doc = ColumnarDocument::from_patterns(&patterns);
+    assert_eq!(doc.len(), 9);
+    assert_eq!(doc.roots(), 0..3);
+    assert_eq!(doc.to_patterns(), patterns);
+}
+
+#[test]
+fn test_children_are_contiguous_and_parents_consistent() {
+    let leaf = |id: &str| node(id, &[], vec![]);
+    let patterns = vec![
+        Pattern::pattern(
+            subject("x", &[], vec![]),
+            vec![
+                Pattern::pattern(subject("y", &[], vec![]), vec![leaf("z")]),
+                leaf("w"),
+            ],
+        ),
+        leaf("v"),
+    ];
+    let doc = ColumnarDocument::from_patterns(&patterns);
+
+    for id in 0..doc.len() {
+        for child in doc.children(id) {
+            assert_eq!(doc.parent(child), Some(id));
+        }
+    }
+    assert_eq!(doc.parent(0), None);
+    let x_children: Vec<_> = doc.children(0).map(|c| doc.node(c).identity()).collect();
+    assert_eq!(x_children, vec!["y", "w"]);
+    assert_eq!(doc.to_pattern(0), patterns[0]);
+}
+
+#[test]
+fn test_property_values_round_trip_losslessly() {
+    let mut meta = HashMap::new();
+    meta.insert("k".to_string(), Value::VString("v".to_string()));
+    let patterns = vec![
+        node(
+            "a",
+            &["A", "B"],
+            vec![
+                ("i", Value::VInteger(i64::MIN)),
+                ("d", Value::VDecimal(-0.0)),
+                ("b", Value::VBoolean(false)),
+                ("s", Value::VString(String::new())),
+                ("sym", Value::VSymbol("s".to_string())),
+                ("map", Value::VMap(meta)),
+                (
+                    "r",
+                    Value::VRange(RangeValue {
+                        lower: None,
+                        upper: Some(3.0),
+                    }),
+                ),
+            ],
+        ),
+        node("b", &[], vec![("b", Value::VBoolean(true))]),
+    ];
+
+    let doc = ColumnarDocument::from_patterns(&patterns);
+    let back = doc.to_patterns();
+    assert_eq!(back, patterns);
+    let Value::VDecimal(d) = back[0].value.properties["d"] else {
+        panic!("expected decimal");
+    };
+    assert!(d.is_sign_negative());
+}
+
+#[test]
+fn test_uniform_columns_use_typed_storage() {
+    let patterns = vec![
+        node(
+            "a",
+            &[],
+            vec![("n", Value::VInteger(1)), ("x", Value::VInteger(1))],
+        ),
+        node(
+            "b",
+            &[],
+            vec![("n", Value::VInteger(2)), ("x", Value::VDecimal(2.0))],
+        ),
+        node("c", &[], vec![("flag", Value::VBoolean(true))]),
+    ];
+    let doc = ColumnarDocument::from_patterns(&patterns);
+
+    assert!(matches!(doc.column("n").unwrap().data(), ColumnData::Integer(v) if v == &[1, 2, 0]));
+    assert!(matches!(
+        doc.column("x").unwrap().data(),
+        ColumnData::Mixed(_)
+    ));
+    assert!(matches!(
+        doc.column("flag").unwrap().data(),
+        ColumnData::Boolean(_)
+    ));
+    assert_eq!(doc.column("n").unwrap().present().count_ones(), 2);
+    assert!(doc.column("missing").is_none());
+    assert_eq!(doc.to_patterns(), patterns);
+}
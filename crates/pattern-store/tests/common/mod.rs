@@ -0,0 +1,21 @@
+//! Fixtures shared by the integration tests
+
+#![allow(dead_code)]
+
+use pattern_core::{Pattern, Subject, Symbol, Value};
+
+pub fn subject(id: &str, labels: &[&str], props: Vec<(&str, Value)>) -> Subject {
+    Subject {
+        identity: Symbol(id.to_string()),
+        labels: labels.iter().map(|l| l.to_string()).collect(),
+        properties: props.into_iter().map(|(k, v)| (k.to_string(), v)).collect(),
+    }
+}
+
+pub fn node(id: &str, labels: &[&str], props: Vec<(&str, Value)>) -> Pattern<Subject> {
+    Pattern::point(subject(id, labels, props))
+}
+
+pub fn rel(label: &str, left: Pattern<Subject>, right: Pattern<Subject>) -> Pattern<Subject> {
+    Pattern::pattern(subject("", &[label], vec![]), vec![left, right])
+}
@@ -0,0 +1,157 @@
+//! Tests for PatternStore insertion, sharing and materialization
+
+mod common;
+
+use common::{node, rel, subject};
+use pattern_core::{Pattern, RangeValue, Subject, Value};
+use pattern_store::PatternStore;
+use std::collections::{HashMap, HashSet};
+
+fn person(id: &str) -> Pattern<Subject> {
+    node(
+        id,
+        &["Person"],
+        vec![("name", Value::VString(id.to_uppercase()))],
+    )
+}
+
+#[test]
+fn test_identical_patterns_share_an_id() {
+    let mut store = PatternStore::new();
+    let a = store.insert(&person("alice"));
+    let b = store.insert(&person("alice"));
+    let c = store.insert(&person("bob"));
+    assert_eq!(a, b);
+    assert_ne!(a, c);
+    assert_eq!(store.len(), 2);
+}
+
+#[test]
+fn test_subpatterns_are_shared() {
+    let mut store = PatternStore::new();
+    let ids = store.insert_all(&[
+        person("alice"),
+        person("bob"),
+        rel("KNOWS", person("alice"), person("bob")),
+    ]);
+
+    let knows = store.get(ids[2]).unwrap();
+    assert_eq!(knows.elements.as_ref(), &[ids[0], ids[1]]);
+    assert_eq!(store.len(), 3);
+}
+
+#[test]
+fn test_materialize_round_trips() {
+    let mut meta = HashMap::new();
+    meta.insert("nested".to_string(), Value::VBoolean(true));
+    let rich = node(
+        "x",
+        &["A", "B"],
+        vec![
+            ("i", Value::VInteger(-7)),
+            ("d", Value::VDecimal(-0.0)),
+            ("s", Value::VString("text".to_string())),
+            ("sym", Value::VSymbol("foo".to_string())),
+            (
+                "t",
+                Value::VTaggedString {
+                    tag: "url".to_string(),
+                    content: "http://example.com".to_string(),
+                },
+            ),
+            (
+                "arr",
+                Value::VArray(vec![Value::VInteger(1), Value::VString("two".to_string())]),
+            ),
+            ("map", Value::VMap(meta)),
+            (
+                "r",
+                Value::VRange(RangeValue {
+                    lower: Some(1.0),
+                    upper: None,
+                }),
+            ),
+            (
+                "m",
+                Value::VMeasurement {
+                    unit: "kg".to_string(),
+                    value: 2.5,
+                },
+            ),
+        ],
+    );
+    let doc = rel("LINKS", rich.clone(), person("bob"));
+
+    let mut store = PatternStore::new();
+    let id = store.insert(&doc);
+    assert_eq!(store.materialize(id), Some(doc));
+}
+
+#[test]
+fn test_equal_floats_share_an_id() {
+    let mut store = PatternStore::new();
+    let zero = store.insert(&node("n", &[], vec![("d", Value::VDecimal(0.0))]));
+    let negative = store.insert(&node("n", &[], vec![("d", Value::VDecimal(-0.0))]));
+    assert_eq!(zero, negative);
+
+    let nan = store.insert(&node("n", &[], vec![("d", Value::VDecimal(f64::NAN))]));
+    let other = store.insert(&node("n", &[], vec![("d", Value::VDecimal(-f64::NAN))]));
+    assert_eq!(nan, other);
+    assert_ne!(nan, zero);
+}
+
+#[test]
+fn test_label_and_property_order_do_not_matter() {
+    let mut a = subject("n", &[], vec![]);
+    a.labels = HashSet::from(["X".to_string(), "Y".to_string()]);
+    let mut b = subject("n", &[], vec![]);
+    b.labels.insert("Y".to_string());
+    b.labels.insert("X".to_string());
+
+    let mut store = PatternStore::new();
+    let ia = store.insert(&Pattern::point(a));
+    let ib = store.insert(&Pattern::point(b));
+    assert_eq!(ia, ib);
+}
+
+#[test]
+fn test_find_does_not_insert() {
+    let mut store = PatternStore::new();
+    store.insert(&person("alice"));
+
+    assert!(store.find(&person("alice")).is_some());
+    assert!(store.find(&person("carol")).is_none());
+    assert!(store
+        .find(&rel("KNOWS", person("alice"), person("alice")))
+        .is_none());
+    assert_eq!(store.len(), 1);
+    assert!(store.lookup("carol").is_none());
+}
+
+#[test]
+fn test_strings_are_interned_once() {
+    let mut store = PatternStore::new();
+    store.insert_all(&[person("alice"), person("bob")]);
+
+    let person_sym = store.lookup("Person").unwrap();
+    let alice = store.subject(
+        store
+            .get(store.find(&person("alice")).unwrap())
+            .unwrap()
+            .subject,
+    );
+    assert_eq!(alice.unwrap().labels.as_ref(), &[person_sym]);
+    assert_eq!(store.resolve(person_sym), "Person");
+    // "alice", "bob", "Person", "name", "ALICE", "BOB"
+    assert_eq!(store.stats().interned_strings, 6);
+}
+
+#[test]
+fn test_unknown_id_materializes_to_none() {
+    let mut other = PatternStore::new();
+    let id = other.insert(&person("alice"));
+    let store = PatternStore::new();
+    assert!(store.get(id).is_none());
+    assert!(store.materialize(id).is_none());
+    assert!(store.is_empty());
+}
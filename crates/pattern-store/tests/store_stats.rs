@@ -0,0 +1,72 @@
+//! Tests for PatternStore sharing statistics
+
+use pattern_core::{Pattern, Subject, Symbol, Value};
+use pattern_store::PatternStore;
+use std::collections::{HashMap, HashSet};
+
+fn person(i: usize) -> Pattern<Subject> {
+    Pattern::point(Subject {
+        identity: Symbol(format!("person{}", i)),
+        labels: HashSet::from(["Person".to_string(), "Employee".to_string()]),
+        properties: HashMap::from([
+            (
+                "department".to_string(),
+                Value::VString("Engineering".to_string()),
+            ),
+            ("active".to_string(), Value::VBoolean(true)),
+        ]),
+    })
+}
+
+fn knows(a: Pattern<Subject>, b: Pattern<Subject>) -> Pattern<Subject> {
+    Pattern::pattern(
+        Subject {
+            identity: Symbol(String::new()),
+            labels: HashSet::from(["KNOWS".to_string()]),
+            properties: HashMap::new(),
+        },
+        vec![a, b],
+    )
+}
+
+#[test]
+fn test_empty_store_stats() {
+    let stats = PatternStore::new().stats();
+    assert_eq!(stats.inserted_nodes, 0);
+    assert_eq!(stats.bytes_saved(), 0);
+    assert_eq!(stats.compression_ratio(), 1.0);
+}
+
+#[test]
+fn test_repetitive_document_saves_memory() {
+    let n = 100;
+    let mut doc: Vec<_> = (0..n).map(person).collect();
+    for i in 0..n {
+        doc.push(knows(person(i), person((i + 1) % n)));
+    }
+
+    let mut store = PatternStore::new();
+    store.insert_all(&doc);
+    let stats = store.stats();
+
+    assert_eq!(stats.inserted_nodes, n + 3 * n);
+    assert_eq!(stats.unique_patterns, 2 * n);
+    // Each person subject plus one shared KNOWS subject
+    assert_eq!(stats.unique_subjects, n + 1);
+    assert!(stats.stored_bytes < stats.logical_bytes);
+    assert!(stats.compression_ratio() > 2.0, "{}", stats);
+}
+
+#[test]
+fn test_stats_count_repeated_inserts() {
+    let mut store = PatternStore::new();
+    store.insert(&person(1));
+    let once = store.stats();
+    store.insert(&person(1));
+    let twice = store.stats();
+
+    assert_eq!(twice.inserted_nodes, 2);
+    assert_eq!(twice.unique_patterns, 1);
+    assert_eq!(twice.logical_bytes, 2 * once.logical_bytes);
+    assert_eq!(twice.stored_bytes, once.stored_bytes);
+}